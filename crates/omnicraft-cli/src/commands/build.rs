@@ -3,9 +3,9 @@
 //! Builds the project for production.
 
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::info;
-use omnicraft_compiler::CompilationTarget;
+use omnicraft_compiler::ModuleGraph;
 
 /// Run the build command
 pub async fn run(
@@ -24,29 +24,20 @@ pub async fn run(
         .await
         .context("Failed to create output directory")?;
 
-    // 1. Find all .omni files
-    let mut files = Vec::new();
-    find_omni_files(&dir, &mut files).await?;
+    // 1. Resolve the entry file and everything it imports
+    let entry = find_entry(&dir).await?;
+    info!("Entry: {:?}", entry);
+    let graph = ModuleGraph::build(&entry)?;
+    info!("Found {} modules", graph.modules().len());
 
-    info!("Found {} .omni files", files.len());
-
-    // 2. Compile all files
-    for file in &files {
-        let source = tokio::fs::read_to_string(file).await?;
-        let file_name = file.file_name().and_then(|n| n.to_str()).unwrap_or("Component");
-
-        match omnicraft_compiler::compile(&source, file_name, CompilationTarget::Rust) {
-            Ok(rust_code) => {
-                let output_name = file.file_stem().and_then(|n| n.to_str()).unwrap_or("output");
-                let output_path = output.join(format!("{}.rs", output_name));
-
-                tokio::fs::write(&output_path, rust_code).await?;
-                info!("✓ {}", file_name);
-            }
-            Err(e) => {
-                eprintln!("✗ {} - {}", file_name, e);
-            }
-        }
+    // 2. Compile the graph into one crate, a module per file
+    let src = output.join("src");
+    tokio::fs::create_dir_all(&src)
+        .await
+        .context("Failed to create source directory")?;
+    for (path, code) in graph.compile()? {
+        tokio::fs::write(src.join(&path), code).await?;
+        info!("✓ {}", path.display());
     }
 
     // 3. Generate Cargo.toml for the compiled code
@@ -65,7 +56,31 @@ pub async fn run(
     Ok(())
 }
 
-async fn find_omni_files(dir: &PathBuf, files: &mut Vec<PathBuf>) -> Result<()> {
+/// Entry file named by `omnicraft.config.json`, else `src/App.omni` or
+/// `App.omni` in the project directory
+async fn find_entry(dir: &Path) -> Result<PathBuf> {
+    let config = dir.join("omnicraft.config.json");
+    if let Ok(text) = tokio::fs::read_to_string(&config).await {
+        let config: serde_json::Value =
+            serde_json::from_str(&text).with_context(|| format!("Invalid {:?}", config))?;
+        if let Some(entry) = config.get("entry").and_then(|entry| entry.as_str()) {
+            return Ok(dir.join(entry));
+        }
+    }
+
+    ["src/App.omni", "App.omni"]
+        .iter()
+        .map(|candidate| dir.join(candidate))
+        .find(|candidate| candidate.is_file())
+        .with_context(|| {
+            format!(
+                "No entry file in {:?}; expected src/App.omni or an \"entry\" in omnicraft.config.json",
+                dir
+            )
+        })
+}
+
+pub async fn find_omni_files(dir: &PathBuf, files: &mut Vec<PathBuf>) -> Result<()> {
     let mut entries = tokio::fs::read_dir(dir).await?;
 
     while let Some(entry) = entries.next_entry().await? {
@@ -11,7 +11,7 @@ pub async fn run(files: Vec<PathBuf>) -> Result<()> {
     info!("Checking {} file(s)...", files.len());
 
     let mut errors = 0;
-    let mut warnings = 0;
+    let warnings = 0;
 
     for file in &files {
         if !file.exists() {
@@ -23,26 +23,23 @@ pub async fn run(files: Vec<PathBuf>) -> Result<()> {
         let source = tokio::fs::read_to_string(file).await?;
         let file_name = file.file_name().and_then(|n| n.to_str()).unwrap_or("unknown");
 
-        // Tokenize
-        let tokens = match omnicraft_compiler::Lexer::new(&source).tokenize() {
-            Ok(tokens) => tokens,
-            Err(e) => {
-                eprintln!("✗ {} - Lexer error: {}", file_name, e);
-                errors += 1;
-                continue;
-            }
-        };
-
-        // Parse
-        match omnicraft_compiler::Parser::new(tokens, file_name).parse() {
-            Ok(_component) => {
-                println!("✓ {} - OK", file_name);
-            }
-            Err(e) => {
-                eprintln!("✗ {} - Parse error: {}", file_name, e);
-                errors += 1;
-            }
+        // Tokenize, reporting every lexical error in the file
+        let (tokens, lex_errors) = omnicraft_compiler::Lexer::new(&source).tokenize_with_recovery();
+        for e in &lex_errors {
+            eprintln!("✗ {} - Lexer error: {}", file_name, e);
         }
+
+        // Parse the recovered token stream, reporting every syntax error too
+        let (_component, parse_errors) =
+            omnicraft_compiler::Parser::new(tokens, file_name).parse_with_recovery();
+        for e in &parse_errors {
+            eprintln!("✗ {} - Parse error: {}", file_name, e);
+        }
+
+        if lex_errors.is_empty() && parse_errors.is_empty() {
+            println!("✓ {} - OK", file_name);
+        }
+        errors += lex_errors.len() + parse_errors.len();
     }
 
     println!();
@@ -3,7 +3,7 @@
 //! Compiles .omni files to Rust code.
 
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
 /// Run the compile command
@@ -31,7 +31,7 @@ pub async fn run(input: PathBuf, output: PathBuf, watch: bool, format: String) -
     Ok(())
 }
 
-pub async fn compile_file(input: &PathBuf, output: &PathBuf, format: &str) -> Result<()> {
+pub async fn compile_file(input: &Path, output: &Path, format: &str) -> Result<()> {
     let source = tokio::fs::read_to_string(input)
         .await
         .context("Failed to read input file")?;
@@ -78,7 +78,7 @@ pub async fn compile_file(input: &PathBuf, output: &PathBuf, format: &str) -> Re
     Ok(())
 }
 
-pub async fn compile_directory(input: &PathBuf, output: &PathBuf, format: &str) -> Result<()> {
+pub async fn compile_directory(input: &Path, output: &Path, format: &str) -> Result<()> {
     let mut entries = tokio::fs::read_dir(input).await?;
 
     while let Some(entry) = entries.next_entry().await? {
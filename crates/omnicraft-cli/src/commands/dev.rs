@@ -10,18 +10,17 @@ use axum::{
     routing::get,
     Router,
 };
-use futures::{sink::SinkExt, stream::{StreamExt, SplitSink}};
+use futures::{sink::SinkExt, stream::StreamExt};
 use std::{
     path::PathBuf,
-    sync::{Arc, Mutex},
-    collections::HashMap,
+    sync::Arc,
 };
 use tokio::sync::broadcast;
 use tower_http::{
     services::ServeDir,
     cors::CorsLayer,
 };
-use tracing::{info, error, warn};
+use tracing::{info, error};
 
 /// Shared state for the dev server
 struct AppState {
@@ -160,7 +159,7 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     let mut rx = state.tx.subscribe();
 
     // Spawn a task to forward broadcast messages to this client
-    let mut send_task = tokio::spawn(async move {
+    let send_task = tokio::spawn(async move {
         while let Ok(msg) = rx.recv().await {
             let json = serde_json::to_string(&msg).unwrap();
             if sender.send(Message::Text(json)).await.is_err() {
@@ -188,7 +187,7 @@ async fn build_project(dir: &std::path::Path) -> Result<()> {
     // 2. Build WASM
     info!("Building WASM...");
     let output = tokio::process::Command::new("cargo")
-        .args(&["build", "--target", "wasm32-unknown-unknown"])
+        .args(["build", "--target", "wasm32-unknown-unknown"])
         .current_dir(dir)
         .output()
         .await?;
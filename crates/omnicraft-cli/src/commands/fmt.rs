@@ -0,0 +1,69 @@
+//! Fmt Command
+//!
+//! Format .omni files in place, or check that they are formatted.
+
+use anyhow::{Context, Result};
+use omnicraft_compiler::{format_source, FormatOptions};
+use std::path::PathBuf;
+use tracing::info;
+
+/// Run the fmt command
+pub async fn run(paths: Vec<PathBuf>, check: bool) -> Result<()> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            super::build::find_omni_files(&path, &mut files).await?;
+        } else {
+            files.push(path);
+        }
+    }
+    info!("Formatting {} file(s)...", files.len());
+
+    let options = FormatOptions::default();
+    let mut errors = 0;
+    let mut unformatted = 0;
+
+    for file in &files {
+        let source = match tokio::fs::read_to_string(file).await {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("✗ {} - {}", file.display(), e);
+                errors += 1;
+                continue;
+            }
+        };
+
+        let formatted = match format_source(&source, &options) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("✗ {} - {}", file.display(), e);
+                errors += 1;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+        if check {
+            eprintln!("✗ {} - Not formatted", file.display());
+            unformatted += 1;
+        } else {
+            tokio::fs::write(file, formatted)
+                .await
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            println!("✓ {} - Formatted", file.display());
+        }
+    }
+
+    if check && unformatted > 0 {
+        println!();
+        println!("{} file(s) need formatting, run `omnicraft fmt`", unformatted);
+    }
+
+    if errors > 0 || unformatted > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -154,7 +154,7 @@ edition = "2021"
 
 [lib]
 crate-type = ["cdylib", "rlib"]
-path = "dist/App.rs"
+path = "dist/src/lib.rs"
 
 [dependencies]
 wasm-bindgen = "0.2"
@@ -4,4 +4,5 @@ pub mod build;
 pub mod check;
 pub mod compile;
 pub mod dev;
+pub mod fmt;
 pub mod init;
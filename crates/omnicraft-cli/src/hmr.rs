@@ -8,7 +8,7 @@ use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::time::Duration;
-use tracing::{info, warn, error};
+use tracing::{info, error};
 
 /// HMR event types
 #[derive(Debug, Clone)]
@@ -81,6 +81,7 @@ impl FileWatcher {
     }
 
     /// Wait for the next file change event (blocking)
+    #[allow(dead_code)]
     pub fn wait(&self) -> Option<HmrEvent> {
         match self.receiver.recv() {
             Ok(Ok(event)) => self.process_event(event),
@@ -154,10 +155,10 @@ impl FileWatcher {
         }
 
         // Check extension
-        if let Some(ext) = path.extension() {
-            if let Some(ext_str) = ext.to_str() {
-                return self.extensions.contains(ext_str);
-            }
+        if let Some(ext) = path.extension()
+            && let Some(ext_str) = ext.to_str()
+        {
+            return self.extensions.contains(ext_str);
         }
 
         false
@@ -218,6 +219,7 @@ pub enum HmrMessage {
     #[serde(rename = "reload")]
     Reload,
     /// Module update (partial)
+    #[allow(dead_code)]
     #[serde(rename = "update")]
     Update { path: String },
     /// Error message
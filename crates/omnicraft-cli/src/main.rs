@@ -88,6 +88,17 @@ enum Commands {
         #[arg(required = true)]
         files: Vec<PathBuf>,
     },
+
+    /// Format .omni files
+    Fmt {
+        /// Files or directories to format
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Fail if any file is not formatted, without writing changes
+        #[arg(long)]
+        check: bool,
+    },
 }
 
 #[tokio::main]
@@ -132,6 +143,10 @@ async fn main() -> Result<()> {
         Commands::Check { files } => {
             commands::check::run(files).await?;
         }
+
+        Commands::Fmt { paths, check } => {
+            commands::fmt::run(paths, check).await?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,60 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const MESSY: &str = "<canvas width={800}   height={600}>\n<circle x={1}/>\n</canvas>\n<style>\ncircle{fill:red}\n</style>\n";
+const FORMATTED: &str = "<canvas width={800} height={600}>\n  <circle x={1} />\n</canvas>\n\n<style>\n  circle {\n    fill: red;\n  }\n</style>\n";
+
+/// Fresh scratch directory for one test
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("omnicraft-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn omnicraft(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_omnicraft"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_fmt_check_rejects_unformatted_file() {
+    let dir = scratch_dir("fmt-check-unformatted");
+    let file = dir.join("App.omni");
+    fs::write(&file, MESSY).unwrap();
+
+    let output = omnicraft(&["fmt", "--check", file.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Not formatted"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), MESSY);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fmt_check_accepts_formatted_file() {
+    let dir = scratch_dir("fmt-check-formatted");
+    let file = dir.join("App.omni");
+    fs::write(&file, FORMATTED).unwrap();
+
+    let output = omnicraft(&["fmt", "--check", dir.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fmt_rewrites_unformatted_file() {
+    let dir = scratch_dir("fmt-write");
+    let file = dir.join("App.omni");
+    fs::write(&file, MESSY).unwrap();
+
+    let output = omnicraft(&["fmt", file.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(&file).unwrap(), FORMATTED);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
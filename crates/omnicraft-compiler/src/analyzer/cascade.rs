@@ -0,0 +1,344 @@
+//! Style Cascade
+//!
+//! Resolves the rules of the `<style>` section onto the elements of the
+//! template at compile time. Styles are scoped: a rule only matches the
+//! elements of the component that declares it, never those rendered by a
+//! child component.
+//!
+//! Elements whose classes or id can change at runtime, and the elements
+//! nested in them, keep their `Style` properties for the runtime
+//! stylesheet; only `font-*` properties are resolved for them here.
+
+use crate::ast::{
+    Attribute, AttributeValue, Component, CompoundSelector, CssDeclaration, CssRule, Directive,
+    DirectiveName, ElementTag, Literal, Node, NodeKind, Selector,
+};
+use crate::lexer::{Lexer, TokenKind};
+
+/// CSS properties the runtime understands, with the attribute they set
+const PROPERTIES: [(&str, &str); 7] = [
+    ("fill", "fill"),
+    ("stroke", "stroke"),
+    ("stroke-width", "strokeWidth"),
+    ("opacity", "opacity"),
+    ("font-size", "fontSize"),
+    ("font-family", "fontFamily"),
+    ("font-weight", "fontWeight"),
+];
+
+/// Properties of the runtime `Style`, which the runtime stylesheet
+/// resolves for elements with dynamic classes
+pub const RUNTIME_PROPERTIES: [&str; 4] = ["fill", "stroke", "stroke-width", "opacity"];
+
+/// What a selector can see of an element
+#[derive(Debug, Clone)]
+struct Subject {
+    tag: ElementTag,
+    id: Option<String>,
+    classes: Vec<String>,
+    /// Whether the element or an ancestor has dynamic classes or id
+    dynamic: bool,
+}
+
+/// Whether an element's classes or id can change at runtime
+pub fn has_dynamic_classes(attributes: &[Attribute], directives: &[Directive]) -> bool {
+    directives.iter().any(|directive| directive.name == DirectiveName::Class)
+        || attributes.iter().any(|attr| {
+            (attr.name == "class" || attr.name == "id") && !matches!(attr.value, AttributeValue::Static(_))
+        })
+}
+
+/// Whether any element of `nodes` has dynamic classes or id, outside
+/// child components
+pub fn needs_runtime_styles(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| match &node.kind {
+        NodeKind::Element { tag: ElementTag::Component(_), .. } => false,
+        NodeKind::Element { attributes, directives, children, .. } => {
+            has_dynamic_classes(attributes, directives) || needs_runtime_styles(children)
+        }
+        NodeKind::IfBlock { then_branch, else_branch, .. } => {
+            needs_runtime_styles(then_branch) || else_branch.as_deref().is_some_and(needs_runtime_styles)
+        }
+        NodeKind::EachBlock { body, .. } => needs_runtime_styles(body),
+        NodeKind::AwaitBlock { pending, then_branch, catch_branch, .. } => {
+            needs_runtime_styles(pending)
+                || [then_branch, catch_branch]
+                    .into_iter()
+                    .flatten()
+                    .any(|branch| needs_runtime_styles(&branch.body))
+        }
+        _ => false,
+    })
+}
+
+/// Apply the stylesheet of `component` to the elements of its template
+///
+/// Matching rules apply in order of specificity, then source order.
+/// Attributes written on an element win over the stylesheet, the way
+/// inline styles do in CSS.
+pub fn apply_styles(component: &Component) -> Component {
+    let mut component = component.clone();
+    if let Some(rules) = component.style.as_ref().map(|style| style.rules.clone()) {
+        cascade(&mut component.template.children, &rules, &mut Vec::new());
+    }
+    component
+}
+
+fn cascade(nodes: &mut [Node], rules: &[CssRule], ancestors: &mut Vec<Subject>) {
+    for node in nodes {
+        match &mut node.kind {
+            NodeKind::Element { tag, attributes, children, directives, .. } => {
+                let mut subject = subject(tag, attributes);
+                subject.dynamic = has_dynamic_classes(attributes, directives)
+                    || ancestors.last().is_some_and(|parent| parent.dynamic);
+                if !matches!(tag, ElementTag::Component(_)) {
+                    apply_rules(attributes, rules, &subject, ancestors);
+                }
+
+                ancestors.push(subject);
+                cascade(children, rules, ancestors);
+                ancestors.pop();
+            }
+            // Blocks are transparent to selectors
+            NodeKind::IfBlock { then_branch, else_branch, .. } => {
+                cascade(then_branch, rules, ancestors);
+                if let Some(else_branch) = else_branch {
+                    cascade(else_branch, rules, ancestors);
+                }
+            }
+            NodeKind::EachBlock { body, .. } => cascade(body, rules, ancestors),
+            NodeKind::AwaitBlock { pending, then_branch, catch_branch, .. } => {
+                cascade(pending, rules, ancestors);
+                for branch in [then_branch, catch_branch].into_iter().flatten() {
+                    cascade(&mut branch.body, rules, ancestors);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn subject(tag: &ElementTag, attributes: &[Attribute]) -> Subject {
+    let static_value = |name: &str| {
+        attributes.iter().find_map(|attr| match &attr.value {
+            AttributeValue::Static(Literal::String(value)) if attr.name == name => Some(value.clone()),
+            _ => None,
+        })
+    };
+
+    Subject {
+        tag: tag.clone(),
+        id: static_value("id"),
+        classes: static_value("class")
+            .map(|classes| classes.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+        dynamic: false,
+    }
+}
+
+fn apply_rules(
+    attributes: &mut Vec<Attribute>,
+    rules: &[CssRule],
+    subject: &Subject,
+    ancestors: &[Subject],
+) {
+    let mut matched: Vec<_> = rules
+        .iter()
+        .enumerate()
+        .filter_map(|(order, rule)| {
+            rule.selectors
+                .iter()
+                .filter(|selector| selector_matches(selector, subject, ancestors))
+                .map(Selector::specificity)
+                .max()
+                .map(|specificity| (specificity, order, rule))
+        })
+        .collect();
+    matched.sort_by_key(|(specificity, order, _)| (*specificity, *order));
+
+    // Later declarations override earlier ones
+    let mut resolved: Vec<(&str, &CssDeclaration)> = Vec::new();
+    for declaration in matched.iter().flat_map(|(_, _, rule)| &rule.declarations) {
+        let Some((_, name)) = PROPERTIES.iter().find(|(property, _)| *property == declaration.property) else {
+            continue;
+        };
+        if subject.dynamic && RUNTIME_PROPERTIES.contains(&declaration.property.as_str()) {
+            continue;
+        }
+        resolved.retain(|(existing, _)| existing != name);
+        resolved.push((name, declaration));
+    }
+
+    for (name, declaration) in resolved {
+        if attributes.iter().any(|attr| attr.name == name) {
+            continue;
+        }
+        attributes.push(Attribute {
+            name: name.to_string(),
+            value: AttributeValue::Static(css_value(&declaration.value)),
+            span: declaration.span,
+        });
+    }
+}
+
+/// The last compound matches the element, earlier ones match ancestors
+/// from the inside out
+fn selector_matches(selector: &Selector, subject: &Subject, ancestors: &[Subject]) -> bool {
+    let Some((last, rest)) = selector.compounds.split_last() else {
+        return false;
+    };
+    if !compound_matches(last, subject) {
+        return false;
+    }
+
+    let mut ancestors = ancestors.iter().rev();
+    rest.iter()
+        .rev()
+        .all(|compound| ancestors.any(|ancestor| compound_matches(compound, ancestor)))
+}
+
+fn compound_matches(compound: &CompoundSelector, subject: &Subject) -> bool {
+    compound
+        .tag
+        .as_deref()
+        .is_none_or(|tag| ElementTag::from_str(tag).as_ref() == Some(&subject.tag))
+        && compound.id.as_ref().is_none_or(|id| subject.id.as_ref() == Some(id))
+        && compound.classes.iter().all(|class| subject.classes.contains(class))
+}
+
+/// A lone number, dimension or string keeps its type; anything else is
+/// passed on as written
+pub fn css_value(value: &str) -> Literal {
+    match Lexer::new(value).tokenize().as_deref() {
+        Ok([token]) => match &token.kind {
+            TokenKind::Number(n) => Literal::Number(*n),
+            TokenKind::Dimension((n, unit)) => Literal::Dimension(*n, *unit),
+            TokenKind::StringLiteral(s) | TokenKind::StringLiteralSingle(s) => Literal::String(s.clone()),
+            _ => Literal::String(value.to_string()),
+        },
+        _ => Literal::String(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn styled(source: &str) -> Component {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let component = Parser::new(tokens, "test.omni").parse().unwrap();
+        apply_styles(&component)
+    }
+
+    fn attributes(node: &Node) -> &[Attribute] {
+        match &node.kind {
+            NodeKind::Element { attributes, .. } => attributes,
+            _ => panic!("expected element"),
+        }
+    }
+
+    fn attribute<'a>(node: &'a Node, name: &str) -> Option<&'a AttributeValue> {
+        attributes(node).iter().find(|attr| attr.name == name).map(|attr| &attr.value)
+    }
+
+    #[test]
+    fn test_cascade_specificity_and_order() {
+        let component = styled(
+            r#"<canvas>
+  <circle class="dot big" id="main" />
+  <circle class="dot" fill="green" />
+  <rect />
+</canvas>
+
+<style>
+  #main { fill: gold; }
+  .dot.big { fill: red; stroke-width: 2; }
+  circle { fill: blue; opacity: 0.5; }
+  .dot { stroke: black; }
+  circle { opacity: 1; font-family: "Fira Sans"; }
+</style>"#,
+        );
+        let nodes = &component.template.children;
+
+        let fill = |node| attribute(node, "fill").cloned();
+        assert_eq!(fill(&nodes[0]), Some(AttributeValue::Static(Literal::String("gold".into()))));
+        assert_eq!(
+            attribute(&nodes[0], "strokeWidth"),
+            Some(&AttributeValue::Static(Literal::Number(2.0)))
+        );
+        assert_eq!(
+            attribute(&nodes[0], "opacity"),
+            Some(&AttributeValue::Static(Literal::Number(1.0)))
+        );
+        assert_eq!(
+            attribute(&nodes[0], "fontFamily"),
+            Some(&AttributeValue::Static(Literal::String("Fira Sans".into())))
+        );
+
+        // An explicit attribute wins over the stylesheet
+        assert_eq!(fill(&nodes[1]), Some(AttributeValue::Static(Literal::String("green".into()))));
+        assert!(attribute(&nodes[1], "stroke").is_some());
+        assert_eq!(attributes(&nodes[1]).iter().filter(|attr| attr.name == "fill").count(), 1);
+
+        assert!(attributes(&nodes[2]).is_empty());
+    }
+
+    #[test]
+    fn test_cascade_descendants_and_blocks() {
+        let component = styled(
+            r#"<canvas>
+  <group class="legend">
+    {#if visible}
+      <text>Legend</text>
+    {/if}
+  </group>
+  <text>Title</text>
+  <Badge />
+</canvas>
+
+<style>
+  .legend text { font-size: 12px; text-anchor: middle; }
+  text { font-weight: bold; }
+  Badge { fill: red; }
+</style>"#,
+        );
+        let nodes = &component.template.children;
+
+        let NodeKind::Element { children, .. } = &nodes[0].kind else { panic!("expected group") };
+        let NodeKind::IfBlock { then_branch, .. } = &children[0].kind else { panic!("expected if") };
+        assert_eq!(
+            attribute(&then_branch[0], "fontSize"),
+            Some(&AttributeValue::Static(Literal::Dimension(12.0, crate::ast::Unit::Px)))
+        );
+        assert!(attribute(&then_branch[0], "fontWeight").is_some());
+
+        assert!(attribute(&nodes[1], "fontSize").is_none());
+        assert!(attribute(&nodes[1], "fontWeight").is_some());
+
+        // Child components are styled by their own stylesheet
+        assert!(attributes(&nodes[2]).is_empty());
+    }
+
+    #[test]
+    fn test_cascade_leaves_dynamic_classes_to_runtime() {
+        let component = styled(
+            r#"<canvas>
+  <group class:active={selected()}>
+    <text>Label</text>
+  </group>
+</canvas>
+
+<style>
+  text { fill: red; font-size: 12px; }
+</style>"#,
+        );
+        assert!(needs_runtime_styles(&component.template.children));
+
+        let NodeKind::Element { children, .. } = &component.template.children[0].kind else {
+            panic!("expected group")
+        };
+        assert!(attribute(&children[0], "fill").is_none());
+        assert!(attribute(&children[0], "fontSize").is_some());
+    }
+}
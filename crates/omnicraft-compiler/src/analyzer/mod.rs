@@ -5,23 +5,35 @@
 //! - Build scope tree
 //! - Infer types
 
+pub mod cascade;
 pub mod dependency;
 pub mod scope;
 pub mod types;
 
-use crate::ast::{Component, Expression, Node, ReactiveKind, Statement};
-use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use crate::ast::{
+    Component, Directive, DirectiveName, ElementTag, Expression, ExpressionKind, Literal, Node, NodeKind,
+    ObjectProperty, Pattern, PropertyKey, ReactiveKind, Statement, StatementKind, Unit, VarKind,
+};
+use anyhow::{anyhow, bail, Result};
 use tracing::{instrument, debug, trace};
 
 pub use dependency::DependencyGraph;
 pub use scope::{Scope, ScopeKind, Symbol};
 pub use types::{InferredType, TypeContext};
 
+/// Properties a `bind:` directive can target, with the type of signal
+/// they need
+pub const BINDABLE_PROPERTIES: [(&str, InferredType); 4] = [
+    ("x", InferredType::Number),
+    ("y", InferredType::Number),
+    ("rotation", InferredType::Number),
+    ("text", InferredType::String),
+];
+
 /// Analyzed component with semantic information
 #[derive(Debug, Clone)]
 pub struct AnalyzedComponent {
-    /// Original component
+    /// Original component, with its stylesheet applied to the template
     pub component: Component,
     /// Scope tree
     pub root_scope: Scope,
@@ -51,6 +63,14 @@ impl Analyzer {
     #[instrument(skip(self), fields(component = %component.name))]
     pub fn analyze(&mut self, component: &Component) -> Result<AnalyzedComponent> {
         debug!("Starting analysis");
+        if let Some(ref style) = component.style {
+            for declaration in style.rules.iter().flat_map(|rule| &rule.declarations) {
+                if matches!(cascade::css_value(&declaration.value), Literal::Dimension(_, Unit::Percent)) {
+                    check_percent(Some(&declaration.property))?;
+                }
+            }
+        }
+
         // 1. Analyze script section (variables, functions)
         if let Some(ref script) = component.script {
             for stmt in &script.statements {
@@ -61,8 +81,9 @@ impl Analyzer {
         // 2. Analyze template section (element bindings)
         self.analyze_template(&component.template)?;
 
+        // 3. Resolve the stylesheet onto the elements it matches
         Ok(AnalyzedComponent {
-            component: component.clone(),
+            component: cascade::apply_styles(component),
             root_scope: self.scope_stack.first().cloned().unwrap_or_default(),
             dependencies: self.dependencies.clone(),
             types: self.types.clone(),
@@ -72,26 +93,39 @@ impl Analyzer {
     #[instrument(skip(self))]
     fn analyze_statement(&mut self, stmt: &Statement) -> Result<()> {
         trace!("Analyzing statement");
-        match stmt {
-            Statement::VariableDeclaration {
+        match &stmt.kind {
+            StatementKind::VariableDeclaration {
                 name,
                 init,
                 reactive,
                 ..
             } => {
                 // Infer type from initializer
-                let inferred_type = if let Some(expr) = init {
+                let init_type = if let Some(expr) = init {
                     self.infer_expression_type(expr)
                 } else {
                     InferredType::Unknown
                 };
 
+                // An annotation wins over the initializer, which must match it
+                let inferred_type = match stmt.kind.declared_type().map(InferredType::from) {
+                    Some(declared) => {
+                        check_initializer(name, &declared, init_type.inner_type())?;
+                        match reactive {
+                            ReactiveKind::Signal => InferredType::Signal(Box::new(declared)),
+                            ReactiveKind::None => declared,
+                            _ => init_type,
+                        }
+                    }
+                    None => init_type,
+                };
+
                 // Register in scope
                 let symbol = Symbol {
                     name: name.clone(),
                     ty: inferred_type.clone(),
                     reactive: *reactive,
-                    mutable: matches!(stmt, Statement::VariableDeclaration { kind, .. } if *kind == crate::ast::VarKind::Let),
+                    mutable: matches!(&stmt.kind, StatementKind::VariableDeclaration { kind, .. } if *kind == crate::ast::VarKind::Let),
                 };
                 self.current_scope_mut().add_symbol(symbol);
 
@@ -108,9 +142,15 @@ impl Analyzer {
                 self.types.set(name.clone(), inferred_type);
             }
 
-            Statement::FunctionDeclaration { name, body, .. } => {
+            StatementKind::Destructure { kind, pattern, init } => {
+                self.analyze_expression(init, None)?;
+                self.declare_pattern(pattern, *kind == VarKind::Let);
+            }
+
+            StatementKind::FunctionDeclaration { name, params, body, .. } => {
                 // Enter function scope
                 self.push_scope(ScopeKind::Function);
+                self.declare_params(params);
 
                 for stmt in body {
                     self.analyze_statement(stmt)?;
@@ -129,7 +169,7 @@ impl Analyzer {
                 self.current_scope_mut().add_symbol(symbol);
             }
 
-            Statement::If { condition, then_branch, else_branch } => {
+            StatementKind::If { condition, then_branch, else_branch } => {
                 self.analyze_expression(condition, None)?;
                 
                 self.push_scope(ScopeKind::Block);
@@ -147,11 +187,66 @@ impl Analyzer {
                 }
             }
 
-            Statement::Return(Some(expr)) => {
+            StatementKind::For { init, condition, update, body } => {
+                // Bindings in the header are scoped to the loop
+                self.push_scope(ScopeKind::Block);
+                if let Some(init) = init {
+                    self.analyze_statement(init)?;
+                }
+                if let Some(condition) = condition {
+                    self.analyze_expression(condition, None)?;
+                }
+                if let Some(update) = update {
+                    self.analyze_expression(update, None)?;
+                }
+                self.analyze_block(body)?;
+                self.pop_scope();
+            }
+
+            StatementKind::While { condition, body } => {
+                self.analyze_expression(condition, None)?;
+                self.analyze_block(body)?;
+            }
+
+            StatementKind::Block(body) => self.analyze_block(body)?,
+
+            StatementKind::Export(declaration) => {
+                if self.scope_stack.len() > 1 {
+                    bail!("`export` is only allowed at the top level of a script");
+                }
+                if let StatementKind::VariableDeclaration {
+                    kind: VarKind::Let,
+                    name,
+                    reactive,
+                    ..
+                } = &declaration.kind
+                    && *reactive != ReactiveKind::None
+                {
+                    bail!("Prop `{}` cannot be initialized with a reactive primitive", name);
+                }
+                self.analyze_statement(declaration)?;
+            }
+
+            StatementKind::Import(import) => {
+                if self.scope_stack.len() > 1 {
+                    bail!("`import` is only allowed at the top level of a script");
+                }
+                for specifier in &import.specifiers {
+                    let symbol = Symbol {
+                        name: specifier.local().to_string(),
+                        ty: InferredType::Unknown,
+                        reactive: ReactiveKind::None,
+                        mutable: false,
+                    };
+                    self.current_scope_mut().add_symbol(symbol);
+                }
+            }
+
+            StatementKind::Return(Some(expr)) => {
                 self.analyze_expression(expr, None)?;
             }
 
-            Statement::Expression(expr) => {
+            StatementKind::Expression(expr) => {
                 self.analyze_expression(expr, None)?;
             }
 
@@ -161,28 +256,38 @@ impl Analyzer {
         Ok(())
     }
 
+    /// Analyze statements in their own block scope
+    fn analyze_block(&mut self, statements: &[Statement]) -> Result<()> {
+        self.push_scope(ScopeKind::Block);
+        for stmt in statements {
+            self.analyze_statement(stmt)?;
+        }
+        self.pop_scope();
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     fn analyze_expression(&mut self, expr: &Expression, context: Option<&str>) -> Result<()> {
-        match expr {
-            Expression::Identifier(name) => {
+        match &expr.kind {
+            ExpressionKind::Identifier(name) => {
                 // Check if this is a signal access
-                if self.dependencies.is_signal(name) {
-                    if let Some(ctx) = context {
-                        self.dependencies.add_dependency(ctx.to_string(), name.clone());
-                    }
+                if self.dependencies.is_signal(name)
+                    && let Some(ctx) = context
+                {
+                    self.dependencies.add_dependency(ctx.to_string(), name.clone());
                 }
             }
 
-            Expression::Call { callee, args } => {
+            ExpressionKind::Call { callee, args, .. } => {
                 // Check for signal() calls
-                if let Expression::Identifier(name) = callee.as_ref() {
-                    if name == "signal" || name == "memo" || name == "effect" {
-                        // Reactive primitive call
-                        for arg in args {
-                            self.analyze_expression(arg, context)?;
-                        }
-                        return Ok(());
+                if let ExpressionKind::Identifier(name) = &callee.kind
+                    && (name == "signal" || name == "memo" || name == "effect")
+                {
+                    // Reactive primitive call
+                    for arg in args {
+                        self.analyze_expression(arg, context)?;
                     }
+                    return Ok(());
                 }
 
                 // Check for signal.get() or signal() accessor
@@ -192,31 +297,43 @@ impl Analyzer {
                 }
             }
 
-            Expression::Binary { left, right, .. } => {
+            ExpressionKind::Binary { left, right, .. } => {
                 self.analyze_expression(left, context)?;
                 self.analyze_expression(right, context)?;
             }
 
-            Expression::Member { object, .. } => {
+            ExpressionKind::Member { object, .. } => {
                 self.analyze_expression(object, context)?;
             }
 
-            Expression::Arrow { body, .. } => {
+            ExpressionKind::Assign { target, value, .. } => {
+                self.check_assignable(target)?;
+                self.analyze_expression(target, context)?;
+                self.analyze_expression(value, context)?;
+            }
+
+            ExpressionKind::Update { target, .. } => {
+                self.check_assignable(target)?;
+                self.analyze_expression(target, context)?;
+            }
+
+            ExpressionKind::Arrow { params, body, .. } => {
                 self.push_scope(ScopeKind::Function);
+                self.declare_params(params);
                 match body {
                     crate::ast::ArrowBody::Expression(expr) => {
                         self.analyze_expression(expr, context)?;
                     }
                     crate::ast::ArrowBody::Block(stmts) => {
                         for stmt in stmts {
-                            self.analyze_statement(&stmt)?;
+                            self.analyze_statement(stmt)?;
                         }
                     }
                 }
                 self.pop_scope();
             }
 
-            Expression::Template { parts } => {
+            ExpressionKind::Template { parts } => {
                 for part in parts {
                     if let crate::ast::TemplatePart::Expression(expr) = part {
                         self.analyze_expression(expr, context)?;
@@ -224,24 +341,38 @@ impl Analyzer {
                 }
             }
 
-            Expression::Ternary { condition, then_expr, else_expr } => {
+            ExpressionKind::Ternary { condition, then_expr, else_expr } => {
                 self.analyze_expression(condition, context)?;
                 self.analyze_expression(then_expr, context)?;
                 self.analyze_expression(else_expr, context)?;
             }
 
-            Expression::Array(items) => {
+            ExpressionKind::Array(items) => {
                 for item in items {
                     self.analyze_expression(item, context)?;
                 }
             }
 
-            Expression::Object(props) => {
-                for (_, value) in props {
-                    self.analyze_expression(value, context)?;
+            ExpressionKind::Object(props) => {
+                for prop in props {
+                    match prop {
+                        ObjectProperty::KeyValue { key, value } => {
+                            if let PropertyKey::Computed(key) = key {
+                                self.analyze_expression(key, context)?;
+                            }
+                            self.analyze_expression(value, context)?;
+                        }
+                        ObjectProperty::Spread(expr) => self.analyze_expression(expr, context)?,
+                    }
                 }
             }
 
+            ExpressionKind::Spread(expr) => {
+                self.analyze_expression(expr, context)?;
+            }
+
+            ExpressionKind::Literal(Literal::Dimension(_, Unit::Percent)) => check_percent(context)?,
+
             _ => {}
         }
 
@@ -257,35 +388,76 @@ impl Analyzer {
 
     #[instrument(skip(self))]
     fn analyze_node(&mut self, node: &Node) -> Result<()> {
-        match node {
-            Node::Element { attributes, children, directives, .. } => {
+        match &node.kind {
+            NodeKind::Element { tag, attributes, children, directives, key } => {
+                if let Some(key) = key {
+                    if let ElementTag::Component(name) = tag {
+                        bail!("`key` needs an element, but `<{}>` is a component", name);
+                    }
+                    self.analyze_expression(key, None)?;
+                }
+
                 // Analyze dynamic attributes
                 for attr in attributes {
-                    if let crate::ast::AttributeValue::Dynamic(expr) = &attr.value {
-                        self.analyze_expression(expr, Some(&attr.name))?;
+                    match &attr.value {
+                        crate::ast::AttributeValue::Dynamic(expr) => {
+                            self.analyze_expression(expr, Some(&attr.name))?;
+                        }
+                        crate::ast::AttributeValue::Spread(expr) => self.analyze_expression(expr, None)?,
+                        crate::ast::AttributeValue::Static(Literal::Dimension(_, Unit::Percent)) => {
+                            check_percent(Some(&attr.name))?
+                        }
+                        _ => {}
                     }
                 }
 
                 // Analyze directives
                 for directive in directives {
+                    match directive.name {
+                        DirectiveName::Bind => self.check_binding(directive)?,
+                        DirectiveName::Ref => self.check_ref(directive)?,
+                        DirectiveName::Use => self.check_action(directive)?,
+                        // Declares a name rather than reading one
+                        DirectiveName::Let => continue,
+                        _ => {}
+                    }
                     self.analyze_expression(&directive.value, None)?;
                 }
 
-                // Recurse into children
+                // Recurse into children; the content nested in a component
+                // sees the props of the slots it fills
+                let slot_props = match tag {
+                    ElementTag::Component(_) => slot_props(directives, children),
+                    _ => Vec::new(),
+                };
+                self.push_scope(ScopeKind::Block);
+                self.declare_names(&slot_props);
                 for child in children {
                     self.analyze_node(child)?;
                 }
+                self.pop_scope();
+            }
+
+            NodeKind::Slot { props, fallback, .. } => {
+                for prop in props {
+                    if let crate::ast::AttributeValue::Dynamic(expr) = &prop.value {
+                        self.analyze_expression(expr, Some(&prop.name))?;
+                    }
+                }
+                for child in fallback {
+                    self.analyze_node(child)?;
+                }
             }
 
-            Node::Text { content } => {
+            NodeKind::Text { content } => {
                 self.analyze_expression(content, None)?;
             }
 
-            Node::Expression { expr } => {
+            NodeKind::Expression { expr } => {
                 self.analyze_expression(expr, None)?;
             }
 
-            Node::IfBlock { condition, then_branch, else_branch } => {
+            NodeKind::IfBlock { condition, then_branch, else_branch } => {
                 self.analyze_expression(condition, None)?;
 
                 for child in then_branch {
@@ -299,12 +471,34 @@ impl Analyzer {
                 }
             }
 
-            Node::EachBlock { expression, body, .. } => {
+            NodeKind::EachBlock { expression, binding, index, body, key } => {
                 self.analyze_expression(expression, None)?;
 
+                self.push_scope(ScopeKind::Block);
+                self.declare_names(std::iter::once(binding).chain(index));
+                if let Some(key) = key {
+                    self.analyze_expression(key, None)?;
+                }
                 for child in body {
                     self.analyze_node(child)?;
                 }
+                self.pop_scope();
+            }
+
+            NodeKind::AwaitBlock { promise, pending, then_branch, catch_branch } => {
+                self.analyze_expression(promise, None)?;
+
+                for child in pending {
+                    self.analyze_node(child)?;
+                }
+                for branch in then_branch.iter().chain(catch_branch) {
+                    self.push_scope(ScopeKind::Block);
+                    self.declare_names(&branch.binding);
+                    for child in &branch.body {
+                        self.analyze_node(child)?;
+                    }
+                    self.pop_scope();
+                }
             }
 
             _ => {}
@@ -314,19 +508,21 @@ impl Analyzer {
     }
 
     fn infer_expression_type(&self, expr: &Expression) -> InferredType {
-        match expr {
-            Expression::Literal(lit) => match lit {
+        match &expr.kind {
+            ExpressionKind::Literal(lit) => match lit {
                 crate::ast::Literal::String(_) => InferredType::String,
-                crate::ast::Literal::Number(_) => InferredType::Number,
+                crate::ast::Literal::Number(_) | crate::ast::Literal::Dimension(..) => {
+                    InferredType::Number
+                }
                 crate::ast::Literal::Boolean(_) => InferredType::Boolean,
                 crate::ast::Literal::Null => InferredType::Null,
             },
-            Expression::Call { callee, args } => {
-                if let Expression::Identifier(name) = callee.as_ref() {
-                    if name == "signal" {
-                        if let Some(first_arg) = args.first() {
-                            return InferredType::Signal(Box::new(self.infer_expression_type(first_arg)));
-                        }
+            ExpressionKind::Call { callee, args, .. } => {
+                if let ExpressionKind::Identifier(name) = &callee.kind {
+                    if name == "signal"
+                        && let Some(first_arg) = args.first()
+                    {
+                        return InferredType::Signal(Box::new(self.infer_expression_type(first_arg)));
                     }
                     if name == "memo" {
                         return InferredType::Memo;
@@ -334,13 +530,114 @@ impl Analyzer {
                 }
                 InferredType::Unknown
             }
-            Expression::Arrow { .. } => InferredType::Function,
-            Expression::Array(_) => InferredType::Array,
-            Expression::Object(_) => InferredType::Object,
+            ExpressionKind::Arrow { .. } => InferredType::Function,
+            ExpressionKind::Template { .. } => InferredType::String,
+            ExpressionKind::Array(_) => InferredType::Array,
+            ExpressionKind::Object(_) => InferredType::Object,
             _ => InferredType::Unknown,
         }
     }
 
+    /// Register parameters in the current scope; they shadow outer bindings
+    fn declare_params(&mut self, params: &[crate::ast::Parameter]) {
+        for param in params {
+            match (&param.pattern, &param.ty) {
+                (Pattern::Identifier(name), Some(ty)) => self.current_scope_mut().add_symbol(Symbol {
+                    name: name.clone(),
+                    ty: ty.into(),
+                    reactive: ReactiveKind::None,
+                    mutable: true,
+                }),
+                (pattern, _) => self.declare_pattern(pattern, true),
+            }
+        }
+    }
+
+    /// Register every name a pattern binds in the current scope
+    fn declare_pattern(&mut self, pattern: &Pattern, mutable: bool) {
+        for name in pattern.bound_names() {
+            self.current_scope_mut().add_symbol(Symbol {
+                name: name.to_string(),
+                ty: InferredType::Unknown,
+                reactive: ReactiveKind::None,
+                mutable,
+            });
+        }
+    }
+
+    /// Register constant template bindings, such as `{#each}` items
+    fn declare_names<'a>(&mut self, names: impl IntoIterator<Item = &'a String>) {
+        for name in names {
+            self.current_scope_mut().add_symbol(Symbol {
+                name: name.clone(),
+                ty: InferredType::Unknown,
+                reactive: ReactiveKind::None,
+                mutable: false,
+            });
+        }
+    }
+
+    /// Reject assignments to `const` bindings
+    fn check_assignable(&self, target: &Expression) -> Result<()> {
+        if let ExpressionKind::Identifier(name) = &target.kind
+            && let Some(symbol) = self.lookup(name)
+            && !symbol.mutable
+        {
+            bail!("Assignment to constant `{}`", name);
+        }
+        Ok(())
+    }
+
+    /// Reject `bind:` directives that don't bind a bindable property to a
+    /// writable signal of the right type
+    fn check_binding(&self, directive: &Directive) -> Result<()> {
+        let property = directive.arg.as_deref().unwrap_or_default();
+        let Some((_, expected)) = BINDABLE_PROPERTIES.iter().find(|(name, _)| *name == property) else {
+            bail!("`{}` cannot be bound with `bind:`", property);
+        };
+        let ExpressionKind::Identifier(name) = &directive.value.kind else {
+            bail!("`bind:{}` needs a signal, not an expression", property);
+        };
+        let symbol = self
+            .lookup(name)
+            .filter(|symbol| symbol.reactive == ReactiveKind::Signal)
+            .ok_or_else(|| anyhow!("`bind:{}` needs a writable signal, but `{}` is not one", property, name))?;
+        let held = symbol.ty.inner_type();
+        if *held != InferredType::Unknown && held != expected {
+            bail!("`bind:{}` needs a {} signal, but `{}` holds a {}", property, expected, name, held);
+        }
+        Ok(())
+    }
+
+    /// Reject `ref={...}` directives that don't name a `let` variable to
+    /// hold the entity
+    fn check_ref(&self, directive: &Directive) -> Result<()> {
+        let ExpressionKind::Identifier(name) = &directive.value.kind else {
+            bail!("`ref` needs a variable, not an expression");
+        };
+        match self.lookup(name) {
+            Some(symbol) if symbol.mutable && symbol.reactive == ReactiveKind::None => Ok(()),
+            _ => bail!("`ref` needs a `let` variable, but `{}` is not one", name),
+        }
+    }
+
+    /// Reject `use:` directives naming something other than a function;
+    /// names that aren't declared, or are imported, have no known type
+    fn check_action(&self, directive: &Directive) -> Result<()> {
+        let action = directive.arg.as_deref().unwrap_or_default();
+        match self.lookup(action) {
+            Some(symbol) if !matches!(symbol.ty, InferredType::Function | InferredType::Unknown) => {
+                bail!("`use:{}` needs an action function, but `{}` is a {}", action, action, symbol.ty)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Find the innermost symbol named `name`
+    fn lookup(&self, name: &str) -> Option<&Symbol> {
+        self.scope_stack.iter().rev().find_map(|scope| scope.get_symbol(name))
+    }
+
     fn current_scope_mut(&mut self) -> &mut Scope {
         self.scope_stack.last_mut().expect("scope stack is empty")
     }
@@ -356,6 +653,45 @@ impl Analyzer {
     }
 }
 
+/// Names bound by the `let:` directives of a component and the content
+/// nested in it
+fn slot_props(directives: &[Directive], children: &[Node]) -> Vec<String> {
+    let child_directives = children.iter().flat_map(|child| match &child.kind {
+        NodeKind::Element { directives, .. } => directives.as_slice(),
+        _ => &[],
+    });
+    directives
+        .iter()
+        .chain(child_directives)
+        .filter(|directive| directive.name == DirectiveName::Let)
+        .filter_map(|directive| match &directive.value.kind {
+            ExpressionKind::Identifier(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Reject a percentage outside `opacity`, the one property whose base
+/// is known at compile time
+fn check_percent(property: Option<&str>) -> Result<()> {
+    match property {
+        Some("opacity") => Ok(()),
+        Some(property) => bail!("`{}` cannot be a percentage, since it has no size to be relative to", property),
+        None => bail!("A percentage is only allowed as an `opacity`"),
+    }
+}
+
+/// Reject an initializer whose type contradicts the declared type
+fn check_initializer(name: &str, declared: &InferredType, init: &InferredType) -> Result<()> {
+    let is_primitive = |ty: &InferredType| {
+        matches!(ty, InferredType::Number | InferredType::String | InferredType::Boolean)
+    };
+    if is_primitive(declared) && is_primitive(init) && declared != init {
+        bail!("`{}` is declared as {} but initialized with a {}", name, declared, init);
+    }
+    Ok(())
+}
+
 impl Default for Analyzer {
     fn default() -> Self {
         Self::new()
@@ -405,4 +741,132 @@ mod tests {
         let analyzed = analyze(&component).unwrap();
         assert!(analyzed.dependencies.is_signal("count"));
     }
+
+    #[test]
+    fn test_analyze_assignments() {
+        let source = r##"
+<script>
+  let total = 0;
+  for (let i = 0; i < 10; i++) {
+    total += i;
+  }
+</script>
+
+<canvas width={800} height={600}>
+</canvas>
+"##;
+        assert!(analyze(&parse(source)).is_ok());
+
+        let source = r##"
+<script>
+  const limit = 10;
+  while (true) {
+    limit = 5;
+  }
+</script>
+
+<canvas width={800} height={600}>
+</canvas>
+"##;
+        let error = analyze(&parse(source)).unwrap_err();
+        assert!(error.to_string().contains("limit"));
+    }
+
+    #[test]
+    fn test_analyze_annotations() {
+        let source = r##"
+<script>
+  const items = signal<string[]>([]);
+  let total: number;
+  function label(count: number): string {
+    return `${count}`;
+  }
+</script>
+
+<canvas width={800} height={600}>
+</canvas>
+"##;
+        let analyzed = analyze(&parse(source)).unwrap();
+        assert_eq!(
+            analyzed.types.get("items"),
+            Some(&InferredType::Signal(Box::new(InferredType::Array)))
+        );
+        assert_eq!(analyzed.types.get("total"), Some(&InferredType::Number));
+
+        let source = r##"
+<script>
+  const count = signal<number>("zero");
+</script>
+
+<canvas width={800} height={600}>
+</canvas>
+"##;
+        let error = analyze(&parse(source)).unwrap_err();
+        assert!(error.to_string().contains("declared as number"));
+    }
+
+    #[test]
+    fn test_analyze_bindings() {
+        let script = r##"
+<script>
+  const posX = signal(0);
+  const label = signal("Title");
+  const doubled = memo(() => posX() * 2);
+  const fixed = 5;
+</script>
+"##;
+        let check = |template: &str| analyze(&parse(&format!("{}\n<canvas>{}</canvas>", script, template)));
+
+        assert!(check("<text bind:x={posX} bind:text={label} />").is_ok());
+
+        let error = |template: &str| check(template).unwrap_err().to_string();
+        assert!(error("<circle bind:x={doubled} />").contains("writable signal"));
+        assert!(error("<circle bind:x={fixed} />").contains("writable signal"));
+        assert!(error("<circle bind:x={posX() + 1} />").contains("not an expression"));
+        assert!(error("<circle bind:fill={label} />").contains("cannot be bound"));
+        assert!(error("<text bind:text={posX} />").contains("needs a string signal"));
+    }
+
+    #[test]
+    fn test_analyze_refs_and_actions() {
+        let script = r##"
+<script>
+  let ball;
+  const fixed = null;
+  const speed = signal(2);
+  function draggable(entity, options) {}
+</script>
+"##;
+        let check = |template: &str| analyze(&parse(&format!("{}\n<canvas>{}</canvas>", script, template)));
+
+        assert!(check("<circle ref={ball} use:draggable={speed()} use:imported />").is_ok());
+
+        let error = |template: &str| check(template).unwrap_err().to_string();
+        assert!(error("<circle ref={fixed} />").contains("`let` variable"));
+        assert!(error("<circle ref={speed} />").contains("`let` variable"));
+        assert!(error("<circle use:speed />").contains("action function"));
+    }
+
+    #[test]
+    fn test_analyze_slot_props() {
+        let script = "<script>\n  let item;\n</script>\n";
+        let check = |template: &str| analyze(&parse(&format!("{}<canvas>{}</canvas>", script, template)));
+
+        assert!(check("<List><circle ref={item} /></List>").is_ok());
+        assert!(check("<List><text slot=\"row\" let:index>{index}</text></List><circle ref={item} />").is_ok());
+        assert!(check("<slot item={item}><circle ref={item} /></slot>").is_ok());
+
+        // A slot prop shadows the script variable inside the component
+        let error = check("<List let:item><circle ref={item} /></List>").unwrap_err().to_string();
+        assert!(error.contains("`let` variable"));
+    }
+
+    #[test]
+    fn test_analyze_keys() {
+        let source = "<script>\n  const items = signal([]);\n</script>\n<canvas>{#each items() as item}<circle key={item.id} />{/each}</canvas>";
+        assert!(analyze(&parse(source)).is_ok());
+
+        let error = analyze(&parse("<canvas><Badge key={1} /></canvas>")).unwrap_err().to_string();
+        assert!(error.contains("`<Badge>` is a component"), "{}", error);
+    }
 }
@@ -2,6 +2,7 @@
 //!
 //! Infers types from expressions and tracks type information.
 
+use crate::ast::Type;
 use std::collections::HashMap;
 
 /// Inferred type
@@ -34,6 +35,22 @@ impl InferredType {
     }
 }
 
+impl From<&Type> for InferredType {
+    fn from(ty: &Type) -> Self {
+        match ty {
+            Type::Number => InferredType::Number,
+            Type::String => InferredType::String,
+            Type::Boolean => InferredType::Boolean,
+            Type::Array(_) => InferredType::Array,
+            Type::Object(_) => InferredType::Object,
+            Type::Function { .. } => InferredType::Function,
+            Type::Signal(inner) => InferredType::Signal(Box::new(inner.as_ref().into())),
+            Type::Custom(name) if name == "null" => InferredType::Null,
+            Type::Void | Type::Any | Type::Union(_) | Type::Custom(_) => InferredType::Unknown,
+        }
+    }
+}
+
 impl std::fmt::Display for InferredType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -101,4 +118,11 @@ mod tests {
         assert!(ctx.get("count").unwrap().is_reactive());
         assert!(!ctx.get("name").unwrap().is_reactive());
     }
+
+    #[test]
+    fn test_from_annotation() {
+        let ty = Type::Signal(Box::new(Type::Array(Box::new(Type::Custom("Color".to_string())))));
+        assert_eq!(InferredType::from(&ty), InferredType::Signal(Box::new(InferredType::Array)));
+        assert_eq!(InferredType::from(&Type::Any), InferredType::Unknown);
+    }
 }
@@ -1,7 +1,10 @@
 //! Abstract Syntax Tree (AST) for `.omni` components
 //!
 //! Represents the parsed structure of an OmniCraft component file.
+//! Statements, expressions, template nodes, attributes, directives and
+//! parameters carry the source span they were parsed from.
 
+use crate::lexer::Span;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -9,12 +12,23 @@ use std::collections::HashMap;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Component {
     pub name: String,
+    /// Props declared with `export let`
+    pub props: Vec<Prop>,
     pub script: Option<Script>,
     pub template: Template,
     pub style: Option<Style>,
     pub metadata: ComponentMetadata,
 }
 
+/// A prop declared as `export let name: Type = default`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Prop {
+    pub name: String,
+    pub ty: Option<Type>,
+    pub default: Option<Expression>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ComponentMetadata {
     pub file_path: String,
@@ -35,30 +49,46 @@ pub struct Script {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum Statement {
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum StatementKind {
     VariableDeclaration {
         kind: VarKind,
         name: String,
+        /// Annotation, as in `let x: number`
+        ty: Option<Type>,
         init: Option<Expression>,
         reactive: ReactiveKind,
     },
     FunctionDeclaration {
         name: String,
         params: Vec<Parameter>,
+        return_type: Option<Type>,
         body: Vec<Statement>,
         is_async: bool,
     },
     Expression(Expression),
     Return(Option<Expression>),
+    /// `const { a, b } = init` or `let [x, y] = init`
+    Destructure {
+        kind: VarKind,
+        pattern: Pattern,
+        init: Expression,
+    },
     If {
         condition: Expression,
         then_branch: Vec<Statement>,
         else_branch: Option<Vec<Statement>>,
     },
+    /// `for (init; condition; update) body`, each header part optional
     For {
-        init: Box<Statement>,
-        condition: Expression,
-        update: Expression,
+        init: Option<Box<Statement>>,
+        condition: Option<Expression>,
+        update: Option<Expression>,
         body: Vec<Statement>,
     },
     While {
@@ -66,6 +96,42 @@ pub enum Statement {
         body: Vec<Statement>,
     },
     Block(Vec<Statement>),
+    /// `export` before a declaration; an exported `let` declares a prop
+    Export(Box<Statement>),
+    /// `import ... from "./File.omni"`
+    Import(Import),
+    Break,
+    Continue,
+    /// Placeholder for a statement that failed to parse
+    Error,
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl StatementKind {
+    /// Value type declared for a variable, by its annotation or the type
+    /// argument of `signal<T>(...)`
+    ///
+    /// A reactive variable annotated `Signal<T>` holds a `T`.
+    pub fn declared_type(&self) -> Option<&Type> {
+        let StatementKind::VariableDeclaration { ty, init, reactive, .. } = self else {
+            return None;
+        };
+        let declared = ty.as_ref().or_else(|| match init.as_ref().map(|init| &init.kind) {
+            Some(ExpressionKind::Call { type_args, .. }) if *reactive != ReactiveKind::None => {
+                type_args.first()
+            }
+            _ => None,
+        })?;
+        match declared {
+            Type::Signal(inner) if *reactive != ReactiveKind::None => Some(inner),
+            declared => Some(declared),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -84,9 +150,33 @@ pub enum ReactiveKind {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Parameter {
-    pub name: String,
+    pub pattern: Pattern,
     pub ty: Option<Type>,
     pub default: Option<Expression>,
+    pub span: Span,
+}
+
+/// Binding target of a declaration or parameter
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Pattern {
+    Identifier(String),
+    /// `{ a, b: c, d = 1, ...rest }`
+    Object {
+        properties: Vec<(String, BindingElement)>,
+        rest: Option<String>,
+    },
+    /// `[a, , b = 1, ...rest]`, with `None` for holes
+    Array {
+        elements: Vec<Option<BindingElement>>,
+        rest: Option<String>,
+    },
+}
+
+/// A pattern inside a destructuring pattern, with its default value
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BindingElement {
+    pub pattern: Pattern,
+    pub default: Option<Expression>,
 }
 
 // ============================================================================
@@ -94,7 +184,13 @@ pub struct Parameter {
 // ============================================================================
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum Expression {
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ExpressionKind {
     Identifier(String),
     Literal(Literal),
     Binary {
@@ -106,21 +202,42 @@ pub enum Expression {
         op: UnaryOp,
         operand: Box<Expression>,
     },
+    /// `target = value` or a compound assignment such as `target += value`
+    Assign {
+        op: AssignOp,
+        target: Box<Expression>,
+        value: Box<Expression>,
+    },
+    /// `++target`, `target++`, `--target` or `target--`
+    Update {
+        op: UpdateOp,
+        prefix: bool,
+        target: Box<Expression>,
+    },
+    /// `callee(args)`, or `callee?.(args)` when `optional`
     Call {
         callee: Box<Expression>,
+        /// Type arguments, as in `signal<number[]>([])`
+        type_args: Vec<Type>,
         args: Vec<Expression>,
+        optional: bool,
     },
+    /// `object.property`, or `object?.property` when `optional`
     Member {
         object: Box<Expression>,
         property: String,
         computed: bool,
+        optional: bool,
     },
+    /// `object[index]`, or `object?.[index]` when `optional`
     Index {
         object: Box<Expression>,
         index: Box<Expression>,
+        optional: bool,
     },
     Arrow {
         params: Vec<Parameter>,
+        return_type: Option<Type>,
         body: ArrowBody,
     },
     Ternary {
@@ -131,8 +248,51 @@ pub enum Expression {
     Template {
         parts: Vec<TemplatePart>,
     },
+    /// Array literal; elements may be `Spread`
     Array(Vec<Expression>),
-    Object(Vec<(String, Expression)>),
+    Object(Vec<ObjectProperty>),
+    /// `...expr` inside an array literal
+    Spread(Box<Expression>),
+    /// Placeholder for an expression that failed to parse
+    Error,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ObjectProperty {
+    /// `key: value`; shorthand `key` has an identifier value of the same name
+    KeyValue { key: PropertyKey, value: Expression },
+    /// `...expr`
+    Spread(Expression),
+}
+
+impl ObjectProperty {
+    /// Rebuild the property with `f` applied to its key and value expressions
+    pub fn map(&self, mut f: impl FnMut(&Expression) -> Expression) -> Self {
+        match self {
+            ObjectProperty::KeyValue { key, value } => ObjectProperty::KeyValue {
+                key: match key {
+                    PropertyKey::Static(name) => PropertyKey::Static(name.clone()),
+                    PropertyKey::Computed(key) => PropertyKey::Computed(f(key)),
+                },
+                value: f(value),
+            },
+            ObjectProperty::Spread(expr) => ObjectProperty::Spread(f(expr)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PropertyKey {
+    /// Identifier, string or number key
+    Static(String),
+    /// `[expr]`
+    Computed(Expression),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -145,10 +305,65 @@ pub enum ArrowBody {
 pub enum Literal {
     String(String),
     Number(f64),
+    /// Number with a unit suffix, e.g. `45deg` or `300ms`
+    Dimension(f64, Unit),
     Boolean(bool),
     Null,
 }
 
+/// Unit suffix of a numeric literal
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Unit {
+    Px,
+    Percent,
+    Deg,
+    Rad,
+    Turn,
+    S,
+    Ms,
+}
+
+impl Unit {
+    /// Suffixes in matching order (longest first where they overlap)
+    const SUFFIXES: [(&'static str, Unit); 7] = [
+        ("turn", Unit::Turn),
+        ("deg", Unit::Deg),
+        ("rad", Unit::Rad),
+        ("px", Unit::Px),
+        ("ms", Unit::Ms),
+        ("s", Unit::S),
+        ("%", Unit::Percent),
+    ];
+
+    /// Split a literal such as `45deg` into its numeric text and unit
+    pub fn split_suffix(literal: &str) -> Option<(&str, Unit)> {
+        Self::SUFFIXES
+            .iter()
+            .find_map(|(suffix, unit)| literal.strip_suffix(suffix).map(|n| (n, *unit)))
+    }
+
+    pub fn suffix(self) -> &'static str {
+        Self::SUFFIXES
+            .iter()
+            .find(|(_, unit)| *unit == self)
+            .map(|(suffix, _)| *suffix)
+            .unwrap_or_default()
+    }
+
+    /// Convert a value to the runtime's base unit: pixels, radians or
+    /// seconds. Percentages become a fraction of their base, which the
+    /// analyzer only accepts where the base is 1, as for `opacity`.
+    pub fn to_base(self, value: f64) -> f64 {
+        match self {
+            Unit::Px | Unit::Rad | Unit::S => value,
+            Unit::Percent => value / 100.0,
+            Unit::Deg => value.to_radians(),
+            Unit::Turn => value * std::f64::consts::TAU,
+            Unit::Ms => value / 1000.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum BinaryOp {
     Add,
@@ -164,6 +379,8 @@ pub enum BinaryOp {
     Ge,
     And,
     Or,
+    /// `??`
+    Nullish,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -172,6 +389,22 @@ pub enum UnaryOp {
     Not,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum AssignOp {
+    Assign,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum UpdateOp {
+    Increment,
+    Decrement,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TemplatePart {
     String(String),
@@ -197,7 +430,13 @@ pub struct CanvasNode {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum Node {
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum NodeKind {
     Element {
         tag: ElementTag,
         attributes: Vec<Attribute>,
@@ -223,10 +462,45 @@ pub enum Node {
         body: Vec<Node>,
         key: Option<Expression>,
     },
+    /// `{#await promise}pending{:then value}...{:catch error}...{/await}`
+    AwaitBlock {
+        promise: Expression,
+        pending: Vec<Node>,
+        then_branch: Option<AwaitBranch>,
+        catch_branch: Option<AwaitBranch>,
+    },
+    /// `<slot name="header" prop={value}>fallback</slot>`, where a
+    /// component renders the content its parent nested in it
     Slot {
         name: Option<String>,
         props: Vec<Attribute>,
+        /// Rendered when the parent provides no content for the slot
+        fallback: Vec<Node>,
     },
+    /// Placeholder for template content that failed to parse
+    Error,
+}
+
+impl Node {
+    pub fn new(kind: NodeKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+/// `{:then value}` or `{:catch error}` branch of an await block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AwaitBranch {
+    pub binding: Option<String>,
+    pub body: Vec<Node>,
+}
+
+impl AwaitBranch {
+    pub fn map_body(&self, f: impl FnMut(&Node) -> Node) -> Self {
+        Self {
+            binding: self.binding.clone(),
+            body: self.body.iter().map(f).collect(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -245,6 +519,7 @@ pub enum ElementTag {
 }
 
 impl ElementTag {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "circle" => Some(ElementTag::Circle),
@@ -273,6 +548,7 @@ impl ElementTag {
 pub struct Attribute {
     pub name: String,
     pub value: AttributeValue,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -289,6 +565,7 @@ pub struct Directive {
     pub arg: Option<String>,
     pub value: Expression,
     pub modifiers: Vec<String>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -297,6 +574,8 @@ pub enum DirectiveName {
     Bind, // :value, bind:value
     Ref,  // ref={element}
     Use,  // use:action
+    Class, // class:active={cond}
+    Let,   // let:item, a slot prop
 }
 
 // ============================================================================
@@ -310,16 +589,49 @@ pub struct Style {
     pub scoped: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CssRule {
+    /// Selector list as written
     pub selector: String,
+    pub selectors: Vec<Selector>,
     pub declarations: Vec<CssDeclaration>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Compound selectors separated by the descendant combinator, outermost
+/// first; the last one matches the element itself
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Selector {
+    pub compounds: Vec<CompoundSelector>,
+}
+
+/// `tag.class#id`, or `*` with neither tag nor classes nor id
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct CompoundSelector {
+    pub tag: Option<String>,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+}
+
+impl Selector {
+    /// `(ids, classes, tags)`, compared lexicographically
+    pub fn specificity(&self) -> (usize, usize, usize) {
+        self.compounds.iter().fold((0, 0, 0), |(ids, classes, tags), compound| {
+            (
+                ids + usize::from(compound.id.is_some()),
+                classes + compound.classes.len(),
+                tags + usize::from(compound.tag.is_some()),
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CssDeclaration {
     pub property: String,
+    /// Value as written, without the trailing `;`
     pub value: String,
+    pub span: Span,
 }
 
 // ============================================================================
@@ -348,13 +660,13 @@ pub enum Type {
 // Imports/Exports
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Import {
     pub source: String,
     pub specifiers: Vec<ImportSpecifier>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ImportSpecifier {
     Named {
         name: String,
@@ -364,8 +676,266 @@ pub enum ImportSpecifier {
     Namespace(String),
 }
 
+impl ImportSpecifier {
+    /// Name the import binds in the importing file
+    pub fn local(&self) -> &str {
+        match self {
+            ImportSpecifier::Named { name, alias } => alias.as_deref().unwrap_or(name),
+            ImportSpecifier::Default(name) | ImportSpecifier::Namespace(name) => name,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Export {
     pub name: String,
     pub value: Option<Expression>,
 }
+
+// ============================================================================
+// Span Shifting
+// ============================================================================
+
+impl Statement {
+    /// Move the spans of this statement and everything in it by `delta` bytes
+    pub fn shift_spans(&mut self, delta: isize) {
+        self.span = self.span.shifted(delta);
+        match &mut self.kind {
+            StatementKind::VariableDeclaration { init, .. } => {
+                init.iter_mut().for_each(|e| e.shift_spans(delta));
+            }
+            StatementKind::FunctionDeclaration { params, body, .. } => {
+                params.iter_mut().for_each(|p| p.shift_spans(delta));
+                body.iter_mut().for_each(|s| s.shift_spans(delta));
+            }
+            StatementKind::Expression(expr) => expr.shift_spans(delta),
+            StatementKind::Return(value) => {
+                value.iter_mut().for_each(|e| e.shift_spans(delta));
+            }
+            StatementKind::Destructure { pattern, init, .. } => {
+                pattern.shift_spans(delta);
+                init.shift_spans(delta);
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                condition.shift_spans(delta);
+                then_branch.iter_mut().for_each(|s| s.shift_spans(delta));
+                else_branch.iter_mut().flatten().for_each(|s| s.shift_spans(delta));
+            }
+            StatementKind::For {
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                init.iter_mut().for_each(|s| s.shift_spans(delta));
+                condition.iter_mut().for_each(|e| e.shift_spans(delta));
+                update.iter_mut().for_each(|e| e.shift_spans(delta));
+                body.iter_mut().for_each(|s| s.shift_spans(delta));
+            }
+            StatementKind::While { condition, body } => {
+                condition.shift_spans(delta);
+                body.iter_mut().for_each(|s| s.shift_spans(delta));
+            }
+            StatementKind::Block(body) => body.iter_mut().for_each(|s| s.shift_spans(delta)),
+            StatementKind::Export(declaration) => declaration.shift_spans(delta),
+            StatementKind::Import(_)
+            | StatementKind::Break
+            | StatementKind::Continue
+            | StatementKind::Error => {}
+        }
+    }
+}
+
+impl Parameter {
+    pub fn shift_spans(&mut self, delta: isize) {
+        self.span = self.span.shifted(delta);
+        self.pattern.shift_spans(delta);
+        self.default.iter_mut().for_each(|e| e.shift_spans(delta));
+    }
+}
+
+impl Pattern {
+    /// Names the pattern binds, in source order
+    pub fn bound_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_names(&mut names);
+        names
+    }
+
+    fn collect_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        let rest = match self {
+            Pattern::Identifier(name) => {
+                names.push(name);
+                return;
+            }
+            Pattern::Object { properties, rest } => {
+                properties
+                    .iter()
+                    .for_each(|(_, element)| element.pattern.collect_names(names));
+                rest
+            }
+            Pattern::Array { elements, rest } => {
+                elements
+                    .iter()
+                    .flatten()
+                    .for_each(|element| element.pattern.collect_names(names));
+                rest
+            }
+        };
+        names.extend(rest.as_deref());
+    }
+
+    pub fn shift_spans(&mut self, delta: isize) {
+        let elements: Vec<&mut BindingElement> = match self {
+            Pattern::Identifier(_) => return,
+            Pattern::Object { properties, .. } => properties.iter_mut().map(|(_, e)| e).collect(),
+            Pattern::Array { elements, .. } => elements.iter_mut().flatten().collect(),
+        };
+        for element in elements {
+            element.pattern.shift_spans(delta);
+            element.default.iter_mut().for_each(|e| e.shift_spans(delta));
+        }
+    }
+}
+
+impl Expression {
+    /// Move the spans of this expression and its subexpressions by `delta` bytes
+    pub fn shift_spans(&mut self, delta: isize) {
+        self.span = self.span.shifted(delta);
+        match &mut self.kind {
+            ExpressionKind::Identifier(_) | ExpressionKind::Literal(_) | ExpressionKind::Error => {}
+            ExpressionKind::Binary { left, right, .. } => {
+                left.shift_spans(delta);
+                right.shift_spans(delta);
+            }
+            ExpressionKind::Unary { operand, .. } => operand.shift_spans(delta),
+            ExpressionKind::Assign { target, value, .. } => {
+                target.shift_spans(delta);
+                value.shift_spans(delta);
+            }
+            ExpressionKind::Update { target, .. } => target.shift_spans(delta),
+            ExpressionKind::Call { callee, args, .. } => {
+                callee.shift_spans(delta);
+                args.iter_mut().for_each(|e| e.shift_spans(delta));
+            }
+            ExpressionKind::Member { object, .. } => object.shift_spans(delta),
+            ExpressionKind::Index { object, index, .. } => {
+                object.shift_spans(delta);
+                index.shift_spans(delta);
+            }
+            ExpressionKind::Arrow { params, body, .. } => {
+                params.iter_mut().for_each(|p| p.shift_spans(delta));
+                match body {
+                    ArrowBody::Expression(e) => e.shift_spans(delta),
+                    ArrowBody::Block(body) => body.iter_mut().for_each(|s| s.shift_spans(delta)),
+                }
+            }
+            ExpressionKind::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                condition.shift_spans(delta);
+                then_expr.shift_spans(delta);
+                else_expr.shift_spans(delta);
+            }
+            ExpressionKind::Template { parts } => {
+                for part in parts {
+                    if let TemplatePart::Expression(e) = part {
+                        e.shift_spans(delta);
+                    }
+                }
+            }
+            ExpressionKind::Array(elements) => elements.iter_mut().for_each(|e| e.shift_spans(delta)),
+            ExpressionKind::Object(props) => {
+                for prop in props {
+                    match prop {
+                        ObjectProperty::KeyValue { key, value } => {
+                            if let PropertyKey::Computed(key) = key {
+                                key.shift_spans(delta);
+                            }
+                            value.shift_spans(delta);
+                        }
+                        ObjectProperty::Spread(e) => e.shift_spans(delta),
+                    }
+                }
+            }
+            ExpressionKind::Spread(e) => e.shift_spans(delta),
+        }
+    }
+}
+
+impl Node {
+    /// Move the spans of this node and everything in it by `delta` bytes
+    pub fn shift_spans(&mut self, delta: isize) {
+        self.span = self.span.shifted(delta);
+        match &mut self.kind {
+            NodeKind::Element {
+                attributes,
+                children,
+                directives,
+                key,
+                ..
+            } => {
+                attributes.iter_mut().for_each(|a| a.shift_spans(delta));
+                children.iter_mut().for_each(|n| n.shift_spans(delta));
+                for directive in directives {
+                    directive.span = directive.span.shifted(delta);
+                    directive.value.shift_spans(delta);
+                }
+                key.iter_mut().for_each(|e| e.shift_spans(delta));
+            }
+            NodeKind::Text { content: expr } | NodeKind::Expression { expr } => expr.shift_spans(delta),
+            NodeKind::IfBlock {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                condition.shift_spans(delta);
+                then_branch.iter_mut().for_each(|n| n.shift_spans(delta));
+                else_branch.iter_mut().flatten().for_each(|n| n.shift_spans(delta));
+            }
+            NodeKind::EachBlock {
+                expression,
+                body,
+                key,
+                ..
+            } => {
+                expression.shift_spans(delta);
+                body.iter_mut().for_each(|n| n.shift_spans(delta));
+                key.iter_mut().for_each(|e| e.shift_spans(delta));
+            }
+            NodeKind::AwaitBlock {
+                promise,
+                pending,
+                then_branch,
+                catch_branch,
+            } => {
+                promise.shift_spans(delta);
+                pending.iter_mut().for_each(|n| n.shift_spans(delta));
+                for branch in then_branch.iter_mut().chain(catch_branch) {
+                    branch.body.iter_mut().for_each(|n| n.shift_spans(delta));
+                }
+            }
+            NodeKind::Slot { props, fallback, .. } => {
+                props.iter_mut().for_each(|a| a.shift_spans(delta));
+                fallback.iter_mut().for_each(|n| n.shift_spans(delta));
+            }
+            NodeKind::Error => {}
+        }
+    }
+}
+
+impl Attribute {
+    pub fn shift_spans(&mut self, delta: isize) {
+        self.span = self.span.shifted(delta);
+        match &mut self.value {
+            AttributeValue::Dynamic(e) | AttributeValue::Spread(e) => e.shift_spans(delta),
+            AttributeValue::Static(_) | AttributeValue::Boolean(_) => {}
+        }
+    }
+}
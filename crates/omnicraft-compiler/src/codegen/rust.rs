@@ -2,8 +2,11 @@
 //!
 //! Generates Rust code from the AST.
 
+use crate::analyzer::cascade;
 use crate::ast::*;
+use crate::lexer::Span;
 use anyhow::Result;
+use std::collections::HashMap;
 use std::fmt::Write;
 use tracing::{instrument, debug};
 
@@ -11,6 +14,53 @@ use tracing::{instrument, debug};
 pub struct RustGenerator {
     output: String,
     indent: usize,
+    /// Reactive variables declared by the component's script
+    signals: Vec<String>,
+    /// Props of the component, bound as locals where the template runs
+    props: Vec<String>,
+    /// Rust types of the component's props and reactive variables
+    types: HashMap<String, String>,
+    /// Number of enclosing region render functions
+    region_depth: usize,
+    /// Locals bound by enclosing blocks, such as `{#each}` items, which
+    /// closures outliving the render clone instead of moving
+    region_locals: Vec<String>,
+    /// Rust module of each import source, when compiling a module graph
+    modules: HashMap<String, String>,
+    /// Whether to emit the `#[wasm_bindgen(start)]` function
+    entry_point: bool,
+    /// Stylesheet scope of the component, when some elements are styled
+    /// at runtime
+    style_scope: Option<String>,
+    /// Enclosing elements, innermost last
+    parents: Vec<ParentElement>,
+    /// Parameter count of each function declared by the script, which
+    /// become methods of the component
+    functions: HashMap<String, usize>,
+    /// Script functions used as event handlers, whose first parameter is
+    /// the event
+    handlers: Vec<String>,
+    /// Script functions used as `use:` actions, with the type of the
+    /// options they are given
+    actions: HashMap<String, String>,
+    /// Script variables holding an element's entity through `ref={...}`
+    refs: Vec<String>,
+    /// Whether the template has a `<slot>`
+    has_slots: bool,
+    /// Whether the template mounts child components, which are created
+    /// with the component's own `Context`
+    has_children: bool,
+}
+
+/// An element enclosing the nodes being generated
+struct ParentElement {
+    /// Local bound to the element's entity
+    binding: String,
+    /// Whether the element is styled by the runtime stylesheet
+    runtime_styled: bool,
+    /// Whether children record the element as their `Parent`, for runtime
+    /// styling, event bubbling and stack layout
+    linked: bool,
 }
 
 impl RustGenerator {
@@ -18,27 +68,149 @@ impl RustGenerator {
         Self {
             output: String::new(),
             indent: 0,
+            signals: Vec::new(),
+            props: Vec::new(),
+            types: HashMap::new(),
+            region_depth: 0,
+            region_locals: Vec::new(),
+            modules: HashMap::new(),
+            entry_point: true,
+            style_scope: None,
+            parents: Vec::new(),
+            functions: HashMap::new(),
+            handlers: Vec::new(),
+            actions: HashMap::new(),
+            refs: Vec::new(),
+            has_slots: false,
+            has_children: false,
         }
     }
 
+    /// Use the given Rust module names for import sources instead of
+    /// deriving them from the file names
+    pub fn with_modules(mut self, modules: HashMap<String, String>) -> Self {
+        self.modules = modules;
+        self
+    }
+
+    /// Emit the WASM entry point; only one module of a crate may have it
+    pub fn with_entry_point(mut self, entry_point: bool) -> Self {
+        self.entry_point = entry_point;
+        self
+    }
+
     /// Generate Rust code from a component
-    #[instrument(skip(self), fields(component = %component.name))]
     pub fn generate(&mut self, component: &Component) -> Result<String> {
-        debug!("Generating Rust code for component");
+        self.generate_components(std::slice::from_ref(component))
+    }
+
+    /// Generate Rust code for the components of one file, which share its
+    /// imports; only the first one gets the WASM entry point
+    #[instrument(skip_all, fields(components = components.len()))]
+    pub fn generate_components(&mut self, components: &[Component]) -> Result<String> {
+        debug!("Generating Rust code for components");
         self.output.clear();
+        let Some(main) = components.first() else {
+            return Ok(String::new());
+        };
 
         // Generate module header
-        self.emit_header(component)?;
+        self.emit_header(main)?;
+        self.emit_imports(main, components)?;
 
-        // Generate component struct
-        self.emit_component_struct(component)?;
+        for (i, component) in components.iter().enumerate() {
+            if i > 0 {
+                writeln!(self.output)?;
+            }
+            self.prepare(component);
+            self.emit_exported_items(component)?;
 
-        // Generate component impl
-        self.emit_component_impl(component)?;
+            // Generate props and component structs
+            self.emit_props_struct(component)?;
+            self.emit_component_struct(component)?;
+
+            // Generate component impl
+            self.emit_component_impl(component, self.entry_point && i == 0)?;
+        }
 
         Ok(self.output.clone())
     }
 
+    /// Collect what generating `component` needs to know up front
+    fn prepare(&mut self, component: &Component) {
+        self.signals = component
+            .script
+            .iter()
+            .flat_map(|script| &script.statements)
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::VariableDeclaration { name, reactive, .. } if *reactive != ReactiveKind::None => {
+                    Some(name.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        self.props = component.props.iter().map(|prop| prop.name.clone()).collect();
+        // Later declarations may read the earlier ones
+        self.types = HashMap::new();
+        for prop in &component.props {
+            let ty = match &prop.ty {
+                Some(ty) => rust_type(ty),
+                None => self.infer_type(prop.default.as_ref()),
+            };
+            self.types.insert(prop.name.clone(), ty);
+        }
+        for stmt in component.script.iter().flat_map(|script| &script.statements) {
+            if let StatementKind::VariableDeclaration { name, reactive, init, .. } = &stmt.kind
+                && *reactive != ReactiveKind::None
+            {
+                let ty = stmt
+                    .kind
+                    .declared_type()
+                    .map_or_else(|| self.infer_type(init.as_ref()), rust_type);
+                self.types.insert(name.clone(), ty);
+            }
+        }
+        self.has_slots = renders_slots(&component.template.children);
+        if self.has_slots {
+            // Slot content is passed like a prop
+            self.props.push("slots".to_string());
+        }
+        self.functions = component
+            .script
+            .iter()
+            .flat_map(|script| &script.statements)
+            .filter_map(|stmt| match &stmt.kind {
+                StatementKind::FunctionDeclaration { name, params, .. } => Some((name.clone(), params.len())),
+                _ => None,
+            })
+            .collect();
+        let mut tags = Vec::new();
+        collect_component_tags(&component.template.children, &mut tags);
+        self.has_children = !tags.is_empty();
+        self.refs = Vec::new();
+        collect_directive_names(&component.template.children, &DirectiveName::Ref, &mut self.refs);
+        self.handlers = Vec::new();
+        collect_directive_names(&component.template.children, &DirectiveName::On, &mut self.handlers);
+        let mut uses = Vec::new();
+        collect_directives(&component.template.children, &DirectiveName::Use, &mut uses);
+        self.actions = HashMap::new();
+        for directive in uses {
+            if let Some(action) = directive.arg.as_ref().filter(|action| self.functions.contains_key(*action)) {
+                let options = match &directive.value.kind {
+                    ExpressionKind::Literal(Literal::Null) => "()".to_string(),
+                    _ => self.infer_expr_type(&directive.value),
+                };
+                self.actions.insert(action.clone(), options);
+            }
+        }
+        self.style_scope = component
+            .style
+            .as_ref()
+            .filter(|style| !style.rules.is_empty())
+            .filter(|_| cascade::needs_runtime_styles(&component.template.children))
+            .map(|_| component.name.clone());
+    }
+
     fn emit_header(&mut self, component: &Component) -> Result<()> {
         writeln!(
             self.output,
@@ -47,41 +219,211 @@ impl RustGenerator {
         writeln!(self.output, "//! Source: {}", component.metadata.file_path)?;
         writeln!(self.output, "//! Do not edit manually.")?;
         writeln!(self.output)?;
-        writeln!(self.output, "#![allow(unused)]")?;
+        writeln!(self.output, "#![allow(unused, clippy::all)]")?;
         writeln!(self.output)?;
         writeln!(self.output, "use omnicraft_runtime::prelude::*;")?;
+        writeln!(self.output, "use std::collections::HashMap;")?;
         writeln!(self.output, "use wasm_bindgen::prelude::*;")?;
         writeln!(self.output)?;
         Ok(())
     }
 
+    /// Emit a `use` for every import; a default import brings in the
+    /// component and props structs of the imported file, as does a named
+    /// import used as a component by any of `components`
+    fn emit_imports(&mut self, component: &Component, components: &[Component]) -> Result<()> {
+        let Some(script) = &component.script else {
+            return Ok(());
+        };
+        if script.imports.is_empty() {
+            return Ok(());
+        }
+        let mut tags = Vec::new();
+        for component in components {
+            collect_component_tags(&component.template.children, &mut tags);
+        }
+
+        for import in &script.imports {
+            let module = format!("crate::{}", self.module_of(&import.source));
+            let imported = self.to_pascal_case(&crate::module_graph::component_name(&import.source));
+            let mut items = Vec::new();
+            for specifier in &import.specifiers {
+                match specifier {
+                    ImportSpecifier::Default(local) => {
+                        let local = self.to_pascal_case(local);
+                        for suffix in ["Component", "Props"] {
+                            items.push(use_item(
+                                &format!("{}{}", imported, suffix),
+                                &format!("{}{}", local, suffix),
+                            ));
+                        }
+                    }
+                    ImportSpecifier::Named { name, alias } => {
+                        let local = alias.as_deref().unwrap_or(name);
+                        if tags.iter().any(|tag| tag == local) {
+                            for suffix in ["Component", "Props"] {
+                                items.push(use_item(
+                                    &format!("{}{}", name, suffix),
+                                    &format!("{}{}", local, suffix),
+                                ));
+                            }
+                        } else {
+                            items.push(use_item(name, local));
+                        }
+                    }
+                    ImportSpecifier::Namespace(local) if self.module_of(&import.source) == *local => {
+                        writeln!(self.output, "use {};", module)?;
+                    }
+                    ImportSpecifier::Namespace(local) => {
+                        writeln!(self.output, "use {} as {};", module, local)?;
+                    }
+                }
+            }
+            match items.as_slice() {
+                [] => {}
+                [item] => writeln!(self.output, "use {}::{};", module, item)?,
+                items => writeln!(self.output, "use {}::{{{}}};", module, items.join(", "))?,
+            }
+        }
+        writeln!(self.output)?;
+        Ok(())
+    }
+
+    /// Rust module an import source compiles to
+    fn module_of(&self, source: &str) -> String {
+        self.modules
+            .get(source)
+            .cloned()
+            .unwrap_or_else(|| crate::module_graph::module_name(&crate::module_graph::component_name(source)))
+    }
+
+    /// Emit exported functions and constants as module items, so other
+    /// files can import them
+    fn emit_exported_items(&mut self, component: &Component) -> Result<()> {
+        let Some(script) = &component.script else {
+            return Ok(());
+        };
+        for stmt in &script.statements {
+            let StatementKind::Export(declaration) = &stmt.kind else {
+                continue;
+            };
+            match &declaration.kind {
+                StatementKind::FunctionDeclaration { name, params, return_type, body, .. } => {
+                    let (rendered, prologue) = self.params_to_rust(params);
+                    // Item functions need a type on every parameter
+                    let params: Vec<String> = params
+                        .iter()
+                        .zip(rendered)
+                        .map(|(param, rendered)| {
+                            if rust_annotation(param.ty.as_ref()).is_empty() {
+                                format!("{}: {}", rendered, self.infer_type(None))
+                            } else {
+                                rendered
+                            }
+                        })
+                        .collect();
+                    let return_type = self.return_type_to_rust(return_type.as_ref(), body);
+                    writeln!(
+                        self.output,
+                        "pub fn {}({}){} {}",
+                        name,
+                        params.join(", "),
+                        return_type,
+                        self.block_with_prologue(prologue, body)
+                    )?;
+                    writeln!(self.output)?;
+                }
+                StatementKind::VariableDeclaration {
+                    kind: VarKind::Const,
+                    name,
+                    init: Some(init),
+                    reactive: ReactiveKind::None,
+                    ..
+                } => {
+                    let ty = declaration
+                        .kind
+                        .declared_type()
+                        .map_or_else(|| self.infer_type(Some(init)), rust_type);
+                    // Constants cannot own a `String`
+                    let ty = if ty == "String" { "&str".to_string() } else { ty };
+                    writeln!(self.output, "#[allow(non_upper_case_globals)]")?;
+                    writeln!(self.output, "pub const {}: {} = {};", name, ty, self.expr_to_rust(init))?;
+                    writeln!(self.output)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit the props struct, whose `Default` applies the declared defaults
+    fn emit_props_struct(&mut self, component: &Component) -> Result<()> {
+        let props_name = self.props_struct_name(&component.name);
+
+        writeln!(self.output, "/// Props of {}", component.name)?;
+        writeln!(self.output, "#[derive(Clone)]")?;
+        writeln!(self.output, "pub struct {} {{", props_name)?;
+        self.indent += 1;
+        for prop in &component.props {
+            self.emit_line(&format!("pub {}: {},", prop.name, self.types[&prop.name]))?;
+        }
+        if self.has_slots {
+            self.emit_line("pub slots: Slots,")?;
+        }
+        self.indent -= 1;
+        writeln!(self.output, "}}")?;
+        writeln!(self.output)?;
+
+        writeln!(self.output, "impl Default for {} {{", props_name)?;
+        self.indent += 1;
+        self.emit_line("fn default() -> Self {")?;
+        self.indent += 1;
+        self.emit_line("Self {")?;
+        self.indent += 1;
+        for prop in &component.props {
+            let value = match &prop.default {
+                Some(default @ Expression { kind: ExpressionKind::Literal(Literal::String(_)), .. }) => {
+                    format!("{}.to_string()", self.expr_to_rust(default))
+                }
+                Some(default) => self.expr_to_rust(default),
+                None => "Default::default()".to_string(),
+            };
+            self.emit_line(&format!("{}: {},", prop.name, value))?;
+        }
+        if self.has_slots {
+            self.emit_line("slots: Slots::new(),")?;
+        }
+        self.indent -= 1;
+        self.emit_line("}")?;
+        self.indent -= 1;
+        self.emit_line("}")?;
+        self.indent -= 1;
+        writeln!(self.output, "}}")?;
+        writeln!(self.output)?;
+        Ok(())
+    }
+
     fn emit_component_struct(&mut self, component: &Component) -> Result<()> {
         let struct_name = format!("{}Component", self.to_pascal_case(&component.name));
 
+        // Handlers and render functions keep clones of the component to
+        // call its script functions
         writeln!(self.output, "/// {}", component.name)?;
-        writeln!(self.output, "#[wasm_bindgen]")?;
+        writeln!(self.output, "#[derive(Clone)]")?;
         writeln!(self.output, "pub struct {} {{", struct_name)?;
         self.indent += 1;
+        self.emit_line(&format!("pub props: {},", self.props_struct_name(&component.name)))?;
 
-        // Add signal fields from script
-        if let Some(script) = &component.script {
-            for stmt in &script.statements {
-                if let Statement::VariableDeclaration {
-                    name,
-                    reactive,
-                    init,
-                    ..
-                } = stmt
-                {
-                    if *reactive != ReactiveKind::None {
-                        let ty = self.infer_type(init.as_ref());
-                        self.emit_line(&format!(
-                            "pub {}: Signal<{}>,",
-                            name, ty
-                        ))?;
-                    }
-                }
-            }
+        // Add signal fields from script; memos are signals kept up to date
+        // with the signals they read
+        for signal in self.signals.clone() {
+            self.emit_line(&format!("pub {}: Signal<{}>,", signal, self.types[&signal]))?;
+        }
+        for entity_ref in self.refs.clone() {
+            self.emit_line(&format!("pub {}: EntityRef,", entity_ref))?;
+        }
+        if self.has_children {
+            self.emit_line("ctx: Context,")?;
         }
 
         self.indent -= 1;
@@ -90,7 +432,7 @@ impl RustGenerator {
         Ok(())
     }
 
-    fn emit_component_impl(&mut self, component: &Component) -> Result<()> {
+    fn emit_component_impl(&mut self, component: &Component, entry_point: bool) -> Result<()> {
         let struct_name = format!("{}Component", self.to_pascal_case(&component.name));
 
         // impl OmniComponent trait
@@ -111,7 +453,9 @@ impl RustGenerator {
         self.emit_new_fn(component, &struct_name)?;
 
         // WASM entry point
-        self.emit_wasm_entry_point(component, &struct_name)?;
+        if entry_point {
+            self.emit_wasm_entry_point(component, &struct_name)?;
+        }
 
         Ok(())
     }
@@ -119,63 +463,122 @@ impl RustGenerator {
     fn emit_create_fn(&mut self, component: &Component) -> Result<()> {
         self.emit_line("fn create(ctx: &mut Context) -> Self {")?;
         self.indent += 1;
+        self.emit_line(&format!(
+            "Self::with_props(ctx, {}::default())",
+            self.props_struct_name(&component.name)
+        ))?;
+        self.indent -= 1;
+        self.emit_line("}")?;
+        writeln!(self.output)?;
+        Ok(())
+    }
 
-        self.emit_line("Self {")?;
+    /// Emit `with_props`, which creates the component's signals; their
+    /// initializers may read the props
+    fn emit_with_props_fn(&mut self, component: &Component) -> Result<()> {
+        self.emit_line("/// Create a component instance with the given props")?;
+        self.emit_line(&format!(
+            "pub fn with_props(ctx: &mut Context, props: {}) -> Self {{",
+            self.props_struct_name(&component.name)
+        ))?;
         self.indent += 1;
+        self.emit_prop_bindings("props.")?;
 
-        // Initialize signals
+        // Create the signals in order, since initializers may read earlier
+        // ones
         if let Some(script) = &component.script {
             for stmt in &script.statements {
-                if let Statement::VariableDeclaration {
-                    name,
-                    reactive,
-                    init,
-                    ..
-                } = stmt
+                if let StatementKind::VariableDeclaration { name, reactive, init, .. } = &stmt.kind
+                    && *reactive != ReactiveKind::None
                 {
-                    if *reactive == ReactiveKind::Signal {
-                        if let Some(init_expr) = init {
-                            let init_val = self.expr_to_rust(init_expr);
-                            // Extract inner value from signal() call
-                            let inner_val = if let Expression::Call { args, .. } = init_expr {
-                                if let Some(arg) = args.first() {
-                                    self.expr_to_rust(arg)
-                                } else {
-                                    "Default::default()".to_string()
-                                }
-                            } else {
-                                init_val
-                            };
-                            self.emit_line(&format!(
-                                "{}: ctx.create_signal({}),",
-                                name, inner_val
-                            ))?;
-                        }
-                    }
+                    self.emit_signal(name, reactive, init.as_ref())?;
                 }
             }
         }
 
+        self.emit_line("Self {")?;
+        self.indent += 1;
+        self.emit_line("props,")?;
+        for signal in self.signals.clone() {
+            self.emit_line(&format!("{},", signal))?;
+        }
+        for entity_ref in self.refs.clone() {
+            self.emit_line(&format!("{}: EntityRef::new(),", entity_ref))?;
+        }
+        if self.has_children {
+            self.emit_line("ctx: ctx.clone(),")?;
+        }
+
         self.indent -= 1;
         self.emit_line("}")?;
 
         self.indent -= 1;
         self.emit_line("}")?;
-        writeln!(self.output)?;
+        Ok(())
+    }
+
+    /// Create the signal of a reactive declaration as a local; a memo or
+    /// effect is computed once, then again whenever a signal it reads
+    /// changes
+    fn emit_signal(&mut self, name: &str, reactive: &ReactiveKind, init: Option<&Expression>) -> Result<()> {
+        // The value passed to `signal()`, `memo()` or `effect()`
+        let argument = match init {
+            Some(Expression { kind: ExpressionKind::Call { args, .. }, .. }) => args.first(),
+            init => init,
+        };
+        let value = match (reactive, argument) {
+            (ReactiveKind::Signal, Some(value)) => self.owned_to_rust(value),
+            (_, Some(Expression { kind: ExpressionKind::Arrow { params, body, .. }, .. })) if params.is_empty() => {
+                match body {
+                    ArrowBody::Expression(e) => self.expr_to_rust(e),
+                    ArrowBody::Block(body) => format!("(|| {})()", self.block_to_rust(body)),
+                }
+            }
+            _ => "Default::default()".to_string(),
+        };
+        self.emit_line(&format!("let {} = ctx.create_signal({});", name, value))?;
+        if *reactive == ReactiveKind::Signal {
+            return Ok(());
+        }
+
+        let dependencies = names_in(&self.signals, &value);
+        for dependency in &dependencies {
+            self.emit_line(&format!("{}.subscribe({{", dependency))?;
+            self.indent += 1;
+            for signal in dependencies.iter().map(String::as_str).chain([name]) {
+                self.emit_line(&format!("let {0} = {0}.clone();", signal))?;
+            }
+            for prop in names_in(&self.props, &value) {
+                self.emit_line(&format!("let {0} = {0}.clone();", prop))?;
+            }
+            self.emit_line(&format!("move || {}.set({})", name, value))?;
+            self.indent -= 1;
+            self.emit_line("});")?;
+        }
         Ok(())
     }
 
     fn emit_mount_fn(&mut self, component: &Component) -> Result<()> {
         self.emit_line("fn mount(&self, world: &mut World) {")?;
         self.indent += 1;
+        self.emit_prop_bindings("self.props.")?;
+        for signal in self.signals.clone() {
+            self.emit_line(&format!("let {0} = self.{0}.clone();", signal))?;
+        }
+        if !self.functions.is_empty() {
+            self.emit_line("let this = self.clone();")?;
+        }
+        if self.has_children {
+            self.emit_line("let ctx = &mut self.ctx.clone();")?;
+        }
 
         // Setup canvas
         if let Some(width) = &component.template.canvas.width {
-            let w = self.expr_to_rust(width);
+            let w = self.number_to_rust(width);
             self.emit_line(&format!("world.set_canvas_width({});", w))?;
         }
         if let Some(height) = &component.template.canvas.height {
-            let h = self.expr_to_rust(height);
+            let h = self.number_to_rust(height);
             self.emit_line(&format!("world.set_canvas_height({});", h))?;
         }
         if let Some(background) = &component.template.canvas.background {
@@ -194,6 +597,10 @@ impl RustGenerator {
         }
         writeln!(self.output)?;
 
+        if let (Some(scope), Some(style)) = (&self.style_scope, &component.style) {
+            self.emit_stylesheet(scope.clone(), style)?;
+        }
+
         // Create entities for each element
         for (i, node) in component.template.children.iter().enumerate() {
             self.emit_node(node, i)?;
@@ -205,164 +612,997 @@ impl RustGenerator {
     }
 
     fn emit_node(&mut self, node: &Node, index: usize) -> Result<()> {
-        match node {
-            Node::Element {
+        match &node.kind {
+            NodeKind::Element {
                 tag,
                 attributes,
                 children,
-                ..
+                directives,
+                key,
             } => {
+                if let ElementTag::Component(name) = tag {
+                    return self.emit_child_component(name, attributes, directives, children, index);
+                }
                 let entity_name = format!("entity_{}", index);
 
-                // Create entity
-                self.emit_line(&format!("let {} = world.spawn_empty().id();", entity_name))?;
+                // Create entity; a keyed one may be taken over from the
+                // region's previous render
+                match key {
+                    Some(key) => self.emit_line(&format!(
+                        "let ({}, reused_{}) = world.spawn_keyed(Key::new({}));",
+                        entity_name,
+                        index,
+                        self.expr_to_rust(key)
+                    ))?,
+                    None => self.emit_line(&format!("let {} = world.spawn_empty().id();", entity_name))?,
+                }
+                if self.region_depth > 0 {
+                    self.emit_line(&format!("entities.push({});", entity_name))?;
+                }
 
                 // Add transform component
-                let x = self.find_attr_value(attributes, "x").unwrap_or("0.0".to_string());
-                let y = self.find_attr_value(attributes, "y").unwrap_or("0.0".to_string());
+                let x = self.find_number_attr(attributes, "x").unwrap_or("0.0".to_string());
+                let y = self.find_number_attr(attributes, "y").unwrap_or("0.0".to_string());
+                let rotation = self
+                    .find_number_attr(attributes, "rotation")
+                    .map(|r| format!(".with_rotation({})", r))
+                    .unwrap_or_default();
                 self.emit_line(&format!(
-                    "world.entity_mut({}).insert(Transform::from_xy({}, {}));",
-                    entity_name, x, y
+                    "world.entity_mut({}).insert(Transform::from_xy({}, {}){});",
+                    entity_name, x, y, rotation
                 ))?;
 
                 // Add shape component
+                let mut text_binding = None;
                 match tag {
                     ElementTag::Circle => {
-                        let radius = self.find_attr_value(attributes, "radius").unwrap_or("10.0".to_string());
+                        let radius = self.find_number_attr(attributes, "radius").unwrap_or("10.0".to_string());
                         self.emit_line(&format!(
                             "world.entity_mut({}).insert(Shape::Circle {{ radius: {} }});",
                             entity_name, radius
                         ))?;
                     }
                     ElementTag::Rectangle => {
-                        let width = self.find_attr_value(attributes, "width").unwrap_or("100.0".to_string());
-                        let height = self.find_attr_value(attributes, "height").unwrap_or("100.0".to_string());
+                        let width = self.find_number_attr(attributes, "width").unwrap_or("100.0".to_string());
+                        let height = self.find_number_attr(attributes, "height").unwrap_or("100.0".to_string());
                         self.emit_line(&format!(
                             "world.entity_mut({}).insert(Shape::Rectangle {{ width: {}, height: {} }});",
                             entity_name, width, height
                         ))?;
                     }
                     ElementTag::Text => {
-                        let content = self.find_attr_value(attributes, "content").unwrap_or("\"\"".to_string());
+                        let content = self
+                            .find_attr_value(attributes, "content")
+                            .or_else(|| text_children_to_expr(children).map(|e| self.expr_to_rust(&e)))
+                            .unwrap_or("\"\"".to_string());
+                        let mut fields = vec![format!("text: {}.to_string()", content)];
+                        text_binding = Some(content.clone());
+                        if let Some(size) = self.find_number_attr(attributes, "fontSize") {
+                            fields.push(format!("font_size: {}", size));
+                        }
+                        if let Some(family) = self.find_attr_value(attributes, "fontFamily") {
+                            fields.push(format!("font_family: {}.to_string()", family));
+                        }
+                        if let Some(weight) = attributes
+                            .iter()
+                            .find(|a| a.name == "fontWeight")
+                            .and_then(|a| font_weight_to_rust(&a.value))
+                        {
+                            fields.push(format!("font_weight: {}", weight));
+                        }
                         self.emit_line(&format!(
-                            "world.entity_mut({}).insert(TextContent {{ text: {}.to_string(), ..Default::default() }});",
-                            entity_name, content
+                            "world.entity_mut({}).insert(TextContent {{ {}, ..Default::default() }});",
+                            entity_name,
+                            fields.join(", ")
                         ))?;
                     }
                     _ => {}
                 }
 
+                // Columns and rows lay out their children
+                let stack = match tag {
+                    ElementTag::Group => self.stack_to_rust(attributes),
+                    _ => None,
+                };
+                if let Some(stack) = &stack {
+                    self.emit_line(&format!("world.entity_mut({}).insert({});", entity_name, stack))?;
+                }
+
+                // Elements with dynamic classes, and those nested in them, are
+                // styled by the runtime stylesheet
+                let runtime_styled = self.style_scope.is_some()
+                    && (cascade::has_dynamic_classes(attributes, directives)
+                        || self.parents.last().is_some_and(|parent| parent.runtime_styled));
+                let has_handlers = directives.iter().any(|d| d.name == DirectiveName::On);
+                let linked = runtime_styled
+                    || has_handlers
+                    || stack.is_some()
+                    || self.parents.last().is_some_and(|parent| parent.linked);
+                if let Some(parent) = self.parents.last().filter(|parent| parent.linked) {
+                    self.emit_line(&format!("world.entity_mut({}).insert(Parent({}));", entity_name, parent.binding))?;
+                }
+                self.emit_classes(&entity_name, attributes)?;
+                if key.is_some() {
+                    // A reused entity still has its class bindings,
+                    // bindings, handlers, ref and actions
+                    let outer = std::mem::take(&mut self.output);
+                    self.indent += 1;
+                    self.emit_class_bindings(&entity_name, directives)?;
+                    self.emit_bindings(&entity_name, directives)?;
+                    self.emit_text_binding(&entity_name, text_binding.as_deref())?;
+                    self.emit_event_handlers(&entity_name, directives)?;
+                    self.emit_refs_and_actions(&entity_name, directives)?;
+                    self.indent -= 1;
+                    let mount = std::mem::replace(&mut self.output, outer);
+                    if !mount.is_empty() {
+                        self.emit_line(&format!("if !reused_{} {{", index))?;
+                        self.output.push_str(&mount);
+                        self.emit_line("}")?;
+                    }
+                } else {
+                    self.emit_class_bindings(&entity_name, directives)?;
+                    self.emit_bindings(&entity_name, directives)?;
+                    self.emit_text_binding(&entity_name, text_binding.as_deref())?;
+                    self.emit_event_handlers(&entity_name, directives)?;
+                    self.emit_refs_and_actions(&entity_name, directives)?;
+                }
+
                 // Add style component
-                let fill = self.find_attr_value(attributes, "fill");
-                let stroke = self.find_attr_value(attributes, "stroke");
-                if fill.is_some() || stroke.is_some() {
+                let fill = self.find_color_attr(attributes, "fill");
+                let stroke = self.find_color_attr(attributes, "stroke");
+                let stroke_width = self.find_number_attr(attributes, "strokeWidth");
+                let opacity = self.find_number_attr(attributes, "opacity");
+                if runtime_styled {
+                    let inline = style_declarations(&[
+                        ("fill", fill.map(|f| format!("Color::parse({})", f))),
+                        ("stroke", stroke.map(|s| format!("Color::parse({})", s))),
+                        ("stroke_width", stroke_width),
+                        ("opacity", opacity),
+                    ]);
+                    self.emit_line(&format!(
+                        "world.entity_mut({}).insert((Style::default(), ScopedStyle::new({}, {})));",
+                        entity_name,
+                        rust_string_literal(self.style_scope.as_deref().unwrap_or_default()),
+                        inline
+                    ))?;
+                } else if fill.is_some() || stroke.is_some() || stroke_width.is_some() || opacity.is_some() {
                     let fill_str = fill.map(|f| format!("Some(Color::parse({}))", f))
                         .unwrap_or_else(|| "None".to_string());
                     let stroke_str = stroke.map(|s| format!("Some(Color::parse({}))", s))
                         .unwrap_or_else(|| "None".to_string());
+                    let extra: String = [("stroke_width", stroke_width), ("opacity", opacity)]
+                        .into_iter()
+                        .filter_map(|(field, value)| value.map(|v| format!("{}: {}, ", field, v)))
+                        .collect();
                     self.emit_line(&format!(
-                        "world.entity_mut({}).insert(Style {{ fill: {}, stroke: {}, ..Default::default() }});",
-                        entity_name, fill_str, stroke_str
+                        "world.entity_mut({}).insert(Style {{ fill: {}, stroke: {}, {}..Default::default() }});",
+                        entity_name, fill_str, stroke_str, extra
                     ))?;
                 }
 
                 writeln!(self.output)?;
 
-                // Process children
+                // Children may shadow `entity_name`, so linked ones refer to
+                // their parent by depth
+                let binding = format!("parent_{}", self.parents.len());
+                if (linked && !children.is_empty()) || contains_slot(children) {
+                    self.emit_line(&format!("let {} = {};", binding, entity_name))?;
+                }
+
+                // Process children (text content was folded in above)
+                self.parents.push(ParentElement { binding, runtime_styled, linked });
                 for (i, child) in children.iter().enumerate() {
+                    if *tag == ElementTag::Text
+                        && matches!(child.kind, NodeKind::Text { .. } | NodeKind::Expression { .. })
+                    {
+                        continue;
+                    }
                     self.emit_node(child, index * 100 + i)?;
                 }
+                self.parents.pop();
             }
-            Node::Expression { expr } => {
+            NodeKind::Expression { expr } => {
                 let expr_str = self.expr_to_rust(expr);
                 self.emit_line(&format!("// Expression: {}", expr_str))?;
             }
+            NodeKind::IfBlock { .. } | NodeKind::EachBlock { .. } | NodeKind::AwaitBlock { .. } => {
+                self.emit_region(node, index)?;
+            }
+            NodeKind::Slot { name, props, fallback } => {
+                self.emit_slot(name.as_deref(), props, fallback, index)?;
+            }
             _ => {}
         }
 
         Ok(())
     }
 
-    fn emit_new_fn(&mut self, _component: &Component, struct_name: &str) -> Result<()> {
-        writeln!(self.output, "impl {} {{", struct_name)?;
-        self.indent += 1;
+    /// Render the content the parent provided for a slot under the
+    /// enclosing element, or the slot's fallback content
+    fn emit_slot(&mut self, name: Option<&str>, props: &[Attribute], fallback: &[Node], index: usize) -> Result<()> {
+        let name = rust_string_literal(name.unwrap_or("default"));
+        let mut slot_props = "SlotProps::new()".to_string();
+        // Slot props are read back by type, so pass owned values
+        for prop in props {
+            let value = match &prop.value {
+                AttributeValue::Static(Literal::String(s)) => format!("{}.to_string()", rust_string_literal(s)),
+                AttributeValue::Dynamic(Expression { kind: ExpressionKind::Identifier(name), .. }) => {
+                    format!("{}.clone()", name)
+                }
+                AttributeValue::Dynamic(expr) => self.owned_to_rust(expr),
+                value => self.attr_value_to_rust(value),
+            };
+            slot_props.push_str(&format!(".with({}, {})", rust_string_literal(&prop.name), value));
+        }
+        let parent = self
+            .parents
+            .last()
+            .map_or("None".to_string(), |parent| format!("Some({})", parent.binding));
+        let render = format!("world.render_slot(&slots, {}, &{}, {})", name, slot_props, parent);
+        let render = if self.region_depth > 0 {
+            format!("entities.extend({});", render)
+        } else {
+            format!("{};", render)
+        };
 
-        self.emit_line("/// Create a new component instance")?;
-        self.emit_line("pub fn new(ctx: &mut Context) -> Self {")?;
-        self.indent += 1;
-        self.emit_line("Self::create(ctx)")?;
-        self.indent -= 1;
-        self.emit_line("}")?;
+        if fallback.is_empty() {
+            self.emit_line(&render)?;
+        } else {
+            self.emit_line(&format!("if slots.contains({}) {{", name))?;
+            self.indent += 1;
+            self.emit_line(&render)?;
+            self.indent -= 1;
+            self.emit_line("} else {")?;
+            self.indent += 1;
+            self.emit_nodes(fallback, index)?;
+            self.indent -= 1;
+            self.emit_line("}")?;
+        }
+        writeln!(self.output)?;
+        Ok(())
+    }
 
-        self.indent -= 1;
-        writeln!(self.output, "}}")?;
+    /// Emit `slots_{index}` with a render function for each slot the
+    /// children of a component fill, returning whether there were any
+    ///
+    /// Children go to the slot named by their `slot` attribute, or the
+    /// default slot; `let:` directives on the component or a child bind the
+    /// props of its slot.
+    fn emit_slot_content(&mut self, directives: &[Directive], children: &[Node], index: usize) -> Result<bool> {
+        let mut slots: Vec<(String, Vec<Node>, Vec<Directive>)> = Vec::new();
+        for child in children {
+            let (name, lets) = match &child.kind {
+                NodeKind::Element { attributes, directives, .. } => {
+                    let name = attributes.iter().find_map(|attr| match &attr.value {
+                        AttributeValue::Static(Literal::String(name)) if attr.name == "slot" => Some(name.clone()),
+                        _ => None,
+                    });
+                    (name, directives.as_slice())
+                }
+                NodeKind::Text { .. } | NodeKind::Error => continue,
+                _ => (None, &[][..]),
+            };
+            let (name, lets) = match name {
+                Some(name) => (name, lets),
+                None => ("default".to_string(), directives),
+            };
+            let lets = lets.iter().filter(|d| d.name == DirectiveName::Let).cloned();
+            match slots.iter_mut().find(|(slot, ..)| *slot == name) {
+                Some((_, nodes, bindings)) => {
+                    nodes.push(child.clone());
+                    bindings.extend(lets);
+                }
+                None => slots.push((name, vec![child.clone()], lets.collect())),
+            }
+        }
+        if slots.is_empty() {
+            return Ok(false);
+        }
+
+        let source = if self.region_depth == 0 { "self." } else { "" };
+        self.emit_line(&format!("let mut slots_{} = Slots::new();", index))?;
+        for (name, nodes, lets) in slots {
+            // Render the body first to find the signals it captures; slot
+            // content joins the child's hierarchy, not this one
+            let outer = std::mem::take(&mut self.output);
+            let parents = std::mem::take(&mut self.parents);
+            self.indent += 2;
+            self.region_depth += 1;
+            self.emit_line("let mut entities = Vec::new();")?;
+            self.emit_nodes(&nodes, index)?;
+            self.emit_line("entities")?;
+            let content = std::mem::take(&mut self.output);
+            // Slot props are typed by how the content uses them
+            for binding in &lets {
+                if let ExpressionKind::Identifier(local) = &binding.value.kind {
+                    self.emit_line(&format!(
+                        "let {}: {} = slot_props.get({}).unwrap_or_default();",
+                        local,
+                        slot_prop_type(local, &content),
+                        rust_string_literal(binding.arg.as_deref().unwrap_or_default())
+                    ))?;
+                }
+            }
+            self.output.push_str(&content);
+            self.region_depth -= 1;
+            self.indent -= 2;
+            self.parents = parents;
+            let mut body = std::mem::replace(&mut self.output, outer);
+
+            self.emit_line("{")?;
+            self.indent += 1;
+            if let Some(capture) = capture_context(&mut body) {
+                self.emit_line(&capture)?;
+            }
+            for signal in self.fields_in(&body) {
+                self.emit_line(&format!("let {0} = {1}{0}.clone();", signal, source))?;
+            }
+            for prop in self.locals_in(&body) {
+                self.emit_line(&format!("let {0} = {0}.clone();", prop))?;
+            }
+            self.emit_line(&format!(
+                "slots_{}.insert({}, move |world: &mut World, slot_props: &SlotProps| {{",
+                index,
+                rust_string_literal(&name)
+            ))?;
+            self.output.push_str(&body);
+            self.emit_line("});")?;
+            self.indent -= 1;
+            self.emit_line("}")?;
+        }
+        Ok(true)
+    }
+
+    /// `Stack` of a group whose `layout` is `column` or `row`
+    fn stack_to_rust(&self, attributes: &[Attribute]) -> Option<String> {
+        let direction = match &attributes.iter().find(|a| a.name == "layout")?.value {
+            AttributeValue::Static(Literal::String(layout)) if layout == "column" => "Column",
+            AttributeValue::Static(Literal::String(layout)) if layout == "row" => "Row",
+            _ => return None,
+        };
+        let mut fields = vec![format!("direction: StackDirection::{}", direction)];
+        for (attribute, field) in [("gap", "gap"), ("padding", "padding"), ("cornerRadius", "corner_radius")] {
+            if let Some(value) = self.find_number_attr(attributes, attribute) {
+                fields.push(format!("{}: {}", field, value));
+            }
+        }
+        if let Some(background) = self.find_color_attr(attributes, "background") {
+            fields.push(format!("background: Some(Color::parse({}))", background));
+        }
+        Some(format!("Stack {{ {}, ..Default::default() }}", fields.join(", ")))
+    }
+
+    /// Emit the `Class` and `Id` of an element
+    fn emit_classes(&mut self, entity: &str, attributes: &[Attribute]) -> Result<()> {
+        if let Some(class) = attributes.iter().find(|a| a.name == "class") {
+            let classes = self.attr_value_to_rust(&class.value);
+            self.emit_line(&format!("world.entity_mut({}).insert(Class::new(&{}));", entity, classes))?;
+        }
+        if let Some(id) = self.find_attr_value(attributes, "id") {
+            self.emit_line(&format!("world.entity_mut({}).insert(Id::new({}));", entity, id))?;
+        }
         Ok(())
     }
 
-    fn emit_wasm_entry_point(&mut self, _component: &Component, struct_name: &str) -> Result<()> {
-        writeln!(self.output)?;
-        self.emit_line("/// WASM Entry Point")?;
-        self.emit_line("#[wasm_bindgen(start)]")?;
-        self.emit_line("pub fn start() -> Result<(), JsValue> {")?;
-        self.indent += 1;
-        
-        self.emit_line("console_error_panic_hook::set_once();")?;
-        self.emit_line("tracing_wasm::set_as_global_default();")?;
-        writeln!(self.output)?;
+    /// Keep each `class:name={...}` of an element in sync with the signals
+    /// it reads
+    fn emit_class_bindings(&mut self, entity: &str, directives: &[Directive]) -> Result<()> {
+        let source = if self.region_depth == 0 { "self." } else { "" };
+        for directive in directives.iter().filter(|d| d.name == DirectiveName::Class) {
+            let class = rust_string_literal(directive.arg.as_deref().unwrap_or_default());
+            let condition = self.expr_to_rust(&directive.value);
+            let signals = names_in(&self.signals, &condition);
 
-        self.emit_line("let mut app = omnicraft_runtime::App::new();")?;
-        self.emit_line("let mut ctx = Context::new();")?;
-        self.emit_line(&format!("let component = {}::new(&mut ctx);", struct_name))?;
-        self.emit_line("component.mount(app.world_mut());")?;
-        writeln!(self.output)?;
+            self.emit_line("{")?;
+            self.indent += 1;
+            for signal in &signals {
+                self.emit_line(&format!("let {0} = {1}{0}.clone();", signal, source))?;
+            }
+            for prop in self.locals_in(&condition) {
+                self.emit_line(&format!("let {0} = {0}.clone();", prop))?;
+            }
+            self.emit_line(&format!("world.bind_class({}, {}, move || {});", entity, class, condition))?;
+            self.indent -= 1;
+            self.emit_line("}")?;
+            for signal in &signals {
+                self.emit_line(&format!(
+                    "world.unsubscribe_on_unmount({2}, {0}{1}.subscribe(world.class_trigger({2}, {3})));",
+                    source, signal, entity, class
+                ))?;
+            }
+        }
+        Ok(())
+    }
 
-        // Run the schedule (for any systems)
-        self.emit_line("app.tick();")?;
-        writeln!(self.output)?;
+    /// Keep the text of an element in sync with the signals its content
+    /// reads
+    fn emit_text_binding(&mut self, entity: &str, content: Option<&str>) -> Result<()> {
+        let Some(content) = content else {
+            return Ok(());
+        };
+        let signals = names_in(&self.signals, content);
+        if signals.is_empty() {
+            return Ok(());
+        }
+        let source = if self.region_depth == 0 { "self." } else { "" };
 
-        // Render to canvas
-        self.emit_line("// Render to canvas")?;
-        self.emit_line("let mut queue = RenderQueue::new();")?;
-        self.emit_line("Renderer::render(app.world_mut(), &mut queue);")?;
-        writeln!(self.output)?;
-        self.emit_line("let renderer = omnicraft_runtime::render::wasm::CanvasRenderer::new(\"canvas\")?;")?;
-        self.emit_line("renderer.execute(&queue.commands);")?;
-        writeln!(self.output)?;
-        
-        self.emit_line("Ok(())")?;
-        
+        self.emit_line("{")?;
+        self.indent += 1;
+        for signal in &signals {
+            self.emit_line(&format!("let {0} = {1}{0}.clone();", signal, source))?;
+        }
+        for local in self.locals_in(content).into_iter().chain(names_in(&self.region_locals, content)) {
+            self.emit_line(&format!("let {0} = {0}.clone();", local))?;
+        }
+        self.emit_line(&format!(
+            "world.bind_value({}, BoundProperty::Text, move || {}.to_string());",
+            entity, content
+        ))?;
         self.indent -= 1;
         self.emit_line("}")?;
+        for signal in &signals {
+            self.emit_line(&format!(
+                "world.unsubscribe_on_unmount({1}, {0}{2}.subscribe(world.binding_trigger({1}, BoundProperty::Text)));",
+                source, entity, signal
+            ))?;
+        }
+        Ok(())
+    }
 
+    /// Register a handler for each `@event={...}` of an element
+    fn emit_event_handlers(&mut self, entity: &str, directives: &[Directive]) -> Result<()> {
+        let source = if self.region_depth == 0 { "self." } else { "" };
+        for directive in directives.iter().filter(|d| d.name == DirectiveName::On) {
+            let event = rust_string_literal(directive.arg.as_deref().unwrap_or_default());
+            let modifiers = event_modifiers(&directive.modifiers);
+            let call = self.handler_call(&directive.value);
+            let register = format!(
+                "world.on_event({}, {}, {}, move |event: &InputEvent| {{ {}; }});",
+                entity, event, modifiers, call
+            );
+
+            let signals = self.fields_in(&call);
+            let props = self.locals_in(&call);
+            if signals.is_empty() && props.is_empty() {
+                self.emit_line(&register)?;
+                continue;
+            }
+            self.emit_line("{")?;
+            self.indent += 1;
+            for signal in signals {
+                self.emit_line(&format!("let {0} = {1}{0}.clone();", signal, source))?;
+            }
+            for prop in props {
+                self.emit_line(&format!("let {0} = {0}.clone();", prop))?;
+            }
+            self.emit_line(&register)?;
+            self.indent -= 1;
+            self.emit_line("}")?;
+        }
         Ok(())
     }
 
-    // ========================================================================
-    // Helper Methods
-    // ========================================================================
+    /// Sync each `bind:prop={signal}` of an element with its signal in both
+    /// directions; the analyzer has checked the signal is bindable
+    fn emit_bindings(&mut self, entity: &str, directives: &[Directive]) -> Result<()> {
+        let source = if self.region_depth == 0 { "self." } else { "" };
+        for directive in directives.iter().filter(|d| d.name == DirectiveName::Bind) {
+            let ExpressionKind::Identifier(signal) = &directive.value.kind else {
+                continue;
+            };
+            let property = self.to_pascal_case(directive.arg.as_deref().unwrap_or_default());
+            self.emit_line(&format!(
+                "world.bind_property({}, BoundProperty::{}, {}{}.clone());",
+                entity, property, source, signal
+            ))?;
+        }
+        Ok(())
+    }
 
-    fn emit_line(&mut self, line: &str) -> Result<()> {
-        let indent = "    ".repeat(self.indent);
-        writeln!(self.output, "{}{}", indent, line)?;
+    /// Point each `ref={...}` variable at the entity, then run each
+    /// `use:action={...}` on it
+    fn emit_refs_and_actions(&mut self, entity: &str, directives: &[Directive]) -> Result<()> {
+        let source = if self.region_depth == 0 { "self." } else { "" };
+        for directive in directives.iter().filter(|d| d.name == DirectiveName::Ref) {
+            if let ExpressionKind::Identifier(entity_ref) = &directive.value.kind {
+                self.emit_line(&format!("world.bind_ref({}, &{}{});", entity, source, entity_ref))?;
+            }
+        }
+        for directive in directives.iter().filter(|d| d.name == DirectiveName::Use) {
+            let options = match &directive.value.kind {
+                ExpressionKind::Literal(Literal::Null) => "()".to_string(),
+                _ => self.expr_to_rust(&directive.value),
+            };
+            let action = directive.arg.as_deref().unwrap_or_default();
+            if self.actions.contains_key(action) {
+                // Script functions are methods, called through the component
+                self.emit_line(&format!(
+                    "world.use_action({}, {{ let this = this.clone(); move |_: &mut World, entity, options| this.{}(entity, options) }}, {});",
+                    entity, action, options
+                ))?;
+            } else {
+                self.emit_line(&format!("world.use_action({}, {}, {});", entity, action, options))?;
+            }
+        }
         Ok(())
     }
 
-    fn expr_to_rust(&self, expr: &Expression) -> String {
-        match expr {
-            Expression::Literal(Literal::Number(n)) => {
-                if n.fract() == 0.0 {
-                    format!("{:.1}", n)
-                } else {
-                    format!("{}", n)
-                }
+    /// Component fields read by `code`: signals and element refs
+    fn fields_in(&self, code: &str) -> Vec<String> {
+        let mut fields = names_in(&self.signals, code);
+        fields.extend(names_in(&self.refs, code));
+        fields
+    }
+
+    /// Locals of `mount` read by `code`, which closures capture: props,
+    /// and `this` for calls to the script's functions
+    fn locals_in(&self, code: &str) -> Vec<String> {
+        let mut locals = names_in(&self.props, code);
+        if self.uses_this(code) {
+            locals.push("this".to_string());
+        }
+        locals
+    }
+
+    /// Whether `code` calls a method for one of the script's functions
+    fn uses_this(&self, code: &str) -> bool {
+        !self.functions.is_empty() && !names_in(&["this".to_string()], code).is_empty()
+    }
+
+    /// Call a handler expression; handlers that take no parameters are
+    /// called without the event
+    fn handler_call(&self, handler: &Expression) -> String {
+        match &handler.kind {
+            ExpressionKind::Arrow { params, body, .. } if params.is_empty() => match body {
+                ArrowBody::Expression(e) => self.expr_to_rust(e),
+                ArrowBody::Block(body) => self.block_to_rust(body),
+            },
+            ExpressionKind::Identifier(name) if self.functions.get(name) == Some(&0) => format!("this.{}()", name),
+            ExpressionKind::Identifier(name) if self.functions.contains_key(name) => format!("this.{}(event)", name),
+            ExpressionKind::Identifier(name) => format!("{}(event)", name),
+            _ => format!("({})(event)", self.expr_to_rust(handler)),
+        }
+    }
+
+    /// Register the component's rules with the runtime stylesheet, one
+    /// rule per selector
+    fn emit_stylesheet(&mut self, scope: String, style: &Style) -> Result<()> {
+        self.emit_line(&format!(
+            "world.get_resource_or_insert_with(Stylesheet::new).insert({}, vec![",
+            rust_string_literal(&scope)
+        ))?;
+        self.indent += 1;
+        for rule in &style.rules {
+            let fields: Vec<(&str, Option<String>)> = cascade::RUNTIME_PROPERTIES
+                .iter()
+                .map(|property| {
+                    let value = rule
+                        .declarations
+                        .iter()
+                        .rev()
+                        .find(|d| d.property == *property)
+                        .map(|d| self.attr_value_to_rust(&AttributeValue::Static(cascade::css_value(&d.value))));
+                    match *property {
+                        "fill" => ("fill", value.map(|v| format!("Color::parse({})", v))),
+                        "stroke" => ("stroke", value.map(|v| format!("Color::parse({})", v))),
+                        "stroke-width" => ("stroke_width", value),
+                        _ => ("opacity", value),
+                    }
+                })
+                .collect();
+            if fields.iter().all(|(_, value)| value.is_none()) {
+                continue;
             }
-            Expression::Literal(Literal::String(s)) => format!("\"{}\"", s),
-            Expression::Literal(Literal::Boolean(b)) => format!("{}", b),
-            Expression::Literal(Literal::Null) => "None".to_string(),
-            Expression::Identifier(name) => name.clone(),
-            Expression::Binary { left, op, right } => {
-                let l = self.expr_to_rust(left);
+            let declarations = style_declarations(&fields);
+            for selector in &rule.selectors {
+                self.emit_line(&format!(
+                    "StyleRule::new({}, {}),",
+                    rust_string_literal(&selector_text(selector)),
+                    declarations
+                ))?;
+            }
+        }
+        self.indent -= 1;
+        self.emit_line("]);")?;
+        writeln!(self.output)?;
+        Ok(())
+    }
+
+    /// Instantiate and mount a child component, passing its attributes as
+    /// props and its children as slot content
+    fn emit_child_component(
+        &mut self,
+        name: &str,
+        attributes: &[Attribute],
+        directives: &[Directive],
+        children: &[Node],
+        index: usize,
+    ) -> Result<()> {
+        let mut fields = Vec::new();
+        let mut base = "Default::default()".to_string();
+        for attribute in attributes {
+            let value = match &attribute.value {
+                AttributeValue::Spread(expr) => {
+                    base = format!("{}.clone()", self.expr_to_rust(expr));
+                    continue;
+                }
+                // Props own their strings
+                AttributeValue::Static(Literal::String(_))
+                | AttributeValue::Dynamic(Expression {
+                    kind: ExpressionKind::Literal(Literal::String(_)),
+                    ..
+                }) => format!("{}.to_string()", self.attr_value_to_rust(&attribute.value)),
+                value => self.attr_value_to_rust(value),
+            };
+            fields.push(format!("{}: {}", attribute.name.replace('-', "_"), value));
+        }
+        if self.emit_slot_content(directives, children, index)? {
+            fields.push(format!("slots: slots_{}", index));
+        }
+        fields.push(format!("..{}", base));
+
+        let child = format!("child_{}", index);
+        let name = self.to_pascal_case(name);
+        self.emit_line(&format!(
+            "let {} = {}Component::with_props(ctx, {} {{ {} }});",
+            child,
+            name,
+            self.props_struct_name(&name),
+            fields.join(", ")
+        ))?;
+
+        // Handlers, and a parent's layout, need an entity standing for the
+        // component, which its entities record as their parent
+        let has_handlers = directives.iter().any(|d| d.name == DirectiveName::On);
+        let parent = self.parents.last().filter(|parent| parent.linked).map(|parent| parent.binding.clone());
+        if !has_handlers && parent.is_none() {
+            if self.region_depth > 0 {
+                self.emit_line(&format!("entities.extend(world.mount_child(&{}));", child))?;
+            } else {
+                self.emit_line(&format!("{}.mount(world);", child))?;
+            }
+            writeln!(self.output)?;
+            return Ok(());
+        }
+
+        let entity_name = format!("entity_{}", index);
+        let mounted = format!("mounted_{}", index);
+        self.emit_line(&format!("let {} = world.spawn(Transform::new()).id();", entity_name))?;
+        if let Some(parent) = parent {
+            self.emit_line(&format!("world.entity_mut({}).insert(Parent({}));", entity_name, parent))?;
+        }
+        self.emit_event_handlers(&entity_name, directives)?;
+        self.emit_line(&format!("let {} = world.mount_child(&{});", mounted, child))?;
+        self.emit_line(&format!("world.adopt({}, &{});", entity_name, mounted))?;
+        if self.region_depth > 0 {
+            self.emit_line(&format!("entities.push({});", entity_name))?;
+            self.emit_line(&format!("entities.extend({});", mounted))?;
+        }
+        writeln!(self.output)?;
+        Ok(())
+    }
+
+    fn emit_nodes(&mut self, nodes: &[Node], index: usize) -> Result<()> {
+        for (i, node) in nodes.iter().enumerate() {
+            self.emit_node(node, index * 100 + i)?;
+        }
+        Ok(())
+    }
+
+    /// Emit a control-flow block as a region whose entities are despawned
+    /// and spawned again when the signals in its header change
+    fn emit_region(&mut self, node: &Node, index: usize) -> Result<()> {
+        let region = format!("region_{}", index);
+        // Signals are fields of the component outside render functions
+        let source = if self.region_depth == 0 { "self." } else { "" };
+
+        // A promise is created once; the region re-renders when it settles
+        let mut captures = Vec::new();
+        let mut triggers = Vec::new();
+        if let NodeKind::AwaitBlock { promise, .. } = &node.kind {
+            let name = format!("promise_{}", index);
+            self.emit_line(&format!("let {} = {};", name, self.expr_to_rust(promise)))?;
+            captures.push(format!("let {0} = {0}.clone();", name));
+            triggers.push(name);
+        }
+
+        // Render the body first to find the signals it captures
+        let outer = std::mem::take(&mut self.output);
+        self.indent += 2;
+        self.region_depth += 1;
+        self.emit_line("let mut entities = Vec::new();")?;
+        let header = self.emit_region_body(node, index)?;
+        self.emit_line("entities")?;
+        self.region_depth -= 1;
+        self.indent -= 2;
+        let mut body = std::mem::replace(&mut self.output, outer);
+
+        if let Some(capture) = capture_context(&mut body) {
+            captures.push(capture);
+        }
+        for signal in self.fields_in(&body) {
+            captures.push(format!("let {0} = {1}{0}.clone();", signal, source));
+        }
+        // Props are locals everywhere the template runs
+        for prop in self.locals_in(&body) {
+            captures.push(format!("let {0} = {0}.clone();", prop));
+        }
+        for signal in names_in(&self.signals, &header) {
+            triggers.push(format!("{}{}", source, signal));
+        }
+
+        self.emit_line(&format!("let {} = {{", region))?;
+        self.indent += 1;
+        for capture in &captures {
+            self.emit_line(capture)?;
+        }
+        self.emit_line("world.spawn_region(move |world: &mut World| {")?;
+        self.output.push_str(&body);
+        self.emit_line("})")?;
+        self.indent -= 1;
+        self.emit_line("};")?;
+        // The subscriptions go away with the region, which an enclosing
+        // region despawns whenever it re-renders
+        for trigger in &triggers {
+            self.emit_line(&format!(
+                "world.unsubscribe_on_unmount({0}, {1}.subscribe(world.region_trigger({0})));",
+                region, trigger
+            ))?;
+        }
+        if self.region_depth > 0 {
+            self.emit_line(&format!("entities.push({});", region))?;
+        }
+        writeln!(self.output)?;
+        Ok(())
+    }
+
+    /// Emit the render function body of a block, returning the code of its
+    /// header expressions
+    fn emit_region_body(&mut self, node: &Node, index: usize) -> Result<String> {
+        let mut header = String::new();
+        match &node.kind {
+            NodeKind::IfBlock { .. } => {
+                // `{:else if}` chains become `else if` in the same region
+                let mut keyword = "if";
+                let mut current = node;
+                while let NodeKind::IfBlock { condition, then_branch, else_branch } = &current.kind {
+                    let condition = self.expr_to_rust(condition);
+                    self.emit_line(&format!("{} {} {{", keyword, condition))?;
+                    header.push_str(&condition);
+                    self.indent += 1;
+                    self.emit_nodes(then_branch, index)?;
+                    self.indent -= 1;
+                    match else_branch.as_deref() {
+                        Some([nested @ Node { kind: NodeKind::IfBlock { .. }, .. }]) => {
+                            keyword = "} else if";
+                            current = nested;
+                        }
+                        Some(nodes) => {
+                            self.emit_line("} else {")?;
+                            self.indent += 1;
+                            self.emit_nodes(nodes, index)?;
+                            self.indent -= 1;
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+                self.emit_line("}")?;
+            }
+            NodeKind::EachBlock { expression, binding, index: position, body, key } => {
+                let items = self.expr_to_rust(expression);
+                let body = match key {
+                    Some(key) => key_each_roots(body, key),
+                    None => body.clone(),
+                };
+                match position {
+                    Some(position) => self.emit_line(&format!(
+                        "for ({}, {}) in {}.into_iter().enumerate() {{",
+                        position, binding, items
+                    ))?,
+                    None => self.emit_line(&format!("for {} in {} {{", binding, items))?,
+                }
+                header.push_str(&items);
+                self.indent += 1;
+                if let Some(position) = position {
+                    // Script numbers are `f64`
+                    self.emit_line(&format!("let {0} = {0} as f64;", position))?;
+                }
+                let locals = self.region_locals.len();
+                self.region_locals.push(binding.clone());
+                self.region_locals.extend(position.clone());
+                self.emit_nodes(&body, index)?;
+                self.region_locals.truncate(locals);
+                self.indent -= 1;
+                self.emit_line("}")?;
+            }
+            NodeKind::AwaitBlock { pending, then_branch, catch_branch, .. } => {
+                self.emit_line(&format!("match promise_{}.state() {{", index))?;
+                self.indent += 1;
+                let branches = [
+                    ("PromiseState::Pending".to_string(), Some(pending.as_slice())),
+                    await_arm("Resolved", then_branch),
+                    await_arm("Rejected", catch_branch),
+                ];
+                let bindings = [&None, then_branch, catch_branch]
+                    .map(|branch| branch.as_ref().and_then(|b| b.binding.clone()));
+                for ((arm, nodes), binding) in branches.into_iter().zip(bindings) {
+                    match nodes {
+                        Some(nodes) if !nodes.is_empty() => {
+                            self.emit_line(&format!("{} => {{", arm))?;
+                            self.indent += 1;
+                            let locals = self.region_locals.len();
+                            self.region_locals.extend(binding);
+                            self.emit_nodes(nodes, index)?;
+                            self.region_locals.truncate(locals);
+                            self.indent -= 1;
+                            self.emit_line("}")?;
+                        }
+                        _ => self.emit_line(&format!("{} => {{}}", arm))?,
+                    }
+                }
+                self.indent -= 1;
+                self.emit_line("}")?;
+            }
+            _ => {}
+        }
+        Ok(header)
+    }
+
+    /// Bind every prop to a local of the same name, read from `source`
+    fn emit_prop_bindings(&mut self, source: &str) -> Result<()> {
+        for prop in self.props.clone() {
+            self.emit_line(&format!("let {0} = {1}{0}.clone();", prop, source))?;
+        }
+        Ok(())
+    }
+
+    fn emit_new_fn(&mut self, component: &Component, struct_name: &str) -> Result<()> {
+        writeln!(self.output, "impl {} {{", struct_name)?;
+        self.indent += 1;
+
+        self.emit_line("/// Create a new component instance")?;
+        self.emit_line("pub fn new(ctx: &mut Context) -> Self {")?;
+        self.indent += 1;
+        self.emit_line("Self::create(ctx)")?;
+        self.indent -= 1;
+        self.emit_line("}")?;
+        writeln!(self.output)?;
+
+        self.emit_with_props_fn(component)?;
+        self.emit_methods(component)?;
+
+        self.indent -= 1;
+        writeln!(self.output, "}}")?;
+        Ok(())
+    }
+
+    /// Emit the functions the script declares as methods; their bodies
+    /// see the component's fields and props as locals, and reach the other
+    /// methods through `this`
+    /// ` -> T` for a function declaration, inferred from what the body
+    /// returns when there is no annotation
+    fn return_type_to_rust(&self, return_type: Option<&Type>, body: &[Statement]) -> String {
+        match return_type {
+            Some(Type::Function { .. }) => String::new(),
+            Some(ty) => format!(" -> {}", rust_type(ty)),
+            None => returned_expression(body)
+                .map(|value| format!(" -> {}", self.infer_expr_type(value)))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn emit_methods(&mut self, component: &Component) -> Result<()> {
+        let Some(script) = &component.script else {
+            return Ok(());
+        };
+        for stmt in &script.statements {
+            let StatementKind::FunctionDeclaration { name, params, return_type, body, .. } = &stmt.kind else {
+                continue;
+            };
+            let (rendered, prologue) = self.params_to_rust(params);
+            // Methods need a type on every parameter; a handler's first
+            // parameter is the event, an action's are the entity and options
+            let mut signature = vec!["&self".to_string()];
+            for (i, (param, rendered)) in params.iter().zip(rendered).enumerate() {
+                if !rust_annotation(param.ty.as_ref()).is_empty() {
+                    signature.push(rendered);
+                } else if i == 0 && self.handlers.contains(name) {
+                    signature.push(format!("{}: &InputEvent", rendered));
+                } else if let Some(options) = self.actions.get(name).filter(|_| i < 2) {
+                    let ty = if i == 0 { "Entity" } else { options };
+                    signature.push(format!("{}: {}", rendered, ty));
+                } else {
+                    signature.push(format!("{}: {}", rendered, self.infer_type(None)));
+                }
+            }
+            let return_type = self.return_type_to_rust(return_type.as_ref(), body);
+            let statements: Vec<String> = prologue
+                .into_iter()
+                .chain(body.iter().map(|stmt| self.stmt_to_rust(stmt)))
+                .collect();
+            let code = statements.join("\n");
+
+            writeln!(self.output)?;
+            self.emit_line(&format!("fn {}({}){} {{", name, signature.join(", "), return_type))?;
+            self.indent += 1;
+            for field in self.fields_in(&code) {
+                self.emit_line(&format!("let {0} = self.{0}.clone();", field))?;
+            }
+            for prop in names_in(&self.props, &code) {
+                self.emit_line(&format!("let {0} = self.props.{0}.clone();", prop))?;
+            }
+            if self.uses_this(&code) {
+                self.emit_line("let this = self;")?;
+            }
+            for statement in &statements {
+                self.emit_line(statement)?;
+            }
+            self.indent -= 1;
+            self.emit_line("}")?;
+        }
+        Ok(())
+    }
+
+    fn emit_wasm_entry_point(&mut self, _component: &Component, struct_name: &str) -> Result<()> {
+        writeln!(self.output)?;
+        self.emit_line("/// WASM Entry Point")?;
+        // The canvas renderer only exists on the web, and `App::new`
+        // installs the panic hook there
+        self.emit_line("#[cfg(target_arch = \"wasm32\")]")?;
+        self.emit_line("#[wasm_bindgen(start)]")?;
+        self.emit_line("pub fn start() -> Result<(), JsValue> {")?;
+        self.indent += 1;
+
+        self.emit_line("let mut app = omnicraft_runtime::App::new();")?;
+        self.emit_line("let mut ctx = Context::new();")?;
+        self.emit_line(&format!("let component = {}::new(&mut ctx);", struct_name))?;
+        self.emit_line("component.mount(app.world_mut());")?;
+        writeln!(self.output)?;
+
+        // The runtime owns the app from here on, drawing it to the canvas
+        // and forwarding the canvas' input to it
+        self.emit_line("omnicraft_runtime::render::wasm::run(app, \"canvas\")")?;
+
+        self.indent -= 1;
+        self.emit_line("}")?;
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Helper Methods
+    // ========================================================================
+
+    fn emit_line(&mut self, line: &str) -> Result<()> {
+        let indent = "    ".repeat(self.indent);
+        writeln!(self.output, "{}{}", indent, line)?;
+        Ok(())
+    }
+
+    /// An expression stored in a signal or a collection, whose string
+    /// literals become `String` to match the inferred type
+    fn owned_to_rust(&self, expr: &Expression) -> String {
+        match &expr.kind {
+            ExpressionKind::Literal(Literal::String(_)) => format!("{}.to_string()", self.expr_to_rust(expr)),
+            _ => self.expr_to_rust(expr),
+        }
+    }
+
+    fn expr_to_rust(&self, expr: &Expression) -> String {
+        match &expr.kind {
+            ExpressionKind::Literal(Literal::Number(n)) => rust_number(*n),
+            ExpressionKind::Literal(Literal::Dimension(n, unit)) => rust_number(unit.to_base(*n)),
+            ExpressionKind::Literal(Literal::String(s)) => rust_string_literal(s),
+            ExpressionKind::Literal(Literal::Boolean(b)) => format!("{}", b),
+            ExpressionKind::Literal(Literal::Null) => "None".to_string(),
+            ExpressionKind::Identifier(name) => name.clone(),
+            ExpressionKind::Binary { left, op, right } => {
+                let l = self.expr_to_rust(left);
                 let r = self.expr_to_rust(right);
+                if *op == BinaryOp::Nullish {
+                    return format!("{}.unwrap_or_else(|| {})", l, r);
+                }
                 let op_str = match op {
                     BinaryOp::Add => "+",
                     BinaryOp::Sub => "-",
@@ -377,54 +1617,96 @@ impl RustGenerator {
                     BinaryOp::Ge => ">=",
                     BinaryOp::And => "&&",
                     BinaryOp::Or => "||",
+                    BinaryOp::Nullish => unreachable!("lowered above"),
                 };
                 format!("({} {} {})", l, op_str, r)
             }
-            Expression::Unary { op, operand } => {
+            ExpressionKind::Unary { op, operand } => {
                 let inner = self.expr_to_rust(operand);
                 match op {
                     UnaryOp::Neg => format!("-{}", inner),
                     UnaryOp::Not => format!("!{}", inner),
                 }
             }
-            Expression::Call { callee, args } => {
-                let callee_str = self.expr_to_rust(callee);
-                let args_str: Vec<String> = args.iter().map(|a| self.expr_to_rust(a)).collect();
-                format!("{}({})", callee_str, args_str.join(", "))
-            }
-            Expression::Member { object, property, .. } => {
-                let obj = self.expr_to_rust(object);
-                format!("{}.{}", obj, property)
+            // Signals are written through `set`
+            ExpressionKind::Assign { op, target, value } if self.signal_target(target).is_some() => {
+                let name = self.signal_target(target).unwrap_or_default();
+                let value = self.expr_to_rust(value);
+                let op_str = match op {
+                    AssignOp::Assign => return format!("{}.set({})", name, value),
+                    AssignOp::Add => "+",
+                    AssignOp::Sub => "-",
+                    AssignOp::Mul => "*",
+                    AssignOp::Div => "/",
+                    AssignOp::Mod => "%",
+                };
+                format!("{}.set({}.get() {} {})", name, name, op_str, value)
             }
-            Expression::Index { object, index } => {
-                let obj = self.expr_to_rust(object);
-                let idx = self.expr_to_rust(index);
-                format!("{}[{}]", obj, idx)
+            ExpressionKind::Update { op, prefix, target } if self.signal_target(target).is_some() => {
+                let name = self.signal_target(target).unwrap_or_default();
+                let step = signal_update_to_rust(*op, name, &format!("{}.get()", name));
+                if *prefix {
+                    format!("{{ {}; {}.get() }}", step, name)
+                } else {
+                    let step = signal_update_to_rust(*op, name, "old");
+                    format!("{{ let old = {}.get(); {}; old }}", name, step)
+                }
             }
-            Expression::Arrow { params, body } => {
-                let params_str: Vec<String> = params.iter().map(|p| p.name.clone()).collect();
-                let body_str = match body {
-                    ArrowBody::Expression(e) => self.expr_to_rust(e),
-                    ArrowBody::Block(_) => "{ /* block */ }".to_string(),
+            ExpressionKind::Assign { op, target, value } => {
+                let op_str = match op {
+                    AssignOp::Assign => "=",
+                    AssignOp::Add => "+=",
+                    AssignOp::Sub => "-=",
+                    AssignOp::Mul => "*=",
+                    AssignOp::Div => "/=",
+                    AssignOp::Mod => "%=",
                 };
-                if params_str.is_empty() {
-                    format!("|| {}", body_str)
+                format!("{} {} {}", self.expr_to_rust(target), op_str, self.expr_to_rust(value))
+            }
+            ExpressionKind::Update { op, prefix, target } => {
+                let target = self.expr_to_rust(target);
+                let step = update_to_rust(*op, &target);
+                if *prefix {
+                    format!("{{ {}; {} }}", step, target)
                 } else {
-                    format!("|{}| {}", params_str.join(", "), body_str)
+                    format!("{{ let old = {}; {}; old }}", target, step)
                 }
             }
-            Expression::Ternary { condition, then_expr, else_expr } => {
+            ExpressionKind::Call { .. }
+            | ExpressionKind::Member { .. }
+            | ExpressionKind::Index { .. } => self.chain_to_rust(expr).0,
+            ExpressionKind::Arrow { params, return_type, body } => {
+                let (params_str, prologue) = self.params_to_rust(params);
+                // A closure with a return type needs a block body
+                let return_type = match return_type {
+                    Some(Type::Function { .. }) | None => None,
+                    Some(ty) => Some(rust_type(ty)),
+                };
+                let body_str = match body {
+                    ArrowBody::Expression(e) if prologue.is_empty() && return_type.is_none() => {
+                        self.expr_to_rust(e)
+                    }
+                    ArrowBody::Expression(e) => {
+                        let statements = prologue.iter().map(|s| format!("{} ", s)).collect::<String>();
+                        format!("{{ {}{} }}", statements, self.expr_to_rust(e))
+                    }
+                    ArrowBody::Block(body) => self.block_with_prologue(prologue, body),
+                };
+                let return_type = return_type.map(|ty| format!(" -> {}", ty)).unwrap_or_default();
+                format!("|{}|{} {}", params_str.join(", "), return_type, body_str)
+            }
+            ExpressionKind::Ternary { condition, then_expr, else_expr } => {
                 let cond = self.expr_to_rust(condition);
                 let then_str = self.expr_to_rust(then_expr);
                 let else_str = self.expr_to_rust(else_expr);
                 format!("if {} {{ {} }} else {{ {} }}", cond, then_str, else_str)
             }
-            Expression::Template { parts } => {
+            ExpressionKind::Template { parts } => {
                 let mut result = String::from("format!(\"");
                 let mut args = Vec::new();
                 for part in parts {
                     match part {
-                        TemplatePart::String(s) => result.push_str(s),
+                        TemplatePart::String(s) => result.push_str(&escape_format_text(s)),
                         TemplatePart::Expression(e) => {
                             result.push_str("{}");
                             args.push(self.expr_to_rust(e));
@@ -439,72 +1721,531 @@ impl RustGenerator {
                 result.push(')');
                 result
             }
-            Expression::Array(elements) => {
-                let elems: Vec<String> = elements.iter().map(|e| self.expr_to_rust(e)).collect();
-                format!("vec![{}]", elems.join(", "))
+            ExpressionKind::Array(elements) => {
+                let ty = self.infer_expr_type(expr);
+                self.array_to_rust(elements, vec_element_type(&ty).unwrap_or(UNTYPED))
             }
-            Expression::Object(props) => {
-                // For now, generate a HashMap
-                let pairs: Vec<String> = props.iter()
-                    .map(|(k, v)| format!("(\"{}\".to_string(), {})", k, self.expr_to_rust(v)))
-                    .collect();
-                format!("HashMap::from([{}])", pairs.join(", "))
+            ExpressionKind::Object(props) => {
+                let ty = self.infer_expr_type(expr);
+                self.object_to_rust(props, map_value_type(&ty).unwrap_or(UNTYPED))
             }
+            // Spreads are lowered by the array literal containing them
+            ExpressionKind::Spread(inner) => self.expr_to_rust(inner),
+            ExpressionKind::Error => "unreachable!(\"expression with a parse error\")".to_string(),
         }
     }
 
-    fn find_attr_value(&self, attributes: &[Attribute], name: &str) -> Option<String> {
-        attributes.iter().find(|a| a.name == name).map(|a| {
-            match &a.value {
-                AttributeValue::Static(lit) => match lit {
-                    Literal::Number(n) => {
-                        if n.fract() == 0.0 {
-                            format!("{:.1}", n)
-                        } else {
-                            format!("{}", n)
-                        }
+    /// Lower an array literal to a `Vec` of `element`s
+    fn array_to_rust(&self, elements: &[Expression], element: &str) -> String {
+        if elements.iter().any(|e| matches!(e.kind, ExpressionKind::Spread(_))) {
+            let mut out = String::from("{ let mut items = Vec::new();");
+            for e in elements {
+                match &e.kind {
+                    ExpressionKind::Spread(inner) => {
+                        let ty = self.infer_expr_type(inner);
+                        let converted = convert_to("item", vec_element_type(&ty).unwrap_or(UNTYPED), element);
+                        let convert = match converted.as_str() {
+                            "item" => String::new(),
+                            _ => format!(".map(|item| {})", converted),
+                        };
+                        out.push_str(&format!(
+                            " items.extend({}.iter().cloned(){});",
+                            self.expr_to_rust(inner),
+                            convert
+                        ))
+                    }
+                    _ => out.push_str(&format!(" items.push({});", self.typed_to_rust(e, element))),
+                }
+            }
+            out.push_str(" items }");
+            return out;
+        }
+        let elems: Vec<String> = elements.iter().map(|e| self.typed_to_rust(e, element)).collect();
+        format!("vec![{}]", elems.join(", "))
+    }
+
+    /// Lower an object literal to a `HashMap` with `value`s
+    fn object_to_rust(&self, props: &[ObjectProperty], value: &str) -> String {
+        let entry = |key: &PropertyKey, v: &Expression| {
+            let key = match key {
+                PropertyKey::Static(name) => format!("{}.to_string()", rust_string_literal(name)),
+                PropertyKey::Computed(key) => format!("({}).to_string()", self.expr_to_rust(key)),
+            };
+            (key, self.typed_to_rust(v, value))
+        };
+        if props.iter().any(|p| matches!(p, ObjectProperty::Spread(_))) {
+            let mut out = String::from("{ let mut map = HashMap::new();");
+            for prop in props {
+                match prop {
+                    ObjectProperty::KeyValue { key, value } => {
+                        let (key, value) = entry(key, value);
+                        out.push_str(&format!(" map.insert({}, {});", key, value));
+                    }
+                    ObjectProperty::Spread(inner) => {
+                        let ty = self.infer_expr_type(inner);
+                        let converted = convert_to("value", map_value_type(&ty).unwrap_or(UNTYPED), value);
+                        let convert = match converted.as_str() {
+                            "value" => String::new(),
+                            _ => format!(".into_iter().map(|(key, value)| (key, {}))", converted),
+                        };
+                        out.push_str(&format!(" map.extend({}.clone(){});", self.expr_to_rust(inner), convert))
                     }
-                    Literal::String(s) => format!("\"{}\"", s),
-                    Literal::Boolean(b) => format!("{}", b),
-                    Literal::Null => "None".to_string(),
+                }
+            }
+            out.push_str(" map }");
+            return out;
+        }
+        let pairs: Vec<String> = props
+            .iter()
+            .filter_map(|p| match p {
+                ObjectProperty::KeyValue { key, value } => {
+                    let (key, value) = entry(key, value);
+                    Some(format!("({}, {})", key, value))
+                }
+                ObjectProperty::Spread(_) => None,
+            })
+            .collect();
+        format!("HashMap::from([{}])", pairs.join(", "))
+    }
+
+    /// An owned value of the Rust type `ty`, converted to the runtime's
+    /// `Value` where a literal mixes value types
+    fn typed_to_rust(&self, expr: &Expression, ty: &str) -> String {
+        match (&expr.kind, map_value_type(ty), vec_element_type(ty)) {
+            (ExpressionKind::Object(props), Some(value), _) => return self.object_to_rust(props, value),
+            (ExpressionKind::Array(elements), _, Some(element)) => return self.array_to_rust(elements, element),
+            _ => {}
+        }
+        convert_to(&self.owned_to_rust(expr), &self.infer_expr_type(expr), ty)
+    }
+
+    /// Lower a member, index and call chain, along with the Rust type it
+    /// evaluates to. Members of maps are looked up by key. An optional
+    /// link on an `Option` wraps the rest of the chain in `Option::map`, or
+    /// `and_then` if the rest is an `Option` too; on anything else it is
+    /// an ordinary link.
+    fn chain_to_rust(&self, expr: &Expression) -> (String, String) {
+        let mut links = Vec::new();
+        let mut base = expr;
+        loop {
+            match &base.kind {
+                ExpressionKind::Member { object, .. } | ExpressionKind::Index { object, .. } => {
+                    links.push(base);
+                    base = object;
+                }
+                ExpressionKind::Call { callee, .. } => {
+                    links.push(base);
+                    base = callee;
+                }
+                _ => break,
+            }
+        }
+        links.reverse();
+        // Reading a signal is calling it without arguments, and the
+        // script's functions are methods
+        let call = |link: Option<&&Expression>| match link.map(|link| &link.kind) {
+            Some(ExpressionKind::Call { args, optional: false, .. }) => Some(args.is_empty()),
+            _ => None,
+        };
+        match &base.kind {
+            ExpressionKind::Identifier(name) if self.signals.contains(name) && call(links.first()) == Some(true) => {
+                let ty = self.types.get(name).cloned().unwrap_or_else(|| UNTYPED.to_string());
+                self.links_to_rust(format!("{}.get()", name), ty, &links[1..])
+            }
+            ExpressionKind::Identifier(name) if self.functions.contains_key(name) && call(links.first()).is_some() => {
+                self.links_to_rust(format!("this.{}", name), UNTYPED.to_string(), &links)
+            }
+            _ => self.links_to_rust(self.expr_to_rust(base), self.infer_expr_type(base), &links),
+        }
+    }
+
+    fn links_to_rust(&self, mut out: String, mut ty: String, links: &[&Expression]) -> (String, String) {
+        for (i, link) in links.iter().enumerate() {
+            let unwrap = is_optional_link(link) && option_inner(&ty).is_some();
+            let receiver = if unwrap { option_inner(&ty).unwrap_or(UNTYPED) } else { ty.as_str() };
+            let (suffix, next) = match &link.kind {
+                ExpressionKind::Member { property, .. } => match map_value_type(receiver) {
+                    Some(value) => (format!("[{}].clone()", rust_string_literal(property)), value),
+                    None if receiver == "Value" => (format!(".get({})", rust_string_literal(property)), "Value"),
+                    None => (format!(".{}", property), UNTYPED),
+                },
+                ExpressionKind::Index { index, .. } => match vec_element_type(receiver) {
+                    // Script numbers are floats
+                    Some(element) => {
+                        let index = match &index.kind {
+                            ExpressionKind::Literal(Literal::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => {
+                                format!("{}", *n as usize)
+                            }
+                            _ => format!("{} as usize", self.expr_to_rust(index)),
+                        };
+                        (format!("[{}].clone()", index), element)
+                    }
+                    None => (
+                        format!("[{}]", self.expr_to_rust(index)),
+                        map_value_type(receiver).unwrap_or(UNTYPED),
+                    ),
                 },
-                AttributeValue::Dynamic(expr) => self.expr_to_rust(expr),
-                AttributeValue::Spread(_) => "/* spread */".to_string(),
-                AttributeValue::Boolean(b) => format!("{}", b),
+                ExpressionKind::Call { args, .. } => {
+                    let args: Vec<String> = args.iter().map(|a| self.expr_to_rust(a)).collect();
+                    (format!("({})", args.join(", ")), UNTYPED)
+                }
+                _ => unreachable!("chain links are members, indexes and calls"),
+            };
+            let next = next.to_string();
+            if unwrap {
+                let (body, body_ty) = self.links_to_rust(format!("v{}", suffix), next, &links[i + 1..]);
+                return if option_inner(&body_ty).is_some() {
+                    (format!("{}.as_ref().and_then(|v| {})", out, body), body_ty)
+                } else {
+                    (format!("{}.as_ref().map(|v| {})", out, body), format!("Option<{}>", body_ty))
+                };
+            }
+            out.push_str(&suffix);
+            ty = next;
+        }
+        (out, ty)
+    }
+
+    /// Closure parameter names, and the statements destructuring the
+    /// parameters that are patterns
+    fn params_to_rust(&self, params: &[Parameter]) -> (Vec<String>, Vec<String>) {
+        let mut names = Vec::new();
+        let mut prologue = Vec::new();
+        for (i, param) in params.iter().enumerate() {
+            let annotation = rust_annotation(param.ty.as_ref());
+            match &param.pattern {
+                Pattern::Identifier(name) => names.push(format!("{}{}", name, annotation)),
+                pattern => {
+                    let name = format!("arg{}", i);
+                    self.pattern_to_rust(pattern, &name, false, &mut prologue);
+                    names.push(format!("{}{}", name, annotation));
+                }
+            }
+        }
+        (names, prologue)
+    }
+
+    /// Emit `let` statements binding the names in `pattern` from `source`
+    fn pattern_to_rust(&self, pattern: &Pattern, source: &str, mutable: bool, out: &mut Vec<String>) {
+        let binding = |name: &str| if mutable { format!("mut {}", name) } else { name.to_string() };
+        let element_source = |element: &BindingElement, key: String| match &element.default {
+            Some(default) => format!(
+                "{}.get({}).cloned().unwrap_or_else(|| {})",
+                source,
+                key,
+                self.expr_to_rust(default)
+            ),
+            None => format!("{}[{}].clone()", source, key),
+        };
+        match pattern {
+            Pattern::Identifier(name) => out.push(format!("let {} = {};", binding(name), source)),
+            Pattern::Object { properties, rest } => {
+                for (key, element) in properties {
+                    let value = element_source(element, rust_string_literal(key));
+                    self.pattern_to_rust(&element.pattern, &value, mutable, out);
+                }
+                if let Some(rest) = rest {
+                    let removed: String = properties
+                        .iter()
+                        .map(|(key, _)| format!(" {}.remove({});", rest, rust_string_literal(key)))
+                        .collect();
+                    out.push(format!(
+                        "let {} = {{ let mut {} = {}.clone();{} {} }};",
+                        binding(rest),
+                        rest,
+                        source,
+                        removed,
+                        rest
+                    ));
+                }
+            }
+            Pattern::Array { elements, rest } => {
+                for (i, element) in elements.iter().enumerate() {
+                    if let Some(element) = element {
+                        let value = element_source(element, i.to_string());
+                        self.pattern_to_rust(&element.pattern, &value, mutable, out);
+                    }
+                }
+                if let Some(rest) = rest {
+                    out.push(format!("let {} = {}[{}..].to_vec();", binding(rest), source, elements.len()));
+                }
+            }
+        }
+    }
+
+    /// Render statements as a single-line Rust block
+    fn block_to_rust(&self, statements: &[Statement]) -> String {
+        self.block_with_prologue(Vec::new(), statements)
+    }
+
+    /// Render a block that starts with already rendered statements
+    fn block_with_prologue(&self, mut body: Vec<String>, statements: &[Statement]) -> String {
+        body.extend(statements.iter().map(|s| self.stmt_to_rust(s)));
+        if body.is_empty() {
+            "{}".to_string()
+        } else {
+            format!("{{ {} }}", body.join(" "))
+        }
+    }
+
+    fn stmt_to_rust(&self, stmt: &Statement) -> String {
+        match &stmt.kind {
+            StatementKind::VariableDeclaration { kind, name, ty, init, .. } => {
+                let binding = match kind {
+                    VarKind::Const => name.clone(),
+                    VarKind::Let => format!("mut {}", name),
+                } + &rust_annotation(ty.as_ref());
+                match init {
+                    Some(init) => format!("let {} = {};", binding, self.expr_to_rust(init)),
+                    None => format!("let {};", binding),
+                }
+            }
+            StatementKind::Destructure { kind, pattern, init } => {
+                let mut out = Vec::new();
+                let source = match &init.kind {
+                    ExpressionKind::Identifier(name) => name.clone(),
+                    _ => {
+                        out.push(format!("let destructured = {};", self.expr_to_rust(init)));
+                        "destructured".to_string()
+                    }
+                };
+                self.pattern_to_rust(pattern, &source, *kind == VarKind::Let, &mut out);
+                out.join(" ")
             }
+            StatementKind::FunctionDeclaration { name, params, return_type, body, .. } => {
+                let (params, prologue) = self.params_to_rust(params);
+                let return_type = match return_type {
+                    Some(Type::Function { .. }) | None => String::new(),
+                    Some(ty) => format!(" -> {}", rust_type(ty)),
+                };
+                format!(
+                    "let {} = |{}|{} {};",
+                    name,
+                    params.join(", "),
+                    return_type,
+                    self.block_with_prologue(prologue, body)
+                )
+            }
+            StatementKind::Expression(expr) => format!("{};", self.expr_stmt_to_rust(expr)),
+            StatementKind::Return(Some(expr)) => format!("return {};", self.expr_to_rust(expr)),
+            StatementKind::Return(None) => "return;".to_string(),
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let mut out = format!(
+                    "if {} {}",
+                    self.expr_to_rust(condition),
+                    self.block_to_rust(then_branch)
+                );
+                if let Some(else_branch) = else_branch {
+                    out.push_str(" else ");
+                    out.push_str(&self.block_to_rust(else_branch));
+                }
+                out
+            }
+            StatementKind::For {
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                // The update runs at the top of every iteration but the
+                // first, so `continue` still reaches it
+                let mut out = String::from("{ ");
+                if let Some(init) = init {
+                    out.push_str(&self.stmt_to_rust(init));
+                    out.push(' ');
+                }
+                out.push_str("let mut started = false; loop { ");
+                if let Some(update) = update {
+                    out.push_str(&format!("if started {{ {}; }} ", self.expr_stmt_to_rust(update)));
+                }
+                out.push_str("started = true; ");
+                if let Some(condition) = condition {
+                    out.push_str(&format!("if !({}) {{ break; }} ", self.expr_to_rust(condition)));
+                }
+                out.push_str(&format!("{} }} }}", self.block_to_rust(body)));
+                out
+            }
+            StatementKind::While { condition, body } => {
+                format!("while {} {}", self.expr_to_rust(condition), self.block_to_rust(body))
+            }
+            StatementKind::Block(body) => self.block_to_rust(body),
+            StatementKind::Export(declaration) => self.stmt_to_rust(declaration),
+            // Imports become `use` items at the top of the module
+            StatementKind::Import(_) => String::new(),
+            StatementKind::Break => "break;".to_string(),
+            StatementKind::Continue => "continue;".to_string(),
+            StatementKind::Error => "unreachable!(\"statement with a parse error\");".to_string(),
+        }
+    }
+
+    /// Render an expression whose value is discarded; `i++` needs no
+    /// block keeping the old value
+    fn expr_stmt_to_rust(&self, expr: &Expression) -> String {
+        match &expr.kind {
+            ExpressionKind::Update { op, target, .. } => match self.signal_target(target) {
+                Some(name) => signal_update_to_rust(*op, name, &format!("{}.get()", name)),
+                None => update_to_rust(*op, &self.expr_to_rust(target)),
+            },
+            _ => self.expr_to_rust(expr),
+        }
+    }
+
+    /// The signal an assignment or update writes to, if any
+    fn signal_target<'a>(&self, target: &'a Expression) -> Option<&'a str> {
+        match &target.kind {
+            ExpressionKind::Identifier(name) if self.signals.contains(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn find_attr_value(&self, attributes: &[Attribute], name: &str) -> Option<String> {
+        attributes
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| self.attr_value_to_rust(&a.value))
+    }
+
+    /// A number attribute as the `f32` the runtime's components store;
+    /// script numbers are `f64`
+    fn find_number_attr(&self, attributes: &[Attribute], name: &str) -> Option<String> {
+        attributes.iter().find(|a| a.name == name).map(|a| match &a.value {
+            AttributeValue::Dynamic(expr) => self.number_to_rust(expr),
+            value => self.attr_value_to_rust(value),
         })
     }
 
+    fn number_to_rust(&self, expr: &Expression) -> String {
+        let value = self.expr_to_rust(expr);
+        if self.infer_expr_type(expr) == "Value" {
+            return format!("{}.as_number() as f32", value);
+        }
+        match &expr.kind {
+            ExpressionKind::Literal(_) => value,
+            ExpressionKind::Identifier(_)
+            | ExpressionKind::Binary { .. }
+            | ExpressionKind::Call { .. }
+            | ExpressionKind::Member { .. }
+            | ExpressionKind::Index { .. } => format!("{} as f32", value),
+            _ => format!("({}) as f32", value),
+        }
+    }
+
+    /// A color attribute as the string `Color::parse` takes; dynamic values
+    /// may be owned strings
+    fn find_color_attr(&self, attributes: &[Attribute], name: &str) -> Option<String> {
+        attributes.iter().find(|a| a.name == name).map(|a| match &a.value {
+            AttributeValue::Dynamic(Expression { kind: ExpressionKind::Literal(_), .. }) | AttributeValue::Static(_) => {
+                self.attr_value_to_rust(&a.value)
+            }
+            value => format!("&{}", self.attr_value_to_rust(value)),
+        })
+    }
+
+    fn attr_value_to_rust(&self, value: &AttributeValue) -> String {
+        match value {
+            AttributeValue::Static(lit) => match lit {
+                Literal::Number(n) => rust_number(*n),
+                Literal::Dimension(n, unit) => rust_number(unit.to_base(*n)),
+                Literal::String(s) => rust_string_literal(s),
+                Literal::Boolean(b) => format!("{}", b),
+                Literal::Null => "None".to_string(),
+            },
+            AttributeValue::Dynamic(expr) => self.expr_to_rust(expr),
+            AttributeValue::Spread(_) => "/* spread */".to_string(),
+            AttributeValue::Boolean(b) => format!("{}", b),
+        }
+    }
+
     fn infer_type(&self, expr: Option<&Expression>) -> String {
         match expr {
-            Some(Expression::Call { callee, args, .. }) => {
-                if let Expression::Identifier(name) = callee.as_ref() {
-                    if name == "signal" || name == "memo" {
-                        if let Some(first_arg) = args.first() {
-                            return self.infer_expr_type(first_arg);
+            Some(Expression {
+                kind: ExpressionKind::Call { callee, args, .. },
+                ..
+            }) => {
+                if let ExpressionKind::Identifier(name) = &callee.kind
+                    && let Some(first_arg) = args.first()
+                {
+                    match (name.as_str(), &first_arg.kind) {
+                        ("signal", _) => return self.infer_expr_type(first_arg),
+                        // A memo holds what its function returns
+                        ("memo", ExpressionKind::Arrow { body: ArrowBody::Expression(value), .. }) => {
+                            return self.infer_expr_type(value);
+                        }
+                        ("memo", ExpressionKind::Arrow { body: ArrowBody::Block(body), .. }) => {
+                            return returned_expression(body)
+                                .map_or_else(|| "()".to_string(), |value| self.infer_expr_type(value));
                         }
+                        ("effect", _) => return "()".to_string(),
+                        _ => {}
                     }
                 }
-                "Any".to_string()
+                UNTYPED.to_string()
             }
             Some(expr) => self.infer_expr_type(expr),
-            None => "Any".to_string(),
+            None => UNTYPED.to_string(),
         }
     }
 
     fn infer_expr_type(&self, expr: &Expression) -> String {
-        match expr {
-            Expression::Literal(Literal::Number(_)) => "f64".to_string(),
-            Expression::Literal(Literal::String(_)) => "String".to_string(),
-            Expression::Literal(Literal::Boolean(_)) => "bool".to_string(),
-            Expression::Array(_) => "Vec<Any>".to_string(),
-            Expression::Arrow { .. } => "Fn()".to_string(),
-            _ => "Any".to_string(),
+        match &expr.kind {
+            ExpressionKind::Literal(Literal::Number(_) | Literal::Dimension(..)) => "f64".to_string(),
+            ExpressionKind::Literal(Literal::String(_)) => "String".to_string(),
+            ExpressionKind::Literal(Literal::Boolean(_)) => "bool".to_string(),
+            ExpressionKind::Array(elements) => {
+                let types = elements.iter().filter_map(|element| match &element.kind {
+                    ExpressionKind::Spread(inner) => {
+                        vec_element_type(&self.infer_expr_type(inner)).map(str::to_string)
+                    }
+                    _ => Some(self.infer_expr_type(element)),
+                });
+                format!("Vec<{}>", common_type(types))
+            }
+            // Object literals lower to maps, which need one value type
+            ExpressionKind::Object(properties) => {
+                let types = properties.iter().filter_map(|property| match property {
+                    ObjectProperty::KeyValue { value, .. } => Some(self.infer_expr_type(value)),
+                    ObjectProperty::Spread(inner) => map_value_type(&self.infer_expr_type(inner)).map(str::to_string),
+                });
+                format!("HashMap<String, {}>", common_type(types))
+            }
+            ExpressionKind::Member { .. } | ExpressionKind::Index { .. } => self.chain_to_rust(expr).1,
+            ExpressionKind::Arrow { .. } => "Fn()".to_string(),
+            ExpressionKind::Template { .. } => "String".to_string(),
+            ExpressionKind::Binary { left, op, right } => match op {
+                BinaryOp::Add if [left, right].iter().any(|side| self.infer_expr_type(side) == "String") => {
+                    "String".to_string()
+                }
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => "f64".to_string(),
+                BinaryOp::Nullish => self.infer_expr_type(right),
+                _ => "bool".to_string(),
+            },
+            ExpressionKind::Unary { op: UnaryOp::Neg, .. } => "f64".to_string(),
+            ExpressionKind::Unary { op: UnaryOp::Not, .. } => "bool".to_string(),
+            ExpressionKind::Ternary { then_expr, .. } => self.infer_expr_type(then_expr),
+            // Props, and signals read by calling them
+            ExpressionKind::Identifier(name) if self.props.contains(name) => {
+                self.types.get(name).cloned().unwrap_or_else(|| UNTYPED.to_string())
+            }
+            ExpressionKind::Call { callee, args, .. } if args.is_empty() => match &callee.kind {
+                ExpressionKind::Identifier(name) if self.signals.contains(name) => {
+                    self.types.get(name).cloned().unwrap_or_else(|| UNTYPED.to_string())
+                }
+                _ => UNTYPED.to_string(),
+            },
+            _ => UNTYPED.to_string(),
         }
     }
 
+    fn props_struct_name(&self, component: &str) -> String {
+        format!("{}Props", self.to_pascal_case(component))
+    }
+
     fn to_pascal_case(&self, s: &str) -> String {
-        s.split(|c: char| c == '_' || c == '-' || c == ' ')
+        s.split(['_', '-', ' '])
             .filter(|part| !part.is_empty())
             .map(|part| {
                 let mut chars = part.chars();
@@ -517,6 +2258,476 @@ impl RustGenerator {
     }
 }
 
+/// Whether `nodes` has a `<slot>`, directly or inside blocks
+fn contains_slot(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| match &node.kind {
+        NodeKind::Slot { .. } => true,
+        NodeKind::IfBlock { then_branch, else_branch, .. } => {
+            contains_slot(then_branch) || else_branch.as_deref().is_some_and(contains_slot)
+        }
+        NodeKind::EachBlock { body, .. } => contains_slot(body),
+        NodeKind::AwaitBlock { pending, then_branch, catch_branch, .. } => {
+            contains_slot(pending)
+                || [then_branch, catch_branch]
+                    .into_iter()
+                    .flatten()
+                    .any(|branch| contains_slot(&branch.body))
+        }
+        _ => false,
+    })
+}
+
+/// Whether a template renders slot content anywhere
+fn renders_slots(nodes: &[Node]) -> bool {
+    contains_slot(nodes)
+        || nodes.iter().any(|node| match &node.kind {
+            NodeKind::Element { children, .. } => renders_slots(children),
+            NodeKind::IfBlock { then_branch, else_branch, .. } => {
+                renders_slots(then_branch) || else_branch.as_deref().is_some_and(renders_slots)
+            }
+            NodeKind::EachBlock { body, .. } => renders_slots(body),
+            NodeKind::AwaitBlock { pending, then_branch, catch_branch, .. } => {
+                renders_slots(pending)
+                    || [then_branch, catch_branch]
+                        .into_iter()
+                        .flatten()
+                        .any(|branch| renders_slots(&branch.body))
+            }
+            _ => false,
+        })
+}
+
+/// Render functions creating child components get their own handle on
+/// the component's `Context`: returns the capture to emit before the
+/// closure and borrows it at the start of `body`
+fn capture_context(body: &mut String) -> Option<String> {
+    if !body.contains("with_props(ctx, ") {
+        return None;
+    }
+    let indent: String = body.chars().take_while(|c| *c == ' ').collect();
+    body.insert_str(0, &format!("{}let ctx = &mut ctx.clone();\n", indent));
+    Some("let ctx = ctx.clone();".to_string())
+}
+
+/// Collect the names of the components used in `nodes`, once each
+fn collect_component_tags(nodes: &[Node], tags: &mut Vec<String>) {
+    for node in nodes {
+        match &node.kind {
+            NodeKind::Element { tag, children, .. } => {
+                if let ElementTag::Component(name) = tag
+                    && !tags.contains(name)
+                {
+                    tags.push(name.clone());
+                }
+                collect_component_tags(children, tags);
+            }
+            NodeKind::IfBlock { then_branch, else_branch, .. } => {
+                collect_component_tags(then_branch, tags);
+                if let Some(else_branch) = else_branch {
+                    collect_component_tags(else_branch, tags);
+                }
+            }
+            NodeKind::EachBlock { body, .. } => collect_component_tags(body, tags),
+            NodeKind::AwaitBlock { pending, then_branch, catch_branch, .. } => {
+                collect_component_tags(pending, tags);
+                for branch in [then_branch, catch_branch].into_iter().flatten() {
+                    collect_component_tags(&branch.body, tags);
+                }
+            }
+            NodeKind::Slot { fallback, .. } => collect_component_tags(fallback, tags),
+            _ => {}
+        }
+    }
+}
+
+/// Rust type of the slot prop `local` given the code of the slot content:
+/// a number if the content computes with it or uses it as one, otherwise
+/// a string
+fn slot_prop_type(local: &str, content: &str) -> &'static str {
+    let numeric = [" as f32", " + ", " - ", " * ", " / ", " % ", " < ", " > ", " <= ", " >= "];
+    let is_number = content.match_indices(local).any(|(at, _)| {
+        let standalone = |c: Option<char>| !c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let before = &content[..at];
+        let after = &content[at + local.len()..];
+        standalone(before.chars().next_back())
+            && standalone(after.chars().next())
+            && numeric.iter().any(|op| after.starts_with(op) || before.ends_with(op))
+    });
+    if is_number { "f64" } else { "String" }
+}
+
+/// Collect the identifiers that directives of kind `name` in `nodes` are
+/// set to, once each, e.g. the variables of every `ref={...}`
+fn collect_directive_names(nodes: &[Node], name: &DirectiveName, out: &mut Vec<String>) {
+    let mut directives = Vec::new();
+    collect_directives(nodes, name, &mut directives);
+    for directive in directives {
+        if let ExpressionKind::Identifier(value) = &directive.value.kind
+            && !out.contains(value)
+        {
+            out.push(value.clone());
+        }
+    }
+}
+
+/// Collect the directives of kind `name` in `nodes`, in template order
+fn collect_directives<'a>(nodes: &'a [Node], name: &DirectiveName, out: &mut Vec<&'a Directive>) {
+    for node in nodes {
+        match &node.kind {
+            NodeKind::Element { directives, children, .. } => {
+                out.extend(directives.iter().filter(|d| d.name == *name));
+                collect_directives(children, name, out);
+            }
+            NodeKind::IfBlock { then_branch, else_branch, .. } => {
+                collect_directives(then_branch, name, out);
+                if let Some(else_branch) = else_branch {
+                    collect_directives(else_branch, name, out);
+                }
+            }
+            NodeKind::EachBlock { body, .. } => collect_directives(body, name, out),
+            NodeKind::AwaitBlock { pending, then_branch, catch_branch, .. } => {
+                collect_directives(pending, name, out);
+                for branch in [then_branch, catch_branch].into_iter().flatten() {
+                    collect_directives(&branch.body, name, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `EventModifiers` for the modifiers of an event directive
+fn event_modifiers(modifiers: &[String]) -> String {
+    let set: Vec<String> = modifiers
+        .iter()
+        .map(|m| match m.as_str() {
+            "self" => "self_only: true".to_string(),
+            m => format!("{}: true", m),
+        })
+        .collect();
+    if set.is_empty() {
+        "EventModifiers::default()".to_string()
+    } else {
+        format!("EventModifiers {{ {}, ..Default::default() }}", set.join(", "))
+    }
+}
+
+/// `StyleDeclarations` setting the given fields
+fn style_declarations(fields: &[(&str, Option<String>)]) -> String {
+    let set: Vec<String> = fields
+        .iter()
+        .filter_map(|(field, value)| value.as_ref().map(|v| format!("{}: Some({})", field, v)))
+        .collect();
+    if set.is_empty() {
+        "StyleDeclarations::default()".to_string()
+    } else {
+        format!("StyleDeclarations {{ {}, ..Default::default() }}", set.join(", "))
+    }
+}
+
+/// A selector as the runtime parses it, e.g. `.legend circle.active`
+fn selector_text(selector: &Selector) -> String {
+    selector
+        .compounds
+        .iter()
+        .map(|compound| {
+            let mut text = compound.tag.clone().unwrap_or_default();
+            if let Some(id) = &compound.id {
+                text.push('#');
+                text.push_str(id);
+            }
+            for class in &compound.classes {
+                text.push('.');
+                text.push_str(class);
+            }
+            if text.is_empty() {
+                text.push('*');
+            }
+            text
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `FontWeight` variant for a static `fontWeight`, e.g. `bold` or `600`
+fn font_weight_to_rust(value: &AttributeValue) -> Option<String> {
+    match value {
+        AttributeValue::Static(Literal::String(s)) => match s.as_str() {
+            "normal" => Some("FontWeight::Normal".to_string()),
+            "bold" | "bolder" => Some("FontWeight::Bold".to_string()),
+            "light" | "lighter" => Some("FontWeight::Light".to_string()),
+            _ => None,
+        },
+        AttributeValue::Static(Literal::Number(n)) => Some(format!("FontWeight::Custom({})", *n as u16)),
+        _ => None,
+    }
+}
+
+/// Join the text and expression children of an element into one expression
+fn text_children_to_expr(children: &[Node]) -> Option<Expression> {
+    let mut span: Option<Span> = None;
+    let parts: Vec<TemplatePart> = children
+        .iter()
+        .filter_map(|child| {
+            let part = match &child.kind {
+                NodeKind::Text {
+                    content:
+                        Expression {
+                            kind: ExpressionKind::Literal(Literal::String(s)),
+                            ..
+                        },
+                } => TemplatePart::String(s.clone()),
+                NodeKind::Text { content: expr } | NodeKind::Expression { expr } => {
+                    TemplatePart::Expression(expr.clone())
+                }
+                _ => return None,
+            };
+            span = Some(span.map_or(child.span, |span| span.merge(child.span)));
+            Some(part)
+        })
+        .collect();
+
+    let span = span?;
+    match parts.as_slice() {
+        [] => None,
+        [TemplatePart::String(s)] => Some(Expression::new(
+            ExpressionKind::Literal(Literal::String(s.clone())),
+            span,
+        )),
+        _ => Some(Expression::new(ExpressionKind::Template { parts }, span)),
+    }
+}
+
+/// Body of a keyed `{#each}` with the block's key on its root elements,
+/// so the runtime reuses their entities across renders; with several
+/// roots, each key is suffixed with the root's position so they stay
+/// distinct
+fn key_each_roots(body: &[Node], key: &Expression) -> Vec<Node> {
+    let is_root = |node: &Node| {
+        matches!(&node.kind, NodeKind::Element { tag, .. } if !matches!(tag, ElementTag::Component(_)))
+    };
+    let roots = body.iter().filter(|node| is_root(node)).count();
+    let mut position = 0;
+    body.iter()
+        .cloned()
+        .map(|mut node| {
+            if !is_root(&node) {
+                return node;
+            }
+            // An element's own key wins over the block's
+            if let NodeKind::Element { key: element_key @ None, .. } = &mut node.kind {
+                *element_key = Some(if roots == 1 {
+                    key.clone()
+                } else {
+                    Expression::new(
+                        ExpressionKind::Template {
+                            parts: vec![
+                                TemplatePart::Expression(key.clone()),
+                                TemplatePart::String(format!("#{}", position)),
+                            ],
+                        },
+                        key.span,
+                    )
+                });
+            }
+            position += 1;
+            node
+        })
+        .collect()
+}
+
+/// Match arm pattern and body of a `{:then}` or `{:catch}` branch
+fn await_arm<'a>(variant: &str, branch: &'a Option<AwaitBranch>) -> (String, Option<&'a [Node]>) {
+    let binding = branch
+        .as_ref()
+        .and_then(|b| b.binding.as_deref())
+        .unwrap_or("_");
+    (
+        format!("PromiseState::{}({})", variant, binding),
+        branch.as_ref().map(|b| b.body.as_slice()),
+    )
+}
+
+fn is_optional_link(expr: &Expression) -> bool {
+    matches!(
+        expr.kind,
+        ExpressionKind::Member { optional: true, .. }
+            | ExpressionKind::Index { optional: true, .. }
+            | ExpressionKind::Call { optional: true, .. }
+    )
+}
+
+/// The names in `names` that occur as words in `code`
+fn names_in(names: &[String], code: &str) -> Vec<String> {
+    let words: std::collections::HashSet<&str> =
+        code.split(|c: char| !c.is_alphanumeric() && c != '_').collect();
+    names
+        .iter()
+        .filter(|name| words.contains(name.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// `name` or `name as alias` in a `use` item
+fn use_item(name: &str, alias: &str) -> String {
+    if name == alias {
+        name.to_string()
+    } else {
+        format!("{} as {}", name, alias)
+    }
+}
+
+/// Rust type of values whose type is unknown or has no Rust counterpart;
+/// numbers are the most common script values
+const UNTYPED: &str = "f64";
+
+/// Rust type for a type annotation
+fn rust_type(ty: &Type) -> String {
+    match ty {
+        Type::Number => "f64".to_string(),
+        Type::String => "String".to_string(),
+        Type::Boolean => "bool".to_string(),
+        Type::Void => "()".to_string(),
+        Type::Any => UNTYPED.to_string(),
+        Type::Array(inner) => format!("Vec<{}>", rust_type(inner)),
+        // Object literals lower to maps, which need one value type
+        Type::Object(fields) => format!("HashMap<String, {}>", common_type(fields.values().map(rust_type))),
+        Type::Function { params, return_type } => {
+            let params: Vec<String> = params.iter().map(rust_type).collect();
+            format!("Box<dyn Fn({}) -> {}>", params.join(", "), rust_type(return_type))
+        }
+        Type::Signal(inner) => format!("Signal<{}>", rust_type(inner)),
+        // `T | null` is the only union with a Rust counterpart
+        Type::Union(members) => {
+            let is_null = |ty: &Type| matches!(ty, Type::Custom(name) if name == "null");
+            match members.iter().filter(|ty| !is_null(ty)).collect::<Vec<_>>()[..] {
+                [inner] if members.len() == 2 => format!("Option<{}>", rust_type(inner)),
+                _ => UNTYPED.to_string(),
+            }
+        }
+        Type::Custom(name) if name == "null" => "()".to_string(),
+        Type::Custom(name) => name.clone(),
+    }
+}
+
+/// The one type of the values of an object or array literal; values of
+/// differing types are held as the runtime's `Value`
+fn common_type(types: impl IntoIterator<Item = String>) -> String {
+    let types: Vec<String> = types.into_iter().collect();
+    match types.first() {
+        None => UNTYPED.to_string(),
+        Some(first) if types.iter().all(|ty| ty == first) => first.clone(),
+        _ if types.iter().all(|ty| map_value_type(ty).is_some()) => "HashMap<String, Value>".to_string(),
+        _ => "Value".to_string(),
+    }
+}
+
+/// Convert `value` from the Rust type `from` to `to`, where they differ
+/// by holding the runtime's `Value`
+fn convert_to(value: &str, from: &str, to: &str) -> String {
+    if from == to {
+        return value.to_string();
+    }
+    if to == "Value" {
+        return format!("Value::from({})", value);
+    }
+    match (map_value_type(from), map_value_type(to)) {
+        (Some(_), Some("Value")) => format!(
+            "{}.into_iter().map(|(key, value)| (key, Value::from(value))).collect::<HashMap<_, _>>()",
+            value
+        ),
+        _ => value.to_string(),
+    }
+}
+
+/// `T` of `HashMap<String, T>`
+fn map_value_type(ty: &str) -> Option<&str> {
+    ty.strip_prefix("HashMap<String, ")?.strip_suffix('>')
+}
+
+/// `T` of `Vec<T>`
+fn vec_element_type(ty: &str) -> Option<&str> {
+    ty.strip_prefix("Vec<")?.strip_suffix('>')
+}
+
+/// `T` of `Option<T>`
+fn option_inner(ty: &str) -> Option<&str> {
+    ty.strip_prefix("Option<")?.strip_suffix('>')
+}
+
+/// `: T` for an annotated binding, or nothing to let Rust infer the type
+///
+/// Closures have no nameable type, so function types are always inferred.
+fn rust_annotation(ty: Option<&Type>) -> String {
+    match ty {
+        Some(Type::Function { .. }) | None => String::new(),
+        Some(ty) => format!(": {}", rust_type(ty)),
+    }
+}
+
+/// The value of the first `return` in a function body, outside nested
+/// functions
+fn returned_expression(body: &[Statement]) -> Option<&Expression> {
+    body.iter().find_map(|stmt| match &stmt.kind {
+        StatementKind::Return(value) => value.as_ref(),
+        StatementKind::If { then_branch, else_branch, .. } => returned_expression(then_branch)
+            .or_else(|| else_branch.as_deref().and_then(returned_expression)),
+        StatementKind::For { body, .. } | StatementKind::While { body, .. } | StatementKind::Block(body) => {
+            returned_expression(body)
+        }
+        _ => None,
+    })
+}
+
+/// `target += 1.0` or `target -= 1.0`
+fn update_to_rust(op: UpdateOp, target: &str) -> String {
+    match op {
+        UpdateOp::Increment => format!("{} += 1.0", target),
+        UpdateOp::Decrement => format!("{} -= 1.0", target),
+    }
+}
+
+/// Set `signal` to one more or less than `current`
+fn signal_update_to_rust(op: UpdateOp, signal: &str, current: &str) -> String {
+    match op {
+        UpdateOp::Increment => format!("{}.set({} + 1.0)", signal, current),
+        UpdateOp::Decrement => format!("{}.set({} - 1.0)", signal, current),
+    }
+}
+
+/// Format a number as a Rust float literal
+fn rust_number(n: f64) -> String {
+    if n.fract() == 0.0 {
+        format!("{:.1}", n)
+    } else {
+        format!("{}", n)
+    }
+}
+
+/// Quote and escape a string as a Rust string literal
+fn rust_string_literal(s: &str) -> String {
+    // `Debug` for `str` produces valid Rust escapes
+    format!("{:?}", s)
+}
+
+/// Escape literal text for use inside a `format!` string
+fn escape_format_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '{' => out.push_str("{{"),
+            '}' => out.push_str("}}"),
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 impl Default for RustGenerator {
     fn default() -> Self {
         Self::new()
@@ -12,6 +12,12 @@ pub struct TypeScriptGenerator {
     indent: usize,
 }
 
+impl Default for TypeScriptGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TypeScriptGenerator {
     pub fn new() -> Self {
         Self {
@@ -22,16 +28,30 @@ impl TypeScriptGenerator {
 
     /// Generate TypeScript definition (`.d.ts`) for a component
     pub fn generate(&mut self, component: &Component) -> Result<String> {
+        self.generate_components(std::slice::from_ref(component))
+    }
+
+    /// Generate TypeScript definitions for the components of one file
+    pub fn generate_components(&mut self, components: &[Component]) -> Result<String> {
         self.output.clear();
+        let Some(main) = components.first() else {
+            return Ok(String::new());
+        };
 
         // Header
-        self.emit_header(component)?;
+        self.emit_header(main)?;
 
-        // Interface for Props (future proofing, mostly for attributes/signals exposed)
-        self.emit_props_interface(component)?;
+        for (i, component) in components.iter().enumerate() {
+            if i > 0 {
+                writeln!(self.output)?;
+            }
+
+            // Interface for the props declared with `export let`
+            self.emit_props_interface(component)?;
 
-        // Component Class definition
-        self.emit_component_class(component)?;
+            // Component Class definition
+            self.emit_component_class(component)?;
+        }
 
         Ok(self.output.clone())
     }
@@ -46,10 +66,17 @@ impl TypeScriptGenerator {
     fn emit_props_interface(&mut self, component: &Component) -> Result<()> {
         let name = &component.name;
         writeln!(self.output, "export interface {}Props {{", name)?;
-        
-        // TODO: In future, we would analyze props here.
-        // For now, assume no explicit props until we implement props in AST.
-        
+        self.indent += 1;
+        for prop in &component.props {
+            let ty = match &prop.ty {
+                Some(ty) => ts_type(ty),
+                None => self.infer_ts_type(prop.default.as_ref()),
+            };
+            // Props with a default may be left out
+            let optional = if prop.default.is_some() { "?" } else { "" };
+            self.emit_line(&format!("{}{}: {};", prop.name, optional, ty))?;
+        }
+        self.indent -= 1;
         writeln!(self.output, "}}")?;
         writeln!(self.output)?;
         Ok(())
@@ -65,7 +92,13 @@ impl TypeScriptGenerator {
         self.indent += 1;
 
         // Constructor
-        self.emit_line(&format!("constructor(props?: {}Props);", name))?;
+        // Props are required once one of them has no default
+        let optional = if component.props.iter().all(|prop| prop.default.is_some()) {
+            "?"
+        } else {
+            ""
+        };
+        self.emit_line(&format!("constructor(props{}: {}Props);", optional, name))?;
         writeln!(self.output)?;
 
         // Expose signals as public readonly properties/getters?
@@ -83,17 +116,19 @@ impl TypeScriptGenerator {
     fn emit_signals(&mut self, component: &Component) -> Result<()> {
         if let Some(script) = &component.script {
             for stmt in &script.statements {
-                if let Statement::VariableDeclaration {
+                if let StatementKind::VariableDeclaration {
                     name,
                     reactive,
                     init,
                     ..
-                } = stmt
+                } = &stmt.kind
+                    && *reactive != ReactiveKind::None
                 {
-                    if *reactive != ReactiveKind::None {
-                        let ty = self.infer_ts_type(init.as_ref());
-                        self.emit_line(&format!("readonly {}: Signal<{}>;", name, ty))?;
-                    }
+                    let ty = stmt
+                        .kind
+                        .declared_type()
+                        .map_or_else(|| self.infer_ts_type(init.as_ref()), ts_type);
+                    self.emit_line(&format!("readonly {}: Signal<{}>;", name, ty))?;
                 }
             }
         }
@@ -102,13 +137,15 @@ impl TypeScriptGenerator {
 
     fn infer_ts_type(&self, expr: Option<&Expression>) -> String {
         match expr {
-            Some(Expression::Call { callee, args, .. }) => {
-                if let Expression::Identifier(name) = callee.as_ref() {
-                    if name == "signal" || name == "memo" {
-                        if let Some(first_arg) = args.first() {
-                            return self.infer_expr_ts_type(first_arg);
-                        }
-                    }
+            Some(Expression {
+                kind: ExpressionKind::Call { callee, args, .. },
+                ..
+            }) => {
+                if let ExpressionKind::Identifier(name) = &callee.kind
+                    && (name == "signal" || name == "memo")
+                    && let Some(first_arg) = args.first()
+                {
+                    return self.infer_expr_ts_type(first_arg);
                 }
                 "any".to_string()
             }
@@ -118,12 +155,14 @@ impl TypeScriptGenerator {
     }
 
     fn infer_expr_ts_type(&self, expr: &Expression) -> String {
-        match expr {
-            Expression::Literal(Literal::Number(_)) => "number".to_string(),
-            Expression::Literal(Literal::String(_)) => "string".to_string(),
-            Expression::Literal(Literal::Boolean(_)) => "boolean".to_string(),
-            Expression::Array(_) => "any[]".to_string(),
-            Expression::Arrow { .. } => "() => void".to_string(),
+        match &expr.kind {
+            ExpressionKind::Literal(Literal::Number(_) | Literal::Dimension(..)) => "number".to_string(),
+            ExpressionKind::Literal(Literal::String(_)) => "string".to_string(),
+            ExpressionKind::Literal(Literal::Boolean(_)) => "boolean".to_string(),
+            ExpressionKind::Array(_) => "any[]".to_string(),
+            ExpressionKind::Object(_) => "Record<string, any>".to_string(),
+            ExpressionKind::Arrow { .. } => "() => void".to_string(),
+            ExpressionKind::Template { .. } => "string".to_string(),
             _ => "any".to_string(),
         }
     }
@@ -135,6 +174,42 @@ impl TypeScriptGenerator {
     }
 }
 
+/// Render a type annotation in TypeScript syntax
+fn ts_type(ty: &Type) -> String {
+    // Unions and function types need parentheses as array elements
+    let element = |ty: &Type| match ty {
+        Type::Union(_) | Type::Function { .. } => format!("({})", ts_type(ty)),
+        ty => ts_type(ty),
+    };
+    match ty {
+        Type::Number => "number".to_string(),
+        Type::String => "string".to_string(),
+        Type::Boolean => "boolean".to_string(),
+        Type::Void => "void".to_string(),
+        Type::Any => "any".to_string(),
+        Type::Array(inner) => format!("{}[]", element(inner)),
+        Type::Object(fields) => {
+            let mut fields: Vec<String> = fields
+                .iter()
+                .map(|(key, ty)| format!("{}: {}", key, ts_type(ty)))
+                .collect();
+            fields.sort();
+            format!("{{ {} }}", fields.join("; "))
+        }
+        Type::Function { params, return_type } => {
+            let params: Vec<String> = params
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| format!("arg{}: {}", i, ts_type(ty)))
+                .collect();
+            format!("({}) => {}", params.join(", "), ts_type(return_type))
+        }
+        Type::Signal(inner) => format!("Signal<{}>", ts_type(inner)),
+        Type::Union(members) => members.iter().map(element).collect::<Vec<_>>().join(" | "),
+        Type::Custom(name) => name.clone(),
+    }
+}
+
 // Minimal Signal type definition
 // users would likely import this from the runtime lib
 // but for d.ts generation we might presume it exists globally or imports it.
@@ -0,0 +1,405 @@
+//! Concrete Syntax Tree
+//!
+//! Lossless tree of a `.omni` file built alongside the AST. Every byte of
+//! the source is kept, including whitespace and comments, so tools such as
+//! formatters and codemods can round-trip a file exactly.
+//!
+//! The tree follows the green/red design: `GreenNode`s are immutable and
+//! position-independent, `SyntaxNode`s add parents and offsets on demand.
+
+pub mod green;
+pub mod red;
+
+use crate::ast::Component;
+use crate::lexer::{Lexer, LexerError, Span, Token, TokenKind};
+use crate::parser::{ParseError, Parser};
+use std::sync::Arc;
+
+pub use green::{GreenElement, GreenNode, GreenToken};
+pub use red::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+/// Kind of a syntax tree node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Root,
+    /// Tokens left over after a parse error
+    Error,
+
+    // Sections
+    ScriptSection,
+    CanvasSection,
+    StyleSection,
+
+    // Template
+    Element,
+    Attribute,
+    /// `{expr}` between element children
+    Interpolation,
+    IfBlock,
+    EachBlock,
+    AwaitBlock,
+    /// `{#if cond}`, `{:else}`, `{/if}` and the other tags of a block
+    BlockTag,
+
+    // Statements
+    ImportDecl,
+    VariableDecl,
+    FunctionDecl,
+    ParamList,
+    Param,
+    Block,
+    IfStmt,
+    ForStmt,
+    WhileStmt,
+    BreakStmt,
+    ContinueStmt,
+    ReturnStmt,
+    ExprStmt,
+
+    // Expressions
+    Literal,
+    Name,
+    TemplateLiteral,
+    ArrayExpr,
+    ObjectExpr,
+    /// `key: value` or shorthand `key` in an object literal
+    Property,
+    /// `...expr`, or `...rest` in a pattern
+    SpreadExpr,
+    ObjectPattern,
+    ArrayPattern,
+    ParenExpr,
+    ArrowExpr,
+    CallExpr,
+    ArgList,
+    MemberExpr,
+    IndexExpr,
+    UnaryExpr,
+    UpdateExpr,
+    BinaryExpr,
+    AssignExpr,
+    TernaryExpr,
+
+    // Types
+    /// `: Type` after a variable, parameter or parameter list
+    TypeAnnotation,
+    Type,
+    /// `<T>` in `signal<T>(...)`
+    TypeArgs,
+}
+
+/// Source text the lexer skips
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    HtmlComment,
+}
+
+/// Kind of a syntax tree leaf
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxTokenKind {
+    Token(TokenKind),
+    Trivia(TriviaKind),
+}
+
+/// A file parsed into both a CST and an AST
+#[derive(Debug)]
+pub struct ParsedFile {
+    pub cst: Arc<GreenNode>,
+    pub lex_errors: Vec<LexerError>,
+    pub ast: Result<Component, ParseError>,
+}
+
+impl ParsedFile {
+    /// Red tree view of the CST
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.cst.clone())
+    }
+}
+
+/// Parse a file into a lossless CST and the AST
+pub fn parse_file(source: &str, file_name: &str) -> ParsedFile {
+    let (tokens, lex_errors) = Lexer::new(source).tokenize_with_recovery();
+    let mut parser = Parser::new(tokens, file_name).with_cst(source);
+    let ast = parser.parse();
+    let cst = parser.finish_cst().expect("CST building was enabled");
+
+    ParsedFile {
+        cst,
+        lex_errors,
+        ast,
+    }
+}
+
+/// Lower a CST to the AST
+///
+/// The CST keeps every token, so this feeds its non-trivia tokens back
+/// through the AST parser. Use it after rewriting a tree to get the AST of
+/// the edited file.
+pub fn lower(root: &SyntaxNode, file_name: &str) -> Result<Component, ParseError> {
+    let tokens = root
+        .tokens()
+        .into_iter()
+        .filter_map(|token| match token.kind() {
+            SyntaxTokenKind::Token(kind) => Some(Token {
+                kind: kind.clone(),
+                span: token.span(),
+                text: token.text().to_string(),
+            }),
+            SyntaxTokenKind::Trivia(_) => None,
+        })
+        .collect();
+
+    Parser::new(tokens, file_name).parse()
+}
+
+/// Builds a green tree while the parser consumes tokens
+///
+/// Nodes are created after the fact by wrapping every element added since a
+/// checkpoint, so parse functions only need to mark where they started.
+/// Trivia is flushed before each checkpoint and therefore belongs to the
+/// enclosing node rather than the node that follows it.
+#[derive(Debug)]
+pub(crate) struct CstBuilder {
+    source: String,
+    offset: usize,
+    children: Vec<GreenElement>,
+}
+
+impl CstBuilder {
+    pub(crate) fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            offset: 0,
+            children: Vec::new(),
+        }
+    }
+
+    /// Add a consumed token, preceded by any trivia before it
+    pub(crate) fn token(&mut self, token: &Token) {
+        self.trivia_until(token.span.start);
+        self.children.push(
+            GreenToken::new(
+                SyntaxTokenKind::Token(token.kind.clone()),
+                token.text.clone(),
+            )
+            .into(),
+        );
+        self.offset = token.span.end;
+    }
+
+    /// Mark the start of a node whose first token starts at `next`
+    pub(crate) fn checkpoint(&mut self, next: Option<usize>) -> usize {
+        if let Some(next) = next {
+            self.trivia_until(next);
+        }
+        self.children.len()
+    }
+
+    /// Wrap everything added since `checkpoint` into a node
+    ///
+    /// Trivia after the node's last token stays in the parent, so nodes
+    /// start and end with a token.
+    pub(crate) fn wrap(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        let mut children = self.children.split_off(checkpoint.min(self.children.len()));
+        let trivia = children
+            .iter()
+            .rev()
+            .take_while(|child| {
+                matches!(child, GreenElement::Token(token) if matches!(token.kind(), SyntaxTokenKind::Trivia(_)))
+            })
+            .count();
+        let trailing = children.split_off(children.len() - trivia);
+        self.children.push(GreenNode::new(kind, children).into());
+        self.children.extend(trailing);
+    }
+
+    /// Finish the tree, putting unconsumed tokens into an `Error` node
+    pub(crate) fn finish(mut self, rest: &[Token]) -> GreenNode {
+        if let Some(first) = rest.first() {
+            let checkpoint = self.checkpoint(Some(first.span.start));
+            for token in rest {
+                self.token(token);
+            }
+            self.wrap(checkpoint, SyntaxKind::Error);
+        }
+        self.trivia_until(self.source.len());
+        GreenNode::new(SyntaxKind::Root, self.children)
+    }
+
+    fn trivia_until(&mut self, end: usize) {
+        while self.offset < end {
+            let rest = &self.source[self.offset..end];
+            let (kind, len) = classify_trivia(rest);
+            self.children
+                .push(GreenToken::new(SyntaxTokenKind::Trivia(kind), &rest[..len]).into());
+            self.offset += len;
+        }
+    }
+}
+
+/// Kind and byte length of the trivia at the start of `text`
+fn classify_trivia(text: &str) -> (TriviaKind, usize) {
+    let until = |terminator: &str| {
+        text.find(terminator)
+            .map_or(text.len(), |i| i + terminator.len())
+    };
+
+    if text.starts_with("//") {
+        (
+            TriviaKind::LineComment,
+            text.find('\n').unwrap_or(text.len()),
+        )
+    } else if text.starts_with("/*") {
+        (TriviaKind::BlockComment, until("*/"))
+    } else if text.starts_with("<!--") {
+        (TriviaKind::HtmlComment, until("-->"))
+    } else {
+        // The lexer only skips whitespace and comments, so anything else
+        // cannot occur; it is kept as whitespace to stay lossless
+        let len = text
+            .find(|c: char| !c.is_whitespace())
+            .filter(|&i| i > 0)
+            .unwrap_or_else(|| text.chars().next().map_or(text.len(), char::len_utf8));
+        (TriviaKind::Whitespace, len)
+    }
+}
+
+impl SyntaxNode {
+    /// Span of the node without leading and trailing trivia
+    pub fn trimmed_span(&self) -> Span {
+        let tokens: Vec<SyntaxToken> = self
+            .tokens()
+            .into_iter()
+            .filter(|t| !t.is_trivia())
+            .collect();
+        match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => first.span().merge(last.span()),
+            _ => self.span(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r##"<!-- Counter component -->
+<script>
+  // Reactive state
+  const count = signal(0); /* inline */
+  const doubled = memo(() => count() * 2);
+</script>
+
+<canvas width={800} height={600}>
+  <!-- Shapes -->
+  <circle x={400} y={300} radius={50 + count()} fill="#00d4ff" />
+  <text x={10}>Count: {count()}</text>
+</canvas>
+"##;
+
+    #[test]
+    fn test_round_trip() {
+        let parsed = parse_file(SOURCE, "Counter.omni");
+        assert!(parsed.ast.is_ok(), "{:?}", parsed.ast.err());
+        assert_eq!(parsed.cst.text(), SOURCE);
+        assert_eq!(parsed.cst.text_len(), SOURCE.len());
+
+        let lowered = lower(&parsed.syntax(), "Counter.omni").unwrap();
+        assert_eq!(format!("{lowered:?}"), format!("{:?}", parsed.ast.unwrap()));
+    }
+
+    #[test]
+    fn test_round_trip_with_errors() {
+        let source = "<script> const = ; </script> # <canvas><circle x={ /></canvas>";
+        let parsed = parse_file(source, "Broken.omni");
+        assert!(parsed.ast.is_err());
+        assert_eq!(parsed.cst.text(), source);
+        assert!(
+            parsed
+                .syntax()
+                .children()
+                .any(|n| n.kind() == SyntaxKind::Error)
+        );
+    }
+
+    #[test]
+    fn test_structure_and_trivia() {
+        let root = parse_file(SOURCE, "Counter.omni").syntax();
+        let kinds: Vec<SyntaxKind> = root.children().map(|n| n.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![SyntaxKind::ScriptSection, SyntaxKind::CanvasSection]
+        );
+
+        let comments: Vec<String> = root
+            .tokens()
+            .into_iter()
+            .filter(|t| {
+                matches!(
+                    t.kind(),
+                    SyntaxTokenKind::Trivia(
+                        TriviaKind::LineComment
+                            | TriviaKind::BlockComment
+                            | TriviaKind::HtmlComment
+                    )
+                )
+            })
+            .map(|t| t.text().to_string())
+            .collect();
+        assert_eq!(
+            comments,
+            vec![
+                "<!-- Counter component -->",
+                "// Reactive state",
+                "/* inline */",
+                "<!-- Shapes -->"
+            ]
+        );
+
+        let circle = root
+            .descendants()
+            .into_iter()
+            .find(|n| n.kind() == SyntaxKind::Element)
+            .unwrap();
+        assert_eq!(
+            &SOURCE[circle.trimmed_span().start..circle.trimmed_span().end],
+            circle.text().trim()
+        );
+        assert!(circle.text().starts_with("<circle"));
+        let binary = circle
+            .descendants()
+            .into_iter()
+            .find(|n| n.kind() == SyntaxKind::BinaryExpr)
+            .unwrap();
+        assert_eq!(binary.text(), "50 + count()");
+    }
+
+    #[test]
+    fn test_lower_after_rewrite() {
+        let root = parse_file(SOURCE, "Counter.omni").syntax();
+        let literal = root
+            .descendants()
+            .into_iter()
+            .find(|n| n.kind() == SyntaxKind::Literal && n.text() == "800")
+            .unwrap();
+        let token = literal.child_tokens().next().unwrap();
+        let new_root = token.replace_with(GreenToken::new(
+            SyntaxTokenKind::Token(TokenKind::Number(1024.0)),
+            "1024",
+        ));
+
+        let edited = SyntaxNode::new_root(new_root);
+        assert_eq!(edited.text(), SOURCE.replace("{800}", "{1024}"));
+
+        let component = lower(&edited, "Counter.omni").unwrap();
+        let width = component.template.canvas.width.unwrap();
+        assert_eq!(
+            width.kind,
+            crate::ast::ExpressionKind::Literal(crate::ast::Literal::Number(1024.0))
+        );
+        assert_eq!(&edited.text()[width.span.start..width.span.end], "1024");
+    }
+}
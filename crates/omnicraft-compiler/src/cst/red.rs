@@ -0,0 +1,190 @@
+//! Red Tree
+//!
+//! Cursor view over the green tree that adds parent links and absolute
+//! offsets. Red nodes are created on demand while walking the tree.
+
+use super::green::{GreenElement, GreenNode, GreenToken};
+use super::{SyntaxKind, SyntaxTokenKind};
+use crate::lexer::Span;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Node of the syntax tree with its position in the file
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+#[derive(Debug)]
+struct NodeData {
+    green: Arc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    index: usize,
+    offset: usize,
+}
+
+/// Token of the syntax tree with its position in the file
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    green: Arc<GreenToken>,
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+
+/// Child of a syntax node
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    /// Root of a tree
+    pub fn new_root(green: Arc<GreenNode>) -> Self {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.0.offset, self.0.offset + self.0.green.text_len())
+    }
+
+    /// Exact source text covered by the node, trivia included
+    pub fn text(&self) -> String {
+        self.0.green.text()
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// Child nodes and tokens in source order
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut elements = Vec::with_capacity(self.0.green.children().len());
+        for (index, child) in self.0.green.children().iter().enumerate() {
+            elements.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    parent: Some(self.clone()),
+                    index,
+                    offset,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    index,
+                    offset,
+                }),
+            });
+            offset += child.text_len();
+        }
+        elements
+    }
+
+    /// Child nodes in source order
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+    }
+
+    /// Tokens directly under this node, trivia included
+    pub fn child_tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                SyntaxElement::Token(token) => Some(token),
+                SyntaxElement::Node(_) => None,
+            })
+    }
+
+    /// This node and all nodes below it, in pre-order
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+        for child in self.children() {
+            nodes.extend(child.descendants());
+        }
+        nodes
+    }
+
+    /// All tokens below this node in source order, trivia included
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// Replace this node's green node, returning the new root green node
+    pub fn replace_with(&self, replacement: GreenNode) -> Arc<GreenNode> {
+        match &self.0.parent {
+            None => Arc::new(replacement),
+            Some(parent) => {
+                let new_parent = parent
+                    .0
+                    .green
+                    .replace_child(self.0.index, replacement.into());
+                parent.replace_with(new_parent)
+            }
+        }
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> &SyntaxTokenKind {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.offset, self.offset + self.green.text().len())
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind(), SyntaxTokenKind::Trivia(_))
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+
+    /// Replace this token, returning the new root green node
+    pub fn replace_with(&self, replacement: GreenToken) -> Arc<GreenNode> {
+        let new_parent = self
+            .parent
+            .green()
+            .replace_child(self.index, replacement.into());
+        self.parent.replace_with(new_parent)
+    }
+}
+
+impl SyntaxElement {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span(),
+            SyntaxElement::Token(token) => token.span(),
+        }
+    }
+}
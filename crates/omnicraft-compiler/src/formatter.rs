@@ -0,0 +1,1649 @@
+//! Formatter for `.omni` files
+//!
+//! Pretty-prints a file from its lossless CST, so comments survive
+//! formatting. The output differs from the input only in layout, quote
+//! style, optional semicolons and the spelling of CSS selectors, and
+//! formatting it again changes nothing.
+
+use crate::ast::{CompoundSelector, CssRule, Selector};
+use crate::cst::{
+    parse_file, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, SyntaxTokenKind, TriviaKind,
+};
+use crate::incremental::TextEdit;
+use crate::lexer::{LexerError, Span, TokenKind};
+use crate::parser::ParseError;
+use std::ops::Range;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("Cannot format a file with lexer errors: {0}")]
+    Lex(LexerError),
+
+    #[error("Cannot format a file with parse errors: {0}")]
+    Parse(#[from] ParseError),
+}
+
+/// Quote character used for string literals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuoteStyle {
+    #[default]
+    Double,
+    Single,
+}
+
+/// Formatter settings
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Spaces per indentation level
+    pub indent_width: usize,
+    /// Indent with tabs instead of spaces
+    pub use_tabs: bool,
+    /// Line width beyond which element attributes, and the elements of
+    /// arrays, objects and argument lists, wrap one per line
+    pub max_width: usize,
+    pub quote_style: QuoteStyle,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 2,
+            use_tabs: false,
+            max_width: 80,
+            quote_style: QuoteStyle::Double,
+        }
+    }
+}
+
+/// Format a whole file
+pub fn format_source(source: &str, options: &FormatOptions) -> Result<String, FormatError> {
+    format_with_units(source, options).map(|(output, _)| output)
+}
+
+/// Format the sections, top-level statements or canvas children that
+/// overlap `range`, returning the edit to apply if anything changes
+pub fn format_range(
+    source: &str,
+    range: Span,
+    options: &FormatOptions,
+) -> Result<Option<TextEdit>, FormatError> {
+    let (output, units) = format_with_units(source, options)?;
+    let overlapping = |depth: usize| -> Vec<&Unit> {
+        units
+            .iter()
+            .filter(|unit| unit.depth == depth && overlaps(unit.source, range))
+            .collect()
+    };
+
+    // Prefer the statements or elements of a single section over the
+    // whole section
+    let sections = overlapping(0);
+    let items = overlapping(1);
+    let chosen = match sections.as_slice() {
+        [section]
+            if !items.is_empty()
+                && section.source.start < range.start
+                && range.end < section.source.end =>
+        {
+            items
+        }
+        _ => sections,
+    };
+    let (Some(first), Some(last)) = (chosen.first(), chosen.last()) else {
+        return Ok(None);
+    };
+
+    let span = Span::new(line_start(source, first.source.start), last.source.end);
+    let text = &output[line_start(&output, first.output.start)..last.output.end];
+    if &source[span.start..span.end] == text {
+        Ok(None)
+    } else {
+        Ok(Some(TextEdit::new(span, text)))
+    }
+}
+
+fn format_with_units(
+    source: &str,
+    options: &FormatOptions,
+) -> Result<(String, Vec<Unit>), FormatError> {
+    let parsed = parse_file(source, "format.omni");
+    if let Some(error) = parsed.lex_errors.into_iter().next() {
+        return Err(FormatError::Lex(error));
+    }
+    let component = parsed.ast?;
+
+    let root = SyntaxNode::new_root(parsed.cst);
+    if is_builder_file(&root) {
+        // The builder DSL has no layout rules yet, so it stays as written
+        return Ok((source.to_string(), Vec::new()));
+    }
+    let mut printer = Printer::new(options);
+    printer.rules = component.style.map(|style| style.rules).unwrap_or_default();
+    printer.root(&root);
+    Ok((printer.out, printer.units))
+}
+
+/// Whether the file is written in the builder DSL, starting with
+/// `component` or `import` instead of a section
+fn is_builder_file(root: &SyntaxNode) -> bool {
+    root.tokens()
+        .iter()
+        .find(|token| !token.is_trivia())
+        .is_some_and(|token| matches!(token.text(), "component" | "import"))
+}
+
+fn overlaps(span: Span, range: Span) -> bool {
+    if range.start == range.end {
+        span.start <= range.start && range.start <= span.end
+    } else {
+        span.start < range.end && range.start < span.end
+    }
+}
+
+/// Start of the line containing `offset`, if only whitespace precedes it
+fn line_start(text: &str, offset: usize) -> usize {
+    let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    if text[start..offset].trim().is_empty() {
+        start
+    } else {
+        offset
+    }
+}
+
+/// Node of the formatted output that range formatting can replace
+#[derive(Debug)]
+struct Unit {
+    /// 0 for sections, 1 for top-level statements and canvas children
+    depth: usize,
+    source: Span,
+    output: Range<usize>,
+}
+
+/// Statement, section, element or comment on its own line
+struct Item {
+    piece: SyntaxElement,
+    blank_before: bool,
+    /// Comments on the same line after the item
+    trailing: Vec<String>,
+}
+
+/// Child of an element body on its own line
+struct Child {
+    blank_before: bool,
+    kind: ChildKind,
+}
+
+enum ChildKind {
+    /// Run of text, interpolations and comments
+    Inline(String),
+    Element(SyntaxNode),
+}
+
+/// Piece of an expression or statement printed on one line
+enum Atom {
+    Token {
+        kind: TokenKind,
+        text: String,
+        unary: bool,
+        /// No space before, like the `:` after an object key
+        attached: bool,
+    },
+    Comment {
+        text: String,
+        line: bool,
+    },
+    Block(SyntaxNode),
+    /// Brace of an object pattern, object type or import list, padded
+    /// inside: `{ a, b }`
+    Brace { open: bool },
+    /// Template literal, printed as one operand
+    Verbatim(String),
+    /// Array, object or argument list, on one line if it fits and one
+    /// element per line otherwise
+    List(List),
+}
+
+struct List {
+    open: &'static str,
+    close: &'static str,
+    /// Padded inside when on one line, like objects: `{ a, b }`
+    padded: bool,
+    elements: Vec<ListElement>,
+}
+
+struct ListElement {
+    /// Line comments on their own lines before the element
+    leading: Vec<String>,
+    atoms: Vec<Atom>,
+    /// Line comments after the element, printed after its comma
+    trailing: Vec<String>,
+}
+
+impl List {
+    /// Whether a line comment forces the list onto several lines
+    fn has_line_comment(&self) -> bool {
+        self.elements.iter().any(|element| {
+            !element.leading.is_empty()
+                || !element.trailing.is_empty()
+                || element.atoms.iter().any(|atom| match atom {
+                    Atom::Comment { line, .. } => *line,
+                    Atom::List(list) => list.has_line_comment(),
+                    _ => false,
+                })
+        })
+    }
+}
+
+struct Printer<'a> {
+    options: &'a FormatOptions,
+    out: String,
+    units: Vec<Unit>,
+    /// Rules of the style section, as parsed
+    rules: Vec<CssRule>,
+}
+
+impl<'a> Printer<'a> {
+    fn new(options: &'a FormatOptions) -> Self {
+        Self {
+            options,
+            out: String::new(),
+            units: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    fn indent(&self, level: usize) -> String {
+        if self.options.use_tabs {
+            "\t".repeat(level)
+        } else {
+            " ".repeat(level * self.options.indent_width)
+        }
+    }
+
+    fn fits(&self, level: usize, text: &str) -> bool {
+        level * self.options.indent_width + text.chars().count() <= self.options.max_width
+    }
+
+    // ========================================================================
+    // Layout
+    // ========================================================================
+
+    fn root(&mut self, root: &SyntaxNode) {
+        let mut prev_section = false;
+        for (i, item) in items(root.children_with_tokens()).iter().enumerate() {
+            let section = matches!(&item.piece, SyntaxElement::Node(node) if node.kind() != SyntaxKind::Error);
+            if i > 0 && (item.blank_before || (section && prev_section)) {
+                self.out.push('\n');
+            }
+            self.item(item, 0, Some(0));
+            prev_section = section;
+        }
+    }
+
+    /// Print items on their own lines at `level`, leaving the output at
+    /// the indentation of the closing delimiter
+    fn body(&mut self, elements: Vec<SyntaxElement>, level: usize, record: Option<usize>) {
+        let items = items(elements);
+        if items.is_empty() {
+            return;
+        }
+
+        self.out.push('\n');
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 && item.blank_before {
+                self.out.push('\n');
+            }
+            self.out.push_str(&self.indent(level));
+            self.item(item, level, record);
+        }
+        self.out.push_str(&self.indent(level - 1));
+    }
+
+    fn item(&mut self, item: &Item, level: usize, record: Option<usize>) {
+        let start = self.out.len();
+        let mut trailing = match &item.piece {
+            SyntaxElement::Node(node) => self.node(node, level),
+            SyntaxElement::Token(token) => {
+                self.out.push_str(token.text());
+                Vec::new()
+            }
+        };
+        if let (Some(depth), SyntaxElement::Node(node)) = (record, &item.piece) {
+            self.units.push(Unit {
+                depth,
+                source: node.trimmed_span(),
+                output: start..self.out.len(),
+            });
+        }
+
+        trailing.extend(item.trailing.iter().cloned());
+        for comment in trailing {
+            self.out.push(' ');
+            self.out.push_str(&comment);
+        }
+        self.out.push('\n');
+    }
+
+    /// Print a node, returning comments that trail it on the same line
+    fn node(&mut self, node: &SyntaxNode, level: usize) -> Vec<String> {
+        match node.kind() {
+            SyntaxKind::ScriptSection => {
+                self.out.push_str("<script>");
+                let body = between(node, TokenKind::GreaterThan, TokenKind::ClosingTag);
+                self.body(body, level + 1, Some(1));
+                self.out.push_str("</script>");
+            }
+            SyntaxKind::StyleSection => self.style(node, level),
+            SyntaxKind::CanvasSection | SyntaxKind::Element => self.element(node, level),
+            SyntaxKind::Block => self.out.push_str(&self.block(node, level)),
+            SyntaxKind::ImportDecl
+            | SyntaxKind::VariableDecl
+            | SyntaxKind::FunctionDecl
+            | SyntaxKind::IfStmt
+            | SyntaxKind::ForStmt
+            | SyntaxKind::WhileStmt
+            | SyntaxKind::BreakStmt
+            | SyntaxKind::ContinueStmt
+            | SyntaxKind::ReturnStmt
+            | SyntaxKind::ExprStmt => {
+                let (text, trailing) = self.statement(node, level);
+                self.out.push_str(&text);
+                return trailing;
+            }
+            _ => self.out.push_str(node.text().trim()),
+        }
+        Vec::new()
+    }
+
+    /// Print the parsed CSS rules one declaration per line
+    ///
+    /// Comments keep their place between rules and declarations; a comment
+    /// on the same line as a declaration stays after it.
+    fn style(&mut self, node: &SyntaxNode, level: usize) {
+        self.out.push_str("<style>");
+        let body = between(node, TokenKind::GreaterThan, TokenKind::ClosingTag);
+        let span = node.span();
+        let rules: Vec<CssRule> = self
+            .rules
+            .iter()
+            .filter(|rule| span.start <= rule.span.start && rule.span.end <= span.end)
+            .cloned()
+            .collect();
+        let mut comments = css_comments(&body).into_iter().peekable();
+        if rules.is_empty() && comments.peek().is_none() {
+            self.out.push_str("</style>");
+            return;
+        }
+
+        self.out.push('\n');
+        for (i, rule) in rules.iter().enumerate() {
+            if i > 0 && blank_line_before(&body, rule.span.start) {
+                self.out.push('\n');
+            }
+            while let Some(comment) = comments.next_if(|c| c.span.start < rule.span.start) {
+                self.css_comment(&comment, level + 1);
+            }
+
+            self.out.push_str(&self.indent(level + 1));
+            self.out.push_str(&selector_list_text(&rule.selectors));
+            self.out.push_str(" {\n");
+            for declaration in &rule.declarations {
+                while let Some(comment) = comments.next_if(|c| c.span.start < declaration.span.start) {
+                    self.css_comment(&comment, level + 2);
+                }
+                self.out.push_str(&self.indent(level + 2));
+                self.out.push_str(&format!("{}: {};\n", declaration.property, declaration.value));
+            }
+            while let Some(comment) = comments.next_if(|c| c.span.start < rule.span.end) {
+                self.css_comment(&comment, level + 2);
+            }
+            self.out.push_str(&self.indent(level + 1));
+            self.out.push_str("}\n");
+        }
+        for comment in comments {
+            self.css_comment(&comment, level + 1);
+        }
+        self.out.push_str(&self.indent(level));
+        self.out.push_str("</style>");
+    }
+
+    /// Print a style comment on its own line, or after the previous line
+    /// when it trailed it in the source
+    fn css_comment(&mut self, comment: &CssComment, level: usize) {
+        if comment.trailing && self.out.ends_with('\n') {
+            self.out.pop();
+            self.out.push(' ');
+        } else {
+            self.out.push_str(&self.indent(level));
+        }
+        self.out.push_str(&comment.text);
+        self.out.push('\n');
+    }
+
+    /// Print an element or the canvas section
+    ///
+    /// Attributes stay on the tag line when it fits and wrap one per line
+    /// otherwise. Children go on their own lines unless the body is a
+    /// single short run of text.
+    fn element(&mut self, node: &SyntaxNode, level: usize) {
+        let record = node.kind() == SyntaxKind::CanvasSection;
+        let mut name = String::new();
+        let mut attributes = Vec::new();
+        let mut line_comment = false;
+        let mut body = Vec::new();
+
+        #[derive(PartialEq)]
+        enum Part {
+            Open,
+            Body,
+            Close,
+        }
+        let mut part = Part::Open;
+        for element in node.children_with_tokens() {
+            match (&part, element) {
+                (Part::Open, SyntaxElement::Node(attribute)) => {
+                    attributes.push(self.attribute(&attribute, level + 1));
+                }
+                (Part::Open, SyntaxElement::Token(token)) => match token.kind() {
+                    SyntaxTokenKind::Trivia(TriviaKind::Whitespace) => {}
+                    SyntaxTokenKind::Trivia(kind) => {
+                        line_comment |= *kind == TriviaKind::LineComment;
+                        attributes.push(token.text().to_string());
+                    }
+                    SyntaxTokenKind::Token(TokenKind::Identifier(_)) if name.is_empty() => {
+                        name = token.text().to_string();
+                    }
+                    SyntaxTokenKind::Token(TokenKind::GreaterThan) => part = Part::Body,
+                    SyntaxTokenKind::Token(TokenKind::SelfClosing) => part = Part::Close,
+                    SyntaxTokenKind::Token(_) => {}
+                },
+                (Part::Body, SyntaxElement::Token(token))
+                    if token.kind() == &SyntaxTokenKind::Token(TokenKind::ClosingTag) =>
+                {
+                    part = Part::Close;
+                }
+                (Part::Body, element) => body.push(element),
+                (Part::Close, SyntaxElement::Token(token)) if is_comment(&token) => {
+                    body.push(SyntaxElement::Token(token));
+                }
+                (Part::Close, _) => {}
+            }
+        }
+
+        let children = self.children(body, level + 1);
+        let self_closing = children.is_empty();
+        let tag_line = format!(
+            "<{name}{}{}",
+            attributes.iter().map(|a| format!(" {a}")).collect::<String>(),
+            if self_closing { " />" } else { ">" }
+        );
+        let tag_fits = !line_comment && self.fits(level, &tag_line);
+
+        if tag_fits {
+            self.out.push_str(&tag_line);
+        } else {
+            self.out.push('<');
+            self.out.push_str(&name);
+            for attribute in &attributes {
+                self.out.push('\n');
+                self.out.push_str(&self.indent(level + 1));
+                self.out.push_str(attribute);
+            }
+            self.out.push('\n');
+            self.out.push_str(&self.indent(level));
+            self.out.push_str(if self_closing { "/>" } else { ">" });
+        }
+        if self_closing {
+            return;
+        }
+
+        let closing = format!("</{name}>");
+        if let [Child { kind: ChildKind::Inline(text), .. }] = children.as_slice()
+            && tag_fits
+            && self.fits(level, &format!("{tag_line}{text}{closing}"))
+        {
+            self.out.push_str(text);
+            self.out.push_str(&closing);
+            return;
+        }
+
+        self.print_children(&children, level + 1, record);
+        self.out.push('\n');
+        self.out.push_str(&self.indent(level));
+        self.out.push_str(&closing);
+    }
+
+    /// Print children on their own lines at `level`, recording elements as
+    /// formatting units if `record` is set
+    fn print_children(&mut self, children: &[Child], level: usize, record: bool) {
+        for (i, child) in children.iter().enumerate() {
+            self.out.push('\n');
+            if i > 0 && child.blank_before {
+                self.out.push('\n');
+            }
+            self.out.push_str(&self.indent(level));
+            match &child.kind {
+                ChildKind::Inline(text) => self.out.push_str(text),
+                ChildKind::Element(element) => {
+                    let start = self.out.len();
+                    if element.kind() == SyntaxKind::Element {
+                        self.element(element, level);
+                    } else {
+                        self.control_block(element, level);
+                    }
+                    if record {
+                        self.units.push(Unit {
+                            depth: 1,
+                            source: element.trimmed_span(),
+                            output: start..self.out.len(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Print an `{#if}`, `{#each}` or `{#await}` block with each tag on its
+    /// own line and the content between tags indented
+    fn control_block(&mut self, node: &SyntaxNode, level: usize) {
+        let mut body = Vec::new();
+        let mut first = true;
+        for element in node.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(tag) if tag.kind() == SyntaxKind::BlockTag => {
+                    if !first {
+                        let children = self.children(std::mem::take(&mut body), level + 1);
+                        self.print_children(&children, level + 1, false);
+                        self.out.push('\n');
+                        self.out.push_str(&self.indent(level));
+                    }
+                    self.out.push_str(&self.block_tag(&tag, level));
+                    first = false;
+                }
+                element => body.push(element),
+            }
+        }
+    }
+
+    /// Print a block tag such as `{#each items as item, i (item.id)}`
+    fn block_tag(&self, node: &SyntaxNode, level: usize) -> String {
+        let mut text = String::new();
+        for element in node.children_with_tokens() {
+            let piece = match &element {
+                SyntaxElement::Node(node) => self.inline(node, level),
+                SyntaxElement::Token(token) if token.kind() == &SyntaxTokenKind::Trivia(TriviaKind::LineComment) => {
+                    format!("{}\n{}", token.text().trim_end(), self.indent(level + 1))
+                }
+                SyntaxElement::Token(token) if is_comment(token) => format!("{} ", token.text()),
+                SyntaxElement::Token(token) if token.is_trivia() => continue,
+                SyntaxElement::Token(token) => token.text().to_string(),
+            };
+            let attached = text.is_empty()
+                || text.ends_with(['{', '#', ':', '/', '(', ' '])
+                || matches!(piece.as_str(), "," | ")" | "}");
+            if !attached {
+                text.push(' ');
+            }
+            text.push_str(&piece);
+        }
+        text
+    }
+
+    /// Group an element body into children
+    ///
+    /// Text, interpolations and comments on the same line form one inline
+    /// run. Whitespace inside a run collapses to a single space, which the
+    /// parser treats the same as any other whitespace; whitespace around
+    /// runs and elements is dropped by the parser and can be re-laid out.
+    fn children(&self, body: Vec<SyntaxElement>, level: usize) -> Vec<Child> {
+        let mut children = Vec::new();
+        let mut run = String::new();
+        let mut run_blank = false;
+        // Newlines in the whitespace since the last content
+        let mut newlines = 0;
+        let mut space = false;
+        let mut after_comment = false;
+
+        let flush = |children: &mut Vec<Child>, run: &mut String, blank: bool| {
+            if !run.is_empty() {
+                children.push(Child {
+                    blank_before: blank,
+                    kind: ChildKind::Inline(std::mem::take(run)),
+                });
+            }
+        };
+
+        for element in body {
+            let content = match element {
+                SyntaxElement::Node(node)
+                    if matches!(
+                        node.kind(),
+                        SyntaxKind::Element | SyntaxKind::IfBlock | SyntaxKind::EachBlock | SyntaxKind::AwaitBlock
+                    ) =>
+                {
+                    flush(&mut children, &mut run, run_blank);
+                    children.push(Child {
+                        blank_before: newlines >= 2,
+                        kind: ChildKind::Element(node),
+                    });
+                    newlines = 0;
+                    space = false;
+                    after_comment = false;
+                    continue;
+                }
+                SyntaxElement::Node(node) => self.inline(&node, level),
+                SyntaxElement::Token(token) => match token.kind() {
+                    SyntaxTokenKind::Token(TokenKind::RawText(_)) => {
+                        let text = token.text();
+                        let trimmed = text.trim_start();
+                        if trimmed.is_empty() {
+                            newlines += text.matches('\n').count();
+                            space = true;
+                            continue;
+                        }
+                        newlines += text[..text.len() - trimmed.len()].matches('\n').count();
+                        space |= trimmed.len() < text.len();
+                        let collapsed = trimmed.split_whitespace().collect::<Vec<_>>().join(" ");
+                        let trailing = &trimmed[trimmed.trim_end().len()..];
+
+                        if after_comment && newlines > 0 {
+                            flush(&mut children, &mut run, run_blank);
+                        }
+                        if run.is_empty() {
+                            run_blank = newlines >= 2;
+                        } else if space {
+                            run.push(' ');
+                        }
+                        run.push_str(&collapsed);
+                        newlines = trailing.matches('\n').count();
+                        space = !trailing.is_empty();
+                        after_comment = false;
+                        continue;
+                    }
+                    SyntaxTokenKind::Trivia(TriviaKind::Whitespace) => continue,
+                    _ => token.text().to_string(),
+                },
+            };
+
+            // Interpolation or comment; comments on their own line stay there
+            let comment = content.starts_with("<!--") || content.starts_with("//") || content.starts_with("/*");
+            if (after_comment || comment) && newlines > 0 {
+                flush(&mut children, &mut run, run_blank);
+            }
+            if run.is_empty() {
+                run_blank = newlines >= 2;
+            } else if space {
+                run.push(' ');
+            }
+            run.push_str(&content);
+            newlines = 0;
+            space = false;
+            after_comment = comment;
+        }
+        flush(&mut children, &mut run, run_blank);
+        children
+    }
+
+    fn attribute(&self, node: &SyntaxNode, level: usize) -> String {
+        let mut text = String::new();
+        let mut value = Vec::new();
+        let mut equals = false;
+        for element in node.children_with_tokens() {
+            if equals {
+                self.flatten(element, &mut value);
+                continue;
+            }
+            if let SyntaxElement::Token(token) = &element {
+                match token.kind() {
+                    SyntaxTokenKind::Token(TokenKind::Equals) => {
+                        text.push('=');
+                        equals = true;
+                    }
+                    SyntaxTokenKind::Token(_) => text.push_str(token.text()),
+                    SyntaxTokenKind::Trivia(_) => {}
+                }
+            } else if let SyntaxElement::Node(spread) = element {
+                // `{...props}` has no `=`; its expression sits between the braces
+                let mut atoms = Vec::new();
+                self.flatten(SyntaxElement::Node(spread), &mut atoms);
+                text.push_str(&self.join(&atoms, level));
+            }
+        }
+        text.push_str(&self.layout(&value, level, text.chars().count()));
+        text
+    }
+
+    // ========================================================================
+    // Statements and Expressions
+    // ========================================================================
+
+    /// Print a statement, returning it and the comments that end it
+    fn statement(&self, node: &SyntaxNode, level: usize) -> (String, Vec<String>) {
+        let mut atoms = Vec::new();
+        for element in node.children_with_tokens() {
+            self.flatten(element, &mut atoms);
+        }
+
+        let mut trailing = Vec::new();
+        while let Some(Atom::Comment { .. }) = atoms.last() {
+            if let Some(Atom::Comment { text, .. }) = atoms.pop() {
+                trailing.insert(0, text);
+            }
+        }
+
+        let needs_semicolon = matches!(
+            node.kind(),
+            SyntaxKind::ImportDecl
+                | SyntaxKind::VariableDecl
+                | SyntaxKind::BreakStmt
+                | SyntaxKind::ContinueStmt
+                | SyntaxKind::ReturnStmt
+                | SyntaxKind::ExprStmt
+        );
+        let has_semicolon = matches!(
+            atoms.last(),
+            Some(Atom::Token {
+                kind: TokenKind::Semicolon,
+                ..
+            })
+        );
+        if needs_semicolon && !has_semicolon {
+            atoms.push(Atom::Token {
+                kind: TokenKind::Semicolon,
+                text: ";".to_string(),
+                unary: false,
+                attached: false,
+            });
+        }
+
+        (self.join(&atoms, level), trailing)
+    }
+
+    fn inline(&self, node: &SyntaxNode, level: usize) -> String {
+        let mut atoms = Vec::new();
+        self.flatten(SyntaxElement::Node(node.clone()), &mut atoms);
+        self.join(&atoms, level)
+    }
+
+    fn block(&self, node: &SyntaxNode, level: usize) -> String {
+        let mut printer = Printer::new(self.options);
+        printer.out.push('{');
+        printer.body(between(node, TokenKind::LeftBrace, TokenKind::RightBrace), level + 1, None);
+        printer.out.push('}');
+        printer.out
+    }
+
+    fn template(&self, node: &SyntaxNode, level: usize) -> String {
+        let mut text = String::new();
+        for element in node.children_with_tokens() {
+            match element {
+                SyntaxElement::Token(token) => match token.kind() {
+                    SyntaxTokenKind::Trivia(TriviaKind::Whitespace) => {}
+                    SyntaxTokenKind::Trivia(TriviaKind::LineComment) => {
+                        text.push(' ');
+                        text.push_str(token.text());
+                        text.push('\n');
+                    }
+                    _ => text.push_str(token.text()),
+                },
+                SyntaxElement::Node(node) => text.push_str(&self.inline(&node, level)),
+            }
+        }
+        text
+    }
+
+    fn flatten(&self, element: SyntaxElement, atoms: &mut Vec<Atom>) {
+        match element {
+            SyntaxElement::Token(token) => match token.kind() {
+                SyntaxTokenKind::Trivia(TriviaKind::Whitespace) => {}
+                SyntaxTokenKind::Trivia(kind) => atoms.push(Atom::Comment {
+                    text: token.text().to_string(),
+                    line: *kind == TriviaKind::LineComment,
+                }),
+                SyntaxTokenKind::Token(kind) => {
+                    let text = match kind {
+                        TokenKind::StringLiteral(_) | TokenKind::StringLiteralSingle(_) => {
+                            requote(token.text(), self.options.quote_style)
+                        }
+                        _ => token.text().to_string(),
+                    };
+                    let parent = token.parent();
+                    if matches!(
+                        parent.kind(),
+                        SyntaxKind::ObjectPattern
+                            | SyntaxKind::Type
+                            | SyntaxKind::ImportDecl
+                    ) && matches!(kind, TokenKind::LeftBrace | TokenKind::RightBrace)
+                    {
+                        atoms.push(Atom::Brace {
+                            open: *kind == TokenKind::LeftBrace,
+                        });
+                        return;
+                    }
+                    // Prefix `++`/`--` and `...` attach to their operand like `!` and `-`
+                    // The angle brackets of `Signal<T>` hug their contents
+                    let generic = matches!(parent.kind(), SyntaxKind::Type | SyntaxKind::TypeArgs);
+                    let unary = parent.kind() == SyntaxKind::UnaryExpr
+                        || (matches!(parent.kind(), SyntaxKind::UpdateExpr | SyntaxKind::SpreadExpr)
+                            && token.span().start == parent.trimmed_span().start)
+                        || (generic && *kind == TokenKind::LessThan);
+                    let attached = match kind {
+                        TokenKind::Colon => matches!(
+                            parent.kind(),
+                            SyntaxKind::Property | SyntaxKind::TypeAnnotation | SyntaxKind::Type
+                        ),
+                        TokenKind::LessThan | TokenKind::GreaterThan => generic,
+                        _ => false,
+                    };
+                    atoms.push(Atom::Token {
+                        kind: kind.clone(),
+                        text,
+                        unary,
+                        attached,
+                    });
+                }
+            },
+            SyntaxElement::Node(node) => match node.kind() {
+                SyntaxKind::Block => atoms.push(Atom::Block(node)),
+                SyntaxKind::ArrayExpr | SyntaxKind::ObjectExpr | SyntaxKind::ArgList => {
+                    let mut list = self.list(&node);
+                    // A call whose only argument is a literal hugs it: `f([`
+                    let hugs = node.kind() == SyntaxKind::ArgList
+                        && matches!(
+                            list.elements.as_slice(),
+                            [ListElement { leading, atoms, trailing }]
+                                if leading.is_empty() && trailing.is_empty() && matches!(atoms.as_slice(), [Atom::List(inner)] if inner.open != "(")
+                        );
+                    if !hugs {
+                        atoms.push(Atom::List(list));
+                        return;
+                    }
+                    let paren = |kind: TokenKind, text: &str| Atom::Token {
+                        kind,
+                        text: text.to_string(),
+                        unary: false,
+                        attached: false,
+                    };
+                    atoms.push(paren(TokenKind::LeftParen, "("));
+                    atoms.append(&mut list.elements[0].atoms);
+                    atoms.push(paren(TokenKind::RightParen, ")"));
+                }
+                SyntaxKind::TemplateLiteral => {
+                    atoms.push(Atom::Verbatim(self.template(&node, 0)));
+                }
+                _ => {
+                    for child in node.children_with_tokens() {
+                        self.flatten(child, atoms);
+                    }
+                    // A body without braces ends in `;`, so an `else` can
+                    // start the next line
+                    let unbraced_body = matches!(
+                        node.kind(),
+                        SyntaxKind::BreakStmt | SyntaxKind::ContinueStmt | SyntaxKind::ReturnStmt | SyntaxKind::ExprStmt
+                    ) && node.parent().is_some_and(|parent| {
+                        matches!(parent.kind(), SyntaxKind::IfStmt | SyntaxKind::ForStmt | SyntaxKind::WhileStmt)
+                            && !followed_by_semicolon(&parent, &node)
+                    });
+                    let ends_statement = atoms
+                        .iter()
+                        .rev()
+                        .find(|atom| !matches!(atom, Atom::Comment { .. }))
+                        .is_some_and(|atom| matches!(atom, Atom::Token { kind: TokenKind::Semicolon, .. }));
+                    if unbraced_body && !ends_statement {
+                        let at = atoms
+                            .iter()
+                            .rposition(|atom| !matches!(atom, Atom::Comment { .. }))
+                            .map_or(0, |i| i + 1);
+                        atoms.insert(
+                            at,
+                            Atom::Token {
+                                kind: TokenKind::Semicolon,
+                                text: ";".to_string(),
+                                unary: false,
+                                attached: false,
+                            },
+                        );
+                    }
+                }
+            },
+        }
+    }
+
+    /// Split the contents of an array, object or argument list into
+    /// elements at its top-level commas
+    fn list(&self, node: &SyntaxNode) -> List {
+        let (open, close, padded) = match node.kind() {
+            SyntaxKind::ArrayExpr => ("[", "]", false),
+            SyntaxKind::ObjectExpr => ("{", "}", true),
+            _ => ("(", ")", false),
+        };
+        let open_kind = match open {
+            "[" => TokenKind::LeftBracket,
+            "{" => TokenKind::LeftBrace,
+            _ => TokenKind::LeftParen,
+        };
+        let close_kind = match close {
+            "]" => TokenKind::RightBracket,
+            "}" => TokenKind::RightBrace,
+            _ => TokenKind::RightParen,
+        };
+
+        let mut elements: Vec<ListElement> = Vec::new();
+        let mut current = Vec::new();
+        // A line comment on the same line as a comma belongs to the element before it
+        let mut after_comma = false;
+        for element in between(node, open_kind, close_kind) {
+            if let SyntaxElement::Token(token) = &element {
+                match token.kind() {
+                    SyntaxTokenKind::Token(TokenKind::Comma) => {
+                        elements.push(ListElement {
+                            leading: Vec::new(),
+                            atoms: std::mem::take(&mut current),
+                            trailing: Vec::new(),
+                        });
+                        after_comma = true;
+                        continue;
+                    }
+                    SyntaxTokenKind::Trivia(TriviaKind::Whitespace) => {
+                        after_comma &= !token.text().contains('\n');
+                        continue;
+                    }
+                    SyntaxTokenKind::Trivia(TriviaKind::LineComment) if after_comma => {
+                        if let Some(last) = elements.last_mut() {
+                            last.trailing.push(token.text().to_string());
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            after_comma = false;
+            self.flatten(element, &mut current);
+        }
+        if !current.is_empty() {
+            elements.push(ListElement {
+                leading: Vec::new(),
+                atoms: current,
+                trailing: Vec::new(),
+            });
+        }
+
+        // Line comments that start an element go on their own lines, and
+        // those that end it after its comma
+        for element in &mut elements {
+            while let Some(Atom::Comment { line: true, .. }) = element.atoms.first() {
+                if let Atom::Comment { text, .. } = element.atoms.remove(0) {
+                    element.leading.push(text);
+                }
+            }
+            let mut moved = Vec::new();
+            while let Some(Atom::Comment { line: true, .. }) = element.atoms.last() {
+                if let Some(Atom::Comment { text, .. }) = element.atoms.pop() {
+                    moved.insert(0, text);
+                }
+            }
+            moved.append(&mut element.trailing);
+            element.trailing = moved;
+        }
+
+        List {
+            open,
+            close,
+            padded,
+            elements,
+        }
+    }
+
+    /// Join atoms with canonical spacing, breaking the outermost lists
+    /// one element per line if the first line would be wider than
+    /// `max_width` or a list holds a line comment
+    fn join(&self, atoms: &[Atom], level: usize) -> String {
+        self.layout(atoms, level, 0)
+    }
+
+    /// Lay out atoms on a line that has `taken` columns of other text
+    /// besides the indentation
+    fn layout(&self, atoms: &[Atom], level: usize, taken: usize) -> String {
+        let flat = self.render(atoms, level, false);
+        let forced = atoms
+            .iter()
+            .any(|atom| matches!(atom, Atom::List(list) if list.has_line_comment()));
+        let first_line = flat.lines().next().unwrap_or_default();
+        if !forced && self.fits(level, &format!("{}{}", " ".repeat(taken), first_line)) {
+            return flat;
+        }
+        self.render(atoms, level, true)
+    }
+
+    fn list_text(&self, list: &List, level: usize, broken: bool) -> String {
+        if list.elements.is_empty() {
+            return format!("{}{}", list.open, list.close);
+        }
+        if !broken {
+            let elements: Vec<String> = list
+                .elements
+                .iter()
+                .map(|element| self.render(&element.atoms, level, false))
+                .collect();
+            let pad = if list.padded { " " } else { "" };
+            return format!("{}{pad}{}{pad}{}", list.open, elements.join(", "), list.close);
+        }
+
+        let mut text = list.open.to_string();
+        for element in &list.elements {
+            for comment in &element.leading {
+                text.push('\n');
+                text.push_str(&self.indent(level + 1));
+                text.push_str(comment);
+            }
+            if element.atoms.is_empty() && element.trailing.is_empty() {
+                continue;
+            }
+            text.push('\n');
+            text.push_str(&self.indent(level + 1));
+            if !element.atoms.is_empty() {
+                text.push_str(&self.layout(&element.atoms, level + 1, 1));
+                text.push(',');
+            }
+            for (i, comment) in element.trailing.iter().enumerate() {
+                if i > 0 || !element.atoms.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(comment);
+            }
+        }
+        text.push('\n');
+        text.push_str(&self.indent(level));
+        text.push_str(list.close);
+        text
+    }
+
+    /// Join atoms with canonical spacing; a line comment ends the line,
+    /// and lists break one element per line if `broken` is set
+    fn render(&self, atoms: &[Atom], level: usize, broken: bool) -> String {
+        let mut text = String::new();
+        let mut prev: Option<&Atom> = None;
+        for atom in atoms {
+            match (prev, atom) {
+                (Some(Atom::Comment { line: true, .. }), _) => {
+                    text.push('\n');
+                    text.push_str(&self.indent(level + 1));
+                }
+                // `else` after a body without braces
+                (
+                    Some(Atom::Token { kind: TokenKind::Semicolon, .. }),
+                    Atom::Token { kind: TokenKind::Else, .. },
+                ) => {
+                    text.push('\n');
+                    text.push_str(&self.indent(level));
+                }
+                (Some(prev), _) if space_between(prev, atom) => text.push(' '),
+                _ => {}
+            }
+            match atom {
+                Atom::Token { text: t, .. } | Atom::Comment { text: t, .. } | Atom::Verbatim(t) => {
+                    text.push_str(t)
+                }
+                Atom::Block(node) => text.push_str(&self.block(node, level)),
+                Atom::Brace { open: true } => text.push('{'),
+                Atom::Brace { open: false } => text.push('}'),
+                Atom::List(list) => text.push_str(&self.list_text(list, level, broken)),
+            }
+            prev = Some(atom);
+        }
+        if let Some(Atom::Comment { line: true, .. }) = prev {
+            text.push('\n');
+            text.push_str(&self.indent(level));
+        }
+        text
+    }
+}
+
+/// Split a list of children into items on their own lines
+fn items(elements: impl IntoIterator<Item = SyntaxElement>) -> Vec<Item> {
+    let mut items: Vec<Item> = Vec::new();
+    let mut newlines = 0;
+    for element in elements {
+        if let SyntaxElement::Token(token) = &element {
+            if token.kind() == &SyntaxTokenKind::Trivia(TriviaKind::Whitespace) {
+                newlines += token.text().matches('\n').count();
+                continue;
+            }
+            if let Some(last) = items.last_mut()
+                && is_comment(token)
+                && newlines == 0
+            {
+                last.trailing.push(token.text().to_string());
+                continue;
+            }
+        }
+        items.push(Item {
+            piece: element,
+            blank_before: newlines >= 2,
+            trailing: Vec::new(),
+        });
+        newlines = 0;
+    }
+    items
+}
+
+/// Children of `node` between its first `open` and last `close` token
+fn between(node: &SyntaxNode, open: TokenKind, close: TokenKind) -> Vec<SyntaxElement> {
+    let elements = node.children_with_tokens();
+    let is = |element: &SyntaxElement, kind: &TokenKind| {
+        matches!(element, SyntaxElement::Token(token) if token.kind() == &SyntaxTokenKind::Token(kind.clone()))
+    };
+    let start = elements.iter().position(|e| is(e, &open)).map_or(0, |i| i + 1);
+    let end = elements
+        .iter()
+        .rposition(|e| is(e, &close))
+        .unwrap_or(elements.len())
+        .max(start);
+    elements[start..end].to_vec()
+}
+
+/// Whether the next token after `child` in `parent` is a `;`, as after
+/// the initializer of a `for` loop
+fn followed_by_semicolon(parent: &SyntaxNode, child: &SyntaxNode) -> bool {
+    parent
+        .children_with_tokens()
+        .into_iter()
+        .skip_while(|element| !matches!(element, SyntaxElement::Node(node) if node.span() == child.span()))
+        .skip(1)
+        .find(|element| !matches!(element, SyntaxElement::Token(token) if token.is_trivia()))
+        .is_some_and(|element| {
+            matches!(element, SyntaxElement::Token(token) if token.kind() == &SyntaxTokenKind::Token(TokenKind::Semicolon))
+        })
+}
+
+fn is_comment(token: &SyntaxToken) -> bool {
+    matches!(token.kind(), SyntaxTokenKind::Trivia(kind) if *kind != TriviaKind::Whitespace)
+}
+
+/// Comment inside a style section
+struct CssComment {
+    span: Span,
+    text: String,
+    /// Whether it follows other code on the same line
+    trailing: bool,
+}
+
+fn css_comments(body: &[SyntaxElement]) -> Vec<CssComment> {
+    let mut comments = Vec::new();
+    let mut newline = true;
+    for element in body {
+        match element {
+            SyntaxElement::Token(token) if is_comment(token) => {
+                comments.push(CssComment {
+                    span: token.span(),
+                    text: token.text().trim_end().to_string(),
+                    trailing: !newline,
+                });
+                newline = token.text().ends_with('\n');
+            }
+            SyntaxElement::Token(token) if token.is_trivia() => {
+                newline |= token.text().contains('\n');
+            }
+            _ => newline = false,
+        }
+    }
+    comments
+}
+
+/// Whether the whitespace just before `offset` holds a blank line
+fn blank_line_before(body: &[SyntaxElement], offset: usize) -> bool {
+    let mut newlines = 0;
+    for element in body {
+        if element.span().start == offset {
+            return newlines >= 2;
+        }
+        match element {
+            SyntaxElement::Token(token) if token.kind() == &SyntaxTokenKind::Trivia(TriviaKind::Whitespace) => {
+                newlines += token.text().matches('\n').count();
+            }
+            _ => newlines = 0,
+        }
+    }
+    false
+}
+
+/// `a.b, c #d`: selectors joined by commas, compounds by spaces
+fn selector_list_text(selectors: &[Selector]) -> String {
+    selectors
+        .iter()
+        .map(|selector| {
+            selector
+                .compounds
+                .iter()
+                .map(compound_selector_text)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn compound_selector_text(compound: &CompoundSelector) -> String {
+    let mut text = compound.tag.clone().unwrap_or_default();
+    if let Some(id) = &compound.id {
+        text.push('#');
+        text.push_str(id);
+    }
+    for class in &compound.classes {
+        text.push('.');
+        text.push_str(class);
+    }
+    if text.is_empty() {
+        text.push('*');
+    }
+    text
+}
+
+fn space_between(prev: &Atom, next: &Atom) -> bool {
+    use TokenKind::*;
+
+    if matches!(prev, Atom::Comment { .. }) || matches!(next, Atom::Comment { .. }) {
+        return true;
+    }
+    if let (Atom::Brace { open: true }, Atom::Brace { open: false }) = (prev, next) {
+        return false;
+    }
+    if let Atom::Token { kind, unary, .. } = prev
+        && (*unary || matches!(kind, LeftParen | LeftBracket | LeftBrace | Dot | QuestionDot))
+    {
+        return false;
+    }
+    // An argument list or index attaches to its operand like `(` and `[`
+    if let Atom::List(list) = next {
+        return list.open == "{" || !ends_operand(prev);
+    }
+    match next {
+        Atom::Token { attached: true, .. } => false,
+        Atom::Token {
+            kind: RightParen | RightBracket | RightBrace | Comma | Semicolon | Dot | QuestionDot,
+            ..
+        } => false,
+        // Calls, indexing and postfix `++`/`--` attach to their operand
+        Atom::Token {
+            kind: LeftParen | LeftBracket | PlusPlus | MinusMinus,
+            ..
+        } => !ends_operand(prev),
+        _ => true,
+    }
+}
+
+fn ends_operand(atom: &Atom) -> bool {
+    use TokenKind::*;
+
+    match atom {
+        Atom::Verbatim(_) | Atom::Brace { open: false } | Atom::List(_) => true,
+        // `signal<T>(...)`
+        Atom::Token { kind: GreaterThan, attached: true, .. } => true,
+        Atom::Token { kind, .. } => matches!(
+            kind,
+            Identifier(_)
+                | Number(_)
+                | Dimension(_)
+                | StringLiteral(_)
+                | StringLiteralSingle(_)
+                | True
+                | False
+                | Null
+                | Signal
+                | Memo
+                | Effect
+                | RightParen
+                | RightBracket
+        ),
+        _ => false,
+    }
+}
+
+/// Re-quote a string literal in the preferred style, unless that would
+/// need more escapes than the current quotes
+fn requote(raw: &str, style: QuoteStyle) -> String {
+    let (preferred, other) = match style {
+        QuoteStyle::Double => ('"', '\''),
+        QuoteStyle::Single => ('\'', '"'),
+    };
+    let Some(body) = raw.strip_prefix(other).and_then(|r| r.strip_suffix(other)) else {
+        return raw.to_string();
+    };
+    if body.matches(preferred).count() > body.matches(other).count() {
+        return raw.to_string();
+    }
+
+    let mut text = String::with_capacity(raw.len() + 2);
+    text.push(preferred);
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == other => text.push(other),
+                Some(next) => {
+                    text.push('\\');
+                    text.push(next);
+                }
+                None => text.push('\\'),
+            }
+        } else {
+            if c == preferred {
+                text.push('\\');
+            }
+            text.push(c);
+        }
+    }
+    text.push(preferred);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn format(source: &str) -> String {
+        format_source(source, &FormatOptions::default()).unwrap()
+    }
+
+    /// The AST of `source` without spans, which formatting moves, and
+    /// CSS selector text, which it normalises
+    fn ast(source: &str) -> serde_json::Value {
+        fn strip_spans(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    map.remove("span");
+                    map.remove("selector");
+                    map.values_mut().for_each(strip_spans);
+                }
+                serde_json::Value::Array(items) => items.iter_mut().for_each(strip_spans),
+                _ => {}
+            }
+        }
+
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let component = Parser::new(tokens, "Test.omni").parse().unwrap();
+        let mut value = serde_json::to_value(&component).unwrap();
+        strip_spans(&mut value);
+        value
+    }
+
+    const MESSY: &str = r##"<!-- Counter -->
+<script>
+const count=signal( 0 )   // state
+  const doubled = memo(()=>count()*2)
+
+
+    function increment(step,extra){
+  count.set( count.get()+step );
+      if(count()>10){ count.set(-1) } else { return }
+    }
+</script>
+<canvas width={800}   height={600}>
+      <!-- Shapes -->
+  <circle x={400} y={300} radius={50+count()} fill='#00d4ff' stroke="#ffffff" opacity={0.5} />
+<text x={10}>
+     Count:   {count()}  items
+  </text>
+
+
+  <group x={1}><rect width={10} height={10} fill='it\'s' /></group>
+</canvas>
+<style>
+      text { font-size: 24px; }
+</style>
+"##;
+
+    const FORMATTED: &str = r##"<!-- Counter -->
+<script>
+  const count = signal(0); // state
+  const doubled = memo(() => count() * 2);
+
+  function increment(step, extra) {
+    count.set(count.get() + step);
+    if (count() > 10) {
+      count.set(-1);
+    } else {
+      return;
+    }
+  }
+</script>
+
+<canvas width={800} height={600}>
+  <!-- Shapes -->
+  <circle
+    x={400}
+    y={300}
+    radius={50 + count()}
+    fill="#00d4ff"
+    stroke="#ffffff"
+    opacity={0.5}
+  />
+  <text x={10}>Count: {count()} items</text>
+
+  <group x={1}>
+    <rect width={10} height={10} fill="it's" />
+  </group>
+</canvas>
+
+<style>
+  text {
+    font-size: 24px;
+  }
+</style>
+"##;
+
+    #[test]
+    fn test_format() {
+        let formatted = format(MESSY);
+        assert_eq!(formatted, FORMATTED);
+        assert_eq!(ast(&formatted), ast(MESSY));
+    }
+
+    #[test]
+    fn test_format_loops() {
+        let messy = "<script>\nfor(let i=0;i<3;i++){ total+=i; if(i==1){continue} }\nwhile(x>0){--x;break}\n{ y=1 }\n</script>\n";
+        let expected = "<script>\n  for (let i = 0; i < 3; i++) {\n    total += i;\n    if (i == 1) {\n      continue;\n    }\n  }\n  while (x > 0) {\n    --x;\n    break;\n  }\n  {\n    y = 1;\n  }\n</script>\n";
+        assert_eq!(format(messy), expected);
+        assert_eq!(ast(expected), ast(messy));
+    }
+
+    #[test]
+    fn test_format_unbraced_bodies() {
+        let messy = "<script>\nfunction sign(x){\nif(x<0)return -1\nelse if(x==0) return 0; else return 1\n}\nwhile(x>0)x--\nfor(i=0;i<3;i++)f(i)\n</script>\n";
+        let expected = "<script>\n  function sign(x) {\n    if (x < 0) return -1;\n    else if (x == 0) return 0;\n    else return 1;\n  }\n  while (x > 0) x--;\n  for (i = 0; i < 3; i++) f(i);\n</script>\n";
+        assert_eq!(format(messy), expected);
+        assert_eq!(format(expected), expected);
+        assert_eq!(ast(expected), ast(messy));
+    }
+
+    #[test]
+    fn test_format_objects() {
+        let messy = "<script>\nconst {a,b:c=1,...d}=obj;\nconst o={x,[k] : 1,...rest,e:{}};\nconst v=f([...xs,1])?.y ?? g?.(x=>x);\n</script>\n";
+        let expected = "<script>\n  const { a, b: c = 1, ...d } = obj;\n  const o = { x, [k]: 1, ...rest, e: {} };\n  const v = f([...xs, 1])?.y ?? g?.(x => x);\n</script>\n";
+        assert_eq!(format(messy), expected);
+        assert_eq!(ast(expected), ast(messy));
+    }
+
+    #[test]
+    fn test_format_wraps_long_lists() {
+        let messy = "<script>\nconst short=[1,2,3,];\nconst o={a:1,b:[3,4,],};\nconst points=signal([{x:100,y:200,label:\"first point\"},{x:300,y:400,label:\"second point\"}]);\nconst total=sum(firstValueInTheList,secondValueInTheList,thirdValueInTheList,last);\n</script>\n<canvas>\n<path points={[[0, 0], [100000, 100000], [200000, 200000], [300000, 300000], [400000, 0]]} />\n</canvas>\n";
+        let expected = r#"<script>
+  const short = [1, 2, 3];
+  const o = { a: 1, b: [3, 4] };
+  const points = signal([
+    { x: 100, y: 200, label: "first point" },
+    { x: 300, y: 400, label: "second point" },
+  ]);
+  const total = sum(
+    firstValueInTheList,
+    secondValueInTheList,
+    thirdValueInTheList,
+    last,
+  );
+</script>
+
+<canvas>
+  <path
+    points={[
+      [0, 0],
+      [100000, 100000],
+      [200000, 200000],
+      [300000, 300000],
+      [400000, 0],
+    ]}
+  />
+</canvas>
+"#;
+        assert_eq!(format(messy), expected);
+        assert_eq!(format(expected), expected);
+        assert_eq!(ast(expected), ast(messy));
+
+        let shapes = include_str!("../../../examples/shapes/App.omni");
+        let formatted = format(shapes);
+        assert!(formatted.lines().all(|line| line.chars().count() <= 80), "{}", formatted);
+        assert!(!formatted.contains(",]") && !formatted.contains(",}"));
+        assert_eq!(format(&formatted), formatted);
+    }
+
+    #[test]
+    fn test_format_comments_in_arrays() {
+        let messy = "<script>\nconst xs=[1, // first\n2,];\nconst ys=[ // leading\n1,\n2 // last\n];\nconst zs=[1, /* two */ 2];\nconst empty=[\n// nothing yet\n];\n</script>\n";
+        let expected = r#"<script>
+  const xs = [
+    1, // first
+    2,
+  ];
+  const ys = [
+    // leading
+    1,
+    2, // last
+  ];
+  const zs = [1, /* two */ 2];
+  const empty = [
+    // nothing yet
+  ];
+</script>
+"#;
+        assert_eq!(format(messy), expected);
+        assert_eq!(format(expected), expected);
+        assert_eq!(ast(expected), ast(messy));
+    }
+
+    #[test]
+    fn test_format_type_annotations() {
+        let messy = "<script>\nlet total :number;\nconst colors=signal < Color[] > ([]);\nfunction f(a:number|null,cb :(x:number)=>void):{x:number;y:string}{}\n</script>\n";
+        let expected = "<script>\n  let total: number;\n  const colors = signal<Color[]>([]);\n  function f(a: number | null, cb: (x: number) => void): { x: number; y: string } {}\n</script>\n";
+        assert_eq!(format(messy), expected);
+        assert_eq!(format(expected), expected);
+        assert_eq!(ast(expected), ast(messy));
+    }
+
+    #[test]
+    fn test_format_control_flow_blocks() {
+        let messy = "<canvas>\n{#if  count()>5}<text>Big</text>{:else if count()>2}\n<text>Medium</text>\n{:else}{#each items() as item,i (item.id)}<circle radius={item} />{/each}{/if}\n{#await load() then data}<text>{data}</text>{:catch}{/await}\n</canvas>\n";
+        let expected = "<canvas>\n  {#if count() > 5}\n    <text>Big</text>\n  {:else if count() > 2}\n    <text>Medium</text>\n  {:else}\n    {#each items() as item, i (item.id)}\n      <circle radius={item} />\n    {/each}\n  {/if}\n  {#await load() then data}\n    <text>{data}</text>\n  {:catch}\n  {/await}\n</canvas>\n";
+        assert_eq!(format(messy), expected);
+        assert_eq!(format(expected), expected);
+        assert_eq!(ast(expected), ast(messy));
+    }
+
+    #[test]
+    fn test_format_props() {
+        let messy = "<script>\nexport   let radius:number=10;\n</script>\n<canvas>\n<Badge   { ...defaults }   label=\"x\" />\n</canvas>\n";
+        let expected = "<script>\n  export let radius: number = 10;\n</script>\n\n<canvas>\n  <Badge {...defaults} label=\"x\" />\n</canvas>\n";
+        assert_eq!(format(messy), expected);
+        assert_eq!(format(expected), expected);
+        assert_eq!(ast(expected), ast(messy));
+    }
+
+    #[test]
+    fn test_format_imports() {
+        let messy = "<script>\nimport Card,{scale,clamp as limit} from './Card.omni'\nimport  *  as icons from \"../icons.omni\";\n</script>\n";
+        let expected = "<script>\n  import Card, { scale, clamp as limit } from \"./Card.omni\";\n  import * as icons from \"../icons.omni\";\n</script>\n";
+        assert_eq!(format(messy), expected);
+        assert_eq!(format(expected), expected);
+        assert_eq!(ast(expected), ast(messy));
+    }
+
+    #[test]
+    fn test_format_style() {
+        let messy = "<style>\n/* dots */\n.dot,circle#main   .ring{fill:red;stroke :  #fff}\n\n\n  text{font-size:24px ; /* big */\n  font-family:Inter,  sans-serif;\n  // body\n  }\n*{opacity:1}\n</style>\n";
+        let expected = "<style>\n  /* dots */\n  .dot, circle#main .ring {\n    fill: red;\n    stroke: #fff;\n  }\n\n  text {\n    font-size: 24px; /* big */\n    font-family: Inter, sans-serif;\n    // body\n  }\n  * {\n    opacity: 1;\n  }\n</style>\n";
+        assert_eq!(format(messy), expected);
+        assert_eq!(format(expected), expected);
+        assert_eq!(ast(expected), ast(messy));
+
+        assert_eq!(format("<style>\n\n</style>\n"), "<style></style>\n");
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        assert_eq!(format(FORMATTED), FORMATTED);
+
+        let inline = "<canvas>\n  <text>{a} {b}<!-- c -->{c}</text>\n</canvas>\n";
+        assert_eq!(format(inline), inline);
+        assert_eq!(ast(&format(inline)), ast(inline));
+    }
+
+    #[test]
+    fn test_quote_style() {
+        let options = FormatOptions {
+            quote_style: QuoteStyle::Single,
+            ..Default::default()
+        };
+        let source = "<script>\n  const a = \"it's\";\n  const b = \"plain \\\"q\\\"\";\n</script>\n";
+        assert_eq!(
+            format_source(source, &options).unwrap(),
+            "<script>\n  const a = \"it's\";\n  const b = 'plain \"q\"';\n</script>\n"
+        );
+    }
+
+    #[test]
+    fn test_leaves_builder_files() {
+        let source = "component Badge {\n    state n = 0\n    render { Text(\"{n}\").fontSize(12) }\n}\n";
+        assert_eq!(format(source), source);
+        assert!(matches!(
+            format_source("component Badge { render { Text(1, 2) } }", &FormatOptions::default()),
+            Err(FormatError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_refuses_broken_input() {
+        assert!(matches!(
+            format_source("<script> const = 1; </script>", &FormatOptions::default()),
+            Err(FormatError::Parse(_))
+        ));
+        assert!(matches!(
+            format_source("<script> const a = \"open; </script>", &FormatOptions::default()),
+            Err(FormatError::Lex(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_range() {
+        let source = "<script>\n  const a=1\n  const b=2\n</script>\n\n<canvas>\n  <circle x={1}/>\n</canvas>\n";
+        let start = source.find("const b").unwrap();
+        let edit = format_range(source, Span::new(start, start + 3), &FormatOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(&source[edit.span.start..edit.span.end], "  const b=2");
+        assert_eq!(edit.text, "  const b = 2;");
+
+        let start = source.find("<circle").unwrap();
+        let edit = format_range(source, Span::new(start, start), &FormatOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(edit.text, "  <circle x={1} />");
+
+        let formatted = format(source);
+        assert_eq!(
+            format_range(&formatted, Span::new(0, formatted.len()), &FormatOptions::default())
+                .unwrap(),
+            None
+        );
+    }
+}
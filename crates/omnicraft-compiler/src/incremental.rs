@@ -0,0 +1,417 @@
+//! Incremental re-lexing and re-parsing
+//!
+//! `IncrementalDocument` keeps the tokens, lexer checkpoints and parsed
+//! subtrees of a source file. A text edit is re-lexed from the last
+//! checkpoint before it until the lexer is back in step with the old token
+//! stream, and the re-parse reuses top-level subtrees whose tokens did not
+//! change.
+
+use crate::ast::Component;
+use crate::lexer::{Checkpoint, Lexer, LexerError, Span, Token, TokenKind};
+use crate::parser::{ParseError, Parser, SubtreeCache};
+use tracing::trace;
+
+/// Replacement of a byte range of the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    /// Byte range in the current source
+    pub span: Span,
+    /// Replacement text
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(span: Span, text: impl Into<String>) -> Self {
+        Self {
+            span,
+            text: text.into(),
+        }
+    }
+}
+
+/// A source file whose tokens and AST are updated edit by edit
+pub struct IncrementalDocument {
+    file_name: String,
+    source: String,
+    tokens: Vec<Token>,
+    lex_errors: Vec<LexerError>,
+    checkpoints: Vec<Checkpoint>,
+    cache: SubtreeCache,
+    component: Component,
+    parse_errors: Vec<ParseError>,
+    relexed: usize,
+}
+
+impl IncrementalDocument {
+    /// Lex and parse a whole document
+    pub fn new(source: impl Into<String>, file_name: &str) -> Self {
+        let source = source.into();
+        let run = Lexer::new(&source).lex_from(&Checkpoint::default(), |_| false);
+        let relexed = run.tokens.len();
+        let (tokens, cache, (component, parse_errors)) =
+            parse(run.tokens, file_name, SubtreeCache::default());
+
+        Self {
+            file_name: file_name.to_string(),
+            source,
+            tokens,
+            lex_errors: run.errors,
+            checkpoints: run.checkpoints,
+            cache,
+            component,
+            parse_errors,
+            relexed,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn lex_errors(&self) -> &[LexerError] {
+        &self.lex_errors
+    }
+
+    /// Parsed component, with `Error` placeholders where parsing failed
+    pub fn component(&self) -> &Component {
+        &self.component
+    }
+
+    pub fn parse_errors(&self) -> &[ParseError] {
+        &self.parse_errors
+    }
+
+    /// Number of tokens produced by the lexer for the last update
+    pub fn relexed_tokens(&self) -> usize {
+        self.relexed
+    }
+
+    /// Apply an edit, re-lexing only the affected region and re-parsing
+    /// with unchanged subtrees reused
+    pub fn apply_edit(&mut self, edit: &TextEdit) {
+        let start = edit.span.start.min(self.source.len());
+        let end = edit.span.end.clamp(start, self.source.len());
+        self.source.replace_range(start..end, &edit.text);
+
+        let offset_delta = edit.text.len() as isize - (end - start) as isize;
+        let new_end = start + edit.text.len();
+
+        // Resume one token before the first token touching the edit, since
+        // the lexer may have looked past the end of that token
+        let mut first = self.tokens.partition_point(|t| t.span.end < start);
+        first = first.saturating_sub(1);
+        // A quote earlier on the line may now close a string, and only an
+        // escaped line break lets a string reach back further
+        let line_start = logical_line_start(&self.source, start);
+        first = first.min(self.tokens.partition_point(|t| t.span.end <= line_start));
+        // A comment opener that found no end may find one in the new text
+        if let Some(opener) = self.unclosed_comment_before(first, start, new_end) {
+            first = first.min(opener);
+        }
+        while first > 0 && self.tokens[first - 1].kind == TokenKind::Error {
+            first -= 1;
+        }
+        let restart_index = self
+            .checkpoints
+            .partition_point(|c| c.token_index <= first)
+            .saturating_sub(1);
+        let restart = self.checkpoints[restart_index].clone();
+
+        // Stop as soon as the new lexer state matches an old checkpoint
+        // past the edited line, whose text decides whether a `<` on it opens
+        // a tag, unless an unexpected character there could fold into an
+        // error token on the other side
+        let line_end = self.source[new_end..].find('\n').map_or(self.source.len(), |i| new_end + i);
+        let old_tokens = &self.tokens;
+        let old_checkpoints = &self.checkpoints;
+        let mut synced = None;
+        let run = Lexer::new(&self.source).lex_from(&restart, |checkpoint| {
+            if checkpoint.offset < line_end {
+                return false;
+            }
+            let old_offset = checkpoint.offset.saturating_add_signed(-offset_delta);
+            let from = old_checkpoints.partition_point(|c| c.offset < old_offset);
+            synced = old_checkpoints[from..]
+                .iter()
+                .take_while(|c| c.offset == old_offset)
+                .position(|c| {
+                    !beside_error(old_tokens, c) && c.shifted(end, offset_delta, 0).same_state(checkpoint)
+                })
+                .map(|i| from + i);
+            synced.is_some()
+        });
+        trace!(
+            relexed = run.tokens.len(),
+            synced = synced.is_some(),
+            "Incremental relex"
+        );
+
+        // Splice tokens, checkpoints and errors
+        let old_tokens_end = synced.map_or(self.tokens.len(), |i| self.checkpoints[i].token_index);
+        let old_offset_end = synced.map_or(usize::MAX, |i| self.checkpoints[i].offset);
+        let old_sync = synced.map(|i| self.checkpoints[i].clone());
+        let replaced = restart.token_index..old_tokens_end;
+        let token_delta = run.tokens.len() as isize - replaced.len() as isize;
+
+        let tail: Vec<Token> = self
+            .tokens
+            .drain(replaced.start..)
+            .skip(replaced.len())
+            .collect();
+        self.tokens.extend(run.tokens.iter().cloned());
+        self.tokens.extend(tail.into_iter().map(|mut token| {
+            token.span = token.span.shifted(offset_delta);
+            token
+        }));
+
+        let tail: Vec<Checkpoint> = synced
+            .map(|i| {
+                self.checkpoints[i..]
+                    .iter()
+                    .map(|c| c.shifted(end, offset_delta, token_delta))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.checkpoints.truncate(restart_index);
+        self.checkpoints.extend(run.checkpoints);
+        self.checkpoints.extend(tail);
+
+        // A template still open at the sync point is reported at the end of
+        // input, after the sync point even when that is the end of input
+        let old_len = self.source.len().saturating_add_signed(-offset_delta);
+        let mut errors: Vec<LexerError> = Vec::new();
+        let mut tail = Vec::new();
+        let mut unclosed = Vec::new();
+        for error in self.lex_errors.drain(..) {
+            let span = error.span();
+            if span.end <= restart.offset {
+                errors.push(error);
+            } else if span.start >= old_offset_end {
+                tail.push(error.with_span(span.shifted(offset_delta)));
+            } else if span.end == old_len
+                && old_sync.as_ref().is_some_and(|c| c.has_open_template(span.start))
+            {
+                let start = if span.start >= end {
+                    span.start.saturating_add_signed(offset_delta)
+                } else {
+                    span.start
+                };
+                unclosed.push(error.with_span(Span::new(start, self.source.len())));
+            }
+        }
+        errors.extend(run.errors);
+        errors.extend(tail);
+        errors.extend(unclosed);
+        self.lex_errors = errors;
+        self.relexed = run.tokens.len();
+
+        // Re-parse, reusing subtrees outside the re-lexed tokens
+        let mut cache = std::mem::take(&mut self.cache);
+        cache.splice(replaced, self.relexed);
+        let tokens = std::mem::take(&mut self.tokens);
+        let (tokens, cache, (component, parse_errors)) = parse(tokens, &self.file_name, cache);
+        self.tokens = tokens;
+        self.cache = cache;
+        self.component = component;
+        self.parse_errors = parse_errors;
+    }
+
+    /// Index of the first `/*` or `<!--` before token `before` that was
+    /// lexed as operators for lack of an end, if the edited text between
+    /// `start` and `new_end` may have supplied one
+    fn unclosed_comment_before(&self, before: usize, start: usize, new_end: usize) -> Option<usize> {
+        let around = self
+            .source
+            .get(start.saturating_sub(2)..(new_end + 2).min(self.source.len()))
+            .unwrap_or(&self.source);
+        let closes_block = around.contains("*/");
+        let closes_html = around.contains("->");
+        if !closes_block && !closes_html {
+            return None;
+        }
+        let end = (before + 1).min(self.tokens.len());
+        self.tokens[..end].windows(2).position(|pair| {
+            let (left, right) = (&pair[0].text, &pair[1].text);
+            pair[0].span.end == pair[1].span.start
+                && ((closes_block && left.ends_with('/') && right.starts_with('*'))
+                    || (closes_html && left.ends_with('<') && right.starts_with('!')))
+        })
+    }
+}
+
+/// Whether an error token ends or starts at the checkpoint
+fn beside_error(tokens: &[Token], checkpoint: &Checkpoint) -> bool {
+    let before = checkpoint.token_index.checked_sub(1).and_then(|i| tokens.get(i));
+    let after = tokens.get(checkpoint.token_index);
+    before.is_some_and(|t| t.kind == TokenKind::Error && t.span.end >= checkpoint.offset)
+        || after.is_some_and(|t| t.kind == TokenKind::Error && t.span.start == checkpoint.offset)
+}
+
+/// Start of the line holding `offset`, extended over lines ending in a
+/// backslash, which a string literal may continue across
+fn logical_line_start(source: &str, offset: usize) -> usize {
+    let mut line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    while line_start > 0 && source[..line_start - 1].ends_with('\\') {
+        line_start = source[..line_start - 1].rfind('\n').map_or(0, |i| i + 1);
+    }
+    line_start
+}
+
+fn parse(
+    tokens: Vec<Token>,
+    file_name: &str,
+    cache: SubtreeCache,
+) -> (Vec<Token>, SubtreeCache, (Component, Vec<ParseError>)) {
+    let mut parser = Parser::new(tokens, file_name).with_cache(cache);
+    let result = parser.parse_with_recovery();
+    let (tokens, cache) = parser.into_parts();
+    (tokens, cache, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"<script>
+  const count = signal(0);
+  const label = `Count: ${count()}`;
+</script>
+
+<canvas width={800} height={600}>
+  <circle x={100} y={100} radius={20} />
+  <text x={10} y={20}>Hello {label}</text>
+  <rect x={200} y={200} width={50} height={50} />
+</canvas>
+"#;
+
+    fn assert_matches_full(doc: &IncrementalDocument) {
+        let full = IncrementalDocument::new(doc.source(), "Test.omni");
+        assert_eq!(doc.tokens(), full.tokens());
+        assert_eq!(doc.lex_errors(), full.lex_errors());
+        assert_eq!(format!("{:?}", doc.component()), format!("{:?}", full.component()));
+        assert_eq!(doc.parse_errors(), full.parse_errors());
+    }
+
+    fn edit(doc: &mut IncrementalDocument, needle: &str, replacement: &str) {
+        let start = doc.source().find(needle).unwrap();
+        doc.apply_edit(&TextEdit::new(
+            Span::new(start, start + needle.len()),
+            replacement,
+        ));
+    }
+
+    #[test]
+    fn test_edit_relexes_only_affected_region() {
+        let mut doc = IncrementalDocument::new(SOURCE, "Test.omni");
+        let total = doc.tokens().len();
+
+        edit(&mut doc, "radius={20}", "radius={25 * 2}");
+        assert!(
+            doc.relexed_tokens() < total / 4,
+            "relexed {} of {}",
+            doc.relexed_tokens(),
+            total
+        );
+        assert!(doc.parse_errors().is_empty());
+        assert_matches_full(&doc);
+    }
+
+    #[test]
+    fn test_edits_match_full_reparse() {
+        let mut doc = IncrementalDocument::new(SOURCE, "Test.omni");
+
+        // Inside a template literal, element text, and across token boundaries
+        edit(&mut doc, "Count: ", "Total: ");
+        assert_matches_full(&doc);
+        edit(&mut doc, "Hello", "Hi there,");
+        assert_matches_full(&doc);
+        edit(&mut doc, "signal(0)", "signal(1_000)");
+        assert_matches_full(&doc);
+        edit(&mut doc, "x={200}", "x={2");
+        assert_matches_full(&doc);
+        assert!(!doc.parse_errors().is_empty());
+        edit(&mut doc, "x={2", "x={200}");
+        assert_matches_full(&doc);
+        assert!(doc.parse_errors().is_empty());
+    }
+
+    #[test]
+    fn test_edit_that_changes_lexer_mode() {
+        let mut doc = IncrementalDocument::new(SOURCE, "Test.omni");
+
+        // Opening a string swallows the rest of the line, closing it restores it
+        edit(&mut doc, "radius={20}", "radius={\"20}");
+        assert!(!doc.lex_errors().is_empty());
+        assert_matches_full(&doc);
+        edit(&mut doc, "\"20}", "20}");
+        assert!(doc.lex_errors().is_empty());
+        assert_matches_full(&doc);
+
+        // An unterminated template reaches the end of the file
+        edit(&mut doc, "`Count", "Count");
+        assert_matches_full(&doc);
+        edit(&mut doc, "Count", "`Count");
+        assert_matches_full(&doc);
+
+        // An operand before a `<` outside the sections makes it a comparison
+        let mut doc = IncrementalDocument::new("a <b> c </b>\n<canvas></canvas>", "Test.omni");
+        edit(&mut doc, "a", "+");
+        assert_matches_full(&doc);
+        edit(&mut doc, "+", "a");
+        assert_matches_full(&doc);
+    }
+
+    #[test]
+    fn test_random_edit_sequences_match_full_reparse() {
+        const FRAGMENTS: &[&str] = &[
+            "\"", "'", "`", "${", "{", "}", "<", ">", "</", "/>", "/*", "*/", "<!--", "-->", "//", "\\",
+            "\n", " ", "x", "42", "%", "text", "script", "=", ";",
+        ];
+        // xorshift, so failures are reproducible
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+
+        for sequence in 0..2000 {
+            let mut doc = IncrementalDocument::new(SOURCE, "Test.omni");
+            for step in 0..8 {
+                let start = next(doc.source().len() + 1);
+                let end = (start + next(6)).min(doc.source().len());
+                let text: String = (0..next(3) + 1).map(|_| FRAGMENTS[next(FRAGMENTS.len())]).collect();
+                let edit = TextEdit::new(Span::new(start, end), text);
+                doc.apply_edit(&edit);
+
+                let full = IncrementalDocument::new(doc.source(), "Test.omni");
+                let context = format!("sequence {}, step {}: {:?}\n{}", sequence, step, edit, doc.source());
+                assert_eq!(doc.tokens(), full.tokens(), "{}", context);
+                assert_eq!(doc.lex_errors(), full.lex_errors(), "{}", context);
+                assert_eq!(
+                    format!("{:?}", doc.component()),
+                    format!("{:?}", full.component()),
+                    "{}",
+                    context
+                );
+                assert_eq!(doc.parse_errors(), full.parse_errors(), "{}", context);
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_at_start_and_end() {
+        let mut doc = IncrementalDocument::new(SOURCE, "Test.omni");
+        doc.apply_edit(&TextEdit::new(Span::new(0, 0), "<!-- header -->\n"));
+        assert_matches_full(&doc);
+
+        let len = doc.source().len();
+        doc.apply_edit(&TextEdit::new(Span::new(len, len), "<style></style>\n"));
+        assert_matches_full(&doc);
+    }
+}
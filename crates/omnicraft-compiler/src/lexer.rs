@@ -2,24 +2,71 @@
 //!
 //! Uses `logos` for fast tokenization of the OmniCraft component syntax.
 
+use crate::ast::Unit;
 use logos::Logos;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq)]
 pub enum LexerError {
-    #[error("Unexpected character at position {0}")]
-    UnexpectedChar(usize),
+    #[error("Unexpected character at position {}", .0.start)]
+    UnexpectedChar(Span),
 
-    #[error("Unterminated string starting at position {0}")]
-    UnterminatedString(usize),
+    #[error("Unterminated string starting at position {}", .0.start)]
+    UnterminatedString(Span),
 
-    #[error("Invalid number format at position {0}")]
-    InvalidNumber(usize),
+    #[error("Invalid number format at position {}", .0.start)]
+    InvalidNumber(Span),
+
+    #[error("Invalid escape sequence at position {}", .0.start)]
+    InvalidEscape(Span),
+}
+
+impl LexerError {
+    /// Source span covered by the error
+    pub fn span(&self) -> Span {
+        match self {
+            LexerError::UnexpectedChar(span)
+            | LexerError::UnterminatedString(span)
+            | LexerError::InvalidNumber(span)
+            | LexerError::InvalidEscape(span) => *span,
+        }
+    }
+
+    /// The same error at a different span
+    pub fn with_span(&self, span: Span) -> Self {
+        match self {
+            LexerError::UnexpectedChar(_) => LexerError::UnexpectedChar(span),
+            LexerError::UnterminatedString(_) => LexerError::UnterminatedString(span),
+            LexerError::InvalidNumber(_) => LexerError::InvalidNumber(span),
+            LexerError::InvalidEscape(_) => LexerError::InvalidEscape(span),
+        }
+    }
+
+    fn from_kind(kind: LexErrorKind, span: Span) -> Self {
+        match kind {
+            LexErrorKind::UnexpectedChar => LexerError::UnexpectedChar(span),
+            LexErrorKind::UnterminatedString => LexerError::UnterminatedString(span),
+            LexErrorKind::InvalidNumber => LexerError::InvalidNumber(span),
+            LexErrorKind::InvalidEscape => LexerError::InvalidEscape(span),
+        }
+    }
+}
+
+/// Error kind reported by `logos` while matching a single token
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LexErrorKind {
+    #[default]
+    UnexpectedChar,
+    UnterminatedString,
+    InvalidNumber,
+    InvalidEscape,
 }
 
 /// Token types for `.omni` files
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t\r\n]+")]
+#[logos(error = LexErrorKind)]
 pub enum TokenKind {
     // Tags
     #[token("<")]
@@ -72,6 +119,27 @@ pub enum TokenKind {
     #[token("%")]
     Percent,
 
+    #[token("+=")]
+    PlusEquals,
+
+    #[token("-=")]
+    MinusEquals,
+
+    #[token("*=")]
+    StarEquals,
+
+    #[token("/=")]
+    SlashEquals,
+
+    #[token("%=")]
+    PercentEquals,
+
+    #[token("++")]
+    PlusPlus,
+
+    #[token("--")]
+    MinusMinus,
+
     #[token("==")]
     DoubleEquals,
 
@@ -90,12 +158,22 @@ pub enum TokenKind {
     #[token("||")]
     Or,
 
+    /// Separates the members of a union type: `number | string`
+    #[token("|")]
+    Pipe,
+
     #[token("!")]
     Not,
 
     #[token("?")]
     Question,
 
+    #[token("?.")]
+    QuestionDot,
+
+    #[token("??")]
+    DoubleQuestion,
+
     #[token(":")]
     Colon,
 
@@ -108,15 +186,32 @@ pub enum TokenKind {
     #[token(".")]
     Dot,
 
+    #[token("...")]
+    Ellipsis,
+
     #[token("=>")]
     Arrow,
 
+    /// Opens a template block: `{#if`
+    #[token("#")]
+    Hash,
+
+    /// Prefixes an event directive: `@click`
+    #[token("@")]
+    At,
+
     #[token("`")]
     Backtick,
 
     #[token("${")]
     TemplateExprStart,
 
+    /// Literal text between backticks, with escapes decoded
+    TemplateString(String),
+
+    /// Raw text inside an element body, whitespace included
+    RawText(String),
+
     // Keywords
     #[token("const")]
     Const,
@@ -139,6 +234,12 @@ pub enum TokenKind {
     #[token("while")]
     While,
 
+    #[token("break")]
+    Break,
+
+    #[token("continue")]
+    Continue,
+
     #[token("return")]
     Return,
 
@@ -160,67 +261,46 @@ pub enum TokenKind {
     #[token("memo")]
     Memo,
 
-    // Tag names (special)
-    #[token("script")]
-    Script,
-
-    #[token("canvas")]
-    Canvas,
-
-    #[token("style")]
-    Style,
-
-    // Element tags
-    #[token("circle")]
-    Circle,
-
-    #[token("rectangle")]
-    Rectangle,
-
-    #[token("rect")]
-    Rect,
-
-    #[token("ellipse")]
-    Ellipse,
-
-    #[token("line")]
-    Line,
-
-    #[token("path")]
-    Path,
-
-    #[token("polygon")]
-    Polygon,
-
-    #[token("text")]
-    Text,
-
-    #[token("image")]
-    Image,
-
-    #[token("group")]
-    Group,
-
-    // Identifiers
+    // Identifiers, including element and section names such as `text`
+    // or `script`, which the parser recognizes by context
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
     Identifier(String),
 
     // Literals
-    #[regex(r"-?[0-9]+(\.[0-9]+)?", |lex| lex.slice().parse::<f64>().ok())]
+    // Sign is left to the parser so `a-1` is a subtraction
+    #[regex(r"[0-9](_?[0-9])*(\.[0-9](_?[0-9])*)?([eE][+-]?[0-9](_?[0-9])*)?", number)]
+    #[regex(r"\.[0-9](_?[0-9])*([eE][+-]?[0-9](_?[0-9])*)?", number)]
+    #[regex(r"0[xX][0-9a-fA-F](_?[0-9a-fA-F])*", number)]
+    #[regex(r"[0-9]+((\.[0-9]+)+\.|\.[0-9]+(\.[0-9]+)+|\.)", invalid_number)]
+    // Hex prefix without digits or ending in `_`, such as `0x` or `0xF_`
+    #[regex(r"0[xX](_|[0-9a-fA-F](_?[0-9a-fA-F])*_)?", invalid_number)]
+    // Exponent marker without digits, such as `1e` or `2.5e+`
+    #[regex(r"([0-9](_?[0-9])*(\.[0-9](_?[0-9])*)?|\.[0-9](_?[0-9])*)[eE][+-]?", invalid_number)]
+    // Trailing or doubled separator, such as `1_` or `1__0`
+    #[regex(r"[0-9][0-9_]*(_|__[0-9_]*)", invalid_number)]
     Number(f64),
 
-    #[regex(r#""[^"]*""#, |lex| {
-        let s = lex.slice();
-        s[1..s.len()-1].to_string()
-    })]
+    /// Number with a unit suffix such as `45deg`, `10px` or `2s`
+    #[regex(
+        r"([0-9](_?[0-9])*(\.[0-9](_?[0-9])*)?|\.[0-9](_?[0-9])*)([eE][+-]?[0-9](_?[0-9])*)?(px|%|deg|rad|turn|s|ms)",
+        dimension
+    )]
+    Dimension((f64, Unit)),
+
+    /// Double-quoted string, with escapes decoded; only an escaped newline
+    /// continues it on the next line
+    #[regex(r#""([^"\\\n]|\\(.|\n))*""#, string_literal)]
     StringLiteral(String),
 
-    #[regex(r#"'[^']*'"#, |lex| {
-        let s = lex.slice();
-        s[1..s.len()-1].to_string()
-    })]
+    /// Single-quoted string, with escapes decoded
+    #[regex(r#"'([^'\\\n]|\\(.|\n))*'"#, string_literal)]
     StringLiteralSingle(String),
 
+    // A quote with no closing partner on the same line
+    #[regex(r#""([^"\\\n]|\\.)*"#, unterminated_string)]
+    #[regex(r#"'([^'\\\n]|\\.)*"#, unterminated_string)]
+    UnterminatedString,
+
     // Comments
     #[regex(r"//[^\n]*", logos::skip, allow_greedy = true)]
     LineComment,
@@ -230,6 +310,121 @@ pub enum TokenKind {
 
     #[regex(r"<!--([^-]|-[^-]|--[^>])*-->", logos::skip)]
     HtmlComment,
+
+    /// Placeholder for source text that could not be tokenized
+    Error,
+}
+
+fn number(lex: &mut logos::Lexer<TokenKind>) -> Result<f64, LexErrorKind> {
+    parse_number(lex.slice())
+}
+
+fn dimension(lex: &mut logos::Lexer<TokenKind>) -> Result<(f64, Unit), LexErrorKind> {
+    let (number, unit) = Unit::split_suffix(lex.slice()).ok_or(LexErrorKind::InvalidNumber)?;
+    Ok((parse_number(number)?, unit))
+}
+
+/// Parse decimal, exponent and hex literals, ignoring `_` separators
+fn parse_number(text: &str) -> Result<f64, LexErrorKind> {
+    let digits = text.replace('_', "");
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).map(|n| n as f64).ok(),
+        None => digits.parse::<f64>().ok(),
+    };
+    value.ok_or(LexErrorKind::InvalidNumber)
+}
+
+fn invalid_number(_: &mut logos::Lexer<TokenKind>) -> Result<f64, LexErrorKind> {
+    Err(LexErrorKind::InvalidNumber)
+}
+
+fn unterminated_string(_: &mut logos::Lexer<TokenKind>) -> Result<(), LexErrorKind> {
+    Err(LexErrorKind::UnterminatedString)
+}
+
+fn string_literal(lex: &mut logos::Lexer<TokenKind>) -> Result<String, LexErrorKind> {
+    let s = lex.slice();
+    unescape(&s[1..s.len() - 1]).map_err(|_| LexErrorKind::InvalidEscape)
+}
+
+/// Decode every escape sequence in the body of a string literal
+///
+/// On failure, returns the byte range of the first invalid escape
+/// relative to `raw`.
+pub fn unescape(raw: &str) -> Result<String, std::ops::Range<usize>> {
+    let mut out = String::with_capacity(raw.len());
+    let mut pos = 0;
+    while let Some(i) = raw[pos..].find('\\') {
+        out.push_str(&raw[pos..pos + i]);
+        let start = pos + i;
+        match decode_escape(&raw[start + 1..]) {
+            Some((c, len)) => {
+                out.extend(c);
+                pos = start + 1 + len;
+            }
+            None => {
+                let end = raw[start + 1..].chars().next().map_or(start + 1, |c| start + 1 + c.len_utf8());
+                return Err(start..end);
+            }
+        }
+    }
+    out.push_str(&raw[pos..]);
+    Ok(out)
+}
+
+/// Decode the escape sequence that follows a backslash
+///
+/// Returns the decoded character (`None` for a line continuation) and the
+/// number of bytes consumed after the backslash.
+fn decode_escape(rest: &str) -> Option<(Option<char>, usize)> {
+    let c = rest.chars().next()?;
+    let simple = match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'v' => '\u{b}',
+        '0' if !rest[1..].starts_with(|c: char| c.is_ascii_digit()) => '\0',
+        '\\' | '"' | '\'' | '`' | '$' => c,
+        '\n' => return Some((None, 1)),
+        'x' => {
+            let code = hex_digits(rest.get(1..3)?)?;
+            return Some((Some(char::from(code as u8)), 3));
+        }
+        'u' => return decode_unicode_escape(rest),
+        _ => return None,
+    };
+    Some((Some(simple), 1))
+}
+
+/// Decode `u{X..}`, `uXXXX` or a `uXXXX\uXXXX` surrogate pair
+fn decode_unicode_escape(rest: &str) -> Option<(Option<char>, usize)> {
+    if let Some(braced) = rest.strip_prefix("u{") {
+        let close = braced.find('}')?;
+        if close == 0 || close > 6 {
+            return None;
+        }
+        let c = char::from_u32(hex_digits(&braced[..close])?)?;
+        return Some((Some(c), close + 3));
+    }
+
+    let high = hex_digits(rest.get(1..5)?)?;
+    if let Some(c) = char::from_u32(high) {
+        return Some((Some(c), 5));
+    }
+
+    // A lone surrogate is only valid as the first half of a pair
+    let low = rest.get(5..11)?.strip_prefix("\\u").and_then(hex_digits)?;
+    let c = char::decode_utf16([high as u16, low as u16]).next()?.ok()?;
+    Some((Some(c), 11))
+}
+
+fn hex_digits(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(s, 16).ok()
 }
 
 /// A token with position information
@@ -241,7 +436,7 @@ pub struct Token {
 }
 
 /// Source span
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -258,6 +453,14 @@ impl Span {
             end: self.end.max(other.end),
         }
     }
+
+    /// Span moved by `delta` bytes
+    pub fn shifted(self, delta: isize) -> Span {
+        Span {
+            start: self.start.saturating_add_signed(delta),
+            end: self.end.saturating_add_signed(delta),
+        }
+    }
 }
 
 /// Lexer for `.omni` files
@@ -270,29 +473,410 @@ impl<'a> Lexer<'a> {
         Self { source }
     }
 
-    /// Tokenize the source into a list of tokens
+    /// Tokenize the source into a list of tokens, stopping at the first error
     pub fn tokenize(&self) -> Result<Vec<Token>, LexerError> {
-        let mut tokens = Vec::new();
+        let (tokens, mut errors) = self.tokenize_with_recovery();
+        if errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(errors.swap_remove(0))
+        }
+    }
+
+    /// Tokenize the whole source, collecting every lexical error
+    ///
+    /// Invalid input is replaced by a single `TokenKind::Error` token and
+    /// lexing resumes at the next position `logos` can match: after a run
+    /// of unexpected characters, at the end of the line for an unterminated
+    /// string, or after the malformed number.
+    pub fn tokenize_with_recovery(&self) -> (Vec<Token>, Vec<LexerError>) {
+        let run = self.lex_from(&Checkpoint::default(), |_| false);
+        (run.tokens, run.errors)
+    }
+
+    /// Lex from `start` until the end of input or until `stop` accepts a
+    /// checkpoint, recording a checkpoint before every step
+    pub(crate) fn lex_from(
+        &self,
+        start: &Checkpoint,
+        mut stop: impl FnMut(&Checkpoint) -> bool,
+    ) -> LexRun {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut errors = Vec::new();
+        let mut checkpoints = Vec::new();
+        let mut modes = start.modes.clone();
         let mut lexer = TokenKind::lexer(self.source);
+        lexer.bump(start.offset);
+
+        loop {
+            let checkpoint = Checkpoint {
+                offset: lexer.span().end,
+                token_index: start.token_index + tokens.len(),
+                modes: modes.clone(),
+            };
+            if !checkpoints.is_empty() && stop(&checkpoint) {
+                return LexRun {
+                    tokens,
+                    errors,
+                    checkpoints,
+                };
+            }
+            checkpoints.push(checkpoint);
+
+            // Template and element text is scanned by hand; `logos` resumes after it
+            match modes.last().copied() {
+                Some(LexMode::Template { start }) => {
+                    let offset = lexer.span().end;
+                    let consumed =
+                        self.scan_template_text(offset, start, &mut tokens, &mut errors, &mut modes);
+                    lexer.bump(consumed);
+                    continue;
+                }
+                Some(LexMode::Content) => {
+                    let offset = lexer.span().end;
+                    let consumed = self.scan_content_text(offset, &mut tokens, &mut errors);
+                    lexer.bump(consumed);
+                }
+                _ => {}
+            }
+
+            let Some(result) = lexer.next() else {
+                break;
+            };
+            let range = lexer.span();
+            let span = Span::new(range.start, range.end);
 
-        while let Some(result) = lexer.next() {
             match result {
+                // `10%3` is a remainder, not a percentage followed by a number
+                Ok(TokenKind::Dimension((value, Unit::Percent))) if self.operand_follows(span.end) => {
+                    let number = Span::new(span.start, span.end - 1);
+                    tokens.push(Token {
+                        kind: TokenKind::Number(value),
+                        span: number,
+                        text: self.source[number.start..number.end].to_string(),
+                    });
+                    tokens.push(Token {
+                        kind: TokenKind::Percent,
+                        span: Span::new(number.end, span.end),
+                        text: "%".to_string(),
+                    });
+                }
                 Ok(kind) => {
-                    let span = lexer.span();
+                    match (&kind, modes.last_mut()) {
+                        (TokenKind::Backtick, _) => {
+                            modes.push(LexMode::Template { start: span.start })
+                        }
+                        (TokenKind::LeftBrace, Some(LexMode::Interpolation { depth })) => {
+                            *depth += 1
+                        }
+                        (TokenKind::LeftBrace, Some(LexMode::Tag { .. } | LexMode::Content)) => {
+                            modes.push(LexMode::Interpolation { depth: 0 })
+                        }
+                        (TokenKind::RightBrace, Some(LexMode::Interpolation { depth })) => {
+                            if *depth == 0 {
+                                // Closes `${`, back to template text
+                                modes.pop();
+                            } else {
+                                *depth -= 1;
+                            }
+                        }
+                        (TokenKind::LessThan, Some(LexMode::Content)) => {
+                            modes.push(LexMode::Tag { body: TagBody::Pending })
+                        }
+                        // Outside a section, as in builder files, `<` may
+                        // be a comparison such as `count < 3`
+                        (TokenKind::LessThan, None) if self.tag_follows(span) => {
+                            modes.push(LexMode::Tag { body: TagBody::Pending })
+                        }
+                        (TokenKind::ClosingTag, None | Some(LexMode::Content | LexMode::Code)) => {
+                            if modes.last().is_some() {
+                                modes.pop();
+                            }
+                            modes.push(LexMode::Tag { body: TagBody::Closing });
+                        }
+                        (
+                            TokenKind::Identifier(name),
+                            Some(LexMode::Tag { body: body @ TagBody::Pending }),
+                        ) => {
+                            *body = if name == "script" || name == "style" {
+                                TagBody::Code
+                            } else {
+                                TagBody::Element
+                            }
+                        }
+                        (TokenKind::GreaterThan, Some(LexMode::Tag { body })) => {
+                            let body = *body;
+                            modes.pop();
+                            match body {
+                                TagBody::Pending | TagBody::Element => modes.push(LexMode::Content),
+                                TagBody::Code => modes.push(LexMode::Code),
+                                TagBody::Closing => {}
+                            }
+                        }
+                        (TokenKind::SelfClosing, Some(LexMode::Tag { .. })) => {
+                            modes.pop();
+                        }
+                        _ => {}
+                    }
+
                     tokens.push(Token {
                         kind,
-                        span: Span::new(span.start, span.end),
+                        span,
+                        text: lexer.slice().to_string(),
+                    });
+                }
+                Err(kind) => {
+                    // Fold adjacent unexpected characters into one error
+                    if kind == LexErrorKind::UnexpectedChar
+                        && let Some(LexerError::UnexpectedChar(prev)) = errors.last_mut()
+                        && prev.end == span.start
+                        && let Some(last) = tokens.last_mut()
+                    {
+                        *prev = prev.merge(span);
+                        last.span = *prev;
+                        last.text.push_str(lexer.slice());
+                        continue;
+                    }
+
+                    errors.push(LexerError::from_kind(kind, span));
+                    tokens.push(Token {
+                        kind: TokenKind::Error,
+                        span,
                         text: lexer.slice().to_string(),
                     });
                 }
-                Err(_) => {
-                    return Err(LexerError::UnexpectedChar(lexer.span().start));
+            }
+        }
+
+        LexRun {
+            tokens,
+            errors,
+            checkpoints,
+        }
+    }
+
+    /// Whether an operand starts after `offset` on the same line
+    fn operand_follows(&self, offset: usize) -> bool {
+        let mut rest = self.source[offset..].trim_start_matches([' ', '\t']).chars();
+        match rest.next() {
+            Some('.') => rest.next().is_some_and(|c| c.is_ascii_digit()),
+            Some(c) => c.is_alphanumeric() || matches!(c, '_' | '$' | '(' | '[' | '"' | '\'' | '`'),
+            None => false,
+        }
+    }
+
+    /// Whether the `<` at `span` opens a tag: a tag name follows it
+    /// directly, and no operand comes before it on the same line
+    fn tag_follows(&self, span: Span) -> bool {
+        let before = self.source[..span.start].trim_end_matches([' ', '\t']);
+        let operand = before
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | ')' | ']' | '"' | '\'' | '`'));
+        !operand && self.source[span.end..].starts_with(|c: char| c.is_ascii_alphabetic())
+    }
+
+    /// Scan template literal text starting at `offset` up to the closing
+    /// backtick or the next `${`, returning the number of bytes consumed.
+    fn scan_template_text(
+        &self,
+        offset: usize,
+        template_start: usize,
+        tokens: &mut Vec<Token>,
+        errors: &mut Vec<LexerError>,
+        modes: &mut Vec<LexMode>,
+    ) -> usize {
+        let rest = &self.source[offset..];
+        let mut cooked = String::new();
+        let mut chars = rest.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '`' => {
+                    self.push_template_text(tokens, &mut cooked, offset, offset + i);
+                    tokens.push(Token {
+                        kind: TokenKind::Backtick,
+                        span: Span::new(offset + i, offset + i + 1),
+                        text: "`".to_string(),
+                    });
+                    modes.pop();
+                    return i + 1;
+                }
+                '$' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+                    self.push_template_text(tokens, &mut cooked, offset, offset + i);
+                    tokens.push(Token {
+                        kind: TokenKind::TemplateExprStart,
+                        span: Span::new(offset + i, offset + i + 2),
+                        text: "${".to_string(),
+                    });
+                    modes.push(LexMode::Interpolation { depth: 0 });
+                    return i + 2;
+                }
+                '\\' => {
+                    let after = offset + i + 1;
+                    match decode_escape(&self.source[after..]) {
+                        Some((decoded, len)) => {
+                            cooked.extend(decoded);
+                            // Skip the remaining bytes of the escape
+                            while chars.peek().is_some_and(|&(j, _)| offset + j < after + len) {
+                                chars.next();
+                            }
+                        }
+                        None => {
+                            let end = chars.peek().map_or(after, |&(j, c)| offset + j + c.len_utf8());
+                            errors.push(LexerError::InvalidEscape(Span::new(offset + i, end)));
+                            cooked.push('\\');
+                        }
+                    }
+                }
+                _ => cooked.push(c),
+            }
+        }
+
+        // Reached end of input without a closing backtick
+        let span = Span::new(template_start, self.source.len());
+        errors.push(LexerError::UnterminatedString(span));
+        if !rest.is_empty() {
+            tokens.push(Token {
+                kind: TokenKind::Error,
+                span: Span::new(offset, self.source.len()),
+                text: rest.to_string(),
+            });
+        }
+        modes.pop();
+        rest.len()
+    }
+
+    /// Scan element body text starting at `offset` up to the next tag or
+    /// `{`, dropping HTML comments, and return the number of bytes consumed.
+    fn scan_content_text(
+        &self,
+        offset: usize,
+        tokens: &mut Vec<Token>,
+        errors: &mut Vec<LexerError>,
+    ) -> usize {
+        let mut pos = offset;
+        loop {
+            let rest = &self.source[pos..];
+            let end = rest.find(['<', '{']).map_or(self.source.len(), |i| pos + i);
+            self.push_raw_text(tokens, pos, end);
+
+            if !self.source[end..].starts_with("<!--") {
+                return end - offset;
+            }
+            match self.source[end..].find("-->") {
+                Some(close) => pos = end + close + 3,
+                None => {
+                    errors.push(LexerError::UnexpectedChar(Span::new(end, self.source.len())));
+                    tokens.push(Token {
+                        kind: TokenKind::Error,
+                        span: Span::new(end, self.source.len()),
+                        text: self.source[end..].to_string(),
+                    });
+                    return self.source.len() - offset;
                 }
             }
         }
+    }
 
-        Ok(tokens)
+    fn push_raw_text(&self, tokens: &mut Vec<Token>, start: usize, end: usize) {
+        if start == end {
+            return;
+        }
+        let text = self.source[start..end].to_string();
+        tokens.push(Token {
+            kind: TokenKind::RawText(text.clone()),
+            span: Span::new(start, end),
+            text,
+        });
     }
+
+    fn push_template_text(&self, tokens: &mut Vec<Token>, cooked: &mut String, start: usize, end: usize) {
+        if start == end {
+            return;
+        }
+        tokens.push(Token {
+            kind: TokenKind::TemplateString(std::mem::take(cooked)),
+            span: Span::new(start, end),
+            text: self.source[start..end].to_string(),
+        });
+    }
+}
+
+/// Lexer state between two steps, from which lexing can be resumed
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Checkpoint {
+    /// Byte offset where the next step starts
+    pub offset: usize,
+    /// Number of tokens produced before this point
+    pub token_index: usize,
+    modes: Vec<LexMode>,
+}
+
+impl Checkpoint {
+    /// Whether lexing identical text from both checkpoints yields the same
+    /// tokens and errors
+    pub(crate) fn same_state(&self, other: &Checkpoint) -> bool {
+        self.modes == other.modes
+    }
+
+    /// Whether a template opened at `start` is still unclosed here
+    pub(crate) fn has_open_template(&self, start: usize) -> bool {
+        self.modes.contains(&LexMode::Template { start })
+    }
+
+    /// Move a checkpoint past an edit that replaced text ending at
+    /// `edit_end`; an open template keeps its start if it began before
+    pub(crate) fn shifted(&self, edit_end: usize, offset_delta: isize, token_delta: isize) -> Checkpoint {
+        let shift = |n: usize, delta: isize| n.saturating_add_signed(delta);
+        Checkpoint {
+            offset: shift(self.offset, offset_delta),
+            token_index: shift(self.token_index, token_delta),
+            modes: self
+                .modes
+                .iter()
+                .map(|mode| match *mode {
+                    LexMode::Template { start } if start >= edit_end => LexMode::Template {
+                        start: shift(start, offset_delta),
+                    },
+                    other => other,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Output of a (possibly partial) lexing run
+#[derive(Debug)]
+pub(crate) struct LexRun {
+    pub tokens: Vec<Token>,
+    pub errors: Vec<LexerError>,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+/// Lexer state for context-sensitive parts of the source
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LexMode {
+    /// Raw template text; `start` is the offset of the opening backtick
+    Template { start: usize },
+    /// Inside `${ ... }` or an element's `{ ... }`; `depth` counts nested braces
+    Interpolation { depth: usize },
+    /// Between `<` or `</` and the closing `>`
+    Tag { body: TagBody },
+    /// Element body, where anything but tags and `{` is text
+    Content,
+    /// Body of `<script>` or `<style>`, where `<` is an operator
+    Code,
+}
+
+/// What follows the tag currently being lexed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TagBody {
+    /// Tag name not seen yet
+    Pending,
+    Element,
+    Code,
+    Closing,
 }
 
 #[cfg(test)]
@@ -305,7 +889,7 @@ mod tests {
         let tokens = Lexer::new(source).tokenize().unwrap();
 
         assert_eq!(tokens[0].kind, TokenKind::LessThan);
-        assert_eq!(tokens[1].kind, TokenKind::Circle);
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("circle".to_string()));
         assert_eq!(tokens[2].kind, TokenKind::Identifier("x".to_string()));
         assert_eq!(tokens[3].kind, TokenKind::Equals);
         assert_eq!(tokens[4].kind, TokenKind::LeftBrace);
@@ -325,14 +909,246 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_escapes() {
+        let source = r#""say \"hi\"\n" 'it\'s' "\u{1F600}é😀\x41\\""#;
+        let tokens = Lexer::new(source).tokenize().unwrap();
+
+        assert_eq!(tokens[0].kind, TokenKind::StringLiteral("say \"hi\"\n".to_string()));
+        assert_eq!(tokens[1].kind, TokenKind::StringLiteralSingle("it's".to_string()));
+        assert_eq!(tokens[2].kind, TokenKind::StringLiteral("😀é😀A\\".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_escape() {
+        let (_, errors) = Lexer::new(r#"a="\q" b=`x\u{110000}`"#).tokenize_with_recovery();
+        assert_eq!(
+            errors,
+            vec![
+                LexerError::InvalidEscape(Span::new(2, 6)),
+                LexerError::InvalidEscape(Span::new(11, 13)),
+            ]
+        );
+        assert_eq!(unescape(r"ok\q"), Err(2..4));
+    }
+
     #[test]
     fn test_script_section() {
         let source = "<script> const count = signal(0); </script>";
         let tokens = Lexer::new(source).tokenize().unwrap();
 
         assert_eq!(tokens[0].kind, TokenKind::LessThan);
-        assert_eq!(tokens[1].kind, TokenKind::Script);
+        assert_eq!(tokens[1].kind, TokenKind::Identifier("script".to_string()));
         assert_eq!(tokens[2].kind, TokenKind::GreaterThan);
         assert_eq!(tokens[3].kind, TokenKind::Const);
     }
+
+    #[test]
+    fn test_recovery_collects_all_errors() {
+        let source = "<circle x={1} ~~ y={2} /> <text content=\"oops />\n<rect § />";
+        let (tokens, errors) = Lexer::new(source).tokenize_with_recovery();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], LexerError::UnexpectedChar(Span::new(14, 16)));
+        assert!(matches!(errors[1], LexerError::UnterminatedString(_)));
+        assert!(matches!(errors[2], LexerError::UnexpectedChar(_)));
+
+        // Tokens after each error are still produced
+        let error_tokens = tokens.iter().filter(|t| t.kind == TokenKind::Error).count();
+        assert_eq!(error_tokens, 3);
+        assert!(tokens.iter().any(|t| t.kind == TokenKind::Identifier("rect".to_string())));
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::SelfClosing);
+    }
+
+    #[test]
+    fn test_unterminated_string_stops_at_end_of_line() {
+        let source = "let a = \"abc\nlet b = \"x\";\nlet c = 'd\n1;";
+        let (tokens, errors) = Lexer::new(source).tokenize_with_recovery();
+
+        assert_eq!(
+            errors,
+            vec![
+                LexerError::UnterminatedString(Span::new(8, 12)),
+                LexerError::UnterminatedString(Span::new(34, 36)),
+            ]
+        );
+        assert!(tokens.iter().any(|t| t.kind == TokenKind::StringLiteral("x".to_string())));
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Semicolon);
+
+        // An escaped newline continues the string
+        let tokens = Lexer::new("\"a\\\nb\"").tokenize().unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::StringLiteral("ab".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_number() {
+        let (tokens, errors) = Lexer::new("x={1.} y={1.2.3}").tokenize_with_recovery();
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], LexerError::InvalidNumber(span) if span == Span::new(3, 5)));
+        assert!(matches!(errors[1], LexerError::InvalidNumber(_)));
+        assert_eq!(tokens[3].kind, TokenKind::Error);
+        assert_eq!(tokens[3].text, "1.");
+    }
+
+    #[test]
+    fn test_incomplete_numbers() {
+        let source = "0x 0xF_ 1e 2.5e+ 1_ 1__0 x";
+        let (tokens, errors) = Lexer::new(source).tokenize_with_recovery();
+
+        let invalid: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                LexerError::InvalidNumber(span) => &source[span.start..span.end],
+                other => panic!("unexpected error {:?}", other),
+            })
+            .collect();
+        assert_eq!(invalid, vec!["0x", "0xF_", "1e", "2.5e+", "1_", "1__0"]);
+        assert_eq!(tokens.len(), 7);
+        assert!(tokens[..6].iter().all(|t| t.kind == TokenKind::Error));
+        assert_eq!(tokens[6].kind, TokenKind::Identifier("x".to_string()));
+    }
+
+    #[test]
+    fn test_numeric_literals() {
+        let source = "0xFF 1e-3 1_000 .5 2.5E2 a-1 45deg 10px 300ms 50%";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let kinds: Vec<_> = tokens.into_iter().map(|t| t.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Number(255.0),
+                TokenKind::Number(0.001),
+                TokenKind::Number(1000.0),
+                TokenKind::Number(0.5),
+                TokenKind::Number(250.0),
+                TokenKind::Identifier("a".to_string()),
+                TokenKind::Minus,
+                TokenKind::Number(1.0),
+                TokenKind::Dimension((45.0, Unit::Deg)),
+                TokenKind::Dimension((10.0, Unit::Px)),
+                TokenKind::Dimension((300.0, Unit::Ms)),
+                TokenKind::Dimension((50.0, Unit::Percent)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_percent_before_operand_is_remainder() {
+        let source = "10%3 10% x 10%(y) 50% 50%}";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Number(10.0),
+                TokenKind::Percent,
+                TokenKind::Number(3.0),
+                TokenKind::Number(10.0),
+                TokenKind::Percent,
+                TokenKind::Identifier("x".to_string()),
+                TokenKind::Number(10.0),
+                TokenKind::Percent,
+                TokenKind::LeftParen,
+                TokenKind::Identifier("y".to_string()),
+                TokenKind::RightParen,
+                TokenKind::Number(50.0),
+                TokenKind::Percent,
+                TokenKind::Dimension((50.0, Unit::Percent)),
+                TokenKind::RightBrace,
+            ]
+        );
+        assert_eq!(tokens[1].span, Span::new(2, 3));
+        assert_eq!(tokens[0].text, "10");
+    }
+
+    #[test]
+    fn test_less_than_outside_sections_is_comparison() {
+        // Only a `<` directly before a name, and not after an operand, opens a tag
+        let source = "a < b ? \"x\" : c<d f(1) <g } <text>{e < 1}</text>";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let raw_text = tokens.iter().any(|t| matches!(t.kind, TokenKind::RawText(_)));
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
+
+        assert!(!raw_text);
+        assert_eq!(kinds[1], TokenKind::LessThan);
+        assert_eq!(kinds[7], TokenKind::LessThan);
+        assert_eq!(kinds[8], TokenKind::Identifier("d".to_string()));
+        assert_eq!(kinds[13], TokenKind::LessThan);
+        assert_eq!(kinds[16], TokenKind::LessThan);
+        assert_eq!(kinds[17], TokenKind::Identifier("text".to_string()));
+        assert_eq!(kinds.last(), Some(&TokenKind::GreaterThan));
+    }
+
+    #[test]
+    fn test_tokenize_reports_first_error() {
+        let result = Lexer::new("<circle ~~ />").tokenize();
+        assert_eq!(result.unwrap_err(), LexerError::UnexpectedChar(Span::new(8, 10)));
+    }
+
+    #[test]
+    fn test_template_literal() {
+        let source = "`Score: ${score()}!`";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Backtick,
+                TokenKind::TemplateString("Score: ".to_string()),
+                TokenKind::TemplateExprStart,
+                TokenKind::Identifier("score".to_string()),
+                TokenKind::LeftParen,
+                TokenKind::RightParen,
+                TokenKind::RightBrace,
+                TokenKind::TemplateString("!".to_string()),
+                TokenKind::Backtick,
+            ]
+        );
+        assert_eq!(tokens[1].span, Span::new(1, 8));
+    }
+
+    #[test]
+    fn test_template_nested_braces_and_templates() {
+        let source = r"`a ${ [1].map((x) => { return `${x}\`` }) } \${b}`";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+
+        let texts: Vec<_> = tokens
+            .iter()
+            .filter_map(|t| match &t.kind {
+                TokenKind::TemplateString(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, vec!["a ", "`", " ${b}"]);
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Backtick);
+    }
+
+    #[test]
+    fn test_unterminated_template() {
+        let (tokens, errors) = Lexer::new("x={`abc ${y}").tokenize_with_recovery();
+
+        assert_eq!(errors, vec![LexerError::UnterminatedString(Span::new(3, 12))]);
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::RightBrace);
+    }
+
+    #[test]
+    fn test_element_text_content() {
+        let source = "<script>a < b</script><text> Hi {x > 1}<!-- c -->! </text>";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
+
+        assert_eq!(kinds[4], TokenKind::LessThan);
+        let text: Vec<_> = tokens
+            .iter()
+            .filter_map(|t| match &t.kind {
+                TokenKind::RawText(s) => Some((s.as_str(), t.span)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, vec![(" Hi ", Span::new(28, 32)), ("! ", Span::new(49, 51))]);
+    }
 }
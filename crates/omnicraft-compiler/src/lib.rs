@@ -12,7 +12,11 @@
 pub mod analyzer;
 pub mod ast;
 pub mod codegen;
+pub mod cst;
+pub mod formatter;
+pub mod incremental;
 pub mod lexer;
+pub mod module_graph;
 pub mod npm;
 pub mod optimizer;
 pub mod parser;
@@ -21,7 +25,11 @@ pub mod sourcemap;
 pub use analyzer::{analyze, AnalyzedComponent, Analyzer};
 pub use ast::*;
 pub use codegen::CodeGenerator;
+pub use cst::{parse_file, ParsedFile, SyntaxKind, SyntaxNode};
+pub use formatter::{format_range, format_source, FormatError, FormatOptions, QuoteStyle};
+pub use incremental::{IncrementalDocument, TextEdit};
 pub use lexer::Lexer;
+pub use module_graph::{ModuleError, ModuleGraph};
 pub use npm::{PackageJson, PackageJsonBuilder};
 pub use optimizer::{optimize, Optimizer, OptimizerConfig};
 pub use parser::Parser;
@@ -42,23 +50,24 @@ pub fn compile(source: &str, file_name: &str, target: CompilationTarget) -> Resu
     let tokens = Lexer::new(source).tokenize()?;
 
     // 2. Parse
-    let component = Parser::new(tokens, file_name).parse()?;
+    let components = Parser::new(tokens, file_name).parse_components()?;
 
-    // 3. Analyze (reactive dependencies, types)
-    let analyzed = analyze(&component)?;
-
-    // 4. Optimize
-    let optimized = optimize(&analyzed)?;
+    // 3. Analyze (reactive dependencies, types) and 4. Optimize each
+    // component of the file
+    let optimized = components
+        .iter()
+        .map(|component| optimize(&analyze(component)?))
+        .collect::<Result<Vec<_>>>()?;
 
     // 5. Generate Code
     match target {
         CompilationTarget::Rust => {
             let mut generator = codegen::RustGenerator::new();
-            generator.generate(&optimized)
+            generator.generate_components(&optimized)
         }
         CompilationTarget::TypeScript => {
             let mut generator = codegen::TypeScriptGenerator::new();
-            generator.generate(&optimized)
+            generator.generate_components(&optimized)
         }
     }
 }
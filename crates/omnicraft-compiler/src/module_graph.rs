@@ -0,0 +1,558 @@
+//! Module graph of a multi-file app
+//!
+//! Starting from an entry file, `ModuleGraph` follows every `import` to the
+//! `.omni` file it names and rejects import cycles. The graph compiles into
+//! one Rust crate: each file becomes a module of `lib.rs`, and imports
+//! become `use` items between those modules.
+
+use crate::ast::{
+    AttributeValue, Component, ElementTag, ExpressionKind, ImportSpecifier, Literal, Node, NodeKind, Type,
+};
+use crate::lexer::Span;
+use crate::codegen::RustGenerator;
+use crate::{analyze, optimize, Lexer, Parser};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Component as PathComponent, Path, PathBuf};
+use thiserror::Error;
+use tracing::{debug, instrument};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ModuleError {
+    #[error("Cannot import \"{specifier}\" in {importer}: only relative paths to .omni files are supported")]
+    UnsupportedSpecifier { specifier: String, importer: String },
+
+    #[error("Import cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+
+    #[error("{module} has no export named `{name}`, imported by {importer}")]
+    MissingExport {
+        name: String,
+        module: String,
+        importer: String,
+    },
+
+    /// Span of the attribute
+    #[error("<{component}> has no prop `{prop}`, in {importer} at position {}", span.start)]
+    UnknownProp {
+        component: String,
+        prop: String,
+        importer: String,
+        span: Span,
+    },
+
+    /// Span of the attribute
+    #[error(
+        "Prop `{prop}` of <{component}> is a {expected}, but is given a {found}, in {importer} at position {}",
+        span.start
+    )]
+    PropTypeMismatch {
+        component: String,
+        prop: String,
+        expected: &'static str,
+        found: &'static str,
+        importer: String,
+        span: Span,
+    },
+}
+
+/// A parsed file of the graph
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub path: PathBuf,
+    /// Name of the Rust module the file compiles to
+    pub name: String,
+    /// Components the file declares, the one named after it first
+    pub components: Vec<Component>,
+    /// Index of the module each import source resolved to
+    pub dependencies: HashMap<String, usize>,
+}
+
+/// Files reachable from an entry file through their imports
+#[derive(Debug, Clone)]
+pub struct ModuleGraph {
+    /// Modules in dependency order; every module comes after the modules
+    /// it imports, so the entry is last
+    modules: Vec<Module>,
+    /// Directory of the entry file, which module names are relative to
+    root: PathBuf,
+}
+
+impl ModuleGraph {
+    /// Build the graph of `entry`, reading files from disk
+    pub fn build(entry: impl AsRef<Path>) -> Result<Self> {
+        Self::build_with(entry, |path| std::fs::read_to_string(path))
+    }
+
+    /// Build the graph of `entry`, reading files with `read`
+    #[instrument(skip(read), fields(entry = %entry.as_ref().display()))]
+    pub fn build_with(
+        entry: impl AsRef<Path>,
+        mut read: impl FnMut(&Path) -> std::io::Result<String>,
+    ) -> Result<Self> {
+        let entry = normalize(entry.as_ref());
+        let mut builder = GraphBuilder {
+            graph: ModuleGraph {
+                modules: Vec::new(),
+                root: entry.parent().map(Path::to_path_buf).unwrap_or_default(),
+            },
+            indices: HashMap::new(),
+            stack: Vec::new(),
+            read: &mut read,
+        };
+        builder.visit(&entry)?;
+        debug!(modules = builder.graph.modules.len(), "Module graph built");
+        Ok(builder.graph)
+    }
+
+    /// Modules in dependency order
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    pub fn entry(&self) -> &Module {
+        self.modules.last().expect("a graph has at least its entry")
+    }
+
+    /// Compile every module to Rust
+    ///
+    /// Returns the files of the crate's `src` directory: `lib.rs`, which
+    /// declares a module per file, then one file per module. Only the entry
+    /// module has the WASM entry point.
+    pub fn compile(&self) -> Result<Vec<(PathBuf, String)>> {
+        let mut lib = String::from("//! Generated by OmniCraft Compiler\n//! Do not edit manually.\n\n");
+        let mut files = Vec::new();
+        for (i, module) in self.modules.iter().enumerate() {
+            lib.push_str(&format!("pub mod {};\n", module.name));
+
+            let optimized = module
+                .components
+                .iter()
+                .map(|component| optimize(&analyze(component)?))
+                .collect::<Result<Vec<_>>>()?;
+            let modules = module
+                .dependencies
+                .iter()
+                .map(|(source, &dependency)| (source.clone(), self.modules[dependency].name.clone()))
+                .collect();
+            let code = RustGenerator::new()
+                .with_modules(modules)
+                .with_entry_point(i == self.modules.len() - 1)
+                .generate_components(&optimized)
+                .with_context(|| format!("Failed to compile {}", module.path.display()))?;
+            files.push((PathBuf::from(format!("{}.rs", module.name)), code));
+        }
+        files.insert(0, (PathBuf::from("lib.rs"), lib));
+        Ok(files)
+    }
+}
+
+/// Depth-first traversal that appends each module after its imports
+struct GraphBuilder<'a, R> {
+    graph: ModuleGraph,
+    indices: HashMap<PathBuf, usize>,
+    /// Files being visited, from the entry down
+    stack: Vec<PathBuf>,
+    read: &'a mut R,
+}
+
+impl<R: FnMut(&Path) -> std::io::Result<String>> GraphBuilder<'_, R> {
+    fn visit(&mut self, path: &Path) -> Result<usize> {
+        if let Some(&index) = self.indices.get(path) {
+            return Ok(index);
+        }
+        if let Some(start) = self.stack.iter().position(|p| p == path) {
+            let cycle = self.stack[start..]
+                .iter()
+                .chain([&path.to_path_buf()])
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(ModuleError::Cycle(cycle).into());
+        }
+
+        let source = (self.read)(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let file_name = path.to_string_lossy();
+        let tokens = Lexer::new(&source).tokenize()?;
+        let components = Parser::new(tokens, &file_name)
+            .parse_components()
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        self.stack.push(path.to_path_buf());
+        let mut dependencies = HashMap::new();
+        // The components of a file share its imports
+        let imports = components.first().and_then(|component| component.script.as_ref());
+        for import in imports.iter().flat_map(|script| &script.imports) {
+            let resolved = resolve(path, &import.source)?;
+            let index = self.visit(&resolved)?;
+            let exported = |name: &String| {
+                self.graph.modules[index]
+                    .components
+                    .iter()
+                    .any(|component| component.metadata.exports.contains(name))
+            };
+            for specifier in &import.specifiers {
+                if let ImportSpecifier::Named { name, .. } = specifier
+                    && !exported(name)
+                {
+                    return Err(ModuleError::MissingExport {
+                        name: name.clone(),
+                        module: resolved.display().to_string(),
+                        importer: path.display().to_string(),
+                    }
+                    .into());
+                }
+            }
+            dependencies.insert(import.source.clone(), index);
+        }
+        self.stack.pop();
+
+        // Components the file can use: its own and the ones it imports
+        let mut usable: HashMap<&str, &Component> =
+            components.iter().map(|component| (component.name.as_str(), component)).collect();
+        for import in imports.iter().flat_map(|script| &script.imports) {
+            let exporter = &self.graph.modules[dependencies[&import.source]].components;
+            for specifier in &import.specifiers {
+                let component = match specifier {
+                    ImportSpecifier::Default(_) => exporter.first(),
+                    ImportSpecifier::Named { name, .. } => exporter.iter().find(|c| c.name == *name),
+                    ImportSpecifier::Namespace(_) => None,
+                };
+                if let Some(component) = component {
+                    usable.insert(specifier.local(), component);
+                }
+            }
+        }
+        for component in &components {
+            check_props(&component.template.children, &usable, path)?;
+        }
+
+        let name = self.unique_name(path);
+        self.graph.modules.push(Module {
+            path: path.to_path_buf(),
+            name,
+            components,
+            dependencies,
+        });
+        let index = self.graph.modules.len() - 1;
+        self.indices.insert(path.to_path_buf(), index);
+        Ok(index)
+    }
+
+    /// Module name from the path relative to the entry's directory, with a
+    /// number appended if another file already took it
+    fn unique_name(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.graph.root).unwrap_or(path).with_extension("");
+        let base = relative
+            .components()
+            .filter_map(|component| match component {
+                PathComponent::Normal(part) => Some(module_name(&part.to_string_lossy())),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("_");
+        let taken = |name: &str| self.graph.modules.iter().any(|module| module.name == name);
+        let mut name = base.clone();
+        let mut n = 2;
+        while taken(&name) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+        name
+    }
+}
+
+/// Reject props a used component doesn't declare, and literal values of
+/// the wrong type for the ones it does
+fn check_props(nodes: &[Node], components: &HashMap<&str, &Component>, importer: &Path) -> Result<(), ModuleError> {
+    for node in nodes {
+        match &node.kind {
+            NodeKind::Element { tag, attributes, children, .. } => {
+                if let ElementTag::Component(name) = tag
+                    && let Some(component) = components.get(name.as_str())
+                {
+                    for attribute in attributes {
+                        if matches!(attribute.value, AttributeValue::Spread(_)) {
+                            continue;
+                        }
+                        let prop_name = attribute.name.replace('-', "_");
+                        let Some(prop) = component.props.iter().find(|prop| prop.name == prop_name) else {
+                            return Err(ModuleError::UnknownProp {
+                                component: name.clone(),
+                                prop: attribute.name.clone(),
+                                importer: importer.display().to_string(),
+                                span: attribute.span,
+                            });
+                        };
+                        let literal = |kind: &ExpressionKind| match kind {
+                            ExpressionKind::Literal(literal) => literal_name(literal),
+                            _ => None,
+                        };
+                        // Untyped props take the type of their default
+                        let expected = prop
+                            .ty
+                            .as_ref()
+                            .and_then(primitive_name)
+                            .or_else(|| literal(&prop.default.as_ref()?.kind));
+                        let found = match &attribute.value {
+                            AttributeValue::Static(value) => literal_name(value),
+                            AttributeValue::Dynamic(expr) => literal(&expr.kind),
+                            AttributeValue::Boolean(_) => Some("boolean"),
+                            AttributeValue::Spread(_) => None,
+                        };
+                        if let (Some(expected), Some(found)) = (expected, found)
+                            && expected != found
+                        {
+                            return Err(ModuleError::PropTypeMismatch {
+                                component: name.clone(),
+                                prop: attribute.name.clone(),
+                                expected,
+                                found,
+                                importer: importer.display().to_string(),
+                                span: attribute.span,
+                            });
+                        }
+                    }
+                }
+                check_props(children, components, importer)?;
+            }
+            NodeKind::IfBlock { then_branch, else_branch, .. } => {
+                check_props(then_branch, components, importer)?;
+                if let Some(else_branch) = else_branch {
+                    check_props(else_branch, components, importer)?;
+                }
+            }
+            NodeKind::EachBlock { body, .. } => check_props(body, components, importer)?,
+            NodeKind::AwaitBlock { pending, then_branch, catch_branch, .. } => {
+                check_props(pending, components, importer)?;
+                for branch in [then_branch, catch_branch].into_iter().flatten() {
+                    check_props(&branch.body, components, importer)?;
+                }
+            }
+            NodeKind::Slot { fallback, .. } => check_props(fallback, components, importer)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Name of a primitive type, which literal prop values are checked against
+fn primitive_name(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Number => Some("number"),
+        Type::String => Some("string"),
+        Type::Boolean => Some("boolean"),
+        _ => None,
+    }
+}
+
+fn literal_name(literal: &Literal) -> Option<&'static str> {
+    match literal {
+        Literal::Number(_) | Literal::Dimension(..) => Some("number"),
+        Literal::String(_) => Some("string"),
+        Literal::Boolean(_) => Some("boolean"),
+        Literal::Null => None,
+    }
+}
+
+/// Resolve the import `specifier` of the file at `importer` to a path
+///
+/// Specifiers are relative to the importing file; `.omni` is added when
+/// the specifier has no extension.
+pub fn resolve(importer: &Path, specifier: &str) -> Result<PathBuf, ModuleError> {
+    let unsupported = || ModuleError::UnsupportedSpecifier {
+        specifier: specifier.to_string(),
+        importer: importer.display().to_string(),
+    };
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return Err(unsupported());
+    }
+
+    let mut path = importer.parent().unwrap_or(Path::new("")).join(specifier);
+    match path.extension() {
+        None => {
+            path.set_extension("omni");
+        }
+        Some(extension) if extension == "omni" => {}
+        Some(_) => return Err(unsupported()),
+    }
+    Ok(normalize(&path))
+}
+
+/// Remove `.` and `..` components without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            PathComponent::CurDir => {}
+            PathComponent::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Component name of an import source, taken from its file name the way
+/// the parser names components
+pub fn component_name(source: &str) -> String {
+    source
+        .rsplit('/')
+        .next()
+        .unwrap_or(source)
+        .trim_end_matches(".omni")
+        .to_string()
+}
+
+/// `snake_case` Rust module name for a file name such as `IconButton`
+pub fn module_name(file_name: &str) -> String {
+    let chars: Vec<char> = file_name.chars().collect();
+    let mut name = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1);
+            let boundary = prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(char::is_uppercase) && next.is_some_and(|n| n.is_lowercase()));
+            if boundary && !name.ends_with('_') {
+                name.push('_');
+            }
+            name.extend(c.to_lowercase());
+        } else if c.is_alphanumeric() {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let name = name.trim_end_matches('_').to_string();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(entries: &[(&str, &str)]) -> impl FnMut(&Path) -> std::io::Result<String> {
+        let files: HashMap<PathBuf, String> = entries
+            .iter()
+            .map(|(path, source)| (PathBuf::from(path), source.to_string()))
+            .collect();
+        move |path| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no such file"))
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let importer = Path::new("src/pages/Home.omni");
+        assert_eq!(resolve(importer, "./Card.omni").unwrap(), PathBuf::from("src/pages/Card.omni"));
+        assert_eq!(resolve(importer, "../ui/Button").unwrap(), PathBuf::from("src/ui/Button.omni"));
+        assert!(matches!(
+            resolve(importer, "omnicraft-ui"),
+            Err(ModuleError::UnsupportedSpecifier { .. })
+        ));
+        assert!(resolve(importer, "./styles.css").is_err());
+    }
+
+    #[test]
+    fn test_module_name() {
+        assert_eq!(module_name("IconButton"), "icon_button");
+        assert_eq!(module_name("HTMLView"), "html_view");
+        assert_eq!(module_name("my-widget"), "my_widget");
+        assert_eq!(module_name("App"), "app");
+    }
+
+    #[test]
+    fn test_build_graph() {
+        let graph = ModuleGraph::build_with(
+            "src/App.omni",
+            files(&[
+                ("src/App.omni", "<script>\nimport Button from \"./ui/Button.omni\";\nimport { scale } from \"./util.omni\";\n</script>\n"),
+                ("src/ui/Button.omni", "<script>\nimport { scale } from \"../util.omni\";\n</script>\n"),
+                ("src/util.omni", "<script>\nexport const scale = 2;\n</script>\n"),
+            ]),
+        )
+        .unwrap();
+
+        let names: Vec<&str> = graph.modules().iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["util", "ui_button", "app"]);
+        assert_eq!(graph.entry().dependencies["./ui/Button.omni"], 1);
+        assert_eq!(graph.entry().dependencies["./util.omni"], 0);
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let cycle = ModuleGraph::build_with(
+            "A.omni",
+            files(&[
+                ("A.omni", "<script>\nimport B from \"./B.omni\";\n</script>\n"),
+                ("B.omni", "<script>\nimport A from \"./A.omni\";\n</script>\n"),
+            ]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            cycle.downcast_ref::<ModuleError>(),
+            Some(&ModuleError::Cycle(vec!["A.omni".into(), "B.omni".into(), "A.omni".into()]))
+        );
+
+        let missing = ModuleGraph::build_with(
+            "A.omni",
+            files(&[
+                ("A.omni", "<script>\nimport { nope } from \"./B.omni\";\n</script>\n"),
+                ("B.omni", "<script>\nexport const yes = 1;\n</script>\n"),
+            ]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            missing.downcast_ref::<ModuleError>(),
+            Some(ModuleError::MissingExport { name, .. }) if name == "nope"
+        ));
+
+        let not_found = ModuleGraph::build_with(
+            "A.omni",
+            files(&[("A.omni", "<script>\nimport B from \"./B.omni\";\n</script>\n")]),
+        )
+        .unwrap_err();
+        assert!(not_found.to_string().contains("Failed to read B.omni"));
+    }
+
+    #[test]
+    fn test_prop_errors() {
+        let button = "<script>\nexport let label = \"\";\nexport let size: number = 1;\n</script>\n<canvas></canvas>\n";
+        let build = |usage: &str| {
+            let app = format!("<script>\nimport Button from \"./Button.omni\";\n</script>\n<canvas>{}</canvas>\n", usage);
+            ModuleGraph::build_with("App.omni", files(&[("App.omni", &app), ("Button.omni", button)]))
+        };
+
+        assert!(build("<Button label=\"Go\" size={2} />").is_ok());
+        // Values the checker can't type are left to rustc
+        assert!(build("{#if true}<Button label={name()} size={1 + 1} />{/if}").is_ok());
+
+        let error = build("{#if true}<Button size={1} bogus={1} />{/if}").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ModuleError>(),
+            Some(ModuleError::UnknownProp { prop, span, .. }) if prop == "bogus" && span.start == 90
+        ));
+
+        let error = build("<Button label={5} />").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ModuleError>(),
+            Some(ModuleError::PropTypeMismatch { prop, expected, found, .. })
+                if prop == "label" && *expected == "string" && *found == "number"
+        ));
+        let error = build("<Button size=\"big\" />").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Prop `size` of <Button> is a number, but is given a string, in App.omni at position 71"
+        );
+    }
+}
@@ -3,7 +3,8 @@
 //! Evaluates constant expressions at compile time.
 
 use crate::ast::{
-    BinaryOp, Component, Expression, Literal, Node, Statement, UnaryOp,
+    BinaryOp, Component, Expression, ExpressionKind, Literal, Node, NodeKind, Statement,
+    StatementKind, TemplatePart, UnaryOp,
 };
 use anyhow::Result;
 
@@ -38,73 +39,114 @@ impl ConstantFolder {
     }
 
     fn fold_statement(&self, stmt: &Statement) -> Statement {
-        match stmt {
-            Statement::VariableDeclaration {
+        let kind = match &stmt.kind {
+            StatementKind::VariableDeclaration {
                 kind,
                 name,
+                ty,
                 init,
                 reactive,
-            } => Statement::VariableDeclaration {
+            } => StatementKind::VariableDeclaration {
                 kind: *kind,
                 name: name.clone(),
+                ty: ty.clone(),
                 init: init.as_ref().map(|e| self.fold_expression(e)),
                 reactive: *reactive,
             },
-            Statement::Return(Some(expr)) => {
-                Statement::Return(Some(self.fold_expression(expr)))
+            StatementKind::Return(Some(expr)) => {
+                StatementKind::Return(Some(self.fold_expression(expr)))
             }
-            Statement::Expression(expr) => {
-                Statement::Expression(self.fold_expression(expr))
+            StatementKind::Expression(expr) => {
+                StatementKind::Expression(self.fold_expression(expr))
             }
-            Statement::If {
+            StatementKind::If {
                 condition,
                 then_branch,
                 else_branch,
-            } => Statement::If {
+            } => StatementKind::If {
                 condition: self.fold_expression(condition),
                 then_branch: then_branch.iter().map(|s| self.fold_statement(s)).collect(),
                 else_branch: else_branch
                     .as_ref()
                     .map(|b| b.iter().map(|s| self.fold_statement(s)).collect()),
             },
-            _ => stmt.clone(),
-        }
+            StatementKind::FunctionDeclaration {
+                name,
+                params,
+                return_type,
+                body,
+                is_async,
+            } => StatementKind::FunctionDeclaration {
+                name: name.clone(),
+                params: params.clone(),
+                return_type: return_type.clone(),
+                body: self.fold_block(body),
+                is_async: *is_async,
+            },
+            StatementKind::For {
+                init,
+                condition,
+                update,
+                body,
+            } => StatementKind::For {
+                init: init.as_ref().map(|s| Box::new(self.fold_statement(s))),
+                condition: condition.as_ref().map(|e| self.fold_expression(e)),
+                update: update.as_ref().map(|e| self.fold_expression(e)),
+                body: self.fold_block(body),
+            },
+            StatementKind::While { condition, body } => StatementKind::While {
+                condition: self.fold_expression(condition),
+                body: self.fold_block(body),
+            },
+            StatementKind::Block(body) => StatementKind::Block(self.fold_block(body)),
+            StatementKind::Export(declaration) => {
+                StatementKind::Export(Box::new(self.fold_statement(declaration)))
+            }
+            _ => return stmt.clone(),
+        };
+        Statement::new(kind, stmt.span)
     }
 
+    fn fold_block(&self, statements: &[Statement]) -> Vec<Statement> {
+        statements.iter().map(|s| self.fold_statement(s)).collect()
+    }
+
+    /// Fold an expression; a folded result keeps the span of the
+    /// expression it replaces
     fn fold_expression(&self, expr: &Expression) -> Expression {
-        match expr {
-            Expression::Binary { left, op, right } => {
+        let kind = match &expr.kind {
+            ExpressionKind::Binary { left, op, right } => {
                 let left = self.fold_expression(left);
                 let right = self.fold_expression(right);
 
                 // Try to evaluate constant expressions
-                if let (Expression::Literal(l), Expression::Literal(r)) = (&left, &right) {
-                    if let Some(result) = self.eval_binary(l, *op, r) {
-                        return Expression::Literal(result);
-                    }
+                if let (ExpressionKind::Literal(l), ExpressionKind::Literal(r)) = (&left.kind, &right.kind)
+                    && let Some(result) = self.eval_binary(l, *op, r)
+                {
+                    return Expression::new(ExpressionKind::Literal(result), expr.span);
                 }
 
-                Expression::Binary {
+                ExpressionKind::Binary {
                     left: Box::new(left),
                     op: *op,
                     right: Box::new(right),
                 }
             }
-            Expression::Unary { op, operand } => {
+            ExpressionKind::Unary { op, operand } => {
                 let operand = self.fold_expression(operand);
 
-                if let Expression::Literal(lit) = &operand {
-                    if let Some(result) = self.eval_unary(*op, lit) {
-                        return Expression::Literal(result);
-                    }
+                if let ExpressionKind::Literal(lit) = &operand.kind
+                    && let Some(result) = self.eval_unary(*op, lit)
+                {
+                    return Expression::new(ExpressionKind::Literal(result), expr.span);
                 }
 
-                Expression::Unary {
+                ExpressionKind::Unary {
                     op: *op,
                     operand: Box::new(operand),
                 }
             }
-            Expression::Ternary {
+            ExpressionKind::Ternary {
                 condition,
                 then_expr,
                 else_expr,
@@ -112,7 +154,7 @@ impl ConstantFolder {
                 let condition = self.fold_expression(condition);
 
                 // If condition is constant, select the branch
-                if let Expression::Literal(Literal::Boolean(b)) = &condition {
+                if let ExpressionKind::Literal(Literal::Boolean(b)) = &condition.kind {
                     if *b {
                         return self.fold_expression(then_expr);
                     } else {
@@ -120,32 +162,56 @@ impl ConstantFolder {
                     }
                 }
 
-                Expression::Ternary {
+                ExpressionKind::Ternary {
                     condition: Box::new(condition),
                     then_expr: Box::new(self.fold_expression(then_expr)),
                     else_expr: Box::new(self.fold_expression(else_expr)),
                 }
             }
-            Expression::Call { callee, args } => Expression::Call {
+            // The target is a place, not a value, so only the value folds
+            ExpressionKind::Assign { op, target, value } => ExpressionKind::Assign {
+                op: *op,
+                target: target.clone(),
+                value: Box::new(self.fold_expression(value)),
+            },
+            ExpressionKind::Call {
+                callee,
+                type_args,
+                args,
+                optional,
+            } => ExpressionKind::Call {
                 callee: Box::new(self.fold_expression(callee)),
+                type_args: type_args.clone(),
                 args: args.iter().map(|a| self.fold_expression(a)).collect(),
+                optional: *optional,
+            },
+            ExpressionKind::Template { parts } => ExpressionKind::Template {
+                parts: parts
+                    .iter()
+                    .map(|part| match part {
+                        TemplatePart::Expression(e) => TemplatePart::Expression(self.fold_expression(e)),
+                        other => other.clone(),
+                    })
+                    .collect(),
             },
-            Expression::Array(items) => {
-                Expression::Array(items.iter().map(|i| self.fold_expression(i)).collect())
+            ExpressionKind::Array(items) => {
+                ExpressionKind::Array(items.iter().map(|i| self.fold_expression(i)).collect())
             }
-            Expression::Object(props) => Expression::Object(
+            ExpressionKind::Object(props) => ExpressionKind::Object(
                 props
                     .iter()
-                    .map(|(k, v)| (k.clone(), self.fold_expression(v)))
+                    .map(|p| p.map(|e| self.fold_expression(e)))
                     .collect(),
             ),
-            _ => expr.clone(),
-        }
+            ExpressionKind::Spread(inner) => ExpressionKind::Spread(Box::new(self.fold_expression(inner))),
+            _ => return expr.clone(),
+        };
+        Expression::new(kind, expr.span)
     }
 
     fn fold_node(&self, node: &Node) -> Node {
-        match node {
-            Node::Element {
+        let kind = match &node.kind {
+            NodeKind::Element {
                 tag,
                 attributes,
                 children,
@@ -164,19 +230,20 @@ impl ConstantFolder {
                         crate::ast::Attribute {
                             name: a.name.clone(),
                             value,
+                            span: a.span,
                         }
                     })
                     .collect();
 
-                Node::Element {
+                NodeKind::Element {
                     tag: tag.clone(),
                     attributes,
                     children: children.iter().map(|c| self.fold_node(c)).collect(),
                     directives: directives.clone(),
-                    key: key.clone(),
+                    key: key.as_ref().map(|key| self.fold_expression(key)),
                 }
             }
-            Node::IfBlock {
+            NodeKind::IfBlock {
                 condition,
                 then_branch,
                 else_branch,
@@ -184,14 +251,14 @@ impl ConstantFolder {
                 let condition = self.fold_expression(condition);
 
                 // Static elimination of branches
-                if let Expression::Literal(Literal::Boolean(b)) = &condition {
-                    if *b {
-                        // Return just the then branch content
-                        // For now, keep as IfBlock for simplicity
-                    }
+                if let ExpressionKind::Literal(Literal::Boolean(b)) = &condition.kind
+                    && *b
+                {
+                    // Return just the then branch content
+                    // For now, keep as IfBlock for simplicity
                 }
 
-                Node::IfBlock {
+                NodeKind::IfBlock {
                     condition,
                     then_branch: then_branch.iter().map(|n| self.fold_node(n)).collect(),
                     else_branch: else_branch
@@ -199,27 +266,44 @@ impl ConstantFolder {
                         .map(|b| b.iter().map(|n| self.fold_node(n)).collect()),
                 }
             }
-            Node::EachBlock {
+            NodeKind::EachBlock {
                 expression,
                 binding,
                 index,
                 body,
                 key,
-            } => Node::EachBlock {
+            } => NodeKind::EachBlock {
                 expression: self.fold_expression(expression),
                 binding: binding.clone(),
                 index: index.clone(),
                 body: body.iter().map(|n| self.fold_node(n)).collect(),
                 key: key.clone(),
             },
-            Node::Text { content } => Node::Text {
+            NodeKind::AwaitBlock {
+                promise,
+                pending,
+                then_branch,
+                catch_branch,
+            } => NodeKind::AwaitBlock {
+                promise: self.fold_expression(promise),
+                pending: pending.iter().map(|n| self.fold_node(n)).collect(),
+                then_branch: then_branch.as_ref().map(|b| b.map_body(|n| self.fold_node(n))),
+                catch_branch: catch_branch.as_ref().map(|b| b.map_body(|n| self.fold_node(n))),
+            },
+            NodeKind::Slot { name, props, fallback } => NodeKind::Slot {
+                name: name.clone(),
+                props: props.clone(),
+                fallback: fallback.iter().map(|n| self.fold_node(n)).collect(),
+            },
+            NodeKind::Text { content } => NodeKind::Text {
                 content: self.fold_expression(content),
             },
-            Node::Expression { expr } => Node::Expression {
+            NodeKind::Expression { expr } => NodeKind::Expression {
                 expr: self.fold_expression(expr),
             },
-            _ => node.clone(),
-        }
+            _ => return node.clone(),
+        };
+        Node::new(kind, node.span)
     }
 
     fn eval_binary(&self, left: &Literal, op: BinaryOp, right: &Literal) -> Option<Literal> {
@@ -251,6 +335,20 @@ impl ConstantFolder {
                 };
                 Some(Literal::Number(result))
             }
+            (Literal::Dimension(l, lu), Literal::Dimension(r, ru)) if lu == ru => match op {
+                BinaryOp::Add => Some(Literal::Dimension(l + r, *lu)),
+                BinaryOp::Sub => Some(Literal::Dimension(l - r, *lu)),
+                _ => None,
+            },
+            (Literal::Dimension(l, unit), Literal::Number(r)) => match op {
+                BinaryOp::Mul => Some(Literal::Dimension(l * r, *unit)),
+                BinaryOp::Div if *r != 0.0 => Some(Literal::Dimension(l / r, *unit)),
+                _ => None,
+            },
+            (Literal::Number(l), Literal::Dimension(r, unit)) => match op {
+                BinaryOp::Mul => Some(Literal::Dimension(l * r, *unit)),
+                _ => None,
+            },
             (Literal::String(l), Literal::String(r)) => {
                 match op {
                     BinaryOp::Add => Some(Literal::String(format!("{}{}", l, r))),
@@ -275,6 +373,7 @@ impl ConstantFolder {
     fn eval_unary(&self, op: UnaryOp, operand: &Literal) -> Option<Literal> {
         match (op, operand) {
             (UnaryOp::Neg, Literal::Number(n)) => Some(Literal::Number(-n)),
+            (UnaryOp::Neg, Literal::Dimension(n, unit)) => Some(Literal::Dimension(-n, *unit)),
             (UnaryOp::Not, Literal::Boolean(b)) => Some(Literal::Boolean(!b)),
             _ => None,
         }
@@ -290,7 +389,8 @@ impl Default for ConstantFolder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::lexer::Lexer;
+    use crate::ast::Unit;
+    use crate::lexer::{Lexer, Span};
     use crate::parser::Parser;
 
     fn parse(source: &str) -> Component {
@@ -298,6 +398,10 @@ mod tests {
         Parser::new(tokens, "test.omni").parse().unwrap()
     }
 
+    fn literal(lit: Literal, start: usize, end: usize) -> Box<Expression> {
+        Box::new(Expression::new(ExpressionKind::Literal(lit), Span::new(start, end)))
+    }
+
     #[test]
     fn test_fold_arithmetic() {
         let source = r##"
@@ -312,39 +416,105 @@ mod tests {
         let component = parse(source);
         let folded = ConstantFolder::new().fold(&component).unwrap();
 
-        // The constant should be folded
-        if let Some(script) = &folded.script {
-            if let Statement::VariableDeclaration { init: Some(Expression::Literal(Literal::Number(n))), .. } = &script.statements[0] {
-                assert_eq!(*n, 3.0);
-            }
-        }
+        // The constant should be folded, keeping the span of `1 + 2`
+        let script = folded.script.unwrap();
+        let StatementKind::VariableDeclaration { init: Some(init), .. } = &script.statements[0].kind else {
+            panic!("expected declaration");
+        };
+        assert_eq!(init.kind, ExpressionKind::Literal(Literal::Number(3.0)));
+        assert_eq!(&source[init.span.start..init.span.end], "1 + 2");
     }
 
     #[test]
     fn test_fold_string_concat() {
-        let expr = Expression::Binary {
-            left: Box::new(Expression::Literal(Literal::String("Hello, ".to_string()))),
-            op: BinaryOp::Add,
-            right: Box::new(Expression::Literal(Literal::String("World!".to_string()))),
-        };
+        let expr = Expression::new(
+            ExpressionKind::Binary {
+                left: literal(Literal::String("Hello, ".to_string()), 0, 9),
+                op: BinaryOp::Add,
+                right: literal(Literal::String("World!".to_string()), 12, 20),
+            },
+            Span::new(0, 20),
+        );
 
         let folder = ConstantFolder::new();
         let result = folder.fold_expression(&expr);
 
-        assert_eq!(result, Expression::Literal(Literal::String("Hello, World!".to_string())));
+        assert_eq!(
+            result,
+            *literal(Literal::String("Hello, World!".to_string()), 0, 20)
+        );
     }
 
     #[test]
-    fn test_fold_comparison() {
-        let expr = Expression::Binary {
-            left: Box::new(Expression::Literal(Literal::Number(5.0))),
-            op: BinaryOp::Gt,
-            right: Box::new(Expression::Literal(Literal::Number(3.0))),
+    fn test_fold_dimension_scaling_commutes() {
+        let scale = |left: Literal, right: Literal| {
+            Expression::new(
+                ExpressionKind::Binary {
+                    left: literal(left, 0, 4),
+                    op: BinaryOp::Mul,
+                    right: literal(right, 7, 8),
+                },
+                Span::new(0, 8),
+            )
         };
 
+        let folder = ConstantFolder::new();
+        let dimension_first = scale(Literal::Dimension(45.0, Unit::Deg), Literal::Number(2.0));
+        let number_first = scale(Literal::Number(2.0), Literal::Dimension(45.0, Unit::Deg));
+
+        let expected = *literal(Literal::Dimension(90.0, Unit::Deg), 0, 8);
+        assert_eq!(folder.fold_expression(&dimension_first), expected);
+        assert_eq!(folder.fold_expression(&number_first), expected);
+    }
+
+    #[test]
+    fn test_fold_comparison() {
+        let expr = Expression::new(
+            ExpressionKind::Binary {
+                left: literal(Literal::Number(5.0), 0, 1),
+                op: BinaryOp::Gt,
+                right: literal(Literal::Number(3.0), 4, 5),
+            },
+            Span::new(0, 5),
+        );
+
         let folder = ConstantFolder::new();
         let result = folder.fold_expression(&expr);
 
-        assert_eq!(result, Expression::Literal(Literal::Boolean(true)));
+        assert_eq!(result, *literal(Literal::Boolean(true), 0, 5));
+    }
+
+    #[test]
+    fn test_fold_inside_loops() {
+        let source = r##"
+<script>
+  function tick() {
+    let x = 0;
+    while (x < 2 * 8) {
+      x += 60 / 2;
+    }
+  }
+</script>
+
+<canvas width={800} height={600}>
+</canvas>
+"##;
+        let folded = ConstantFolder::new().fold(&parse(source)).unwrap();
+        let script = folded.script.unwrap();
+        let StatementKind::FunctionDeclaration { body, .. } = &script.statements[0].kind else {
+            panic!("expected function");
+        };
+        let StatementKind::While { condition, body } = &body[1].kind else {
+            panic!("expected while loop");
+        };
+        let ExpressionKind::Binary { right, .. } = &condition.kind else {
+            panic!("expected comparison");
+        };
+        assert_eq!(right.kind, ExpressionKind::Literal(Literal::Number(16.0)));
+        let StatementKind::Expression(Expression { kind: ExpressionKind::Assign { target, value, .. }, .. }) = &body[0].kind else {
+            panic!("expected assignment");
+        };
+        assert_eq!(target.kind, ExpressionKind::Identifier("x".to_string()));
+        assert_eq!(value.kind, ExpressionKind::Literal(Literal::Number(30.0)));
     }
 }
@@ -3,11 +3,12 @@
 //! Removes unused variables, functions, and expressions.
 
 use crate::analyzer::DependencyGraph;
-use crate::ast::{Component, Node, Script, Statement};
+use crate::ast::{AwaitBranch, Component, Node, NodeKind, Statement, StatementKind};
 use anyhow::Result;
 
 /// Dead code eliminator
 pub struct DeadCodeEliminator<'a> {
+    #[allow(dead_code)]
     dependencies: &'a DependencyGraph,
 }
 
@@ -38,8 +39,8 @@ impl<'a> DeadCodeEliminator<'a> {
     }
 
     fn is_dead_statement(&self, stmt: &Statement) -> bool {
-        match stmt {
-            Statement::VariableDeclaration { name, reactive, .. } => {
+        match &stmt.kind {
+            StatementKind::VariableDeclaration { reactive, .. } => {
                 // Keep all reactive variables
                 if *reactive != crate::ast::ReactiveKind::None {
                     return false;
@@ -50,7 +51,7 @@ impl<'a> DeadCodeEliminator<'a> {
                 // Full DCE would check if the variable is referenced anywhere
                 false
             }
-            Statement::FunctionDeclaration { name, .. } => {
+            StatementKind::FunctionDeclaration { .. } => {
                 // Keep functions that are used as event handlers
                 // For now, keep all functions
                 false
@@ -67,8 +68,8 @@ impl<'a> DeadCodeEliminator<'a> {
     }
 
     fn eliminate_node(&self, node: &Node) -> Option<Node> {
-        match node {
-            Node::Element {
+        let kind = match &node.kind {
+            NodeKind::Element {
                 tag,
                 attributes,
                 children,
@@ -76,15 +77,15 @@ impl<'a> DeadCodeEliminator<'a> {
                 key,
             } => {
                 let children = self.eliminate_nodes(children);
-                Some(Node::Element {
+                NodeKind::Element {
                     tag: tag.clone(),
                     attributes: attributes.clone(),
                     children,
                     directives: directives.clone(),
                     key: key.clone(),
-                })
+                }
             }
-            Node::IfBlock {
+            NodeKind::IfBlock {
                 condition,
                 then_branch,
                 else_branch,
@@ -92,13 +93,13 @@ impl<'a> DeadCodeEliminator<'a> {
                 // TODO: Eliminate static false conditions
                 let then_branch = self.eliminate_nodes(then_branch);
                 let else_branch = else_branch.as_ref().map(|b| self.eliminate_nodes(b));
-                Some(Node::IfBlock {
+                NodeKind::IfBlock {
                     condition: condition.clone(),
                     then_branch,
                     else_branch,
-                })
+                }
             }
-            Node::EachBlock {
+            NodeKind::EachBlock {
                 expression,
                 binding,
                 index,
@@ -106,23 +107,46 @@ impl<'a> DeadCodeEliminator<'a> {
                 key,
             } => {
                 let body = self.eliminate_nodes(body);
-                Some(Node::EachBlock {
+                NodeKind::EachBlock {
                     expression: expression.clone(),
                     binding: binding.clone(),
                     index: index.clone(),
                     body,
                     key: key.clone(),
-                })
+                }
             }
-            _ => Some(node.clone()),
-        }
+            NodeKind::AwaitBlock {
+                promise,
+                pending,
+                then_branch,
+                catch_branch,
+            } => {
+                let eliminate = |branch: &AwaitBranch| AwaitBranch {
+                    binding: branch.binding.clone(),
+                    body: self.eliminate_nodes(&branch.body),
+                };
+                NodeKind::AwaitBlock {
+                    promise: promise.clone(),
+                    pending: self.eliminate_nodes(pending),
+                    then_branch: then_branch.as_ref().map(eliminate),
+                    catch_branch: catch_branch.as_ref().map(eliminate),
+                }
+            }
+            NodeKind::Slot { name, props, fallback } => NodeKind::Slot {
+                name: name.clone(),
+                props: props.clone(),
+                fallback: self.eliminate_nodes(fallback),
+            },
+            _ => return Some(node.clone()),
+        };
+        Some(Node::new(kind, node.span))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::analyzer::{analyze, DependencyGraph};
+    use crate::analyzer::DependencyGraph;
     use crate::lexer::Lexer;
     use crate::parser::Parser;
 
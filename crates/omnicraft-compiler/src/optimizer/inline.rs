@@ -2,7 +2,7 @@
 //!
 //! Inlines small functions and expressions.
 
-use crate::ast::{Component, Expression, Statement};
+use crate::ast::{Component, Expression, ExpressionKind, Statement, StatementKind};
 use anyhow::Result;
 use std::collections::HashMap;
 
@@ -43,14 +43,14 @@ impl InlineExpander {
 
     fn collect_inlinable(&mut self, statements: &[Statement]) {
         for stmt in statements {
-            if let Statement::FunctionDeclaration { name, params, body, .. } = stmt {
+            if let StatementKind::FunctionDeclaration { name, params, body, .. } = &stmt.kind {
                 // Only inline simple functions with single return statement
-                if params.is_empty() && body.len() == 1 {
-                    if let Statement::Return(Some(expr)) = &body[0] {
-                        // Only inline if the function is simple
-                        if self.is_simple_expression(expr) {
-                            self.inlinable.insert(name.clone(), expr.clone());
-                        }
+                if params.is_empty() && body.len() == 1
+                    && let StatementKind::Return(Some(expr)) = &body[0].kind
+                {
+                    // Only inline if the function is simple
+                    if self.is_simple_expression(expr) {
+                        self.inlinable.insert(name.clone(), expr.clone());
                     }
                 }
             }
@@ -58,86 +58,104 @@ impl InlineExpander {
     }
 
     fn is_simple_expression(&self, expr: &Expression) -> bool {
-        match expr {
-            Expression::Literal(_) => true,
-            Expression::Identifier(_) => true,
-            Expression::Binary { left, right, .. } => {
+        match &expr.kind {
+            ExpressionKind::Literal(_) => true,
+            ExpressionKind::Identifier(_) => true,
+            ExpressionKind::Binary { left, right, .. } => {
                 self.is_simple_expression(left) && self.is_simple_expression(right)
             }
-            Expression::Unary { operand, .. } => self.is_simple_expression(operand),
+            ExpressionKind::Unary { operand, .. } => self.is_simple_expression(operand),
             _ => false,
         }
     }
 
     fn expand_statement(&self, stmt: &Statement) -> Statement {
-        match stmt {
-            Statement::VariableDeclaration {
+        let kind = match &stmt.kind {
+            StatementKind::VariableDeclaration {
                 kind,
                 name,
+                ty,
                 init,
                 reactive,
-            } => Statement::VariableDeclaration {
+            } => StatementKind::VariableDeclaration {
                 kind: *kind,
                 name: name.clone(),
+                ty: ty.clone(),
                 init: init.as_ref().map(|e| self.expand_expression(e)),
                 reactive: *reactive,
             },
-            Statement::Return(Some(expr)) => {
-                Statement::Return(Some(self.expand_expression(expr)))
+            StatementKind::Return(Some(expr)) => {
+                StatementKind::Return(Some(self.expand_expression(expr)))
             }
-            Statement::Expression(expr) => {
-                Statement::Expression(self.expand_expression(expr))
+            StatementKind::Expression(expr) => {
+                StatementKind::Expression(self.expand_expression(expr))
             }
-            _ => stmt.clone(),
-        }
+            StatementKind::Export(declaration) => {
+                StatementKind::Export(Box::new(self.expand_statement(declaration)))
+            }
+            _ => return stmt.clone(),
+        };
+        Statement::new(kind, stmt.span)
     }
 
+    /// Expand calls in an expression; an inlined body takes the span of
+    /// the call it replaces
     fn expand_expression(&self, expr: &Expression) -> Expression {
-        match expr {
-            Expression::Call { callee, args } => {
+        let kind = match &expr.kind {
+            ExpressionKind::Call {
+                callee,
+                type_args,
+                args,
+                optional,
+            } => {
                 // Check if this is a call to an inlinable function
-                if let Expression::Identifier(name) = callee.as_ref() {
-                    if args.is_empty() {
-                        if let Some(inlined) = self.inlinable.get(name) {
-                            return inlined.clone();
-                        }
-                    }
+                if let ExpressionKind::Identifier(name) = &callee.kind
+                    && args.is_empty()
+                    && let Some(inlined) = self.inlinable.get(name)
+                {
+                    return Expression::new(inlined.kind.clone(), expr.span);
                 }
 
-                Expression::Call {
+                ExpressionKind::Call {
                     callee: Box::new(self.expand_expression(callee)),
+                    type_args: type_args.clone(),
                     args: args.iter().map(|a| self.expand_expression(a)).collect(),
+                    optional: *optional,
                 }
             }
-            Expression::Binary { left, op, right } => Expression::Binary {
+            ExpressionKind::Binary { left, op, right } => ExpressionKind::Binary {
                 left: Box::new(self.expand_expression(left)),
                 op: *op,
                 right: Box::new(self.expand_expression(right)),
             },
-            Expression::Unary { op, operand } => Expression::Unary {
+            ExpressionKind::Unary { op, operand } => ExpressionKind::Unary {
                 op: *op,
                 operand: Box::new(self.expand_expression(operand)),
             },
-            Expression::Ternary {
+            ExpressionKind::Ternary {
                 condition,
                 then_expr,
                 else_expr,
-            } => Expression::Ternary {
+            } => ExpressionKind::Ternary {
                 condition: Box::new(self.expand_expression(condition)),
                 then_expr: Box::new(self.expand_expression(then_expr)),
                 else_expr: Box::new(self.expand_expression(else_expr)),
             },
-            Expression::Array(items) => {
-                Expression::Array(items.iter().map(|i| self.expand_expression(i)).collect())
+            ExpressionKind::Array(items) => {
+                ExpressionKind::Array(items.iter().map(|i| self.expand_expression(i)).collect())
             }
-            Expression::Object(props) => Expression::Object(
+            ExpressionKind::Object(props) => ExpressionKind::Object(
                 props
                     .iter()
-                    .map(|(k, v)| (k.clone(), self.expand_expression(v)))
+                    .map(|p| p.map(|e| self.expand_expression(e)))
                     .collect(),
             ),
-            _ => expr.clone(),
-        }
+            ExpressionKind::Spread(inner) => {
+                ExpressionKind::Spread(Box::new(self.expand_expression(inner)))
+            }
+            _ => return expr.clone(),
+        };
+        Expression::new(kind, expr.span)
     }
 }
 
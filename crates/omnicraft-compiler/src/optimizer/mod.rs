@@ -13,7 +13,7 @@ pub mod dce;
 pub mod inline;
 
 use crate::analyzer::AnalyzedComponent;
-use crate::ast::{Component, Expression, Literal, Node, Statement};
+use crate::ast::Component;
 use anyhow::Result;
 
 pub use const_fold::ConstantFolder;
@@ -0,0 +1,1369 @@
+//! Builder DSL front-end
+//!
+//! A second syntax for `.omni` files, where each component is declared with
+//! `component Name { ... }` and its view is built from calls with chained
+//! modifiers:
+//!
+//! ```omni
+//! component Counter {
+//!     state count = 0
+//!
+//!     render {
+//!         Text("Count: {count}")
+//!             .fontSize(24)
+//!             .onClick(|| count += 1)
+//!     }
+//! }
+//! ```
+//!
+//! A file holds its imports followed by any number of components, each
+//! lowered to the same `Component` the `<script>`/`<canvas>` syntax parses
+//! to:
+//!
+//! - `state name = value` becomes `const name = signal(value)`; reading
+//!   `name` calls the signal and assigning to it sets the signal
+//! - `prop name: Type = default` becomes `export let name: Type = default`
+//! - `let`, `const` and `function` declarations are kept as they are
+//! - `render { ... }` becomes the template. `Name(args) { children }` is a
+//!   primitive element, or a component for other capitalized names, and
+//!   its positional arguments fill the primitive's main attributes or the
+//!   props of a component declared in the same file, in order.
+//!   `.onEvent(handler)` modifiers become event handlers, `.key(value)` the
+//!   element's key and other modifiers attributes, which the primitive or
+//!   component must have. `if cond { ... }` and `for item, i in items { ... }`
+//!   become control-flow blocks.
+//! - `Column` and `Row` are groups stacking their children, spaced by
+//!   `.gap()` and `.padding()`. `Button(label)` is a row drawing its
+//!   `.backgroundColor()` behind a text label, unless a component named
+//!   `Button` is declared or imported.
+//!
+//! Strings passed to views and modifiers interpolate `{expr}`; `{{` and
+//! `}}` stand for literal braces.
+
+use super::*;
+use crate::lexer::Lexer;
+use std::collections::HashSet;
+
+/// Attributes any primitive's modifiers may set, besides those of its kind
+const COMMON_MODIFIERS: [&str; 5] = ["x", "y", "rotation", "class", "id"];
+
+/// Attributes of painted primitives
+const PAINT_MODIFIERS: &[&str] = &["fill", "stroke", "strokeWidth", "opacity"];
+
+/// Attributes of text
+const TEXT_MODIFIERS: &[&str] = &["fill", "stroke", "strokeWidth", "opacity", "fontSize", "fontFamily", "fontWeight"];
+
+/// Attributes of columns and rows
+const STACK_MODIFIERS: &[&str] = &["gap", "padding", "background", "cornerRadius"];
+
+/// Primitives of the builder DSL, with the element they lower to, the
+/// attributes their positional arguments fill and those their modifiers
+/// may set
+const PRIMITIVES: [(&str, ElementTag, &[&str], &[&str]); 13] = [
+    ("Circle", ElementTag::Circle, &["radius"], PAINT_MODIFIERS),
+    ("Rect", ElementTag::Rectangle, &["width", "height"], PAINT_MODIFIERS),
+    ("Rectangle", ElementTag::Rectangle, &["width", "height"], PAINT_MODIFIERS),
+    ("Ellipse", ElementTag::Ellipse, &["rx", "ry"], PAINT_MODIFIERS),
+    ("Line", ElementTag::Line, &["x2", "y2"], PAINT_MODIFIERS),
+    ("Path", ElementTag::Path, &["d"], PAINT_MODIFIERS),
+    ("Polygon", ElementTag::Polygon, &["points"], PAINT_MODIFIERS),
+    ("Text", ElementTag::Text, &["content"], TEXT_MODIFIERS),
+    ("Image", ElementTag::Image, &["src"], &["opacity"]),
+    ("Video", ElementTag::Video, &["src"], &["opacity"]),
+    ("Group", ElementTag::Group, &[], &[]),
+    ("Column", ElementTag::Group, &[], STACK_MODIFIERS),
+    ("Row", ElementTag::Group, &[], STACK_MODIFIERS),
+];
+
+/// Layout of the groups stacking their children
+const STACKS: [(&str, &str); 2] = [("Column", "column"), ("Row", "row")];
+
+/// Modifiers named differently from the attribute they set
+const MODIFIER_ALIASES: [(&str, &str); 2] = [("color", "fill"), ("backgroundColor", "background")];
+
+/// Built-in `Button(label)`, a row drawing a background around its label,
+/// unless a component of that name is in scope
+const BUTTON: &str = "Button";
+
+/// Attributes of a button set on its label rather than its row
+const BUTTON_LABEL_MODIFIERS: [&str; 4] = ["fill", "fontSize", "fontFamily", "fontWeight"];
+
+/// Components in scope, with the props of those declared in the same file
+/// in declaration order
+type ComponentProps = HashMap<String, Option<Vec<String>>>;
+
+/// A component as written, before its view is lowered
+struct ComponentDecl {
+    name: String,
+    statements: Vec<Statement>,
+    view: Vec<View>,
+}
+
+impl ComponentDecl {
+    /// Names declared with `state`
+    fn states(&self) -> HashSet<String> {
+        self.statements
+            .iter()
+            .filter_map(|statement| match &statement.kind {
+                StatementKind::VariableDeclaration {
+                    name,
+                    reactive: ReactiveKind::Signal,
+                    ..
+                } => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// A node of a `render` block
+#[derive(Clone)]
+enum View {
+    /// `Name(args) { children }.modifier(args)...`
+    Call {
+        name: String,
+        name_span: Span,
+        args: Vec<Expression>,
+        children: Vec<View>,
+        modifiers: Vec<Modifier>,
+        span: Span,
+    },
+    If {
+        condition: Expression,
+        then_branch: Vec<View>,
+        else_branch: Option<Vec<View>>,
+        span: Span,
+    },
+    For {
+        binding: String,
+        index: Option<String>,
+        items: Expression,
+        body: Vec<View>,
+        span: Span,
+    },
+}
+
+/// `.name(args)` after a view
+#[derive(Clone)]
+struct Modifier {
+    name: String,
+    args: Vec<Expression>,
+    span: Span,
+}
+
+impl Parser {
+    /// Check whether the tokens are a builder DSL file rather than
+    /// `<script>`, `<canvas>` and `<style>` sections
+    pub(super) fn at_builder_file(&self) -> bool {
+        self.check_text(0, "component") || self.check_text(0, "import")
+    }
+
+    /// Parse the imports and components of a builder DSL file
+    ///
+    /// The component named after the file, or else the first one, comes
+    /// first.
+    pub(super) fn parse_builder_file(&mut self) -> Vec<Component> {
+        let mut imports = Vec::new();
+        while self.check_text(0, "import") {
+            imports.push(self.recover_statement(Self::parse_import_declaration));
+        }
+
+        let mut declarations = Vec::new();
+        while !self.is_at_end() {
+            if let Ok(declaration) = self.recover(Sync::Brace, Self::parse_component_declaration) {
+                declarations.push(declaration);
+            }
+        }
+        let main = self.infer_component_name();
+        if let Some(i) = declarations.iter().position(|declaration| declaration.name == main) {
+            declarations[..=i].rotate_right(1);
+        }
+
+        // Imported components and those of the file are in scope; positional
+        // arguments of the latter fill their props in order
+        let mut props: ComponentProps = imports
+            .iter()
+            .filter_map(|statement| match &statement.kind {
+                StatementKind::Import(import) => Some(import.specifiers.iter().map(ImportSpecifier::local)),
+                _ => None,
+            })
+            .flatten()
+            .filter(|name| name.starts_with(char::is_uppercase))
+            .map(|name| (name.to_string(), None))
+            .collect();
+        for declaration in &declarations {
+            let names = declared_props(&declaration.statements).into_iter().map(|prop| prop.name);
+            props.insert(declaration.name.clone(), Some(names.collect()));
+        }
+        let names: Vec<String> = declarations.iter().map(|declaration| declaration.name.clone()).collect();
+        let components = declarations
+            .into_iter()
+            .map(|declaration| self.lower_component(declaration, &imports, &props, &names))
+            .collect();
+        self.errors.sort_by_key(|error| error.span().start);
+        components
+    }
+
+    fn parse_component_declaration(&mut self) -> ParseResult<ComponentDecl> {
+        self.consume_word("component")?;
+        let name = self.consume_identifier()?;
+        self.consume(TokenKind::LeftBrace)?;
+
+        let mut declaration = ComponentDecl {
+            name,
+            statements: Vec::new(),
+            view: Vec::new(),
+        };
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            let _ = self.recover(Sync::Statement, |p| p.parse_member(&mut declaration));
+        }
+        self.consume(TokenKind::RightBrace)?;
+        Ok(declaration)
+    }
+
+    /// Parse a `state`, `prop` or `render` member, or a script statement
+    fn parse_member(&mut self, declaration: &mut ComponentDecl) -> ParseResult<()> {
+        let start = self.offset();
+        if self.check_text(0, "state") && self.check_identifier(1) {
+            self.advance();
+            let name = self.consume_identifier()?;
+            let ty = self.parse_type_annotation()?;
+            self.consume(TokenKind::Equals)?;
+            let value = self.parse_expression()?;
+            self.consume_if(TokenKind::Semicolon);
+
+            let span = value.span;
+            let init = Expression::new(
+                ExpressionKind::Call {
+                    callee: Box::new(Expression::new(ExpressionKind::Identifier("signal".to_string()), span)),
+                    type_args: ty.into_iter().collect(),
+                    args: vec![value],
+                    optional: false,
+                },
+                span,
+            );
+            declaration.statements.push(Statement::new(
+                StatementKind::VariableDeclaration {
+                    kind: VarKind::Const,
+                    name,
+                    ty: None,
+                    init: Some(init),
+                    reactive: ReactiveKind::Signal,
+                },
+                self.span_from(start),
+            ));
+        } else if self.check_text(0, "prop") && self.check_identifier(1) {
+            self.advance();
+            let name = self.consume_identifier()?;
+            let ty = self.parse_type_annotation()?;
+            let init = if self.consume_if(TokenKind::Equals) {
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            self.consume_if(TokenKind::Semicolon);
+
+            let span = self.span_from(start);
+            let binding = StatementKind::VariableDeclaration {
+                kind: VarKind::Let,
+                name,
+                ty,
+                init,
+                reactive: ReactiveKind::None,
+            };
+            declaration.statements.push(Statement::new(
+                StatementKind::Export(Box::new(Statement::new(binding, span))),
+                span,
+            ));
+        } else if self.check_text(0, "render") && self.tokens.get(self.pos + 1).is_some_and(|t| t.kind == TokenKind::LeftBrace) {
+            self.advance();
+            declaration.view = self.parse_views()?;
+        } else {
+            let statement = self.parse_statement()?;
+            declaration.statements.push(statement);
+        }
+        Ok(())
+    }
+
+    fn check_identifier(&self, ahead: usize) -> bool {
+        matches!(self.tokens.get(self.pos + ahead).map(|t| &t.kind), Some(TokenKind::Identifier(_)))
+    }
+
+    /// Parse `{ view* }`
+    fn parse_views(&mut self) -> ParseResult<Vec<View>> {
+        self.consume(TokenKind::LeftBrace)?;
+        let mut views = Vec::new();
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() {
+            views.push(self.parse_view()?);
+        }
+        self.consume(TokenKind::RightBrace)?;
+        Ok(views)
+    }
+
+    fn parse_view(&mut self) -> ParseResult<View> {
+        let start = self.offset();
+        if self.consume_if(TokenKind::If) {
+            let condition = self.parse_expression()?;
+            let then_branch = self.parse_views()?;
+            let else_branch = if !self.consume_if(TokenKind::Else) {
+                None
+            } else if self.check(TokenKind::If) {
+                Some(vec![self.parse_view()?])
+            } else {
+                Some(self.parse_views()?)
+            };
+            return Ok(View::If {
+                condition,
+                then_branch,
+                else_branch,
+                span: self.span_from(start),
+            });
+        }
+        if self.consume_if(TokenKind::For) {
+            let binding = self.consume_identifier()?;
+            let index = if self.consume_if(TokenKind::Comma) {
+                Some(self.consume_identifier()?)
+            } else {
+                None
+            };
+            self.consume_word("in")?;
+            let items = self.parse_expression()?;
+            let body = self.parse_views()?;
+            return Ok(View::For {
+                binding,
+                index,
+                items,
+                body,
+                span: self.span_from(start),
+            });
+        }
+
+        let name_span = self.peek_span();
+        let name = self.consume_identifier()?;
+        let args = if self.check(TokenKind::LeftParen) {
+            self.parse_view_arguments()?
+        } else {
+            Vec::new()
+        };
+        let children = if self.check(TokenKind::LeftBrace) {
+            self.parse_views()?
+        } else {
+            Vec::new()
+        };
+        let mut modifiers = Vec::new();
+        while self.check(TokenKind::Dot) {
+            let modifier_start = self.offset();
+            self.advance();
+            let name = self.consume_identifier()?;
+            let args = if self.check(TokenKind::LeftParen) {
+                self.parse_view_arguments()?
+            } else {
+                Vec::new()
+            };
+            modifiers.push(Modifier {
+                name,
+                args,
+                span: self.span_from(modifier_start),
+            });
+        }
+        Ok(View::Call {
+            name,
+            name_span,
+            args,
+            children,
+            modifiers,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parse `(arg, ...)`, where an argument may also be a closure written
+    /// `|| body` or `|a, b| body`
+    fn parse_view_arguments(&mut self) -> ParseResult<Vec<Expression>> {
+        self.consume(TokenKind::LeftParen)?;
+        let mut args = Vec::new();
+        while !self.check(TokenKind::RightParen) {
+            let arg = if self.check(TokenKind::Or) || self.check(TokenKind::Pipe) {
+                self.parse_closure()?
+            } else {
+                self.parse_expression()?
+            };
+            args.push(self.interpolate(arg)?);
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenKind::RightParen)?;
+        Ok(args)
+    }
+
+    fn parse_closure(&mut self) -> ParseResult<Expression> {
+        let start = self.offset();
+        let mut params = Vec::new();
+        if !self.consume_if(TokenKind::Or) {
+            self.consume(TokenKind::Pipe)?;
+            while !self.check(TokenKind::Pipe) {
+                let param_start = self.offset();
+                let name = self.consume_identifier()?;
+                let ty = self.parse_type_annotation()?;
+                params.push(Parameter {
+                    pattern: Pattern::Identifier(name),
+                    ty,
+                    default: None,
+                    span: self.span_from(param_start),
+                });
+                if !self.consume_if(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenKind::Pipe)?;
+        }
+        let body = self.parse_arrow_body()?;
+        Ok(Expression::new(
+            ExpressionKind::Arrow {
+                params,
+                return_type: None,
+                body,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    /// Turn `{expr}` in a string literal into a template
+    fn interpolate(&self, expr: Expression) -> ParseResult<Expression> {
+        let ExpressionKind::Literal(Literal::String(text)) = &expr.kind else {
+            return Ok(expr);
+        };
+        if !text.contains(['{', '}']) {
+            return Ok(expr);
+        }
+
+        // Spans inside the string are exact unless it has escape sequences
+        let base = expr.span.start + 1;
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut i = 0;
+        while let Some(found) = text[i..].find(['{', '}']) {
+            let at = i + found;
+            literal.push_str(&text[i..at]);
+            let brace = &text[at..at + 1];
+            if text[at + 1..].starts_with(brace) {
+                literal.push_str(brace);
+                i = at + 2;
+                continue;
+            }
+            let invalid = ParseError::InvalidExpression(Span::new(base + at, base + at + 1));
+            if brace == "}" {
+                return Err(invalid);
+            }
+            let len = closing_brace(&text[at + 1..]).ok_or(invalid)?;
+            let inner = self.parse_interpolated(&text[at + 1..at + 1 + len], base + at + 1)?;
+            if !literal.is_empty() {
+                parts.push(TemplatePart::String(std::mem::take(&mut literal)));
+            }
+            parts.push(TemplatePart::Expression(inner));
+            i = at + len + 2;
+        }
+        literal.push_str(&text[i..]);
+
+        if parts.is_empty() {
+            let kind = ExpressionKind::Literal(Literal::String(literal));
+            return Ok(Expression::new(kind, expr.span));
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::String(literal));
+        }
+        Ok(Expression::new(ExpressionKind::Template { parts }, expr.span))
+    }
+
+    /// Parse the expression of an interpolation, found at `offset` in the
+    /// source
+    fn parse_interpolated(&self, source: &str, offset: usize) -> ParseResult<Expression> {
+        let invalid = || ParseError::InvalidExpression(Span::new(offset, offset + source.len()));
+        let tokens = Lexer::new(source).tokenize().map_err(|_| invalid())?;
+        let mut parser = Parser::new(tokens, &self.file_name);
+        let mut expr = parser.parse_expression().map_err(|_| invalid())?;
+        if !parser.is_at_end() {
+            return Err(invalid());
+        }
+        expr.shift_spans(offset as isize);
+        Ok(expr)
+    }
+
+    fn lower_component(
+        &mut self,
+        declaration: ComponentDecl,
+        imports: &[Statement],
+        props: &ComponentProps,
+        components: &[String],
+    ) -> Component {
+        let states = declaration.states();
+        let children = self.lower_views(&declaration.view, props, &states);
+        let statements: Vec<Statement> = imports
+            .iter()
+            .cloned()
+            .chain(declaration.statements.iter().map(|statement| read_states_statement(statement, &states)))
+            .collect();
+
+        let script = Script {
+            imports: statements
+                .iter()
+                .filter_map(|statement| match &statement.kind {
+                    StatementKind::Import(import) => Some(import.clone()),
+                    _ => None,
+                })
+                .collect(),
+            exports: statements.iter().filter_map(export_of).collect(),
+            statements,
+        };
+        // Every component of the file may be imported by name
+        let exports = components
+            .iter()
+            .cloned()
+            .chain(script.exports.iter().map(|export| export.name.clone()))
+            .collect();
+        Component {
+            name: declaration.name,
+            props: declared_props(&script.statements),
+            script: Some(script),
+            template: Template {
+                canvas: CanvasNode::default(),
+                children,
+            },
+            style: None,
+            metadata: ComponentMetadata {
+                file_path: self.file_name.clone(),
+                hash: String::new(),
+                exports,
+            },
+        }
+    }
+
+    fn lower_views(
+        &mut self,
+        views: &[View],
+        props: &ComponentProps,
+        states: &HashSet<String>,
+    ) -> Vec<Node> {
+        views.iter().map(|view| self.lower_view(view, props, states)).collect()
+    }
+
+    fn lower_view(&mut self, view: &View, props: &ComponentProps, states: &HashSet<String>) -> Node {
+        match view {
+            View::If {
+                condition,
+                then_branch,
+                else_branch,
+                span,
+            } => Node::new(
+                NodeKind::IfBlock {
+                    condition: read_states(condition, states),
+                    then_branch: self.lower_views(then_branch, props, states),
+                    else_branch: else_branch
+                        .as_ref()
+                        .map(|branch| self.lower_views(branch, props, states)),
+                },
+                *span,
+            ),
+            View::For {
+                binding,
+                index,
+                items,
+                body,
+                span,
+            } => {
+                let inner = shadow(states, [binding.as_str()].into_iter().chain(index.as_deref()));
+                Node::new(
+                    NodeKind::EachBlock {
+                        expression: read_states(items, states),
+                        binding: binding.clone(),
+                        index: index.clone(),
+                        body: self.lower_views(body, props, &inner),
+                        key: None,
+                    },
+                    *span,
+                )
+            }
+            View::Call {
+                name,
+                args,
+                children,
+                modifiers,
+                span,
+                ..
+            } if name == BUTTON && !props.contains_key(BUTTON) => match button_view(args, children, modifiers, *span) {
+                Ok(view) => self.lower_view(&view, props, states),
+                Err(error) => {
+                    self.errors.push(error);
+                    Node::new(NodeKind::Error, *span)
+                }
+            },
+            View::Call {
+                name,
+                name_span,
+                args,
+                children,
+                modifiers,
+                span,
+            } => match lower_call(name, *name_span, args, modifiers, props, states) {
+                Ok((tag, attributes, directives, key)) => Node::new(
+                    NodeKind::Element {
+                        tag,
+                        attributes,
+                        children: self.lower_views(children, props, states),
+                        directives,
+                        key,
+                    },
+                    *span,
+                ),
+                Err(error) => {
+                    self.errors.push(error);
+                    Node::new(NodeKind::Error, *span)
+                }
+            },
+        }
+    }
+}
+
+type LoweredCall = (ElementTag, Vec<Attribute>, Vec<Directive>, Option<Expression>);
+
+/// Lower a view call to the tag, attributes, directives and key of an
+/// element
+fn lower_call(
+    name: &str,
+    name_span: Span,
+    args: &[Expression],
+    modifiers: &[Modifier],
+    props: &ComponentProps,
+    states: &HashSet<String>,
+) -> ParseResult<LoweredCall> {
+    // Modifiers may set any attribute of a component from another file
+    let (tag, positional, allowed) = match PRIMITIVES.iter().find(|(primitive, ..)| *primitive == name) {
+        Some((_, tag, positional, modifiers)) => {
+            let allowed = COMMON_MODIFIERS.iter().chain(*positional).chain(*modifiers).copied();
+            (tag.clone(), positional.to_vec(), Some(allowed.collect()))
+        }
+        None if name.starts_with(char::is_uppercase) => {
+            let props: Option<Vec<&str>> = props
+                .get(name)
+                .and_then(Option::as_ref)
+                .map(|props| props.iter().map(String::as_str).collect());
+            (ElementTag::Component(name.to_string()), props.clone().unwrap_or_default(), props)
+        }
+        None => return Err(ParseError::InvalidElementTag(name.to_string(), name_span)),
+    };
+    if let Some(extra) = args.get(positional.len()) {
+        return Err(ParseError::UnexpectedArgument(name.to_string(), extra.span));
+    }
+
+    let mut attributes: Vec<Attribute> = STACKS
+        .iter()
+        .filter(|(stack, _)| *stack == name)
+        .map(|(_, layout)| Attribute {
+            name: "layout".to_string(),
+            value: AttributeValue::Static(Literal::String(layout.to_string())),
+            span: name_span,
+        })
+        .collect();
+    attributes.extend(positional.iter().zip(args).map(|(name, arg)| Attribute {
+        name: name.to_string(),
+        value: attribute_value(read_states(arg, states)),
+        span: arg.span,
+    }));
+    let mut directives = Vec::new();
+    let mut key = None;
+    for modifier in modifiers {
+        if let Some(extra) = modifier.args.get(1) {
+            return Err(ParseError::UnexpectedArgument(modifier.name.clone(), extra.span));
+        }
+        let value = modifier.args.first().map(|arg| read_states(arg, states));
+        let event = modifier
+            .name
+            .strip_prefix("on")
+            .filter(|event| event.starts_with(char::is_uppercase));
+        if let Some(event) = event {
+            directives.push(Directive {
+                name: DirectiveName::On,
+                arg: Some(event.to_lowercase()),
+                value: value.ok_or(ParseError::InvalidAttribute(modifier.span))?,
+                modifiers: Vec::new(),
+                span: modifier.span,
+            });
+        } else if modifier.name == "key" {
+            // Like the `key` attribute, a key needs a value, and only one
+            // per element
+            if key.is_some() || value.is_none() {
+                return Err(ParseError::InvalidAttribute(modifier.span));
+            }
+            key = value;
+        } else {
+            let name = modifier_attribute(&modifier.name);
+            if allowed.as_ref().is_some_and(|allowed| !allowed.contains(&name)) {
+                return Err(ParseError::UnknownModifier(modifier.name.clone(), modifier.span));
+            }
+            attributes.push(Attribute {
+                name: name.to_string(),
+                value: value.map_or(AttributeValue::Boolean(true), attribute_value),
+                span: modifier.span,
+            });
+        }
+    }
+    Ok((tag, attributes, directives, key))
+}
+
+/// Attribute a modifier sets
+fn modifier_attribute(name: &str) -> &str {
+    MODIFIER_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, attribute)| attribute)
+}
+
+/// Desugar the built-in `Button(label)` to a row with a `Text` label
+/// before its children, sending the modifiers of the label's text to it
+fn button_view(args: &[Expression], children: &[View], modifiers: &[Modifier], span: Span) -> ParseResult<View> {
+    if let Some(extra) = args.get(1) {
+        return Err(ParseError::UnexpectedArgument(BUTTON.to_string(), extra.span));
+    }
+    let (label_modifiers, row_modifiers): (Vec<Modifier>, Vec<Modifier>) = modifiers
+        .iter()
+        .cloned()
+        .partition(|modifier| BUTTON_LABEL_MODIFIERS.contains(&modifier_attribute(&modifier.name)));
+
+    // Defaults come after the modifiers, which they don't override
+    let with_defaults = |mut modifiers: Vec<Modifier>, defaults: &[(&str, Literal)]| {
+        for (name, value) in defaults {
+            if !modifiers.iter().any(|modifier| modifier_attribute(&modifier.name) == *name) {
+                modifiers.push(Modifier {
+                    name: name.to_string(),
+                    args: vec![Expression::new(ExpressionKind::Literal(value.clone()), span)],
+                    span,
+                });
+            }
+        }
+        modifiers
+    };
+    let label = View::Call {
+        name: "Text".to_string(),
+        name_span: span,
+        args: args.to_vec(),
+        children: Vec::new(),
+        modifiers: with_defaults(
+            label_modifiers,
+            &[
+                ("fontSize", Literal::Number(16.0)),
+                ("fill", Literal::String("#ffffff".to_string())),
+            ],
+        ),
+        span,
+    };
+    Ok(View::Call {
+        name: "Row".to_string(),
+        name_span: span,
+        args: Vec::new(),
+        children: std::iter::once(label).chain(children.iter().cloned()).collect(),
+        modifiers: with_defaults(
+            row_modifiers,
+            &[
+                ("padding", Literal::Number(12.0)),
+                ("background", Literal::String("#6366f1".to_string())),
+                ("cornerRadius", Literal::Number(6.0)),
+            ],
+        ),
+        span,
+    })
+}
+
+/// Literal arguments are static attribute values
+fn attribute_value(expr: Expression) -> AttributeValue {
+    match expr.kind {
+        ExpressionKind::Literal(literal) => AttributeValue::Static(literal),
+        _ => AttributeValue::Dynamic(expr),
+    }
+}
+
+/// Length of the text up to the `}` closing an interpolation, skipping
+/// nested braces and strings
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') if depth == 0 => return Some(i),
+            (None, '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `states` without the names a nested scope declares
+fn shadow<'a>(states: &HashSet<String>, names: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+    let mut inner = states.clone();
+    for name in names {
+        inner.remove(name);
+    }
+    inner
+}
+
+/// Call reading the signal of the state `name`
+fn read_state(name: &str, span: Span) -> Expression {
+    Expression::new(
+        ExpressionKind::Call {
+            callee: Box::new(Expression::new(ExpressionKind::Identifier(name.to_string()), span)),
+            type_args: Vec::new(),
+            args: Vec::new(),
+            optional: false,
+        },
+        span,
+    )
+}
+
+/// Call setting the signal of the state `name` to `value`
+fn write_state(name: &str, value: Expression, span: Span) -> Expression {
+    let callee = ExpressionKind::Member {
+        object: Box::new(Expression::new(ExpressionKind::Identifier(name.to_string()), span)),
+        property: "set".to_string(),
+        computed: false,
+        optional: false,
+    };
+    Expression::new(
+        ExpressionKind::Call {
+            callee: Box::new(Expression::new(callee, span)),
+            type_args: Vec::new(),
+            args: vec![value],
+            optional: false,
+        },
+        span,
+    )
+}
+
+/// Rewrite reads of the `states` into signal calls and assignments to
+/// them into `set` calls
+fn read_states(expr: &Expression, states: &HashSet<String>) -> Expression {
+    let go = |expr: &Expression| Box::new(read_states(expr, states));
+    let state = |target: &Expression| match &target.kind {
+        ExpressionKind::Identifier(name) if states.contains(name) => Some(name.clone()),
+        _ => None,
+    };
+    let kind = match &expr.kind {
+        ExpressionKind::Identifier(name) if states.contains(name) => return read_state(name, expr.span),
+        ExpressionKind::Assign { op, target, value } if let Some(name) = state(target) => {
+            let value = read_states(value, states);
+            let op = match op {
+                AssignOp::Assign => return write_state(&name, value, expr.span),
+                AssignOp::Add => BinaryOp::Add,
+                AssignOp::Sub => BinaryOp::Sub,
+                AssignOp::Mul => BinaryOp::Mul,
+                AssignOp::Div => BinaryOp::Div,
+                AssignOp::Mod => BinaryOp::Mod,
+            };
+            return write_state(&name, binary(read_state(&name, target.span), op, value), expr.span);
+        }
+        ExpressionKind::Update { op, target, .. } if let Some(name) = state(target) => {
+            let one = Expression::new(ExpressionKind::Literal(Literal::Number(1.0)), expr.span);
+            let op = match op {
+                UpdateOp::Increment => BinaryOp::Add,
+                UpdateOp::Decrement => BinaryOp::Sub,
+            };
+            return write_state(&name, binary(read_state(&name, target.span), op, one), expr.span);
+        }
+        ExpressionKind::Identifier(_) | ExpressionKind::Literal(_) | ExpressionKind::Error => expr.kind.clone(),
+        ExpressionKind::Binary { left, op, right } => ExpressionKind::Binary {
+            left: go(left),
+            op: *op,
+            right: go(right),
+        },
+        ExpressionKind::Unary { op, operand } => ExpressionKind::Unary {
+            op: *op,
+            operand: go(operand),
+        },
+        ExpressionKind::Assign { op, target, value } => ExpressionKind::Assign {
+            op: *op,
+            target: go(target),
+            value: go(value),
+        },
+        ExpressionKind::Update { op, prefix, target } => ExpressionKind::Update {
+            op: *op,
+            prefix: *prefix,
+            target: go(target),
+        },
+        ExpressionKind::Call {
+            callee,
+            type_args,
+            args,
+            optional,
+        } => ExpressionKind::Call {
+            callee: go(callee),
+            type_args: type_args.clone(),
+            args: args.iter().map(|arg| read_states(arg, states)).collect(),
+            optional: *optional,
+        },
+        ExpressionKind::Member {
+            object,
+            property,
+            computed,
+            optional,
+        } => ExpressionKind::Member {
+            object: go(object),
+            property: property.clone(),
+            computed: *computed,
+            optional: *optional,
+        },
+        ExpressionKind::Index { object, index, optional } => ExpressionKind::Index {
+            object: go(object),
+            index: go(index),
+            optional: *optional,
+        },
+        ExpressionKind::Arrow { params, return_type, body } => {
+            let inner = shadow(states, params.iter().flat_map(|param| param.pattern.bound_names()));
+            ExpressionKind::Arrow {
+                params: params.clone(),
+                return_type: return_type.clone(),
+                body: match body {
+                    ArrowBody::Expression(body) => ArrowBody::Expression(Box::new(read_states(body, &inner))),
+                    ArrowBody::Block(body) => ArrowBody::Block(read_states_block(body, &inner)),
+                },
+            }
+        }
+        ExpressionKind::Ternary {
+            condition,
+            then_expr,
+            else_expr,
+        } => ExpressionKind::Ternary {
+            condition: go(condition),
+            then_expr: go(then_expr),
+            else_expr: go(else_expr),
+        },
+        ExpressionKind::Template { parts } => ExpressionKind::Template {
+            parts: parts
+                .iter()
+                .map(|part| match part {
+                    TemplatePart::String(text) => TemplatePart::String(text.clone()),
+                    TemplatePart::Expression(expr) => TemplatePart::Expression(read_states(expr, states)),
+                })
+                .collect(),
+        },
+        ExpressionKind::Array(elements) => {
+            ExpressionKind::Array(elements.iter().map(|element| read_states(element, states)).collect())
+        }
+        ExpressionKind::Object(properties) => ExpressionKind::Object(
+            properties
+                .iter()
+                .map(|property| property.map(|expr| read_states(expr, states)))
+                .collect(),
+        ),
+        ExpressionKind::Spread(inner) => ExpressionKind::Spread(go(inner)),
+    };
+    Expression::new(kind, expr.span)
+}
+
+/// Rewrite the states in a block, where declarations shadow them for the
+/// statements that follow
+fn read_states_block(statements: &[Statement], states: &HashSet<String>) -> Vec<Statement> {
+    let mut states = states.clone();
+    statements
+        .iter()
+        .map(|statement| {
+            let rewritten = read_states_statement(statement, &states);
+            match &statement.kind {
+                StatementKind::VariableDeclaration { name, .. } | StatementKind::FunctionDeclaration { name, .. } => {
+                    states.remove(name);
+                }
+                StatementKind::Destructure { pattern, .. } => {
+                    for name in pattern.bound_names() {
+                        states.remove(name);
+                    }
+                }
+                _ => {}
+            }
+            rewritten
+        })
+        .collect()
+}
+
+fn read_states_statement(statement: &Statement, states: &HashSet<String>) -> Statement {
+    let expr = |expr: &Expression| read_states(expr, states);
+    let block = |statements: &[Statement]| read_states_block(statements, states);
+    let kind = match &statement.kind {
+        StatementKind::VariableDeclaration {
+            kind,
+            name,
+            ty,
+            init,
+            reactive,
+        } => StatementKind::VariableDeclaration {
+            kind: *kind,
+            name: name.clone(),
+            ty: ty.clone(),
+            init: init.as_ref().map(expr),
+            reactive: *reactive,
+        },
+        StatementKind::FunctionDeclaration {
+            name,
+            params,
+            return_type,
+            body,
+            is_async,
+        } => StatementKind::FunctionDeclaration {
+            name: name.clone(),
+            params: params.clone(),
+            return_type: return_type.clone(),
+            body: read_states_block(
+                body,
+                &shadow(states, params.iter().flat_map(|param| param.pattern.bound_names())),
+            ),
+            is_async: *is_async,
+        },
+        StatementKind::Expression(e) => StatementKind::Expression(expr(e)),
+        StatementKind::Return(value) => StatementKind::Return(value.as_ref().map(expr)),
+        StatementKind::Destructure { kind, pattern, init } => StatementKind::Destructure {
+            kind: *kind,
+            pattern: pattern.clone(),
+            init: expr(init),
+        },
+        StatementKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => StatementKind::If {
+            condition: expr(condition),
+            then_branch: block(then_branch),
+            else_branch: else_branch.as_deref().map(block),
+        },
+        StatementKind::For {
+            init,
+            condition,
+            update,
+            body,
+        } => {
+            // A variable declared in the header is in scope for the rest of
+            // the loop
+            let init = init.as_deref().map(|init| read_states_statement(init, states));
+            let inner = match init.as_ref().map(|init| &init.kind) {
+                Some(StatementKind::VariableDeclaration { name, .. }) => shadow(states, [name.as_str()]),
+                _ => states.clone(),
+            };
+            StatementKind::For {
+                init: init.map(Box::new),
+                condition: condition.as_ref().map(|e| read_states(e, &inner)),
+                update: update.as_ref().map(|e| read_states(e, &inner)),
+                body: read_states_block(body, &inner),
+            }
+        }
+        StatementKind::While { condition, body } => StatementKind::While {
+            condition: expr(condition),
+            body: block(body),
+        },
+        StatementKind::Block(body) => StatementKind::Block(block(body)),
+        StatementKind::Export(declaration) => {
+            StatementKind::Export(Box::new(read_states_statement(declaration, states)))
+        }
+        StatementKind::Import(_) | StatementKind::Break | StatementKind::Continue | StatementKind::Error => {
+            statement.kind.clone()
+        }
+    };
+    Statement::new(kind, statement.span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> ParseResult<Vec<Component>> {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::new(tokens, "Counter.omni").parse_components()
+    }
+
+    #[test]
+    fn test_parse_state_and_render() {
+        let source = r##"
+component Counter {
+    state count = 0
+    const step = 2
+
+    render {
+        Column {
+            Text("Count: {count}").fontSize(24).color("#6366f1")
+            Circle(count * 2).onClick(|| count += step).key("dot")
+            Text(count < 3 ? "small" : "large")
+            Text("{count < step}")
+        }
+        .gap(16)
+    }
+}
+"##;
+        let components = parse(source).unwrap();
+        assert_eq!(components.len(), 1);
+        let component = &components[0];
+        assert_eq!(component.name, "Counter");
+
+        let statements = &component.script.as_ref().unwrap().statements;
+        assert!(matches!(
+            &statements[0].kind,
+            StatementKind::VariableDeclaration { name, reactive: ReactiveKind::Signal, .. } if name == "count"
+        ));
+
+        let NodeKind::Element { tag: ElementTag::Group, attributes, children, .. } = &component.template.children[0].kind
+        else {
+            panic!("expected column group");
+        };
+        let names: Vec<&str> = attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["layout", "gap"]);
+        assert_eq!(attributes[0].value, AttributeValue::Static(Literal::String("column".to_string())));
+
+        let NodeKind::Element { tag: ElementTag::Text, attributes, .. } = &children[0].kind else {
+            panic!("expected text");
+        };
+        let names: Vec<&str> = attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["content", "fontSize", "fill"]);
+        let AttributeValue::Dynamic(Expression { kind: ExpressionKind::Template { parts }, .. }) = &attributes[0].value
+        else {
+            panic!("expected interpolated content");
+        };
+        assert_eq!(parts[0], TemplatePart::String("Count: ".to_string()));
+        let TemplatePart::Expression(read) = &parts[1] else {
+            panic!("expected interpolation");
+        };
+        assert!(matches!(&read.kind, ExpressionKind::Call { args, .. } if args.is_empty()));
+        assert_eq!(&source[read.span.start..read.span.end], "count");
+
+        let NodeKind::Element { tag: ElementTag::Circle, directives, key: Some(_), .. } = &children[1].kind else {
+            panic!("expected keyed circle");
+        };
+        assert_eq!(directives[0].name, DirectiveName::On);
+        assert_eq!(directives[0].arg.as_deref(), Some("click"));
+        let ExpressionKind::Arrow { body: ArrowBody::Expression(body), .. } = &directives[0].value.kind else {
+            panic!("expected closure");
+        };
+        let ExpressionKind::Call { callee, args, .. } = &body.kind else {
+            panic!("expected state write");
+        };
+        assert!(matches!(&callee.kind, ExpressionKind::Member { property, .. } if property == "set"));
+        assert!(matches!(&args[0].kind, ExpressionKind::Binary { op: BinaryOp::Add, .. }));
+
+        // `<` in arguments and interpolations is a comparison
+        let NodeKind::Element { tag: ElementTag::Text, attributes, .. } = &children[2].kind else {
+            panic!("expected text");
+        };
+        let AttributeValue::Dynamic(Expression { kind: ExpressionKind::Ternary { condition, .. }, .. }) =
+            &attributes[0].value
+        else {
+            panic!("expected conditional content");
+        };
+        assert!(matches!(&condition.kind, ExpressionKind::Binary { op: BinaryOp::Lt, .. }));
+        let NodeKind::Element { tag: ElementTag::Text, attributes, .. } = &children[3].kind else {
+            panic!("expected text");
+        };
+        let AttributeValue::Dynamic(Expression { kind: ExpressionKind::Template { parts }, .. }) = &attributes[0].value
+        else {
+            panic!("expected interpolated content");
+        };
+        assert!(matches!(&parts[0], TemplatePart::Expression(e) if matches!(e.kind, ExpressionKind::Binary { op: BinaryOp::Lt, .. })));
+    }
+
+    #[test]
+    fn test_parse_multiple_components() {
+        let source = r##"
+import { clamp } from "./math.omni"
+
+component Badge {
+    prop label: string
+    prop size = 12
+
+    render {
+        Text(label).fontSize(size)
+    }
+}
+
+component Counter {
+    state items = [1, 2]
+
+    render {
+        for item, i in items {
+            Badge("#{i}", item)
+        }
+        if items.length > 1 {
+            Rect(10, 10)
+        } else {
+            Circle(5)
+        }
+    }
+}
+"##;
+        let components = parse(source).unwrap();
+        let names: Vec<&str> = components.iter().map(|c| c.name.as_str()).collect();
+        // The component named after the file comes first
+        assert_eq!(names, ["Counter", "Badge"]);
+        assert_eq!(components[1].props.len(), 2);
+        assert_eq!(components[0].metadata.exports, ["Counter", "Badge"]);
+        for component in &components {
+            assert_eq!(component.script.as_ref().unwrap().imports[0].source, "./math.omni");
+        }
+
+        let NodeKind::EachBlock { expression, binding, index, body, .. } = &components[0].template.children[0].kind
+        else {
+            panic!("expected each block");
+        };
+        assert!(matches!(&expression.kind, ExpressionKind::Call { .. }));
+        assert_eq!((binding.as_str(), index.as_deref()), ("item", Some("i")));
+        let NodeKind::Element { tag: ElementTag::Component(name), attributes, .. } = &body[0].kind else {
+            panic!("expected component");
+        };
+        assert_eq!(name, "Badge");
+        let names: Vec<&str> = attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["label", "size"]);
+        assert!(matches!(&components[0].template.children[1].kind, NodeKind::IfBlock { else_branch: Some(_), .. }));
+    }
+
+    #[test]
+    fn test_state_shadowing() {
+        let source = r#"
+component Counter {
+    state count = 0
+
+    function reset(count) {
+        count = 1
+    }
+
+    render {
+        Text("{{count}}").onClick(|event| { let count = event; count++ })
+    }
+}
+"#;
+        let components = parse(source).unwrap();
+        let statements = &components[0].script.as_ref().unwrap().statements;
+        let StatementKind::FunctionDeclaration { body, .. } = &statements[1].kind else {
+            panic!("expected function");
+        };
+        assert!(matches!(
+            &body[0].kind,
+            StatementKind::Expression(Expression { kind: ExpressionKind::Assign { .. }, .. })
+        ));
+
+        let NodeKind::Element { attributes, directives, .. } = &components[0].template.children[0].kind else {
+            panic!("expected text");
+        };
+        assert_eq!(
+            attributes[0].value,
+            AttributeValue::Static(Literal::String("{count}".to_string()))
+        );
+        let ExpressionKind::Arrow { body: ArrowBody::Block(body), .. } = &directives[0].value.kind else {
+            panic!("expected closure");
+        };
+        assert!(matches!(
+            &body[1].kind,
+            StatementKind::Expression(Expression { kind: ExpressionKind::Update { .. }, .. })
+        ));
+    }
+
+    #[test]
+    fn test_builtin_button() {
+        let source = r##"
+component Counter {
+    state count = 0
+
+    render {
+        Button("Count: {count}")
+            .onClick(|| count += 1)
+            .padding(16)
+            .backgroundColor("#4f46e5")
+            .color("#000000")
+    }
+}
+"##;
+        let components = parse(source).unwrap();
+        let NodeKind::Element { tag: ElementTag::Group, attributes, children, directives, .. } =
+            &components[0].template.children[0].kind
+        else {
+            panic!("expected button row");
+        };
+        let names: Vec<&str> = attributes.iter().map(|a| a.name.as_str()).collect();
+        // Defaults fill in what the modifiers don't set
+        assert_eq!(names, ["layout", "padding", "background", "cornerRadius"]);
+        assert_eq!(attributes[1].value, AttributeValue::Static(Literal::Number(16.0)));
+        assert_eq!(directives[0].arg.as_deref(), Some("click"));
+
+        let NodeKind::Element { tag: ElementTag::Text, attributes, .. } = &children[0].kind else {
+            panic!("expected label");
+        };
+        let names: Vec<&str> = attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["content", "fill", "fontSize"]);
+        assert_eq!(attributes[1].value, AttributeValue::Static(Literal::String("#000000".to_string())));
+
+        // A component named `Button` replaces the built-in one
+        let source = r#"
+component Counter {
+    render {
+        Button("Go").size(2)
+    }
+}
+
+component Button {
+    prop label = ""
+    prop size = 1
+
+    render {
+        Text(label).fontSize(size)
+    }
+}
+"#;
+        let components = parse(source).unwrap();
+        let NodeKind::Element { tag: ElementTag::Component(name), attributes, .. } =
+            &components[0].template.children[0].kind
+        else {
+            panic!("expected component");
+        };
+        assert_eq!(name, "Button");
+        assert_eq!(attributes.len(), 2);
+
+        let components = parse(r#"import { Button } from "./ui.omni" component A { render { Button().tone(1) } }"#).unwrap();
+        assert!(matches!(
+            &components[0].template.children[0].kind,
+            NodeKind::Element { tag: ElementTag::Component(name), .. } if name == "Button"
+        ));
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        assert!(matches!(
+            parse("component A { render { Circle(1, 2) } }"),
+            Err(ParseError::UnexpectedArgument(name, _)) if name == "Circle"
+        ));
+        assert!(matches!(
+            parse("component A { render { Rect().fill(1, 2) } }"),
+            Err(ParseError::UnexpectedArgument(name, _)) if name == "fill"
+        ));
+        assert!(matches!(
+            parse("component A { render { Rect(10, 10).cornerRadius(4) } }"),
+            Err(ParseError::UnknownModifier(name, _)) if name == "cornerRadius"
+        ));
+        assert!(parse("component A { render { B().color(1) } } component B { prop fill = 0 render { } }").is_ok());
+        assert!(matches!(
+            parse("component A { render { B().size(1) } } component B { render { } }"),
+            Err(ParseError::UnknownModifier(name, _)) if name == "size"
+        ));
+        assert!(matches!(
+            parse("component A { render { Button().stroke(1) } }"),
+            Err(ParseError::UnknownModifier(name, _)) if name == "stroke"
+        ));
+        assert!(matches!(
+            parse("component A { render { circle() } }"),
+            Err(ParseError::InvalidElementTag(..))
+        ));
+        assert!(matches!(
+            parse(r#"component A { render { Text("{1 +}") } }"#),
+            Err(ParseError::InvalidExpression(_))
+        ));
+
+        // Errors in one component don't stop the next from parsing
+        let tokens = Lexer::new("component A { state count 1 } component B { render { Group() } }").tokenize().unwrap();
+        let (components, errors) = Parser::new(tokens, "A.omni").parse_components_with_recovery();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[1].template.children.len(), 1);
+    }
+}
@@ -0,0 +1,3746 @@
+//! Parser for `.omni` files
+//!
+//! Recursive descent parser that converts tokens into AST.
+//!
+//! Errors are recovered in panic mode: the parser records the error, skips
+//! to the next `;`, `}`, `</` or `<` and leaves an `Error` placeholder in
+//! the AST, so one parse reports every error in the file.
+
+use crate::ast::*;
+use crate::cst::{CstBuilder, GreenNode, SyntaxKind};
+use crate::lexer::{Span, Token, TokenKind};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{instrument, trace};
+
+mod builder;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ParseError {
+    #[error("Unexpected token: expected {expected}, found {found} at position {}", span.start)]
+    UnexpectedToken {
+        expected: String,
+        found: String,
+        span: Span,
+    },
+
+    #[error("Unexpected end of input")]
+    UnexpectedEof(Span),
+
+    #[error("Invalid element tag: {0}")]
+    InvalidElementTag(String, Span),
+
+    /// Span of the opening tag
+    #[error("Missing closing tag for <{0}>")]
+    MissingClosingTag(String, Span),
+
+    /// Span of the opening block tag
+    #[error("Missing {{/{0}}} for {{#{0}}} block")]
+    UnclosedBlock(String, Span),
+
+    #[error("Invalid attribute syntax")]
+    InvalidAttribute(Span),
+
+    #[error("Invalid expression")]
+    InvalidExpression(Span),
+
+    #[error("Invalid assignment target")]
+    InvalidAssignmentTarget(Span),
+
+    #[error("Unknown modifier `{0}`")]
+    UnknownModifier(String, Span),
+
+    /// Span of the first argument too many
+    #[error("Unexpected argument for `{0}`")]
+    UnexpectedArgument(String, Span),
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof(span)
+            | ParseError::InvalidElementTag(_, span)
+            | ParseError::MissingClosingTag(_, span)
+            | ParseError::UnclosedBlock(_, span)
+            | ParseError::InvalidAttribute(span)
+            | ParseError::InvalidExpression(span)
+            | ParseError::InvalidAssignmentTarget(span)
+            | ParseError::UnknownModifier(_, span)
+            | ParseError::UnexpectedArgument(_, span) => *span,
+        }
+    }
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+/// Modifiers accepted by event directives
+const EVENT_MODIFIERS: [&str; 4] = ["once", "stop", "prevent", "self"];
+
+/// Parser for `.omni` files
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    file_name: String,
+    /// Subtrees from a previous parse that may be reused
+    reuse: SubtreeCache,
+    /// Subtrees recorded during this parse
+    recorded: SubtreeCache,
+    /// Concrete syntax tree being built, if requested
+    cst: Option<CstBuilder>,
+    /// Errors recovered from so far
+    errors: Vec<ParseError>,
+    /// Names of the elements whose children are being parsed
+    open_tags: Vec<String>,
+}
+
+/// Top-level script statements and canvas children of a parse, each with
+/// the range of token indices it was parsed from
+#[derive(Debug, Clone, Default)]
+pub struct SubtreeCache {
+    statements: Vec<(Range<usize>, Option<Statement>)>,
+    nodes: Vec<(Range<usize>, Option<Node>)>,
+}
+
+impl SubtreeCache {
+    /// Update token ranges after the tokens in `old` were replaced by
+    /// `new_len` tokens, dropping subtrees that may be affected
+    pub fn splice(&mut self, old: Range<usize>, new_len: usize) {
+        let delta = new_len as isize - old.len() as isize;
+        splice_entries(&mut self.statements, &old, delta);
+        splice_entries(&mut self.nodes, &old, delta);
+    }
+
+    pub fn len(&self) -> usize {
+        self.statements.len() + self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn splice_entries<T>(entries: &mut Vec<(Range<usize>, Option<T>)>, old: &Range<usize>, delta: isize) {
+    // A subtree may have peeked at the token after its end, so that one
+    // has to be unchanged too
+    entries.retain(|(range, _)| range.end < old.start || range.start >= old.end);
+    for (range, _) in entries.iter_mut() {
+        if range.start >= old.end {
+            *range = range.start.saturating_add_signed(delta)..range.end.saturating_add_signed(delta);
+        }
+    }
+}
+
+/// Take the cached subtree starting at token `pos`, if any
+fn take_cached<T>(entries: &mut [(Range<usize>, Option<T>)], pos: usize) -> Option<(usize, T)> {
+    let i = entries.binary_search_by_key(&pos, |(range, _)| range.start).ok()?;
+    let (range, value) = &mut entries[i];
+    value.take().map(|value| (range.end, value))
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>, file_name: &str) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            file_name: file_name.to_string(),
+            reuse: SubtreeCache::default(),
+            recorded: SubtreeCache::default(),
+            cst: None,
+            errors: Vec::new(),
+            open_tags: Vec::new(),
+        }
+    }
+
+    /// Also build a lossless CST of `source`, the text the tokens came from
+    ///
+    /// Cached subtrees are not reused while building a CST, since they
+    /// carry no syntax nodes.
+    pub fn with_cst(mut self, source: &str) -> Self {
+        self.cst = Some(CstBuilder::new(source));
+        self
+    }
+
+    /// Finish the CST after parsing; tokens the parse did not consume end
+    /// up in an `Error` node
+    pub fn finish_cst(&mut self) -> Option<Arc<GreenNode>> {
+        let builder = self.cst.take()?;
+        Some(Arc::new(builder.finish(&self.tokens[self.pos.min(self.tokens.len())..])))
+    }
+
+    /// Reuse subtrees from a previous parse whose tokens are unchanged
+    pub fn with_cache(mut self, cache: SubtreeCache) -> Self {
+        self.reuse = cache;
+        self
+    }
+
+    /// Give back the tokens and the subtrees recorded by the last parse
+    pub fn into_parts(self) -> (Vec<Token>, SubtreeCache) {
+        (self.tokens, self.recorded)
+    }
+
+    /// Parse the entire component, failing with the first error
+    pub fn parse(&mut self) -> ParseResult<Component> {
+        let (component, errors) = self.parse_with_recovery();
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(component),
+        }
+    }
+
+    /// Parse the entire component, recovering from errors
+    ///
+    /// Returns the component, with `Error` placeholders where parsing
+    /// failed, and every error in source order. Of a file with several
+    /// components, this is the one named after the file, or else the first.
+    pub fn parse_with_recovery(&mut self) -> (Component, Vec<ParseError>) {
+        let (components, errors) = self.parse_components_with_recovery();
+        let component = components.into_iter().next().unwrap_or_else(|| Component {
+            name: self.infer_component_name(),
+            props: Vec::new(),
+            script: None,
+            template: Template {
+                canvas: CanvasNode::default(),
+                children: Vec::new(),
+            },
+            style: None,
+            metadata: ComponentMetadata {
+                file_path: self.file_name.clone(),
+                ..Default::default()
+            },
+        });
+        (component, errors)
+    }
+
+    /// Parse every component of the file, failing with the first error
+    pub fn parse_components(&mut self) -> ParseResult<Vec<Component>> {
+        let (components, errors) = self.parse_components_with_recovery();
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(components),
+        }
+    }
+
+    /// Parse every component of the file, recovering from errors
+    ///
+    /// A file of `<script>`, `<canvas>` and `<style>` sections is one
+    /// component; a builder DSL file declares any number of them, and the
+    /// one named after the file, or else the first, comes first.
+    #[instrument(skip(self), fields(file = %self.file_name))]
+    pub fn parse_components_with_recovery(&mut self) -> (Vec<Component>, Vec<ParseError>) {
+        trace!("Starting parse");
+        let components = if self.at_builder_file() {
+            self.parse_builder_file()
+        } else {
+            vec![self.parse_sections()]
+        };
+        trace!(errors = self.errors.len(), "Parse complete");
+        (components, std::mem::take(&mut self.errors))
+    }
+
+    /// Parse the `<script>`, `<canvas>` and `<style>` sections of a
+    /// component
+    fn parse_sections(&mut self) -> Component {
+        let name = self.infer_component_name();
+
+        let mut script = None;
+        let mut canvas = None;
+        let mut style = None;
+        while !self.is_at_end() {
+            if self.check_tag(TokenKind::LessThan, "script") {
+                if let Ok(section) = self.recover(Sync::Node, Self::parse_script_section) {
+                    script = section;
+                }
+            } else if self.check_tag(TokenKind::LessThan, "canvas") {
+                if let Ok(section) = self.recover(Sync::Node, Self::parse_canvas_section) {
+                    canvas = Some(section);
+                }
+            } else if self.check_tag(TokenKind::LessThan, "style") {
+                if let Ok(section) = self.recover(Sync::Node, Self::parse_style_section) {
+                    style = section;
+                }
+            } else {
+                let _ = self.recover(Sync::Node, |p| {
+                    Err::<(), _>(p.unexpected("<script>, <canvas> or <style>"))
+                });
+            }
+        }
+        let (canvas, children) = canvas.unwrap_or_default();
+
+        let props = script
+            .as_ref()
+            .map_or_else(Vec::new, |script| declared_props(&script.statements));
+        let exports = script
+            .as_ref()
+            .map_or_else(Vec::new, |script| script.exports.iter().map(|e| e.name.clone()).collect());
+        Component {
+            name,
+            props,
+            script,
+            template: Template { canvas, children },
+            style,
+            metadata: ComponentMetadata {
+                file_path: self.file_name.clone(),
+                hash: String::new(),
+                exports,
+            },
+        }
+    }
+
+    fn infer_component_name(&self) -> String {
+        self.file_name
+            .split('/')
+            .next_back()
+            .unwrap_or("Component")
+            .trim_end_matches(".omni")
+            .to_string()
+    }
+
+    // ========================================================================
+    // Script Section
+    // ========================================================================
+
+    #[instrument(skip(self))]
+    fn parse_script_section(&mut self) -> ParseResult<Option<Script>> {
+        if !self.check_tag(TokenKind::LessThan, "script") {
+            return Ok(None);
+        }
+
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::LessThan)?;
+        self.consume_word("script")?;
+        self.consume(TokenKind::GreaterThan)?;
+        let open = self.span_from(start);
+
+        let mut statements = Vec::new();
+        while !self.check_tag(TokenKind::ClosingTag, "script") {
+            if self.is_at_end() || self.at_section_start() {
+                self.errors
+                    .push(ParseError::MissingClosingTag("script".to_string(), open));
+                break;
+            }
+            statements.push(self.recover_statement(Self::parse_cached_statement));
+        }
+
+        self.consume_closing_tag("script");
+        self.cst_wrap(cp, SyntaxKind::ScriptSection);
+
+        let imports = statements
+            .iter()
+            .filter_map(|statement| match &statement.kind {
+                StatementKind::Import(import) => Some(import.clone()),
+                _ => None,
+            })
+            .collect();
+        let exports = statements.iter().filter_map(export_of).collect();
+        Ok(Some(Script {
+            statements,
+            imports,
+            exports,
+        }))
+    }
+
+    /// Parse a top-level statement, reusing a cached one when possible
+    ///
+    /// Statements that needed error recovery are not cached, so their
+    /// errors are reported again on the next parse.
+    fn parse_cached_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.pos;
+        let errors = self.errors.len();
+        let cached = match self.cst {
+            Some(_) => None,
+            None => take_cached(&mut self.reuse.statements, start),
+        };
+        let statement = match cached {
+            Some((end, mut statement)) => {
+                statement.shift_spans(self.tokens[start].span.start as isize - statement.span.start as isize);
+                self.pos = end;
+                statement
+            }
+            None => self.parse_statement()?,
+        };
+        if self.errors.len() == errors {
+            self.recorded
+                .statements
+                .push((start..self.pos, Some(statement.clone())));
+        }
+        Ok(statement)
+    }
+
+    fn parse_statement(&mut self) -> ParseResult<Statement> {
+        match self.peek_kind() {
+            Some(TokenKind::Const) | Some(TokenKind::Let) => self.parse_variable_declaration(),
+            Some(TokenKind::Function) => self.parse_function_declaration(),
+            Some(TokenKind::Identifier(word)) if word == "export" => {
+                match self.tokens.get(self.pos + 1).map(|t| &t.kind) {
+                    Some(TokenKind::Const | TokenKind::Let) => self.parse_variable_declaration(),
+                    Some(TokenKind::Function) => self.parse_function_declaration(),
+                    _ => {
+                        self.advance();
+                        Err(self.unexpected("declaration after `export`"))
+                    }
+                }
+            }
+            Some(TokenKind::Identifier(word)) if word == "import" => self.parse_import_declaration(),
+            Some(TokenKind::If) => self.parse_if_statement(),
+            Some(TokenKind::For) => self.parse_for_statement(),
+            Some(TokenKind::While) => self.parse_while_statement(),
+            Some(TokenKind::Return) => self.parse_return_statement(),
+            Some(TokenKind::Break) => self.parse_jump_statement(TokenKind::Break),
+            Some(TokenKind::Continue) => self.parse_jump_statement(TokenKind::Continue),
+            Some(TokenKind::LeftBrace) => {
+                let start = self.offset();
+                let body = self.parse_block()?;
+                Ok(Statement::new(StatementKind::Block(body), self.span_from(start)))
+            }
+            _ => {
+                let cp = self.cst_start();
+                let start = self.offset();
+                let expr = self.parse_expression()?;
+                self.consume_if(TokenKind::Semicolon);
+                self.cst_wrap(cp, SyntaxKind::ExprStmt);
+                Ok(Statement::new(StatementKind::Expression(expr), self.span_from(start)))
+            }
+        }
+    }
+
+    fn parse_variable_declaration(&mut self) -> ParseResult<Statement> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        let exported = self.consume_export();
+        let mut statement = self.parse_variable_binding()?;
+        self.consume_if(TokenKind::Semicolon);
+        self.cst_wrap(cp, SyntaxKind::VariableDecl);
+        statement.span = self.span_from(statement.span.start);
+        Ok(self.exported(statement, exported, start))
+    }
+
+    /// Consume `export` before a declaration, returning whether it was there
+    fn consume_export(&mut self) -> bool {
+        let exported = self.check_text(0, "export");
+        if exported {
+            self.advance();
+        }
+        exported
+    }
+
+    /// Wrap a declaration that followed `export`
+    fn exported(&self, declaration: Statement, exported: bool, start: usize) -> Statement {
+        if exported {
+            Statement::new(StatementKind::Export(Box::new(declaration)), self.span_from(start))
+        } else {
+            declaration
+        }
+    }
+
+    /// Parse `const name = init` or `let name = init` without the `;`
+    fn parse_variable_binding(&mut self) -> ParseResult<Statement> {
+        let start = self.offset();
+        let kind = if self.consume_if(TokenKind::Const) {
+            VarKind::Const
+        } else {
+            self.consume(TokenKind::Let)?;
+            VarKind::Let
+        };
+
+        if self.check(TokenKind::LeftBrace) || self.check(TokenKind::LeftBracket) {
+            let pattern = self.parse_pattern()?;
+            self.consume(TokenKind::Equals)?;
+            let init = self.parse_expression()?;
+            return Ok(Statement::new(
+                StatementKind::Destructure { kind, pattern, init },
+                self.span_from(start),
+            ));
+        }
+
+        let name = self.consume_identifier()?;
+        let ty = self.parse_type_annotation()?;
+
+        // Only `let` may leave out the initializer
+        let init = if kind == VarKind::Let && !self.check(TokenKind::Equals) {
+            None
+        } else {
+            self.consume(TokenKind::Equals)?;
+            Some(self.parse_expression()?)
+        };
+
+        // Check for reactive types
+        let reactive = init
+            .as_ref()
+            .map_or(ReactiveKind::None, |init| self.detect_reactive_kind(init));
+
+        Ok(Statement::new(
+            StatementKind::VariableDeclaration {
+                kind,
+                name,
+                ty,
+                init,
+                reactive,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    fn detect_reactive_kind(&self, expr: &Expression) -> ReactiveKind {
+        if let ExpressionKind::Call { callee, .. } = &expr.kind
+            && let ExpressionKind::Identifier(name) = &callee.kind
+        {
+            return match name.as_str() {
+                "signal" => ReactiveKind::Signal,
+                "memo" => ReactiveKind::Memo,
+                "effect" => ReactiveKind::Effect,
+                _ => ReactiveKind::None,
+            };
+        }
+        ReactiveKind::None
+    }
+
+    /// Parse `import Default, { name as alias } from "./File.omni"`,
+    /// `import * as ns from "..."` or a bare `import "..."`
+    fn parse_import_declaration(&mut self) -> ParseResult<Statement> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume_word("import")?;
+
+        let mut specifiers = Vec::new();
+        if !matches!(
+            self.peek_kind(),
+            Some(TokenKind::StringLiteral(_) | TokenKind::StringLiteralSingle(_))
+        ) {
+            if let Some(TokenKind::Identifier(name)) = self.peek_kind() {
+                self.advance();
+                specifiers.push(ImportSpecifier::Default(name));
+                if !self.consume_if(TokenKind::Comma) {
+                    self.consume_word("from")?;
+                }
+            }
+            if self.consume_if(TokenKind::Star) {
+                self.consume_word("as")?;
+                specifiers.push(ImportSpecifier::Namespace(self.consume_identifier()?));
+                self.consume_word("from")?;
+            } else if self.consume_if(TokenKind::LeftBrace) {
+                while !self.check(TokenKind::RightBrace) {
+                    let name = self.consume_identifier()?;
+                    let alias = if self.check_text(0, "as") {
+                        self.advance();
+                        Some(self.consume_identifier()?)
+                    } else {
+                        None
+                    };
+                    specifiers.push(ImportSpecifier::Named { name, alias });
+                    if !self.consume_if(TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.consume(TokenKind::RightBrace)?;
+                self.consume_word("from")?;
+            } else if specifiers.is_empty() {
+                return Err(self.unexpected("import specifier"));
+            }
+        }
+
+        let source = match self.peek_kind() {
+            Some(TokenKind::StringLiteral(s) | TokenKind::StringLiteralSingle(s)) => {
+                self.advance();
+                s
+            }
+            _ => return Err(self.unexpected("module path")),
+        };
+        self.consume_if(TokenKind::Semicolon);
+        self.cst_wrap(cp, SyntaxKind::ImportDecl);
+
+        Ok(Statement::new(
+            StatementKind::Import(Import { source, specifiers }),
+            self.span_from(start),
+        ))
+    }
+
+    fn parse_function_declaration(&mut self) -> ParseResult<Statement> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        let exported = self.consume_export();
+        let function_start = self.offset();
+        self.consume(TokenKind::Function)?;
+
+        let name = self.consume_identifier()?;
+
+        let params_cp = self.cst_start();
+        self.consume(TokenKind::LeftParen)?;
+        let params = self.parse_parameter_list()?;
+        self.consume(TokenKind::RightParen)?;
+        self.cst_wrap(params_cp, SyntaxKind::ParamList);
+        let return_type = self.parse_type_annotation()?;
+
+        let body = self.parse_block()?;
+        self.cst_wrap(cp, SyntaxKind::FunctionDecl);
+
+        let declaration = Statement::new(
+            StatementKind::FunctionDeclaration {
+                name,
+                params,
+                return_type,
+                body,
+                is_async: false,
+            },
+            self.span_from(function_start),
+        );
+        Ok(self.exported(declaration, exported, start))
+    }
+
+    fn parse_parameter_list(&mut self) -> ParseResult<Vec<Parameter>> {
+        let mut params = Vec::new();
+
+        while !self.check(TokenKind::RightParen) {
+            let cp = self.cst_start();
+            let start = self.offset();
+            let pattern = self.parse_pattern()?;
+            let ty = self.parse_type_annotation()?;
+            let default = self.parse_default()?;
+            self.cst_wrap(cp, SyntaxKind::Param);
+            params.push(Parameter {
+                pattern,
+                ty,
+                default,
+                span: self.span_from(start),
+            });
+
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+
+        Ok(params)
+    }
+
+    /// Parse a binding pattern: a name, `{ ... }` or `[ ... ]`
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        let cp = self.cst_start();
+        if self.consume_if(TokenKind::LeftBrace) {
+            let mut properties = Vec::new();
+            let mut rest = None;
+            while !self.check(TokenKind::RightBrace) {
+                if self.check(TokenKind::Ellipsis) {
+                    rest = Some(self.parse_rest_binding()?);
+                    break;
+                }
+                let property_cp = self.cst_start();
+                let (key, is_identifier) = self.parse_property_name()?;
+                let element = if self.consume_if(TokenKind::Colon) {
+                    self.parse_binding_element()?
+                } else if is_identifier {
+                    BindingElement {
+                        pattern: Pattern::Identifier(key.clone()),
+                        default: self.parse_default()?,
+                    }
+                } else {
+                    return Err(self.unexpected(":"));
+                };
+                self.cst_wrap(property_cp, SyntaxKind::Property);
+                properties.push((key, element));
+                if !self.consume_if(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenKind::RightBrace)?;
+            self.cst_wrap(cp, SyntaxKind::ObjectPattern);
+            return Ok(Pattern::Object { properties, rest });
+        }
+
+        if self.consume_if(TokenKind::LeftBracket) {
+            let mut elements = Vec::new();
+            let mut rest = None;
+            while !self.check(TokenKind::RightBracket) {
+                if self.consume_if(TokenKind::Comma) {
+                    elements.push(None);
+                    continue;
+                }
+                if self.check(TokenKind::Ellipsis) {
+                    rest = Some(self.parse_rest_binding()?);
+                    break;
+                }
+                elements.push(Some(self.parse_binding_element()?));
+                if !self.consume_if(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenKind::RightBracket)?;
+            self.cst_wrap(cp, SyntaxKind::ArrayPattern);
+            return Ok(Pattern::Array { elements, rest });
+        }
+
+        Ok(Pattern::Identifier(self.consume_identifier()?))
+    }
+
+    /// Parse a pattern with an optional `= default`
+    fn parse_binding_element(&mut self) -> ParseResult<BindingElement> {
+        let pattern = self.parse_pattern()?;
+        let default = self.parse_default()?;
+        Ok(BindingElement { pattern, default })
+    }
+
+    fn parse_default(&mut self) -> ParseResult<Option<Expression>> {
+        if self.consume_if(TokenKind::Equals) {
+            Ok(Some(self.parse_expression()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parse `...name` at the end of a pattern
+    fn parse_rest_binding(&mut self) -> ParseResult<String> {
+        let cp = self.cst_start();
+        self.consume(TokenKind::Ellipsis)?;
+        let name = self.consume_identifier()?;
+        self.cst_wrap(cp, SyntaxKind::SpreadExpr);
+        Ok(name)
+    }
+
+    /// Parse an object key, returning it and whether it was an identifier
+    ///
+    /// Keywords are allowed as keys, as in `{ if: 1 }`.
+    fn parse_property_name(&mut self) -> ParseResult<(String, bool)> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(ParseError::UnexpectedEof(self.peek_span()));
+        };
+        let key = match token.kind {
+            TokenKind::Identifier(name) => (name, true),
+            TokenKind::StringLiteral(s) | TokenKind::StringLiteralSingle(s) => (s, false),
+            TokenKind::Number(n) => (n.to_string(), false),
+            _ if token.text.starts_with(|c: char| c.is_ascii_alphabetic())
+                && token.text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                (token.text, false)
+            }
+            _ => return Err(self.unexpected("property name")),
+        };
+        self.advance();
+        Ok(key)
+    }
+
+    /// Parse `{ statements }`
+    fn parse_block(&mut self) -> ParseResult<Vec<Statement>> {
+        let cp = self.cst_start();
+        self.consume(TokenKind::LeftBrace)?;
+        let statements = self.parse_statement_block();
+        self.consume(TokenKind::RightBrace)?;
+        self.cst_wrap(cp, SyntaxKind::Block);
+        Ok(statements)
+    }
+
+    /// Parse the body of `if`, `else`, `for` or `while`: a block, or a
+    /// single statement that is not a declaration
+    fn parse_body(&mut self) -> ParseResult<Vec<Statement>> {
+        match self.peek_kind() {
+            Some(TokenKind::LeftBrace) => self.parse_block(),
+            Some(TokenKind::Const | TokenKind::Let | TokenKind::Function) => {
+                Err(self.unexpected("statement or block (declarations need braces)"))
+            }
+            Some(TokenKind::Identifier(word)) if word == "import" || word == "export" => {
+                Err(self.unexpected("statement or block (declarations need braces)"))
+            }
+            _ => Ok(vec![self.parse_statement()?]),
+        }
+    }
+
+    fn parse_statement_block(&mut self) -> Vec<Statement> {
+        let mut statements = Vec::new();
+
+        while !self.check(TokenKind::RightBrace) && !self.is_at_end() && !self.at_section_start() {
+            statements.push(self.recover_statement(Self::parse_statement));
+        }
+
+        statements
+    }
+
+    /// Parse a statement, or leave an `Error` placeholder after recovering
+    fn recover_statement(&mut self, parse: fn(&mut Self) -> ParseResult<Statement>) -> Statement {
+        self.recover(Sync::Statement, parse)
+            .unwrap_or_else(|span| Statement::new(StatementKind::Error, span))
+    }
+
+    fn parse_if_statement(&mut self) -> ParseResult<Statement> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::If)?;
+        self.consume(TokenKind::LeftParen)?;
+        let condition = self.parse_expression()?;
+        self.consume(TokenKind::RightParen)?;
+
+        let then_branch = self.parse_body()?;
+
+        let else_branch = if self.consume_if(TokenKind::Else) {
+            Some(self.parse_body()?)
+        } else {
+            None
+        };
+        self.cst_wrap(cp, SyntaxKind::IfStmt);
+
+        Ok(Statement::new(
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    fn parse_for_statement(&mut self) -> ParseResult<Statement> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::For)?;
+        self.consume(TokenKind::LeftParen)?;
+
+        let init = if self.check(TokenKind::Semicolon) {
+            None
+        } else if self.check(TokenKind::Const) || self.check(TokenKind::Let) {
+            let init_cp = self.cst_start();
+            let binding = self.parse_variable_binding()?;
+            self.cst_wrap(init_cp, SyntaxKind::VariableDecl);
+            Some(Box::new(binding))
+        } else {
+            let init_cp = self.cst_start();
+            let expr = self.parse_expression()?;
+            self.cst_wrap(init_cp, SyntaxKind::ExprStmt);
+            let span = expr.span;
+            Some(Box::new(Statement::new(StatementKind::Expression(expr), span)))
+        };
+        self.consume(TokenKind::Semicolon)?;
+
+        let condition = if self.check(TokenKind::Semicolon) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.consume(TokenKind::Semicolon)?;
+
+        let update = if self.check(TokenKind::RightParen) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
+        self.consume(TokenKind::RightParen)?;
+
+        let body = self.parse_body()?;
+        self.cst_wrap(cp, SyntaxKind::ForStmt);
+
+        Ok(Statement::new(
+            StatementKind::For {
+                init,
+                condition,
+                update,
+                body,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    fn parse_while_statement(&mut self) -> ParseResult<Statement> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::While)?;
+        self.consume(TokenKind::LeftParen)?;
+        let condition = self.parse_expression()?;
+        self.consume(TokenKind::RightParen)?;
+
+        let body = self.parse_body()?;
+        self.cst_wrap(cp, SyntaxKind::WhileStmt);
+
+        Ok(Statement::new(
+            StatementKind::While { condition, body },
+            self.span_from(start),
+        ))
+    }
+
+    /// Parse `break` or `continue`
+    fn parse_jump_statement(&mut self, keyword: TokenKind) -> ParseResult<Statement> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        let (kind, syntax) = if keyword == TokenKind::Break {
+            (StatementKind::Break, SyntaxKind::BreakStmt)
+        } else {
+            (StatementKind::Continue, SyntaxKind::ContinueStmt)
+        };
+        self.consume(keyword)?;
+        self.consume_if(TokenKind::Semicolon);
+        self.cst_wrap(cp, syntax);
+
+        Ok(Statement::new(kind, self.span_from(start)))
+    }
+
+    fn parse_return_statement(&mut self) -> ParseResult<Statement> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::Return)?;
+
+        let value = if !self.check(TokenKind::Semicolon) && !self.check(TokenKind::RightBrace) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+
+        self.consume_if(TokenKind::Semicolon);
+        self.cst_wrap(cp, SyntaxKind::ReturnStmt);
+
+        Ok(Statement::new(StatementKind::Return(value), self.span_from(start)))
+    }
+
+    // ========================================================================
+    // Expressions
+    // ========================================================================
+
+    fn parse_expression(&mut self) -> ParseResult<Expression> {
+        self.parse_assignment()
+    }
+
+    /// Parse `target = value` and compound assignments, right-associative
+    fn parse_assignment(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let target = self.parse_ternary()?;
+
+        let op = match self.peek_kind() {
+            Some(TokenKind::Equals) => AssignOp::Assign,
+            Some(TokenKind::PlusEquals) => AssignOp::Add,
+            Some(TokenKind::MinusEquals) => AssignOp::Sub,
+            Some(TokenKind::StarEquals) => AssignOp::Mul,
+            Some(TokenKind::SlashEquals) => AssignOp::Div,
+            Some(TokenKind::PercentEquals) => AssignOp::Mod,
+            _ => return Ok(target),
+        };
+        check_assignment_target(&target)?;
+        self.advance();
+        let value = self.parse_assignment()?;
+        self.cst_wrap(cp, SyntaxKind::AssignExpr);
+
+        let span = target.span.merge(value.span);
+        Ok(Expression::new(
+            ExpressionKind::Assign {
+                op,
+                target: Box::new(target),
+                value: Box::new(value),
+            },
+            span,
+        ))
+    }
+
+    fn parse_ternary(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let condition = self.parse_nullish()?;
+
+        if self.consume_if(TokenKind::Question) {
+            let then_expr = self.parse_expression()?;
+            self.consume(TokenKind::Colon)?;
+            let else_expr = self.parse_expression()?;
+            self.cst_wrap(cp, SyntaxKind::TernaryExpr);
+
+            let span = condition.span.merge(else_expr.span);
+            return Ok(Expression::new(
+                ExpressionKind::Ternary {
+                    condition: Box::new(condition),
+                    then_expr: Box::new(then_expr),
+                    else_expr: Box::new(else_expr),
+                },
+                span,
+            ));
+        }
+
+        Ok(condition)
+    }
+
+    fn parse_nullish(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let mut left = self.parse_or()?;
+
+        while self.consume_if(TokenKind::DoubleQuestion) {
+            let right = self.parse_or()?;
+            self.cst_wrap(cp, SyntaxKind::BinaryExpr);
+            left = binary(left, BinaryOp::Nullish, right);
+        }
+
+        Ok(left)
+    }
+
+    fn parse_or(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let mut left = self.parse_and()?;
+
+        while self.consume_if(TokenKind::Or) {
+            let right = self.parse_and()?;
+            self.cst_wrap(cp, SyntaxKind::BinaryExpr);
+            left = binary(left, BinaryOp::Or, right);
+        }
+
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let mut left = self.parse_equality()?;
+
+        while self.consume_if(TokenKind::And) {
+            let right = self.parse_equality()?;
+            self.cst_wrap(cp, SyntaxKind::BinaryExpr);
+            left = binary(left, BinaryOp::And, right);
+        }
+
+        Ok(left)
+    }
+
+    fn parse_equality(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let mut left = self.parse_comparison()?;
+
+        loop {
+            let op = if self.consume_if(TokenKind::DoubleEquals) {
+                BinaryOp::Eq
+            } else if self.consume_if(TokenKind::NotEquals) {
+                BinaryOp::Ne
+            } else {
+                break;
+            };
+
+            let right = self.parse_comparison()?;
+            self.cst_wrap(cp, SyntaxKind::BinaryExpr);
+            left = binary(left, op, right);
+        }
+
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let mut left = self.parse_additive()?;
+
+        loop {
+            let op = if self.consume_if(TokenKind::LessThan) {
+                BinaryOp::Lt
+            } else if self.consume_if(TokenKind::GreaterThan) {
+                BinaryOp::Gt
+            } else if self.consume_if(TokenKind::LessEquals) {
+                BinaryOp::Le
+            } else if self.consume_if(TokenKind::GreaterEquals) {
+                BinaryOp::Ge
+            } else {
+                break;
+            };
+
+            let right = self.parse_additive()?;
+            self.cst_wrap(cp, SyntaxKind::BinaryExpr);
+            left = binary(left, op, right);
+        }
+
+        Ok(left)
+    }
+
+    fn parse_additive(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let mut left = self.parse_multiplicative()?;
+
+        loop {
+            let op = if self.consume_if(TokenKind::Plus) {
+                BinaryOp::Add
+            } else if self.consume_if(TokenKind::Minus) {
+                BinaryOp::Sub
+            } else {
+                break;
+            };
+
+            let right = self.parse_multiplicative()?;
+            self.cst_wrap(cp, SyntaxKind::BinaryExpr);
+            left = binary(left, op, right);
+        }
+
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let mut left = self.parse_unary()?;
+
+        loop {
+            let op = if self.consume_if(TokenKind::Star) {
+                BinaryOp::Mul
+            } else if self.consume_if(TokenKind::Slash) {
+                BinaryOp::Div
+            } else if self.consume_if(TokenKind::Percent) {
+                BinaryOp::Mod
+            } else {
+                break;
+            };
+
+            let right = self.parse_unary()?;
+            self.cst_wrap(cp, SyntaxKind::BinaryExpr);
+            left = binary(left, op, right);
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        if self.consume_if(TokenKind::Not) {
+            let operand = self.parse_unary()?;
+            self.cst_wrap(cp, SyntaxKind::UnaryExpr);
+            return Ok(Expression::new(
+                ExpressionKind::Unary {
+                    op: UnaryOp::Not,
+                    operand: Box::new(operand),
+                },
+                self.span_from(start),
+            ));
+        }
+
+        if self.consume_if(TokenKind::Minus) {
+            let operand = self.parse_unary()?;
+            self.cst_wrap(cp, SyntaxKind::UnaryExpr);
+            return Ok(Expression::new(
+                ExpressionKind::Unary {
+                    op: UnaryOp::Neg,
+                    operand: Box::new(operand),
+                },
+                self.span_from(start),
+            ));
+        }
+
+        if let Some(op) = self.peek_update_op() {
+            self.advance();
+            let target = self.parse_unary()?;
+            check_assignment_target(&target)?;
+            self.cst_wrap(cp, SyntaxKind::UpdateExpr);
+            return Ok(Expression::new(
+                ExpressionKind::Update {
+                    op,
+                    prefix: true,
+                    target: Box::new(target),
+                },
+                self.span_from(start),
+            ));
+        }
+
+        let expr = self.parse_call()?;
+        if let Some(op) = self.peek_update_op() {
+            check_assignment_target(&expr)?;
+            self.advance();
+            self.cst_wrap(cp, SyntaxKind::UpdateExpr);
+            return Ok(Expression::new(
+                ExpressionKind::Update {
+                    op,
+                    prefix: false,
+                    target: Box::new(expr),
+                },
+                self.span_from(start),
+            ));
+        }
+
+        Ok(expr)
+    }
+
+    fn peek_update_op(&self) -> Option<UpdateOp> {
+        match self.peek_kind() {
+            Some(TokenKind::PlusPlus) => Some(UpdateOp::Increment),
+            Some(TokenKind::MinusMinus) => Some(UpdateOp::Decrement),
+            _ => None,
+        }
+    }
+
+    fn parse_call(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let mut expr = self.parse_primary()?;
+        let start = expr.span.start;
+
+        // `signal` and `memo` are keywords, so a `<` after them always
+        // starts type arguments
+        let mut type_args = Vec::new();
+        if self.check(TokenKind::LessThan)
+            && matches!(&expr.kind, ExpressionKind::Identifier(name) if name == "signal" || name == "memo")
+        {
+            type_args = self.parse_type_arguments()?;
+            if !self.check(TokenKind::LeftParen) {
+                return Err(self.unexpected("("));
+            }
+        }
+
+        loop {
+            // `?.` is followed by a call, an index or a property name
+            let optional = self.consume_if(TokenKind::QuestionDot);
+            let args_cp = self.cst_start();
+            let kind = if self.consume_if(TokenKind::LeftParen) {
+                let args = self.parse_argument_list()?;
+                self.consume(TokenKind::RightParen)?;
+                self.cst_wrap(args_cp, SyntaxKind::ArgList);
+                self.cst_wrap(cp, SyntaxKind::CallExpr);
+                ExpressionKind::Call {
+                    callee: Box::new(expr),
+                    type_args: std::mem::take(&mut type_args),
+                    args,
+                    optional,
+                }
+            } else if self.consume_if(TokenKind::LeftBracket) {
+                let index = self.parse_expression()?;
+                self.consume(TokenKind::RightBracket)?;
+                self.cst_wrap(cp, SyntaxKind::IndexExpr);
+                ExpressionKind::Index {
+                    object: Box::new(expr),
+                    index: Box::new(index),
+                    optional,
+                }
+            } else if optional || self.consume_if(TokenKind::Dot) {
+                let property = self.consume_identifier()?;
+                self.cst_wrap(cp, SyntaxKind::MemberExpr);
+                ExpressionKind::Member {
+                    object: Box::new(expr),
+                    property,
+                    computed: false,
+                    optional,
+                }
+            } else {
+                break;
+            };
+            expr = Expression::new(kind, self.span_from(start));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_argument_list(&mut self) -> ParseResult<Vec<Expression>> {
+        let mut args = Vec::new();
+
+        while !self.check(TokenKind::RightParen) {
+            args.push(self.parse_expression()?);
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+
+        Ok(args)
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<Expression> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        let (expr, kind) = self.parse_primary_kind()?;
+        self.cst_wrap(cp, kind);
+        Ok(Expression::new(expr, self.span_from(start)))
+    }
+
+    /// Parse a primary expression along with its CST node kind
+    ///
+    /// The span of a parenthesized expression includes the parentheses.
+    fn parse_primary_kind(&mut self) -> ParseResult<(ExpressionKind, SyntaxKind)> {
+        // Number
+        if let Some(TokenKind::Number(n)) = self.peek_kind() {
+            self.advance();
+            return Ok((ExpressionKind::Literal(Literal::Number(n)), SyntaxKind::Literal));
+        }
+        if let Some(TokenKind::Dimension((n, unit))) = self.peek_kind() {
+            self.advance();
+            return Ok((ExpressionKind::Literal(Literal::Dimension(n, unit)), SyntaxKind::Literal));
+        }
+
+        // String
+        if let Some(TokenKind::StringLiteral(s)) = self.peek_kind() {
+            let s = s.clone();
+            self.advance();
+            return Ok((ExpressionKind::Literal(Literal::String(s)), SyntaxKind::Literal));
+        }
+        if let Some(TokenKind::StringLiteralSingle(s)) = self.peek_kind() {
+            let s = s.clone();
+            self.advance();
+            return Ok((ExpressionKind::Literal(Literal::String(s)), SyntaxKind::Literal));
+        }
+
+        // Boolean
+        if self.consume_if(TokenKind::True) {
+            return Ok((ExpressionKind::Literal(Literal::Boolean(true)), SyntaxKind::Literal));
+        }
+        if self.consume_if(TokenKind::False) {
+            return Ok((ExpressionKind::Literal(Literal::Boolean(false)), SyntaxKind::Literal));
+        }
+
+        // Null
+        if self.consume_if(TokenKind::Null) {
+            return Ok((ExpressionKind::Literal(Literal::Null), SyntaxKind::Literal));
+        }
+
+        // Identifier or single-parameter arrow function
+        if let Some(TokenKind::Identifier(name)) = self.peek_kind() {
+            let cp = self.cst_start();
+            let span = self.peek_span();
+            self.advance();
+            if self.check(TokenKind::Arrow) {
+                self.cst_wrap(cp, SyntaxKind::Param);
+                self.advance();
+                let body = self.parse_arrow_body()?;
+                let params = vec![Parameter {
+                    pattern: Pattern::Identifier(name),
+                    ty: None,
+                    default: None,
+                    span,
+                }];
+                return Ok((
+                    ExpressionKind::Arrow {
+                        params,
+                        return_type: None,
+                        body,
+                    },
+                    SyntaxKind::ArrowExpr,
+                ));
+            }
+            return Ok((ExpressionKind::Identifier(name), SyntaxKind::Name));
+        }
+
+        // Keywords as identifiers (signal, memo, etc.)
+        if self.check(TokenKind::Signal)
+            || self.check(TokenKind::Memo)
+            || self.check(TokenKind::Effect)
+        {
+            let name = self.peek().map(|t| t.text.clone()).unwrap_or_default();
+            self.advance();
+            return Ok((ExpressionKind::Identifier(name), SyntaxKind::Name));
+        }
+
+        // Template literal
+        if self.consume_if(TokenKind::Backtick) {
+            return Ok((self.parse_template_literal()?, SyntaxKind::TemplateLiteral));
+        }
+
+        // Array
+        if self.consume_if(TokenKind::LeftBracket) {
+            let mut elements = Vec::new();
+            while !self.check(TokenKind::RightBracket) {
+                if self.check(TokenKind::Ellipsis) {
+                    let (expr, span) = self.parse_spread()?;
+                    elements.push(Expression::new(ExpressionKind::Spread(Box::new(expr)), span));
+                } else {
+                    elements.push(self.parse_expression()?);
+                }
+                if !self.consume_if(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenKind::RightBracket)?;
+            return Ok((ExpressionKind::Array(elements), SyntaxKind::ArrayExpr));
+        }
+
+        // Object
+        if self.consume_if(TokenKind::LeftBrace) {
+            let mut properties = Vec::new();
+            while !self.check(TokenKind::RightBrace) {
+                properties.push(self.parse_object_property()?);
+                if !self.consume_if(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenKind::RightBrace)?;
+            return Ok((ExpressionKind::Object(properties), SyntaxKind::ObjectExpr));
+        }
+
+        // Arrow function
+        if self.check(TokenKind::LeftParen) && self.at_arrow_params() {
+            let params_cp = self.cst_start();
+            self.advance();
+            let params = self.parse_parameter_list()?;
+            self.consume(TokenKind::RightParen)?;
+            self.cst_wrap(params_cp, SyntaxKind::ParamList);
+            let return_type = self.parse_type_annotation()?;
+            self.consume(TokenKind::Arrow)?;
+            let body = self.parse_arrow_body()?;
+            return Ok((
+                ExpressionKind::Arrow {
+                    params,
+                    return_type,
+                    body,
+                },
+                SyntaxKind::ArrowExpr,
+            ));
+        }
+
+        // Parenthesized expression
+        if self.consume_if(TokenKind::LeftParen) {
+            let expr = self.parse_expression()?;
+            self.consume(TokenKind::RightParen)?;
+            return Ok((expr.kind, SyntaxKind::ParenExpr));
+        }
+
+        Err(ParseError::InvalidExpression(self.peek_span()))
+    }
+
+    fn parse_template_literal(&mut self) -> ParseResult<ExpressionKind> {
+        let mut parts = Vec::new();
+
+        loop {
+            match self.peek_kind() {
+                Some(TokenKind::TemplateString(s)) => {
+                    self.advance();
+                    parts.push(TemplatePart::String(s));
+                }
+                Some(TokenKind::TemplateExprStart) => {
+                    self.advance();
+                    let expr = self.parse_expression()?;
+                    self.consume(TokenKind::RightBrace)?;
+                    parts.push(TemplatePart::Expression(expr));
+                }
+                Some(TokenKind::Backtick) => {
+                    self.advance();
+                    break;
+                }
+                Some(_) => return Err(self.unexpected("template literal")),
+                None => return Err(ParseError::UnexpectedEof(self.peek_span())),
+            }
+        }
+
+        Ok(ExpressionKind::Template { parts })
+    }
+
+    /// Parse `...expr`, returning the operand and the span of the whole spread
+    fn parse_spread(&mut self) -> ParseResult<(Expression, Span)> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::Ellipsis)?;
+        let expr = self.parse_expression()?;
+        self.cst_wrap(cp, SyntaxKind::SpreadExpr);
+        Ok((expr, self.span_from(start)))
+    }
+
+    fn parse_object_property(&mut self) -> ParseResult<ObjectProperty> {
+        if self.check(TokenKind::Ellipsis) {
+            let (expr, _) = self.parse_spread()?;
+            return Ok(ObjectProperty::Spread(expr));
+        }
+
+        let cp = self.cst_start();
+        let key = if self.consume_if(TokenKind::LeftBracket) {
+            let key = self.parse_expression()?;
+            self.consume(TokenKind::RightBracket)?;
+            PropertyKey::Computed(key)
+        } else {
+            let span = self.peek_span();
+            let (name, is_identifier) = self.parse_property_name()?;
+            if is_identifier && !self.check(TokenKind::Colon) {
+                // Shorthand `{ name }`
+                self.cst_wrap(cp, SyntaxKind::Property);
+                return Ok(ObjectProperty::KeyValue {
+                    key: PropertyKey::Static(name.clone()),
+                    value: Expression::new(ExpressionKind::Identifier(name), span),
+                });
+            }
+            PropertyKey::Static(name)
+        };
+        self.consume(TokenKind::Colon)?;
+        let value = self.parse_expression()?;
+        self.cst_wrap(cp, SyntaxKind::Property);
+        Ok(ObjectProperty::KeyValue { key, value })
+    }
+
+    /// Check whether the `(` at the current position opens the parameters
+    /// of an arrow function, by finding its `)` and looking for `=>`
+    fn at_arrow_params(&self) -> bool {
+        let mut depth = 0usize;
+        for (i, token) in self.tokens[self.pos..].iter().enumerate() {
+            match token.kind {
+                TokenKind::LeftParen
+                | TokenKind::LeftBracket
+                | TokenKind::LeftBrace
+                | TokenKind::TemplateExprStart => depth += 1,
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return match self.tokens.get(self.pos + i + 1).map(|t| &t.kind) {
+                            Some(TokenKind::Arrow) => true,
+                            Some(TokenKind::Colon) => self.at_return_type_arrow(self.pos + i + 2),
+                            _ => false,
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Check whether the tokens from `from` form a return type followed
+    /// by `=>`, as in `(a: number): number => a`
+    fn at_return_type_arrow(&self, from: usize) -> bool {
+        let mut depth = 0usize;
+        for token in self.tokens.get(from..).unwrap_or_default() {
+            match token.kind {
+                TokenKind::Arrow if depth == 0 => return true,
+                TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace | TokenKind::LessThan => {
+                    depth += 1
+                }
+                TokenKind::RightParen
+                | TokenKind::RightBracket
+                | TokenKind::RightBrace
+                | TokenKind::GreaterThan => match depth.checked_sub(1) {
+                    Some(d) => depth = d,
+                    None => return false,
+                },
+                TokenKind::Identifier(_) | TokenKind::Null | TokenKind::Pipe => {}
+                _ if depth > 0 => {}
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    fn parse_arrow_body(&mut self) -> ParseResult<ArrowBody> {
+        if self.check(TokenKind::LeftBrace) {
+            Ok(ArrowBody::Block(self.parse_block()?))
+        } else {
+            let expr = self.parse_expression()?;
+            Ok(ArrowBody::Expression(Box::new(expr)))
+        }
+    }
+
+    // ========================================================================
+    // Types
+    // ========================================================================
+
+    /// Parse `: Type` if it is next
+    fn parse_type_annotation(&mut self) -> ParseResult<Option<Type>> {
+        if !self.check(TokenKind::Colon) {
+            return Ok(None);
+        }
+        let cp = self.cst_start();
+        self.advance();
+        let ty = self.parse_type()?;
+        self.cst_wrap(cp, SyntaxKind::TypeAnnotation);
+        Ok(Some(ty))
+    }
+
+    /// Parse `<T, ...>` after a generic function
+    fn parse_type_arguments(&mut self) -> ParseResult<Vec<Type>> {
+        let cp = self.cst_start();
+        self.consume(TokenKind::LessThan)?;
+        let mut args = vec![self.parse_type()?];
+        while self.consume_if(TokenKind::Comma) {
+            args.push(self.parse_type()?);
+        }
+        self.consume(TokenKind::GreaterThan)?;
+        self.cst_wrap(cp, SyntaxKind::TypeArgs);
+        Ok(args)
+    }
+
+    /// Parse a type, such as `number`, `Color[]`, `string | null` or
+    /// `(a: number) => void`
+    fn parse_type(&mut self) -> ParseResult<Type> {
+        let cp = self.cst_start();
+        let mut members = vec![self.parse_array_type()?];
+        while self.consume_if(TokenKind::Pipe) {
+            members.push(self.parse_array_type()?);
+        }
+        self.cst_wrap(cp, SyntaxKind::Type);
+        Ok(if members.len() == 1 {
+            members.remove(0)
+        } else {
+            Type::Union(members)
+        })
+    }
+
+    /// Parse a type followed by any number of `[]`
+    fn parse_array_type(&mut self) -> ParseResult<Type> {
+        let mut ty = self.parse_primary_type()?;
+        while self.check(TokenKind::LeftBracket)
+            && self.tokens.get(self.pos + 1).is_some_and(|t| t.kind == TokenKind::RightBracket)
+        {
+            self.advance();
+            self.advance();
+            ty = Type::Array(Box::new(ty));
+        }
+        Ok(ty)
+    }
+
+    fn parse_primary_type(&mut self) -> ParseResult<Type> {
+        // Function type, or a parenthesized type
+        if self.check(TokenKind::LeftParen) {
+            let is_function = self.at_arrow_params();
+            self.advance();
+            if !is_function {
+                let ty = self.parse_type()?;
+                self.consume(TokenKind::RightParen)?;
+                return Ok(ty);
+            }
+            let mut params = Vec::new();
+            while !self.check(TokenKind::RightParen) {
+                self.consume_identifier()?;
+                self.consume(TokenKind::Colon)?;
+                params.push(self.parse_type()?);
+                if !self.consume_if(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenKind::RightParen)?;
+            self.consume(TokenKind::Arrow)?;
+            let return_type = Box::new(self.parse_type()?);
+            return Ok(Type::Function { params, return_type });
+        }
+
+        // Object type: `{ x: number; y: number }`
+        if self.consume_if(TokenKind::LeftBrace) {
+            let mut fields = HashMap::new();
+            while !self.check(TokenKind::RightBrace) {
+                let (key, _) = self.parse_property_name()?;
+                self.consume(TokenKind::Colon)?;
+                fields.insert(key, self.parse_type()?);
+                if !self.consume_if(TokenKind::Semicolon) && !self.consume_if(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenKind::RightBrace)?;
+            return Ok(Type::Object(fields));
+        }
+
+        if self.consume_if(TokenKind::Null) {
+            return Ok(Type::Custom("null".to_string()));
+        }
+
+        let Some(TokenKind::Identifier(name)) = self.peek_kind() else {
+            return Err(self.unexpected("type"));
+        };
+        self.advance();
+        Ok(match name.as_str() {
+            "number" => Type::Number,
+            "string" => Type::String,
+            "boolean" => Type::Boolean,
+            "void" => Type::Void,
+            "any" => Type::Any,
+            "Signal" | "Array" if self.consume_if(TokenKind::LessThan) => {
+                let inner = Box::new(self.parse_type()?);
+                self.consume(TokenKind::GreaterThan)?;
+                if name == "Signal" {
+                    Type::Signal(inner)
+                } else {
+                    Type::Array(inner)
+                }
+            }
+            _ => Type::Custom(name),
+        })
+    }
+
+    // ========================================================================
+    // Canvas Section
+    // ========================================================================
+
+    #[instrument(skip(self))]
+    fn parse_canvas_section(&mut self) -> ParseResult<(CanvasNode, Vec<Node>)> {
+        if !self.check_tag(TokenKind::LessThan, "canvas") {
+            // No canvas, create default
+            return Ok((CanvasNode::default(), Vec::new()));
+        }
+
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::LessThan)?;
+        self.consume_word("canvas")?;
+
+        let mut canvas = CanvasNode::default();
+
+        // Parse canvas attributes
+        while !self.check(TokenKind::GreaterThan) && !self.check(TokenKind::SelfClosing) {
+            let (name, value, span) = self.parse_attribute_pair()?;
+            match name.as_str() {
+                "width" => canvas.width = Some(self.attr_value_to_expr(value, span)?),
+                "height" => canvas.height = Some(self.attr_value_to_expr(value, span)?),
+                "background" => canvas.background = Some(self.attr_value_to_expr(value, span)?),
+                _ => {}
+            }
+        }
+
+        if self.consume_if(TokenKind::SelfClosing) {
+            self.cst_wrap(cp, SyntaxKind::CanvasSection);
+            return Ok((canvas, Vec::new()));
+        }
+
+        self.consume(TokenKind::GreaterThan)?;
+        let open = self.span_from(start);
+
+        let children = self.parse_children("canvas", open, true);
+
+        self.consume_closing_tag("canvas");
+        self.cst_wrap(cp, SyntaxKind::CanvasSection);
+
+        Ok((canvas, children))
+    }
+
+    /// Parse a top-level element, reusing a cached one when possible
+    fn parse_cached_node(&mut self) -> ParseResult<Node> {
+        let start = self.pos;
+        let errors = self.errors.len();
+        let cached = match self.cst {
+            Some(_) => None,
+            None => take_cached(&mut self.reuse.nodes, start),
+        };
+        let node = match cached {
+            Some((end, mut node)) => {
+                node.shift_spans(self.tokens[start].span.start as isize - node.span.start as isize);
+                self.pos = end;
+                node
+            }
+            None => self.parse_node()?,
+        };
+        if self.errors.len() == errors {
+            self.recorded.nodes.push((start..self.pos, Some(node.clone())));
+        }
+        Ok(node)
+    }
+
+    fn parse_node(&mut self) -> ParseResult<Node> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::LessThan)?;
+
+        // Get tag name
+        let tag_span = self.peek_span();
+        let tag_name = self.consume_element_tag()?;
+        if tag_name == "slot" {
+            return self.parse_slot(cp, start);
+        }
+        let tag = ElementTag::from_str(&tag_name)
+            .ok_or_else(|| ParseError::InvalidElementTag(tag_name.clone(), tag_span))?;
+
+        // Parse attributes
+        let mut attributes = Vec::new();
+        let mut directives = Vec::new();
+        let mut key = None;
+
+        while !self.check(TokenKind::GreaterThan) && !self.check(TokenKind::SelfClosing) {
+            let (name, value, span) = self.parse_attribute_pair()?;
+
+            // Check for directives
+            if name.starts_with('@') || name.starts_with("on:") {
+                let event = name.strip_prefix('@').or_else(|| name.strip_prefix("on:")).unwrap_or(&name);
+                let mut parts = event.split('.');
+                let event_name = parts.next().unwrap_or_default().to_string();
+                let modifiers: Vec<String> = parts.map(str::to_string).collect();
+                if let Some(unknown) = modifiers.iter().find(|m| !EVENT_MODIFIERS.contains(&m.as_str())) {
+                    return Err(ParseError::UnknownModifier(unknown.clone(), span));
+                }
+                directives.push(Directive {
+                    name: DirectiveName::On,
+                    arg: Some(event_name),
+                    value: self.attr_value_to_expr(value, span)?,
+                    modifiers,
+                    span,
+                });
+            } else if name.starts_with(':') || name.starts_with("bind:") {
+                let prop_name = name
+                    .strip_prefix(':')
+                    .or_else(|| name.strip_prefix("bind:"))
+                    .unwrap_or(&name);
+                if let Some((_, modifier)) = prop_name.split_once('.') {
+                    return Err(ParseError::UnknownModifier(modifier.to_string(), span));
+                }
+                // `bind:text` is short for `bind:text={text}`
+                let value = match value {
+                    AttrValueRaw::Boolean(_) => {
+                        Expression::new(ExpressionKind::Identifier(prop_name.to_string()), span)
+                    }
+                    value => self.attr_value_to_expr(value, span)?,
+                };
+                directives.push(Directive {
+                    name: DirectiveName::Bind,
+                    arg: Some(prop_name.to_string()),
+                    value,
+                    modifiers: Vec::new(),
+                    span,
+                });
+            } else if let Some(prop) = name.strip_prefix("let:") {
+                // `let:item` is short for `let:item={item}`
+                let value = match value {
+                    AttrValueRaw::Boolean(_) => Expression::new(ExpressionKind::Identifier(prop.to_string()), span),
+                    value => self.attr_value_to_expr(value, span)?,
+                };
+                directives.push(Directive {
+                    name: DirectiveName::Let,
+                    arg: Some(prop.to_string()),
+                    value,
+                    modifiers: Vec::new(),
+                    span,
+                });
+            } else if name == "key" {
+                // `key` identifies the element across re-renders; a key
+                // needs a value, and only one per element
+                if key.is_some() || matches!(value, AttrValueRaw::Boolean(_)) {
+                    return Err(ParseError::InvalidAttribute(span));
+                }
+                key = Some(self.attr_value_to_expr(value, span)?);
+            } else if name == "ref" {
+                directives.push(Directive {
+                    name: DirectiveName::Ref,
+                    arg: None,
+                    value: self.attr_value_to_expr(value, span)?,
+                    modifiers: Vec::new(),
+                    span,
+                });
+            } else if let Some(action) = name.strip_prefix("use:") {
+                if let Some((_, modifier)) = action.split_once('.') {
+                    return Err(ParseError::UnknownModifier(modifier.to_string(), span));
+                }
+                // `use:action` alone passes no options
+                let value = match value {
+                    AttrValueRaw::Boolean(_) => Expression::new(ExpressionKind::Literal(Literal::Null), span),
+                    value => self.attr_value_to_expr(value, span)?,
+                };
+                directives.push(Directive {
+                    name: DirectiveName::Use,
+                    arg: Some(action.to_string()),
+                    value,
+                    modifiers: Vec::new(),
+                    span,
+                });
+            } else if let Some(class) = name.strip_prefix("class:") {
+                // `class:active` is short for `class:active={active}`
+                let value = match value {
+                    AttrValueRaw::Boolean(_) => {
+                        Expression::new(ExpressionKind::Identifier(class.to_string()), span)
+                    }
+                    value => self.attr_value_to_expr(value, span)?,
+                };
+                directives.push(Directive {
+                    name: DirectiveName::Class,
+                    arg: Some(class.to_string()),
+                    value,
+                    modifiers: Vec::new(),
+                    span,
+                });
+            } else {
+                attributes.push(Attribute {
+                    name,
+                    value: self.to_attribute_value(value)?,
+                    span,
+                });
+            }
+        }
+
+        // Self-closing or with children
+        if self.consume_if(TokenKind::SelfClosing) {
+            self.cst_wrap(cp, SyntaxKind::Element);
+            return Ok(Node::new(
+                NodeKind::Element {
+                    tag,
+                    attributes,
+                    children: Vec::new(),
+                    directives,
+                    key,
+                },
+                self.span_from(start),
+            ));
+        }
+
+        self.consume(TokenKind::GreaterThan)?;
+        let open = self.span_from(start);
+
+        let children = self.parse_children(&tag_name, open, false);
+
+        self.consume_closing_tag(&tag_name);
+        self.cst_wrap(cp, SyntaxKind::Element);
+
+        Ok(Node::new(
+            NodeKind::Element {
+                tag,
+                attributes,
+                children,
+                directives,
+                key,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    /// Parse element body content up to the closing tag of `tag_name`,
+    /// caching child elements when `top_level` is set
+    ///
+    /// `open` is the span of the opening tag, reported if the closing tag
+    /// is missing. Children stop early at the end of input or at the
+    /// closing tag of an enclosing element.
+    fn parse_children(&mut self, tag_name: &str, open: Span, top_level: bool) -> Vec<Node> {
+        let mut children = Vec::new();
+        self.open_tags.push(tag_name.to_string());
+        while !self.is_closing_tag(tag_name) {
+            if self.at_end_of_body() {
+                self.errors
+                    .push(ParseError::MissingClosingTag(tag_name.to_string(), open));
+                break;
+            }
+            children.push(self.parse_child(top_level));
+        }
+        self.open_tags.pop();
+
+        collapse_whitespace(children)
+    }
+
+    /// Parse text, an interpolation, a control-flow block or an element
+    fn parse_child(&mut self, top_level: bool) -> Node {
+        if let Some(TokenKind::RawText(text)) = self.peek_kind() {
+            let span = self.peek_span();
+            self.advance();
+            Node::new(
+                NodeKind::Text {
+                    content: Expression::new(ExpressionKind::Literal(Literal::String(text)), span),
+                },
+                span,
+            )
+        } else if self.at_block_tag(TokenKind::Hash) {
+            self.recover(Sync::Node, Self::parse_control_block)
+                .unwrap_or_else(|span| Node::new(NodeKind::Error, span))
+        } else if self.check(TokenKind::LeftBrace) {
+            let cp = self.cst_start();
+            let start = self.offset();
+            self.advance();
+            let expr = self.parse_braced_expression();
+            self.cst_wrap(cp, SyntaxKind::Interpolation);
+            Node::new(NodeKind::Expression { expr }, self.span_from(start))
+        } else {
+            let parse = if top_level { Self::parse_cached_node } else { Self::parse_node };
+            self.recover(Sync::Node, parse)
+                .unwrap_or_else(|span| Node::new(NodeKind::Error, span))
+        }
+    }
+
+    /// Check whether the current element or block body ends here: at the
+    /// end of input, an enclosing closing tag, or `{:...}`/`{/...}` inside
+    /// a block
+    fn at_end_of_body(&self) -> bool {
+        self.is_at_end()
+            || self.open_tags.iter().any(|tag| self.is_closing_tag(tag))
+            || (self.open_tags.iter().any(|tag| tag.starts_with('#'))
+                && (self.at_block_tag(TokenKind::Colon) || self.at_block_tag(TokenKind::Slash)))
+    }
+
+    // ========================================================================
+    // Control-Flow Blocks
+    // ========================================================================
+
+    /// Parse `{#if}`, `{#each}` or `{#await}` through its closing tag
+    fn parse_control_block(&mut self) -> ParseResult<Node> {
+        let cp = self.cst_start();
+        let tag_cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::LeftBrace)?;
+        self.consume(TokenKind::Hash)?;
+        let (kind, syntax) = match self.peek_kind() {
+            Some(TokenKind::If) => {
+                self.advance();
+                (self.parse_if_block(tag_cp, start), SyntaxKind::IfBlock)
+            }
+            Some(TokenKind::Identifier(name)) if name == "each" => {
+                self.advance();
+                (self.parse_each_block(tag_cp, start)?, SyntaxKind::EachBlock)
+            }
+            Some(TokenKind::Identifier(name)) if name == "await" => {
+                self.advance();
+                (self.parse_await_block(tag_cp, start)?, SyntaxKind::AwaitBlock)
+            }
+            _ => return Err(self.unexpected("`if`, `each` or `await`")),
+        };
+        self.cst_wrap(cp, syntax);
+        Ok(Node::new(kind, self.span_from(start)))
+    }
+
+    /// Parse the rest of `{#if cond}...{/if}` after `if`
+    fn parse_if_block(&mut self, tag_cp: usize, start: usize) -> NodeKind {
+        let condition = self.parse_braced_expression();
+        self.cst_wrap(tag_cp, SyntaxKind::BlockTag);
+        let open = self.span_from(start);
+        let kind = self.parse_if_branches(condition);
+        self.consume_block_close("if", open);
+        kind
+    }
+
+    /// Parse the branches of an if block; `{:else if cond}` nests another
+    /// `IfBlock` as the else branch
+    fn parse_if_branches(&mut self, condition: Expression) -> NodeKind {
+        let then_branch = self.parse_block_body("if");
+        let mut else_branch = None;
+        if self.at_block_tag(TokenKind::Colon) && self.check_text(2, "else") {
+            let cp = self.cst_start();
+            let start = self.offset();
+            let tag = self.recover(Sync::Brace, |p| {
+                p.advance();
+                p.advance();
+                p.advance();
+                let condition = if p.consume_if(TokenKind::If) {
+                    Some(p.parse_expression()?)
+                } else {
+                    None
+                };
+                p.consume(TokenKind::RightBrace)?;
+                Ok(condition)
+            });
+            self.cst_wrap(cp, SyntaxKind::BlockTag);
+            else_branch = Some(match tag {
+                Ok(Some(condition)) => {
+                    let kind = self.parse_if_branches(condition);
+                    vec![Node::new(kind, self.span_from(start))]
+                }
+                Ok(None) | Err(_) => self.parse_block_body("if"),
+            });
+        }
+        NodeKind::IfBlock {
+            condition,
+            then_branch,
+            else_branch,
+        }
+    }
+
+    /// Parse the rest of `{#each items as item, i (key)}...{/each}` after
+    /// `each`
+    fn parse_each_block(&mut self, tag_cp: usize, start: usize) -> ParseResult<NodeKind> {
+        let expression = self.parse_expression()?;
+        self.consume_word("as")?;
+        let binding = self.consume_identifier()?;
+        let index = if self.consume_if(TokenKind::Comma) {
+            Some(self.consume_identifier()?)
+        } else {
+            None
+        };
+        let key = if self.consume_if(TokenKind::LeftParen) {
+            let key = self.parse_expression()?;
+            self.consume(TokenKind::RightParen)?;
+            Some(key)
+        } else {
+            None
+        };
+        self.consume(TokenKind::RightBrace)?;
+        self.cst_wrap(tag_cp, SyntaxKind::BlockTag);
+        let open = self.span_from(start);
+
+        let body = self.parse_block_body("each");
+        self.consume_block_close("each", open);
+        Ok(NodeKind::EachBlock {
+            expression,
+            binding,
+            index,
+            body,
+            key,
+        })
+    }
+
+    /// Parse the rest of `{#await promise}...{/await}` after `await`;
+    /// `{#await promise then value}` has no pending branch
+    fn parse_await_block(&mut self, tag_cp: usize, start: usize) -> ParseResult<NodeKind> {
+        let promise = self.parse_expression()?;
+        let mut clause = self.parse_await_clause();
+        self.consume(TokenKind::RightBrace)?;
+        self.cst_wrap(tag_cp, SyntaxKind::BlockTag);
+        let open = self.span_from(start);
+
+        let mut pending = Vec::new();
+        let mut then_branch = None;
+        let mut catch_branch = None;
+        loop {
+            let body = self.parse_block_body("await");
+            match clause {
+                None => pending = body,
+                Some((AwaitClause::Then, binding)) => then_branch = Some(AwaitBranch { binding, body }),
+                Some((AwaitClause::Catch, binding)) => catch_branch = Some(AwaitBranch { binding, body }),
+            }
+
+            let at_clause = ["then", "catch"].iter().any(|word| self.check_text(2, word));
+            if !self.at_block_tag(TokenKind::Colon) || !at_clause {
+                break;
+            }
+            let cp = self.cst_start();
+            self.advance();
+            self.advance();
+            clause = self.parse_await_clause();
+            if let Err(error) = self.consume(TokenKind::RightBrace) {
+                self.errors.push(error);
+            }
+            self.cst_wrap(cp, SyntaxKind::BlockTag);
+        }
+
+        self.consume_block_close("await", open);
+        Ok(NodeKind::AwaitBlock {
+            promise,
+            pending,
+            then_branch,
+            catch_branch,
+        })
+    }
+
+    /// Parse `then value` or `catch error` if next; the binding is optional
+    fn parse_await_clause(&mut self) -> Option<(AwaitClause, Option<String>)> {
+        let clause = if self.check_text(0, "then") {
+            AwaitClause::Then
+        } else if self.check_text(0, "catch") {
+            AwaitClause::Catch
+        } else {
+            return None;
+        };
+        self.advance();
+        let binding = self.consume_identifier().ok();
+        Some((clause, binding))
+    }
+
+    /// Parse the content of a block up to its next `{:...}` or `{/...}` tag
+    fn parse_block_body(&mut self, name: &str) -> Vec<Node> {
+        let mut children = Vec::new();
+        self.open_tags.push(format!("#{}", name));
+        while !self.at_end_of_body() {
+            children.push(self.parse_child(false));
+        }
+        self.open_tags.pop();
+
+        collapse_whitespace(children)
+    }
+
+    /// Consume `{/name}`, reporting a missing one at the opening tag `open`
+    ///
+    /// `{:...}` tags the block does not accept are reported and skipped
+    /// along with their content.
+    fn consume_block_close(&mut self, name: &str, open: Span) {
+        while self.at_block_tag(TokenKind::Colon) {
+            let expected = format!("{{/{}}}", name);
+            let _ = self.recover(Sync::Brace, |p| {
+                p.advance();
+                Err::<(), _>(p.unexpected(&expected))
+            });
+            self.parse_block_body(name);
+        }
+
+        if self.at_block_tag(TokenKind::Slash) && self.check_text(2, name) {
+            let cp = self.cst_start();
+            self.advance();
+            self.advance();
+            self.advance();
+            if let Err(error) = self.consume(TokenKind::RightBrace) {
+                self.errors.push(error);
+            }
+            self.cst_wrap(cp, SyntaxKind::BlockTag);
+        } else {
+            self.errors.push(ParseError::UnclosedBlock(name.to_string(), open));
+        }
+    }
+
+    /// Parse the rest of `{expr}` after the `{`, recovering at the `}`
+    fn parse_braced_expression(&mut self) -> Expression {
+        self.recover(Sync::Brace, |p| {
+            let expr = p.parse_expression()?;
+            p.consume(TokenKind::RightBrace)?;
+            Ok(expr)
+        })
+        .unwrap_or_else(|span| Expression::new(ExpressionKind::Error, span))
+    }
+
+    /// Parse the rest of `<slot name="..." prop={value}>fallback</slot>`
+    /// after its tag name
+    fn parse_slot(&mut self, cp: usize, start: usize) -> ParseResult<Node> {
+        let mut name = None;
+        let mut props = Vec::new();
+        while !self.check(TokenKind::GreaterThan) && !self.check(TokenKind::SelfClosing) {
+            let (attr, value, span) = self.parse_attribute_pair()?;
+            match (attr.as_str(), value) {
+                ("name", AttrValueRaw::String(value, _)) => name = Some(value),
+                ("name", _) => return Err(ParseError::InvalidAttribute(span)),
+                (_, value) => props.push(Attribute {
+                    name: attr,
+                    value: self.to_attribute_value(value)?,
+                    span,
+                }),
+            }
+        }
+
+        let fallback = if self.consume_if(TokenKind::SelfClosing) {
+            Vec::new()
+        } else {
+            self.consume(TokenKind::GreaterThan)?;
+            let open = self.span_from(start);
+            let children = self.parse_children("slot", open, false);
+            self.consume_closing_tag("slot");
+            children
+        };
+        self.cst_wrap(cp, SyntaxKind::Element);
+        Ok(Node::new(NodeKind::Slot { name, props, fallback }, self.span_from(start)))
+    }
+
+    /// Parse `name`, `name=value` or a `{...props}` spread, returning the
+    /// span of the whole pair
+    fn parse_attribute_pair(&mut self) -> ParseResult<(String, AttrValueRaw, Span)> {
+        let cp = self.cst_start();
+        let start = self.offset();
+        if self.consume_if(TokenKind::LeftBrace) {
+            let spread = self.cst_start();
+            self.consume(TokenKind::Ellipsis)?;
+            let expr = self.parse_expression()?;
+            self.cst_wrap(spread, SyntaxKind::SpreadExpr);
+            self.consume(TokenKind::RightBrace)?;
+            self.cst_wrap(cp, SyntaxKind::Attribute);
+            return Ok(("...".to_string(), AttrValueRaw::Spread(expr), self.span_from(start)));
+        }
+        let mut name = if self.consume_if(TokenKind::At) {
+            format!("@{}", self.consume_identifier()?)
+        } else if self.consume_if(TokenKind::Colon) {
+            format!(":{}", self.consume_identifier()?)
+        } else if self.check(TokenKind::Let) && self.check_text(1, ":") {
+            // `let:item` slot props
+            self.advance();
+            "let".to_string()
+        } else {
+            self.consume_identifier()?
+        };
+        // `prefix:name` directives, written without spaces
+        if self.check(TokenKind::Colon) && self.offset() == self.prev_end() {
+            self.advance();
+            name = format!("{}:{}", name, self.consume_identifier()?);
+        }
+        // Directive modifiers: `@click.once.stop`
+        if name.contains([':', '@']) {
+            while self.check(TokenKind::Dot) && self.offset() == self.prev_end() {
+                self.advance();
+                name = format!("{}.{}", name, self.consume_identifier()?);
+            }
+        }
+        let value = self.parse_attribute_value()?;
+        self.cst_wrap(cp, SyntaxKind::Attribute);
+        Ok((name, value, self.span_from(start)))
+    }
+
+    fn parse_attribute_value(&mut self) -> ParseResult<AttrValueRaw> {
+        if !self.consume_if(TokenKind::Equals) {
+            // Boolean attribute
+            return Ok(AttrValueRaw::Boolean(true));
+        }
+
+        // Check for expression; a broken one ends at the next attribute
+        if self.consume_if(TokenKind::LeftBrace) {
+            let expr = self
+                .recover(Sync::Attribute, |p| {
+                    let expr = p.parse_expression()?;
+                    p.consume(TokenKind::RightBrace)?;
+                    Ok(expr)
+                })
+                .unwrap_or_else(|span| Expression::new(ExpressionKind::Error, span));
+            return Ok(AttrValueRaw::Expression(expr));
+        }
+
+        // String literal
+        let span = self.peek_span();
+        if let Some(TokenKind::StringLiteral(s)) = self.peek_kind() {
+            let s = s.clone();
+            self.advance();
+            return Ok(AttrValueRaw::String(s, span));
+        }
+        if let Some(TokenKind::StringLiteralSingle(s)) = self.peek_kind() {
+            let s = s.clone();
+            self.advance();
+            return Ok(AttrValueRaw::String(s, span));
+        }
+
+        Err(ParseError::InvalidAttribute(span))
+    }
+
+    /// Convert an attribute value to an expression; a valueless attribute
+    /// takes the span of the attribute
+    fn attr_value_to_expr(&self, value: AttrValueRaw, attr_span: Span) -> ParseResult<Expression> {
+        match value {
+            AttrValueRaw::String(s, span) => Ok(Expression::new(ExpressionKind::Literal(Literal::String(s)), span)),
+            AttrValueRaw::Expression(e) | AttrValueRaw::Spread(e) => Ok(e),
+            AttrValueRaw::Boolean(b) => Ok(Expression::new(ExpressionKind::Literal(Literal::Boolean(b)), attr_span)),
+        }
+    }
+
+    fn to_attribute_value(&self, value: AttrValueRaw) -> ParseResult<AttributeValue> {
+        match value {
+            AttrValueRaw::String(s, _) => Ok(AttributeValue::Static(Literal::String(s))),
+            AttrValueRaw::Expression(e) => Ok(AttributeValue::Dynamic(e)),
+            AttrValueRaw::Spread(e) => Ok(AttributeValue::Spread(e)),
+            AttrValueRaw::Boolean(b) => Ok(AttributeValue::Boolean(b)),
+        }
+    }
+
+    fn is_closing_tag(&self, tag_name: &str) -> bool {
+        if !self.check(TokenKind::ClosingTag) {
+            return false;
+        }
+
+        // Look ahead to check tag name; `rect` and `rectangle` are aliases
+        match self.tokens.get(self.pos + 1).map(|t| &t.kind) {
+            Some(TokenKind::Identifier(s)) => {
+                s == tag_name
+                    || matches!(
+                        (s.as_str(), tag_name),
+                        ("rect", "rectangle") | ("rectangle", "rect")
+                    )
+            }
+            _ => false,
+        }
+    }
+
+    // ========================================================================
+    // Style Section
+    // ========================================================================
+
+    fn parse_style_section(&mut self) -> ParseResult<Option<Style>> {
+        if !self.check_tag(TokenKind::LessThan, "style") {
+            return Ok(None);
+        }
+
+        let cp = self.cst_start();
+        let start = self.offset();
+        self.consume(TokenKind::LessThan)?;
+        self.consume_word("style")?;
+        self.consume(TokenKind::GreaterThan)?;
+        let open = self.span_from(start);
+
+        let mut rules = Vec::new();
+        while !self.check_tag(TokenKind::ClosingTag, "style") {
+            if self.is_at_end() || self.at_section_start() {
+                self.errors
+                    .push(ParseError::MissingClosingTag("style".to_string(), open));
+                break;
+            }
+            if let Ok(rule) = self.recover(Sync::Brace, Self::parse_css_rule) {
+                rules.push(rule);
+            }
+        }
+
+        self.consume_closing_tag("style");
+        self.cst_wrap(cp, SyntaxKind::StyleSection);
+
+        Ok(Some(Style { rules, scoped: true }))
+    }
+
+    /// Parse `selector, selector { property: value; ... }`
+    ///
+    /// A malformed selector list is reported without giving up on the
+    /// declarations, so the rule's braces stay balanced for recovery.
+    fn parse_css_rule(&mut self) -> ParseResult<CssRule> {
+        let start = self.offset();
+        let first = self.pos;
+        while !self.check(TokenKind::LeftBrace) {
+            if self.is_at_end() || self.check_tag(TokenKind::ClosingTag, "style") {
+                return Err(self.unexpected("`{` after CSS selector"));
+            }
+            self.advance();
+        }
+        let selector_tokens = &self.tokens[first..self.pos];
+        let selector = css_text(selector_tokens);
+        let selectors = match parse_selector_list(selector_tokens) {
+            Ok(selectors) => selectors,
+            Err(error) => {
+                self.errors.push(error);
+                Vec::new()
+            }
+        };
+
+        self.consume(TokenKind::LeftBrace)?;
+        let mut declarations = Vec::new();
+        while !self.check(TokenKind::RightBrace) {
+            declarations.push(self.parse_css_declaration()?);
+        }
+        self.consume(TokenKind::RightBrace)?;
+
+        Ok(CssRule {
+            selector,
+            selectors,
+            declarations,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parse `property: value;`; the `;` is optional before `}`
+    fn parse_css_declaration(&mut self) -> ParseResult<CssDeclaration> {
+        let start = self.offset();
+        let mut end = self.pos;
+        let property = css_word(&self.tokens, &mut end).ok_or_else(|| self.unexpected("CSS property"))?;
+        while self.pos < end {
+            self.advance();
+        }
+        self.consume(TokenKind::Colon)?;
+
+        let first = self.pos;
+        while !self.check(TokenKind::Semicolon) && !self.check(TokenKind::RightBrace) {
+            if self.is_at_end() || self.check_tag(TokenKind::ClosingTag, "style") {
+                return Err(self.unexpected("`;` or `}`"));
+            }
+            self.advance();
+        }
+        if self.pos == first {
+            return Err(self.unexpected("CSS value"));
+        }
+        let value = css_text(&self.tokens[first..self.pos]);
+        self.consume_if(TokenKind::Semicolon);
+
+        Ok(CssDeclaration {
+            property,
+            value,
+            span: self.span_from(start),
+        })
+    }
+
+    // ========================================================================
+    // Helper Methods
+    // ========================================================================
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self) -> Option<TokenKind> {
+        self.peek().map(|t| t.kind.clone())
+    }
+
+    /// Span of the next token, or an empty span at the end of input
+    fn peek_span(&self) -> Span {
+        self.peek().map_or_else(|| Span::new(self.prev_end(), self.prev_end()), |t| t.span)
+    }
+
+    /// Byte offset where the next token starts
+    fn offset(&self) -> usize {
+        self.peek_span().start
+    }
+
+    /// End of the last consumed token
+    fn prev_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(0, |t| t.span.end)
+    }
+
+    /// Span from `start` to the end of the last consumed token
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.prev_end().max(start))
+    }
+
+    /// Error for the next token not being `expected`
+    fn unexpected(&self, expected: &str) -> ParseError {
+        ParseError::UnexpectedToken {
+            expected: expected.to_string(),
+            found: self.peek().map(|t| format!("{:?}", t.kind)).unwrap_or("EOF".to_string()),
+            span: self.peek_span(),
+        }
+    }
+
+    fn advance(&mut self) -> Option<&Token> {
+        if !self.is_at_end() {
+            if let Some(cst) = &mut self.cst {
+                cst.token(&self.tokens[self.pos]);
+            }
+            self.pos += 1;
+        }
+        self.tokens.get(self.pos - 1)
+    }
+
+    /// Mark the start of a CST node at the next token
+    fn cst_start(&mut self) -> usize {
+        let next = self.peek().map(|t| t.span.start);
+        self.cst.as_mut().map_or(0, |cst| cst.checkpoint(next))
+    }
+
+    /// Close a CST node started with `cst_start`
+    fn cst_wrap(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        if let Some(cst) = &mut self.cst {
+            cst.wrap(checkpoint, kind);
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn check(&self, kind: TokenKind) -> bool {
+        self.peek_kind() == Some(kind)
+    }
+
+    fn consume(&mut self, kind: TokenKind) -> ParseResult<Token> {
+        if self.check(kind.clone()) {
+            Ok(self.advance().unwrap().clone())
+        } else {
+            Err(self.unexpected(&format!("{:?}", kind)))
+        }
+    }
+
+    fn consume_if(&mut self, kind: TokenKind) -> bool {
+        if self.check(kind) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_identifier(&mut self) -> ParseResult<String> {
+        if let Some(TokenKind::Identifier(s)) = self.peek_kind() {
+            self.advance();
+            Ok(s)
+        } else {
+            Err(self.unexpected("identifier"))
+        }
+    }
+
+    /// Consume an identifier token spelling the contextual keyword `word`
+    fn consume_word(&mut self, word: &str) -> ParseResult<Token> {
+        match self.peek_kind() {
+            Some(TokenKind::Identifier(s)) if s == word => Ok(self.advance().unwrap().clone()),
+            _ => Err(self.unexpected(word)),
+        }
+    }
+
+    /// Consume `</name>` if it is next; a missing closing tag has already
+    /// been reported by the loop that stopped before it
+    fn consume_closing_tag(&mut self, name: &str) {
+        if self.is_closing_tag(name) {
+            self.advance();
+            self.advance();
+            if let Err(error) = self.consume(TokenKind::GreaterThan) {
+                self.errors.push(error);
+            }
+        }
+    }
+
+    /// Check for the opening tag of a section
+    fn at_section_start(&self) -> bool {
+        ["script", "canvas", "style"]
+            .iter()
+            .any(|name| self.check_tag(TokenKind::LessThan, name))
+    }
+
+    /// Run `parse`, recovering from an error by recording it and skipping
+    /// to the next synchronization point
+    ///
+    /// On error, returns the span of everything consumed and skipped; the
+    /// CST wraps those tokens in an `Error` node.
+    fn recover<T>(&mut self, sync: Sync, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> Result<T, Span> {
+        let cp = self.cst_start();
+        let (start, offset) = (self.pos, self.offset());
+        match parse(self) {
+            Ok(value) => Ok(value),
+            Err(error) => {
+                trace!(%error, "Recovering from parse error");
+                self.errors.push(error);
+                self.synchronize(sync, start);
+                // An attribute value may stop where it started, after its `{`
+                if self.pos == start && !self.is_at_end() && sync != Sync::Attribute {
+                    self.advance();
+                }
+                self.cst_wrap(cp, SyntaxKind::Error);
+                Err(self.span_from(offset))
+            }
+        }
+    }
+
+    /// Skip tokens up to the synchronization point of `sync`
+    ///
+    /// Tokens the parse stopped before are only synchronization points once
+    /// something has been skipped, so recovery always makes progress.
+    fn synchronize(&mut self, sync: Sync, start: usize) {
+        let mut depth = 0usize;
+        while let Some(kind) = self.peek_kind() {
+            let skipped = self.pos > start;
+            match kind {
+                TokenKind::Semicolon if sync == Sync::Statement && depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                TokenKind::LeftBrace | TokenKind::TemplateExprStart => depth += 1,
+                TokenKind::RightBrace if depth > 0 => depth -= 1,
+                TokenKind::RightBrace if matches!(sync, Sync::Brace | Sync::Attribute) => {
+                    self.advance();
+                    return;
+                }
+                TokenKind::RightBrace if sync == Sync::Statement => {
+                    // A stray `}` is skipped, otherwise it closes the block
+                    if !skipped {
+                        self.advance();
+                    }
+                    return;
+                }
+                TokenKind::SelfClosing | TokenKind::ClosingTag | TokenKind::LessThan
+                    if sync == Sync::Attribute && depth == 0 =>
+                {
+                    return;
+                }
+                TokenKind::Identifier(_)
+                    if sync == Sync::Attribute
+                        && depth == 0
+                        && self.tokens.get(self.pos + 1).is_some_and(|t| t.kind == TokenKind::Equals) =>
+                {
+                    return;
+                }
+                TokenKind::ClosingTag if skipped => return,
+                TokenKind::LessThan if skipped && sync == Sync::Node => return,
+                TokenKind::LessThan if skipped && self.at_section_start() => return,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Check for `{` followed by `kind`, as in `{#if`, `{:else}` and `{/if}`
+    fn at_block_tag(&self, kind: TokenKind) -> bool {
+        self.check(TokenKind::LeftBrace) && self.tokens.get(self.pos + 1).is_some_and(|t| t.kind == kind)
+    }
+
+    /// Check whether the token `ahead` positions away is spelled `word`,
+    /// whether it is a keyword or an identifier
+    fn check_text(&self, ahead: usize, word: &str) -> bool {
+        self.tokens.get(self.pos + ahead).is_some_and(|t| t.text == word)
+    }
+
+    /// Check for `opener` (`<` or `</`) followed by the tag name `name`
+    fn check_tag(&self, opener: TokenKind, name: &str) -> bool {
+        self.check(opener)
+            && matches!(
+                self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                Some(TokenKind::Identifier(s)) if s == name
+            )
+    }
+
+    fn consume_element_tag(&mut self) -> ParseResult<String> {
+        if let Some(TokenKind::Identifier(s)) = self.peek_kind() {
+            self.advance();
+            return Ok(s);
+        }
+
+        Err(self.unexpected("element tag"))
+    }
+}
+
+/// Where panic-mode recovery resumes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sync {
+    /// After the next `;`, or before the `}` closing the enclosing block
+    Statement,
+    /// After the `}` closing the current `{...}`
+    Brace,
+    /// Before the next `<` or `</`
+    Node,
+    /// After the `}` closing an attribute value, or before the next
+    /// `name=` or the end of the tag
+    Attribute,
+}
+
+/// Branch introduced by `then` or `catch` in an await block
+#[derive(Debug, Clone, Copy)]
+enum AwaitClause {
+    Then,
+    Catch,
+}
+
+#[derive(Debug)]
+enum AttrValueRaw {
+    String(String, Span),
+    Expression(Expression),
+    Spread(Expression),
+    Boolean(bool),
+}
+
+fn binary(left: Expression, op: BinaryOp, right: Expression) -> Expression {
+    let span = left.span.merge(right.span);
+    Expression::new(
+        ExpressionKind::Binary {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        },
+        span,
+    )
+}
+
+/// Only variables, properties and indexed elements can be assigned to
+fn check_assignment_target(target: &Expression) -> ParseResult<()> {
+    match target.kind {
+        ExpressionKind::Identifier(_)
+        | ExpressionKind::Member { optional: false, .. }
+        | ExpressionKind::Index { optional: false, .. } => Ok(()),
+        _ => Err(ParseError::InvalidAssignmentTarget(target.span)),
+    }
+}
+
+/// Collapse whitespace in text children the way HTML does
+///
+/// Whitespace runs become a single space. Text is trimmed where it borders
+/// an element or the edge of its parent, so indentation between tags
+/// disappears while `{a} {b}` keeps its space.
+fn collapse_whitespace(children: Vec<Node>) -> Vec<Node> {
+    let inline: Vec<bool> = children
+        .iter()
+        .map(|node| matches!(node.kind, NodeKind::Text { .. } | NodeKind::Expression { .. }))
+        .collect();
+
+    let mut result = Vec::with_capacity(children.len());
+    for (i, node) in children.into_iter().enumerate() {
+        let NodeKind::Text {
+            content:
+                Expression {
+                    kind: ExpressionKind::Literal(Literal::String(text)),
+                    span,
+                },
+        } = node.kind
+        else {
+            result.push(node);
+            continue;
+        };
+
+        let prev_inline = i > 0 && inline[i - 1];
+        let next_inline = inline.get(i + 1) == Some(&true);
+        let mut collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            // Whitespace alone only separates two inline neighbours
+            if !text.is_empty() && prev_inline && next_inline {
+                collapsed.push(' ');
+            }
+        } else {
+            if text.starts_with(char::is_whitespace) && prev_inline {
+                collapsed.insert(0, ' ');
+            }
+            if text.ends_with(char::is_whitespace) && next_inline {
+                collapsed.push(' ');
+            }
+        }
+        if !collapsed.is_empty() {
+            result.push(Node::new(
+                NodeKind::Text {
+                    content: Expression::new(ExpressionKind::Literal(Literal::String(collapsed)), span),
+                },
+                node.span,
+            ));
+        }
+    }
+    result
+}
+
+/// Text of CSS tokens, with a space wherever the source had whitespace
+/// or comments between them
+fn css_text(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && tokens[i - 1].span.end < token.span.start {
+            text.push(' ');
+        }
+        text.push_str(&token.text);
+    }
+    text
+}
+
+/// Read a CSS identifier such as `font-size` or `col-2` starting at
+/// `tokens[*i]`; the lexer splits it at every `-`, so adjacent tokens are
+/// joined
+fn css_word(tokens: &[Token], i: &mut usize) -> Option<String> {
+    let is_word = |token: &Token| {
+        token
+            .text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    };
+    let mut word = String::new();
+    while let Some(token) = tokens.get(*i) {
+        let adjacent = word.is_empty() || tokens[*i - 1].span.end == token.span.start;
+        if !adjacent || !is_word(token) {
+            break;
+        }
+        word.push_str(&token.text);
+        *i += 1;
+    }
+    (!word.is_empty()).then_some(word)
+}
+
+/// Parse a comma-separated list of tag, `.class`, `#id` and `*`
+/// selectors, combined with the descendant combinator
+fn parse_selector_list(tokens: &[Token]) -> ParseResult<Vec<Selector>> {
+    let mut selectors = Vec::new();
+    for group in tokens.split(|token| token.kind == TokenKind::Comma) {
+        let mut compounds: Vec<CompoundSelector> = Vec::new();
+        let mut i = 0;
+        while i < group.len() {
+            // Whitespace between tokens starts the next compound
+            if i == 0 || group[i - 1].span.end < group[i].span.start {
+                compounds.push(CompoundSelector::default());
+            }
+            let compound = compounds.last_mut().expect("pushed above");
+            let token = &group[i];
+            let unexpected = |token: &Token| ParseError::UnexpectedToken {
+                expected: "CSS selector".to_string(),
+                found: token.text.clone(),
+                span: token.span,
+            };
+            match token.kind {
+                TokenKind::Star => i += 1,
+                TokenKind::Dot | TokenKind::Hash => {
+                    i += 1;
+                    let adjacent = group.get(i).is_some_and(|next| next.span.start == token.span.end);
+                    let name = adjacent
+                        .then(|| css_word(group, &mut i))
+                        .flatten()
+                        .ok_or_else(|| unexpected(group.get(i).unwrap_or(token)))?;
+                    if token.kind == TokenKind::Dot {
+                        compound.classes.push(name);
+                    } else {
+                        compound.id = Some(name);
+                    }
+                }
+                _ => match css_word(group, &mut i) {
+                    Some(tag) if compound == &CompoundSelector::default() => compound.tag = Some(tag),
+                    _ => return Err(unexpected(token)),
+                },
+            }
+        }
+        match group.first() {
+            Some(_) => selectors.push(Selector { compounds }),
+            None => {
+                let span = tokens.first().map_or_else(Span::default, |t| t.span);
+                return Err(ParseError::UnexpectedToken {
+                    expected: "CSS selector".to_string(),
+                    found: ",".to_string(),
+                    span,
+                });
+            }
+        }
+    }
+    Ok(selectors)
+}
+
+/// Props declared by `export let` at the top level of a script
+fn declared_props(statements: &[Statement]) -> Vec<Prop> {
+    statements
+        .iter()
+        .filter_map(|statement| match &statement.kind {
+            StatementKind::Export(declaration) => match &declaration.kind {
+                StatementKind::VariableDeclaration {
+                    kind: VarKind::Let,
+                    name,
+                    ty,
+                    init,
+                    ..
+                } => Some(Prop {
+                    name: name.clone(),
+                    ty: ty.clone(),
+                    default: init.clone(),
+                    span: statement.span,
+                }),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The export made by a top-level statement
+fn export_of(statement: &Statement) -> Option<Export> {
+    let StatementKind::Export(declaration) = &statement.kind else {
+        return None;
+    };
+    match &declaration.kind {
+        StatementKind::VariableDeclaration { name, init, .. } => Some(Export {
+            name: name.clone(),
+            value: init.clone(),
+        }),
+        StatementKind::FunctionDeclaration { name, .. } => Some(Export {
+            name: name.clone(),
+            value: None,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse(source: &str) -> ParseResult<Component> {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::new(tokens, "test.omni").parse()
+    }
+
+    #[test]
+    fn test_parse_simple_canvas() {
+        let source = r##"
+<canvas width={800} height={600}>
+  <circle x={400} y={300} radius={50} fill="#00d4ff" />
+</canvas>
+"##;
+
+        let result = parse(source);
+        assert!(result.is_ok(), "Parse failed: {:?}", result.err());
+
+        let component = result.unwrap();
+        assert_eq!(component.template.children.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_script_section() {
+        let source = r#"
+<script>
+  const count = signal(0);
+  const doubled = memo(() => count() * 2);
+</script>
+
+<canvas width={800} height={600}>
+</canvas>
+"#;
+
+        let result = parse(source);
+        assert!(result.is_ok(), "Parse failed: {:?}", result.err());
+
+        let component = result.unwrap();
+        assert!(component.script.is_some());
+        assert_eq!(component.script.unwrap().statements.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_template_literal() {
+        let source = r#"
+<canvas>
+  <text content={`Score: ${score() * 2} pts`} />
+</canvas>
+"#;
+
+        let component = parse(source).unwrap();
+        let NodeKind::Element { attributes, .. } = &component.template.children[0].kind else {
+            panic!("expected element");
+        };
+        let AttributeValue::Dynamic(Expression {
+            kind: ExpressionKind::Template { parts },
+            ..
+        }) = &attributes[0].value
+        else {
+            panic!("expected template expression");
+        };
+
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], TemplatePart::String("Score: ".to_string()));
+        assert!(matches!(&parts[1], TemplatePart::Expression(e) if matches!(e.kind, ExpressionKind::Binary { .. })));
+        assert_eq!(parts[2], TemplatePart::String(" pts".to_string()));
+    }
+
+    #[test]
+    fn test_lone_whitespace_beside_one_inline_child() {
+        let source = r#"
+<canvas>
+  <text>
+    {name()}
+  </text>
+  <text>
+    {a()} {b()}
+  </text>
+</canvas>
+"#;
+
+        let component = parse(source).unwrap();
+        let texts = &component.template.children;
+        let NodeKind::Element { children, .. } = &texts[0].kind else {
+            panic!("expected element");
+        };
+        assert_eq!(children.len(), 1);
+        assert!(matches!(children[0].kind, NodeKind::Expression { .. }));
+
+        let NodeKind::Element { children, .. } = &texts[1].kind else {
+            panic!("expected element");
+        };
+        assert_eq!(children.len(), 3);
+        let NodeKind::Text { content } = &children[1].kind else {
+            panic!("expected text");
+        };
+        assert_eq!(content.kind, ExpressionKind::Literal(Literal::String(" ".to_string())));
+    }
+
+    #[test]
+    fn test_parse_text_children() {
+        let source = r#"
+<canvas>
+  <text x={10}>
+    Hello,   {name()}
+    {suffix()} !
+  </text>
+</canvas>
+"#;
+
+        let component = parse(source).unwrap();
+        assert_eq!(component.template.children.len(), 1);
+        let NodeKind::Element { children, .. } = &component.template.children[0].kind else {
+            panic!("expected element");
+        };
+
+        let text = |node: &Node| match &node.kind {
+            NodeKind::Text { content } => match &content.kind {
+                ExpressionKind::Literal(Literal::String(s)) => Some(s.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        assert_eq!(children.len(), 5);
+        assert_eq!(text(&children[0]).as_deref(), Some("Hello, "));
+        assert!(matches!(children[1].kind, NodeKind::Expression { .. }));
+        assert_eq!(text(&children[2]).as_deref(), Some(" "));
+        assert!(matches!(children[3].kind, NodeKind::Expression { .. }));
+        assert_eq!(text(&children[4]).as_deref(), Some(" !"));
+    }
+
+    #[test]
+    fn test_parse_subtraction_and_negation() {
+        let source = "<script> const a = b-1; const c = -45deg; </script>";
+        let script = parse(source).unwrap().script.unwrap();
+
+        let StatementKind::VariableDeclaration { init: Some(a), .. } = &script.statements[0].kind else {
+            panic!("expected declaration");
+        };
+        assert!(matches!(a.kind, ExpressionKind::Binary { op: BinaryOp::Sub, .. }));
+
+        let StatementKind::VariableDeclaration { init: Some(c), .. } = &script.statements[1].kind else {
+            panic!("expected declaration");
+        };
+        assert_eq!(
+            *c,
+            Expression::new(
+                ExpressionKind::Unary {
+                    op: UnaryOp::Neg,
+                    operand: Box::new(Expression::new(
+                        ExpressionKind::Literal(Literal::Dimension(45.0, Unit::Deg)),
+                        Span::new(35, 40),
+                    )),
+                },
+                Span::new(34, 40),
+            )
+        );
+    }
+
+    #[test]
+    fn test_element_names_as_identifiers() {
+        let names = [
+            "circle", "rectangle", "rect", "ellipse", "line", "path", "polygon", "text", "image",
+            "group", "style", "script", "canvas",
+        ];
+
+        for name in names {
+            // Section names are not element tags, so only use real tags as elements
+            let element = if ElementTag::from_str(name).is_some() {
+                format!("<{name} />")
+            } else {
+                String::new()
+            };
+            let source = format!(
+                "<script> const {name} = item.{name}; </script><canvas><group>{element}</group></canvas>"
+            );
+            let component = parse(&source).unwrap_or_else(|e| panic!("`{name}` failed: {e}"));
+
+            let script = component.script.unwrap();
+            let StatementKind::VariableDeclaration { name: var, init: Some(init), .. } = &script.statements[0].kind
+            else {
+                panic!("expected declaration for `{name}`");
+            };
+            assert_eq!(var, name);
+            assert!(
+                matches!(&init.kind, ExpressionKind::Member { property, .. } if property == name),
+                "unexpected init for `{name}`: {init:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let source = r#"<script>
+  const total = (a + b) * 2;
+  function reset() { count.set(0); }
+</script>
+<canvas>
+  <circle x={10} visible />
+  <text>Hi {name}</text>
+</canvas>"#;
+        let component = parse(source).unwrap();
+        let text = |span: Span| &source[span.start..span.end];
+
+        let script = component.script.unwrap();
+        assert_eq!(text(script.statements[0].span), "const total = (a + b) * 2;");
+        let StatementKind::VariableDeclaration { init: Some(init), .. } = &script.statements[0].kind else {
+            panic!("expected declaration");
+        };
+        assert_eq!(text(init.span), "(a + b) * 2");
+        let ExpressionKind::Binary { left, .. } = &init.kind else {
+            panic!("expected binary expression");
+        };
+        assert_eq!(text(left.span), "(a + b)");
+
+        assert_eq!(text(script.statements[1].span), "function reset() { count.set(0); }");
+        let StatementKind::FunctionDeclaration { body, .. } = &script.statements[1].kind else {
+            panic!("expected function");
+        };
+        assert_eq!(text(body[0].span), "count.set(0);");
+
+        let circle = &component.template.children[0];
+        assert_eq!(text(circle.span), "<circle x={10} visible />");
+        let NodeKind::Element { attributes, .. } = &circle.kind else {
+            panic!("expected element");
+        };
+        assert_eq!(text(attributes[0].span), "x={10}");
+        assert_eq!(text(attributes[1].span), "visible");
+
+        let label = &component.template.children[1];
+        assert_eq!(text(label.span), "<text>Hi {name}</text>");
+        let NodeKind::Element { children, .. } = &label.kind else {
+            panic!("expected element");
+        };
+        assert_eq!(text(children[0].span), "Hi ");
+        assert_eq!(text(children[1].span), "{name}");
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let span_of = |source: &str| parse(source).unwrap_err().span();
+
+        let source = "<canvas><circle x={1 +} /></canvas>";
+        let span = span_of(source);
+        assert_eq!(&source[span.start..span.end], "}");
+
+        let source = "<canvas><circle x={1} /><blob /></canvas>";
+        let span = span_of(source);
+        assert_eq!(&source[span.start..span.end], "blob");
+
+        let source = "<canvas><group><circle />";
+        let span = span_of(source);
+        assert_eq!(&source[span.start..span.end], "<group>");
+
+        let source = "<script> const x = ; </script>";
+        let error = parse(source).unwrap_err();
+        assert!(matches!(error, ParseError::InvalidExpression(_)));
+        assert_eq!(&source[error.span().start..error.span().end], ";");
+    }
+
+    fn parse_recovering(source: &str) -> (Component, Vec<ParseError>) {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::new(tokens, "Test.omni").parse_with_recovery()
+    }
+
+    #[test]
+    fn test_recovers_multiple_errors() {
+        let source = r#"<script>
+  const a = ;
+  const b = 2;
+  function f() { let c = * 3; return c; }
+</script>
+<canvas width={800}>
+  <circle x={1 +} y={2} />
+  <blob />
+  <rect x={3} />
+  <rect x={1 y={2} />
+  <rect width={2 />
+</canvas>"#;
+        let (component, errors) = parse_recovering(source);
+        let texts: Vec<&str> = errors
+            .iter()
+            .map(|e| &source[e.span().start..e.span().end])
+            .collect();
+        assert_eq!(texts, [";", "*", "}", "blob", "y", "/>"]);
+
+        let statements = &component.script.unwrap().statements;
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(statements[0].kind, StatementKind::Error));
+        assert!(matches!(&statements[1].kind, StatementKind::VariableDeclaration { name, .. } if name == "b"));
+        match &statements[2].kind {
+            StatementKind::FunctionDeclaration { body, .. } => {
+                assert!(matches!(body[0].kind, StatementKind::Error));
+                assert!(matches!(body[1].kind, StatementKind::Return(_)));
+            }
+            other => panic!("expected function, got {:?}", other),
+        }
+
+        let children = &component.template.children;
+        assert_eq!(children.len(), 5);
+        match &children[0].kind {
+            NodeKind::Element { attributes, .. } => {
+                assert!(matches!(attributes[0].value, AttributeValue::Dynamic(Expression { kind: ExpressionKind::Error, .. })));
+                assert_eq!(attributes[1].name, "y");
+            }
+            other => panic!("expected element, got {:?}", other),
+        }
+        assert!(matches!(children[1].kind, NodeKind::Error));
+        assert!(matches!(&children[2].kind, NodeKind::Element { tag, .. } if *tag == ElementTag::Rectangle));
+
+        // An unclosed attribute value ends at the next attribute or the tag end
+        match &children[3].kind {
+            NodeKind::Element { attributes, .. } => {
+                assert!(matches!(attributes[0].value, AttributeValue::Dynamic(Expression { kind: ExpressionKind::Error, .. })));
+                assert_eq!(attributes[1].name, "y");
+            }
+            other => panic!("expected element, got {:?}", other),
+        }
+        assert!(matches!(&children[4].kind, NodeKind::Element { attributes, .. } if attributes.len() == 1));
+    }
+
+    #[test]
+    fn test_recovers_missing_closing_tags() {
+        let source = "<canvas><group><circle></group><rect /></canvas>";
+        let (component, errors) = parse_recovering(source);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], ParseError::MissingClosingTag(tag, _) if tag == "circle"));
+        assert_eq!(component.template.children.len(), 2);
+
+        let source = "<script>\n  const a = 1;\n<canvas><rect /></canvas>";
+        let (component, errors) = parse_recovering(source);
+        assert!(matches!(&errors[0], ParseError::MissingClosingTag(tag, _) if tag == "script"));
+        assert_eq!(component.script.unwrap().statements.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_loops_and_assignment() {
+        let source = r#"<script>
+  let total = 0;
+  for (let i = 0; i < 10; i++) {
+    if (i == 5) { continue; }
+    total += i;
+  }
+  while (total > 0) { total = total - 1; break; }
+  for (;;) { --total; }
+  { const inner = 1; }
+</script>"#;
+        let component = parse(source).unwrap();
+        let statements = component.script.unwrap().statements;
+        assert_eq!(statements.len(), 5);
+
+        let StatementKind::For { init: Some(init), condition: Some(_), update: Some(update), body } = &statements[1].kind else {
+            panic!("expected for loop, got {:?}", statements[1].kind);
+        };
+        assert!(matches!(&init.kind, StatementKind::VariableDeclaration { name, kind: VarKind::Let, .. } if name == "i"));
+        assert!(matches!(update.kind, ExpressionKind::Update { op: UpdateOp::Increment, prefix: false, .. }));
+        assert_eq!(&source[update.span.start..update.span.end], "i++");
+        assert!(matches!(&body[1].kind, StatementKind::Expression(Expression { kind: ExpressionKind::Assign { op: AssignOp::Add, .. }, .. })));
+
+        let StatementKind::While { body, .. } = &statements[2].kind else {
+            panic!("expected while loop");
+        };
+        assert!(matches!(body[1].kind, StatementKind::Break));
+
+        let StatementKind::For { init: None, condition: None, update: None, body } = &statements[3].kind else {
+            panic!("expected empty for header");
+        };
+        assert!(matches!(&body[0].kind, StatementKind::Expression(Expression { kind: ExpressionKind::Update { prefix: true, .. }, .. })));
+        assert!(matches!(&statements[4].kind, StatementKind::Block(body) if body.len() == 1));
+    }
+
+    #[test]
+    fn test_parse_single_statement_bodies() {
+        let source = r#"<script>
+  function sign(x) {
+    if (x < 0) return -1;
+    else if (x == 0) return 0;
+    else return 1;
+  }
+  for (let i = 0; i < 3; i++) total += i;
+  while (total > 0) total--;
+</script>"#;
+        let component = parse(source).unwrap();
+        let statements = component.script.unwrap().statements;
+
+        let StatementKind::FunctionDeclaration { body, .. } = &statements[0].kind else {
+            panic!("expected function");
+        };
+        let StatementKind::If { then_branch, else_branch: Some(else_branch), .. } = &body[0].kind else {
+            panic!("expected if, got {:?}", body[0].kind);
+        };
+        assert!(matches!(then_branch[..], [Statement { kind: StatementKind::Return(Some(_)), .. }]));
+        let StatementKind::If { else_branch: Some(last), .. } = &else_branch[0].kind else {
+            panic!("expected else if");
+        };
+        assert!(matches!(last[..], [Statement { kind: StatementKind::Return(Some(_)), .. }]));
+
+        assert!(matches!(&statements[1].kind, StatementKind::For { body, .. } if body.len() == 1));
+        assert!(matches!(&statements[2].kind, StatementKind::While { body, .. } if body.len() == 1));
+
+        let error = parse("<script>if (x) const y = 1;</script>").unwrap_err();
+        assert!(error.to_string().contains("declarations need braces"), "{}", error);
+    }
+
+    #[test]
+    fn test_assignment_is_right_associative() {
+        let component = parse("<script>a = b.c = 1;</script>").unwrap();
+        let statements = component.script.unwrap().statements;
+        let StatementKind::Expression(expr) = &statements[0].kind else {
+            panic!("expected expression statement");
+        };
+        let ExpressionKind::Assign { target, value, .. } = &expr.kind else {
+            panic!("expected assignment");
+        };
+        assert_eq!(target.kind, ExpressionKind::Identifier("a".to_string()));
+        assert!(matches!(value.kind, ExpressionKind::Assign { .. }));
+
+        let error = parse("<script>a + 1 = 2;</script>").unwrap_err();
+        assert!(matches!(error, ParseError::InvalidAssignmentTarget(_)));
+    }
+
+    #[test]
+    fn test_parse_objects_and_destructuring() {
+        let source = r#"<script>
+  const shape = { type: "circle", x, [key]: 1, ...rest, default: 2 };
+  const { x: left = 0, y, ...others } = shape;
+  const [first, , ...tail] = [...items, 4];
+  const area = ({ w, h }, [a, b] = pair) => w * h;
+</script>"#;
+        let component = parse(source).unwrap();
+        let statements = component.script.unwrap().statements;
+
+        let StatementKind::VariableDeclaration { init: Some(init), .. } = &statements[0].kind else {
+            panic!("expected declaration");
+        };
+        let ExpressionKind::Object(properties) = &init.kind else {
+            panic!("expected object literal, got {:?}", init.kind);
+        };
+        assert_eq!(properties.len(), 5);
+        assert!(matches!(&properties[1], ObjectProperty::KeyValue { key: PropertyKey::Static(k), value }
+            if k == "x" && value.kind == ExpressionKind::Identifier("x".to_string())));
+        assert!(matches!(&properties[2], ObjectProperty::KeyValue { key: PropertyKey::Computed(_), .. }));
+        assert!(matches!(&properties[3], ObjectProperty::Spread(_)));
+        assert!(matches!(&properties[4], ObjectProperty::KeyValue { key: PropertyKey::Static(k), .. } if k == "default"));
+
+        let StatementKind::Destructure { kind: VarKind::Const, pattern, .. } = &statements[1].kind else {
+            panic!("expected destructuring, got {:?}", statements[1].kind);
+        };
+        assert_eq!(pattern.bound_names(), vec!["left", "y", "others"]);
+        let Pattern::Object { properties, rest } = pattern else {
+            panic!("expected object pattern");
+        };
+        assert_eq!(properties[0].0, "x");
+        assert!(properties[0].1.default.is_some());
+        assert_eq!(rest.as_deref(), Some("others"));
+
+        let StatementKind::Destructure { pattern: Pattern::Array { elements, rest }, init, .. } = &statements[2].kind else {
+            panic!("expected array destructuring");
+        };
+        assert!(elements[1].is_none());
+        assert_eq!(rest.as_deref(), Some("tail"));
+        assert!(matches!(&init.kind, ExpressionKind::Array(items) if matches!(items[0].kind, ExpressionKind::Spread(_))));
+
+        let StatementKind::VariableDeclaration { init: Some(init), .. } = &statements[3].kind else {
+            panic!("expected declaration");
+        };
+        let ExpressionKind::Arrow { params, .. } = &init.kind else {
+            panic!("expected arrow function");
+        };
+        assert!(matches!(params[0].pattern, Pattern::Object { .. }));
+        assert!(matches!(params[1].pattern, Pattern::Array { .. }) && params[1].default.is_some());
+    }
+
+    #[test]
+    fn test_parse_optional_chaining_and_nullish() {
+        let component = parse("<script>const v = a?.b.c?.[0]?.(1) ?? (x => x);</script>").unwrap();
+        let statements = component.script.unwrap().statements;
+        let StatementKind::VariableDeclaration { init: Some(init), .. } = &statements[0].kind else {
+            panic!("expected declaration");
+        };
+        let ExpressionKind::Binary { left, op: BinaryOp::Nullish, .. } = &init.kind else {
+            panic!("expected `??`, got {:?}", init.kind);
+        };
+        let ExpressionKind::Call { callee, optional: true, .. } = &left.kind else {
+            panic!("expected optional call");
+        };
+        let ExpressionKind::Index { object, optional: true, .. } = &callee.kind else {
+            panic!("expected optional index");
+        };
+        assert!(matches!(&object.kind, ExpressionKind::Member { optional: false, object, .. }
+            if matches!(object.kind, ExpressionKind::Member { optional: true, .. })));
+
+        let error = parse("<script>a?.b = 1;</script>").unwrap_err();
+        assert!(matches!(error, ParseError::InvalidAssignmentTarget(_)));
+    }
+
+    #[test]
+    fn test_parse_type_annotations() {
+        let source = r#"<script>
+  let total: number;
+  const colors = signal<Color[]>([]);
+  const state: Signal<string | null> = signal(null);
+  function describe(shape: { x: number; y: number }, cb: (n: number) => void = noop): string {
+    return "";
+  }
+  const double = (n: number) => n * 2;
+</script>"#;
+        let component = parse(source).unwrap();
+        let statements = component.script.unwrap().statements;
+
+        let StatementKind::VariableDeclaration { ty: Some(Type::Number), init: None, .. } = &statements[0].kind else {
+            panic!("expected annotated declaration, got {:?}", statements[0].kind);
+        };
+
+        let StatementKind::VariableDeclaration { ty: None, init: Some(init), reactive: ReactiveKind::Signal, .. } =
+            &statements[1].kind
+        else {
+            panic!("expected signal declaration");
+        };
+        let ExpressionKind::Call { type_args, .. } = &init.kind else {
+            panic!("expected call");
+        };
+        let color_list = Type::Array(Box::new(Type::Custom("Color".to_string())));
+        assert_eq!(type_args, &vec![color_list.clone()]);
+        assert_eq!(statements[1].kind.declared_type(), Some(&color_list));
+
+        let nullable = Type::Union(vec![Type::String, Type::Custom("null".to_string())]);
+        assert_eq!(statements[2].kind.declared_type(), Some(&nullable));
+
+        let StatementKind::FunctionDeclaration { params, return_type, .. } = &statements[3].kind else {
+            panic!("expected function");
+        };
+        assert_eq!(return_type, &Some(Type::String));
+        assert!(matches!(&params[0].ty, Some(Type::Object(fields)) if fields.len() == 2));
+        assert_eq!(
+            params[1].ty,
+            Some(Type::Function {
+                params: vec![Type::Number],
+                return_type: Box::new(Type::Void),
+            })
+        );
+        assert!(params[1].default.is_some());
+
+        let StatementKind::VariableDeclaration { init: Some(init), .. } = &statements[4].kind else {
+            panic!("expected declaration");
+        };
+        assert!(matches!(&init.kind, ExpressionKind::Arrow { params, .. } if params[0].ty == Some(Type::Number)));
+
+        assert!(parse("<script>const x: number;</script>").is_err());
+        assert!(parse("<script>const x = signal<number>;</script>").is_err());
+    }
+
+    #[test]
+    fn test_parse_arrow_return_types() {
+        let source = r#"<script>
+  const double = (a: number): number => a * 2;
+  const find = (): Color[] | null => null;
+  const pick = flag ? (a) : b;
+</script>"#;
+        let component = parse(source).unwrap();
+        let statements = component.script.unwrap().statements;
+        let init = |i: usize| match &statements[i].kind {
+            StatementKind::VariableDeclaration { init: Some(init), .. } => init.kind.clone(),
+            other => panic!("expected declaration, got {:?}", other),
+        };
+
+        let ExpressionKind::Arrow { params, return_type, .. } = init(0) else {
+            panic!("expected arrow");
+        };
+        assert_eq!(params[0].ty, Some(Type::Number));
+        assert_eq!(return_type, Some(Type::Number));
+
+        let ExpressionKind::Arrow { return_type, .. } = init(1) else {
+            panic!("expected arrow");
+        };
+        let colors = Type::Array(Box::new(Type::Custom("Color".to_string())));
+        assert_eq!(return_type, Some(Type::Union(vec![colors, Type::Custom("null".to_string())])));
+
+        // A parenthesized branch of a ternary is not an arrow
+        assert!(matches!(init(2), ExpressionKind::Ternary { .. }));
+    }
+
+    #[test]
+    fn test_parse_imports() {
+        let source = r#"<script>
+  import Button from "./Button.omni";
+  import Card, { scale, clamp as limit } from './Card.omni'
+  import * as icons from "../icons.omni";
+  import "./theme.omni";
+</script>"#;
+        let script = parse(source).unwrap().script.unwrap();
+        let named = |name: &str, alias: Option<&str>| ImportSpecifier::Named {
+            name: name.to_string(),
+            alias: alias.map(str::to_string),
+        };
+        assert_eq!(
+            script.imports,
+            vec![
+                Import {
+                    source: "./Button.omni".to_string(),
+                    specifiers: vec![ImportSpecifier::Default("Button".to_string())],
+                },
+                Import {
+                    source: "./Card.omni".to_string(),
+                    specifiers: vec![
+                        ImportSpecifier::Default("Card".to_string()),
+                        named("scale", None),
+                        named("clamp", Some("limit")),
+                    ],
+                },
+                Import {
+                    source: "../icons.omni".to_string(),
+                    specifiers: vec![ImportSpecifier::Namespace("icons".to_string())],
+                },
+                Import {
+                    source: "./theme.omni".to_string(),
+                    specifiers: Vec::new(),
+                },
+            ]
+        );
+        assert_eq!(script.imports[1].specifiers[2].local(), "limit");
+
+        assert!(parse("<script>import from \"./A.omni\";</script>").is_err());
+        assert!(parse("<script>import A \"./A.omni\";</script>").is_err());
+        assert!(parse("<script>import { a } from A;</script>").is_err());
+    }
+
+    #[test]
+    fn test_parse_style_rules() {
+        let source = r#"<canvas></canvas>
+<style>
+  .legend text, #title { font-size: 14px; font-family: "Fira Sans", sans-serif }
+  * { stroke-width: 2; }
+</style>"#;
+        let style = parse(source).unwrap().style.unwrap();
+        assert_eq!(style.rules.len(), 2);
+
+        let rule = &style.rules[0];
+        assert_eq!(rule.selector, ".legend text, #title");
+        assert_eq!(
+            rule.selectors,
+            vec![
+                Selector {
+                    compounds: vec![
+                        CompoundSelector { classes: vec!["legend".to_string()], ..Default::default() },
+                        CompoundSelector { tag: Some("text".to_string()), ..Default::default() },
+                    ],
+                },
+                Selector {
+                    compounds: vec![CompoundSelector { id: Some("title".to_string()), ..Default::default() }],
+                },
+            ]
+        );
+        assert_eq!(rule.selectors[0].specificity(), (0, 1, 1));
+        let declarations: Vec<_> = rule
+            .declarations
+            .iter()
+            .map(|d| (d.property.as_str(), d.value.as_str()))
+            .collect();
+        assert_eq!(
+            declarations,
+            vec![("font-size", "14px"), ("font-family", "\"Fira Sans\", sans-serif")]
+        );
+
+        assert_eq!(style.rules[1].selectors[0].compounds, vec![CompoundSelector::default()]);
+        assert_eq!(style.rules[1].declarations[0].property, "stroke-width");
+    }
+
+    #[test]
+    fn test_recovers_bad_style_rules() {
+        let tokens = Lexer::new("<style>text > { fill: red; } circle { fill: blue; }").tokenize().unwrap();
+        let (component, errors) = Parser::new(tokens, "test.omni").parse_with_recovery();
+        let style = component.style.unwrap();
+        assert_eq!(style.rules.len(), 2);
+        assert!(style.rules[0].selectors.is_empty());
+        assert_eq!(style.rules[1].declarations[0].value, "blue");
+        assert!(matches!(errors[0], ParseError::UnexpectedToken { .. }));
+        assert!(matches!(&errors[1], ParseError::MissingClosingTag(tag, _) if tag == "style"));
+    }
+
+    #[test]
+    fn test_parse_class_directives() {
+        let component = parse(r#"<canvas><circle class="dot" class:active={selected()} class:big /></canvas>"#).unwrap();
+        let NodeKind::Element { attributes, directives, .. } = &component.template.children[0].kind else {
+            panic!("expected element");
+        };
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(directives.len(), 2);
+        assert_eq!(directives[0].name, DirectiveName::Class);
+        assert_eq!(directives[0].arg.as_deref(), Some("active"));
+        assert!(matches!(directives[0].value.kind, ExpressionKind::Call { .. }));
+        assert_eq!(directives[1].value.kind, ExpressionKind::Identifier("big".to_string()));
+
+        // The directive prefix and name must touch; apart, `:active` is a
+        // `bind:` shorthand
+        let component = parse("<canvas><circle class :active={a} /></canvas>").unwrap();
+        let NodeKind::Element { attributes, directives, .. } = &component.template.children[0].kind else {
+            panic!("expected element");
+        };
+        assert_eq!(attributes[0].name, "class");
+        assert_eq!(directives[0].name, DirectiveName::Bind);
+    }
+
+    #[test]
+    fn test_parse_event_directives() {
+        let component =
+            parse("<canvas><rect @click.once.stop={toggle} on:pointermove={(e) => track(e)} /></canvas>").unwrap();
+        let NodeKind::Element { attributes, directives, .. } = &component.template.children[0].kind else {
+            panic!("expected element");
+        };
+        assert!(attributes.is_empty());
+        assert_eq!(directives.len(), 2);
+        assert_eq!(directives[0].name, DirectiveName::On);
+        assert_eq!(directives[0].arg.as_deref(), Some("click"));
+        assert_eq!(directives[0].modifiers, ["once", "stop"]);
+        assert_eq!(directives[1].arg.as_deref(), Some("pointermove"));
+        assert!(directives[1].modifiers.is_empty());
+        assert!(matches!(directives[1].value.kind, ExpressionKind::Arrow { .. }));
+
+        assert!(matches!(
+            parse("<canvas><rect @click.twice={toggle} /></canvas>"),
+            Err(ParseError::UnknownModifier(modifier, _)) if modifier == "twice"
+        ));
+    }
+
+    #[test]
+    fn test_parse_bind_directives() {
+        let component = parse("<canvas><text :x={posX} bind:text={label} bind:y /></canvas>").unwrap();
+        let NodeKind::Element { attributes, directives, .. } = &component.template.children[0].kind else {
+            panic!("expected element");
+        };
+        assert!(attributes.is_empty());
+        let bound: Vec<_> = directives
+            .iter()
+            .map(|d| (d.name.clone(), d.arg.as_deref().unwrap(), d.value.kind.clone()))
+            .collect();
+        let identifier = |name: &str| ExpressionKind::Identifier(name.to_string());
+        assert_eq!(
+            bound,
+            [
+                (DirectiveName::Bind, "x", identifier("posX")),
+                (DirectiveName::Bind, "text", identifier("label")),
+                (DirectiveName::Bind, "y", identifier("y")),
+            ]
+        );
+
+        assert!(matches!(
+            parse("<canvas><text bind:x.lazy={posX} /></canvas>"),
+            Err(ParseError::UnknownModifier(modifier, _)) if modifier == "lazy"
+        ));
+    }
+
+    #[test]
+    fn test_parse_ref_and_use_directives() {
+        let component =
+            parse("<canvas><circle ref={ball} use:draggable={{ axis: 'x' }} use:tween /></canvas>").unwrap();
+        let NodeKind::Element { attributes, directives, .. } = &component.template.children[0].kind else {
+            panic!("expected element");
+        };
+        assert!(attributes.is_empty());
+        assert_eq!(directives[0].name, DirectiveName::Ref);
+        assert_eq!(directives[0].value.kind, ExpressionKind::Identifier("ball".to_string()));
+        assert_eq!(directives[1].name, DirectiveName::Use);
+        assert_eq!(directives[1].arg.as_deref(), Some("draggable"));
+        assert!(matches!(directives[1].value.kind, ExpressionKind::Object(_)));
+        assert_eq!(directives[2].arg.as_deref(), Some("tween"));
+        assert_eq!(directives[2].value.kind, ExpressionKind::Literal(Literal::Null));
+    }
+
+    #[test]
+    fn test_parse_slots() {
+        let source = r#"<canvas>
+  <group>
+    <slot name="header" title={title()}>
+      <text>Untitled</text>
+    </slot>
+    <slot />
+  </group>
+  <Card let:item>
+    <text slot="header" let:title>{title}</text>
+    <circle radius={item.size} />
+  </Card>
+</canvas>"#;
+        let component = parse(source).unwrap();
+        let children = &component.template.children;
+        let NodeKind::Element { children: slots, .. } = &children[0].kind else {
+            panic!("expected group");
+        };
+        let NodeKind::Slot { name, props, fallback } = &slots[0].kind else {
+            panic!("expected slot");
+        };
+        assert_eq!(name.as_deref(), Some("header"));
+        assert_eq!(props[0].name, "title");
+        assert!(matches!(props[0].value, AttributeValue::Dynamic(_)));
+        assert_eq!(fallback.len(), 1);
+        assert!(matches!(&slots[1].kind, NodeKind::Slot { name: None, props, fallback } if props.is_empty() && fallback.is_empty()));
+
+        let NodeKind::Element { directives, children: content, .. } = &children[1].kind else {
+            panic!("expected component");
+        };
+        assert_eq!(directives[0].name, DirectiveName::Let);
+        assert_eq!(directives[0].arg.as_deref(), Some("item"));
+        assert_eq!(directives[0].value.kind, ExpressionKind::Identifier("item".to_string()));
+        let NodeKind::Element { attributes, directives, .. } = &content[0].kind else {
+            panic!("expected element");
+        };
+        assert_eq!(attributes[0].name, "slot");
+        assert_eq!(directives[0].arg.as_deref(), Some("title"));
+
+        assert!(parse("<canvas><slot name={which} /></canvas>").is_err());
+    }
+
+    #[test]
+    fn test_parse_keyed_elements() {
+        let component = parse("<canvas>{#each items() as item}<circle key={item.id} radius={5} />{/each}</canvas>").unwrap();
+        let NodeKind::EachBlock { body, .. } = &component.template.children[0].kind else {
+            panic!("expected each block");
+        };
+        let NodeKind::Element { attributes, key: Some(key), .. } = &body[0].kind else {
+            panic!("expected keyed element");
+        };
+        assert_eq!(attributes.len(), 1);
+        assert!(matches!(&key.kind, ExpressionKind::Member { property, .. } if property == "id"));
+
+        let component = parse(r#"<canvas><rect key="header" /></canvas>"#).unwrap();
+        let NodeKind::Element { key: Some(key), .. } = &component.template.children[0].kind else {
+            panic!("expected keyed element");
+        };
+        assert_eq!(key.kind, ExpressionKind::Literal(Literal::String("header".to_string())));
+
+        assert!(matches!(parse("<canvas><rect key /></canvas>"), Err(ParseError::InvalidAttribute(_))));
+        assert!(matches!(parse("<canvas><rect key={a} key={b} /></canvas>"), Err(ParseError::InvalidAttribute(_))));
+    }
+
+    #[test]
+    fn test_parse_shapes_example() {
+        let source = include_str!("../../../../examples/shapes/App.omni");
+        parse(source).unwrap();
+    }
+
+    #[test]
+    fn test_parse_control_flow_blocks() {
+        let source = r#"<canvas>
+  {#if count() > 10}
+    <text>Big</text>
+  {:else if count() > 5}
+    <text>Medium</text>
+  {:else}
+    <text>Small</text>
+  {/if}
+  {#each items() as item, i (item.id)}
+    <circle x={i * 10} radius={item.size} />
+  {/each}
+  {#await load()}
+    <text>Loading</text>
+  {:then data}
+    <text>{data}</text>
+  {:catch}
+    <text>Failed</text>
+  {/await}
+</canvas>"#;
+        let component = parse(source).unwrap();
+        let children = component.template.children;
+        assert_eq!(children.len(), 3);
+
+        let NodeKind::IfBlock { then_branch, else_branch: Some(else_branch), .. } = &children[0].kind else {
+            panic!("expected if block, got {:?}", children[0].kind);
+        };
+        assert_eq!(then_branch.len(), 1);
+        let NodeKind::IfBlock { else_branch: Some(last), .. } = &else_branch[0].kind else {
+            panic!("expected `else if` to nest an if block");
+        };
+        assert_eq!(last.len(), 1);
+        assert!(source[children[0].span.start..children[0].span.end].ends_with("{/if}"));
+
+        let NodeKind::EachBlock { binding, index, key: Some(key), body, .. } = &children[1].kind else {
+            panic!("expected each block");
+        };
+        assert_eq!((binding.as_str(), index.as_deref()), ("item", Some("i")));
+        assert!(matches!(&key.kind, ExpressionKind::Member { property, .. } if property == "id"));
+        assert_eq!(body.len(), 1);
+
+        let NodeKind::AwaitBlock { pending, then_branch: Some(then_branch), catch_branch: Some(catch_branch), .. } =
+            &children[2].kind
+        else {
+            panic!("expected await block");
+        };
+        assert_eq!(pending.len(), 1);
+        assert_eq!(then_branch.binding.as_deref(), Some("data"));
+        assert_eq!(catch_branch.binding, None);
+    }
+
+    #[test]
+    fn test_recovers_unclosed_blocks() {
+        let tokens = Lexer::new("<canvas>{#if a}<text>x</text>{:then}{/each}</canvas>").tokenize().unwrap();
+        let (component, errors) = Parser::new(tokens, "test.omni").parse_with_recovery();
+        assert!(matches!(component.template.children[0].kind, NodeKind::IfBlock { .. }));
+        assert!(matches!(errors[0], ParseError::UnexpectedToken { .. }));
+        assert!(matches!(&errors[1], ParseError::UnclosedBlock(name, _) if name == "if"));
+    }
+}
+
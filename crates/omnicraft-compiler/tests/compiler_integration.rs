@@ -1,4 +1,7 @@
-use omnicraft_compiler::{compile_rust, CompilationTarget};
+use omnicraft_compiler::{compile, compile_rust, CompilationTarget, ModuleGraph};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use tracing_subscriber::fmt::format::FmtSpan;
 
 fn init_tracing() {
@@ -9,6 +12,106 @@ fn init_tracing() {
         .try_init();
 }
 
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..").canonicalize().unwrap()
+}
+
+/// Run `cargo check` on a crate made of generated files, against the
+/// workspace's runtime, and fail with rustc's errors if it does not compile
+fn cargo_check(name: &str, files: &[(PathBuf, String)]) {
+    let workspace = workspace_root();
+    let dir = workspace.join("target/generated").join(name);
+    let _ = fs::remove_dir_all(dir.join("src"));
+    fs::create_dir_all(dir.join("src")).unwrap();
+    for (path, code) in files {
+        fs::write(dir.join("src").join(path), code).unwrap();
+    }
+    let manifest = format!(
+        r#"[package]
+name = "generated-{}"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+omnicraft-runtime = {{ path = {:?} }}
+wasm-bindgen = "0.2"
+
+[workspace]
+"#,
+        name,
+        workspace.join("crates/omnicraft-runtime")
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    // Resolve to the versions the workspace is locked to, without network
+    fs::copy(workspace.join("Cargo.lock"), dir.join("Cargo.lock")).unwrap();
+
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .args(["check", "--offline", "--quiet", "--message-format", "short"])
+        .env("CARGO_TARGET_DIR", workspace.join("target/generated/target"))
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "generated `{}` does not compile:\n{}",
+        name,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// The runtime's template tests run the output compiled from the `.omni`
+/// files in its `tests/fixtures`
+#[test]
+fn test_runtime_fixtures_are_up_to_date() {
+    init_tracing();
+
+    let fixtures = workspace_root().join("crates/omnicraft-runtime/tests/fixtures");
+    for entry in fs::read_dir(&fixtures).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "omni") {
+            continue;
+        }
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        let expected = compile_rust(&fs::read_to_string(&path).unwrap(), file_name).unwrap();
+        let actual = fs::read_to_string(path.with_extension("rs")).unwrap_or_default();
+        assert!(
+            actual == expected,
+            "{} is out of date; run `omnicraft compile {1} -o {1}`",
+            path.with_extension("rs").display(),
+            "crates/omnicraft-runtime/tests/fixtures"
+        );
+    }
+}
+
+#[test]
+fn test_counter_example_compiles() {
+    init_tracing();
+
+    let graph = ModuleGraph::build(workspace_root().join("examples/counter/App.omni")).unwrap();
+    let files = graph.compile().unwrap();
+    let app = &files.iter().find(|(path, _)| path == Path::new("app.rs")).unwrap().1;
+    for snippet in [
+        "let doubled = ctx.create_signal((count.get() * 2.0));",
+        "move || doubled.set((count.get() * 2.0))",
+        "move |event: &InputEvent| { this.increment(); }",
+        "fn increment(&self) {\n        let count = self.count.clone();\n        count.set((count.get() + 1.0));",
+    ] {
+        assert!(app.contains(snippet), "missing `{}` in\n{}", snippet, app);
+    }
+    cargo_check("counter", &files);
+}
+
+#[test]
+fn test_shapes_example_compiles() {
+    init_tracing();
+
+    let graph = ModuleGraph::build(workspace_root().join("examples/shapes/App.omni")).unwrap();
+    let files = graph.compile().unwrap();
+    let app = &files.iter().find(|(path, _)| path == Path::new("app.rs")).unwrap().1;
+    assert!(app.contains("pub shapes: Signal<Vec<HashMap<String, Value>>>,"), "{}", app);
+    cargo_check("shapes", &files);
+}
+
 #[test]
 fn test_compile_full_component() {
     init_tracing();
@@ -61,12 +164,1040 @@ fn test_compile_with_reactivity() {
     let rust_code = result.unwrap();
     
     assert!(rust_code.contains("pub x: Signal<f64>"));
-    // Current simple type inference sees the arrow function and returns Fn(), 
-    // and codegen treats all reactives as Signal wrappers for now.
-    assert!(rust_code.contains("pub y: Signal<Fn()>"));
-    // The codegen implementation might differ slightly on how it exposes memos, 
-    // code inspection showed it iterates variables.
+    // A memo is a signal of what its function returns, updated whenever
+    // a signal it reads changes
+    assert!(rust_code.contains("pub y: Signal<f64>"));
+    assert!(rust_code.contains("x.subscribe({"));
     
     assert!(rust_code.contains("world.spawn_empty()"));
     assert!(rust_code.contains("Shape::Rectangle"));
 }
+
+#[test]
+fn test_compile_template_literals() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    const count = signal(0);
+    const doubled = memo(() => count() * 2);
+</script>
+
+<canvas width={800} height={600}>
+    <text x={400} y={200} content={`Count: ${count()}`} />
+    <text x={400} y={250} content={`{"doubled": ${doubled()}}`} />
+</canvas>
+"#;
+
+    let result = compile_rust(source, "Counter.omni");
+    assert!(result.is_ok(), "Compilation failed: {:?}", result.err());
+
+    let rust_code = result.unwrap();
+    assert!(rust_code.contains(r#"format!("Count: {}", count.get())"#));
+    assert!(rust_code.contains(r#"format!("{{\"doubled\": {}}}", doubled.get())"#));
+}
+
+#[test]
+fn test_compile_string_escapes() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    const greeting = "say \"hi\"\n\u{1F600}";
+</script>
+
+<canvas width={800} height={600}>
+    <text x={10} y={20} content="C:\\omni \"quoted\"" />
+    <text x={10} y={40} content={greeting} />
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Escapes.omni").unwrap();
+    assert!(rust_code.contains(r#""C:\\omni \"quoted\"""#), "{}", rust_code);
+}
+
+#[test]
+fn test_compile_text_children() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    const name = signal("World");
+</script>
+
+<canvas width={800} height={600}>
+    <text x={10} y={20}>
+        Hello,   {name()}!
+    </text>
+    <text x={10} y={40}>Plain "text"</text>
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Greeting.omni").unwrap();
+    assert!(rust_code.contains(r#"text: format!("Hello, {}!", name.get()).to_string()"#), "{}", rust_code);
+    assert!(rust_code.contains(r#"text: "Plain \"text\"".to_string()"#), "{}", rust_code);
+}
+
+#[test]
+fn test_compile_units() {
+    init_tracing();
+
+    let source = r#"
+<canvas width={800} height={600}>
+    <rect x={100-20} y={1_0} width={0x10} height={.5} rotation={90deg} />
+    <rect x={0} y={0} width={10%3} height={2 * 10px} opacity={50%} />
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Units.omni").unwrap();
+    assert!(
+        rust_code.contains("Transform::from_xy(80.0, 10.0).with_rotation(1.5707963267948966)"),
+        "{}",
+        rust_code
+    );
+    assert!(rust_code.contains("Shape::Rectangle { width: 16.0, height: 0.5 }"), "{}", rust_code);
+    assert!(rust_code.contains("Shape::Rectangle { width: 1.0, height: 20.0 }"), "{}", rust_code);
+    assert!(rust_code.contains("opacity: 0.5"), "{}", rust_code);
+
+    // A percentage of a size the compiler doesn't know is an error
+    for source in [
+        "<canvas><rect width={50%} height={10} /></canvas>",
+        "<script>const w = 50%;</script><canvas></canvas>",
+        "<canvas><rect class=\"r\" /></canvas><style>.r { width: 50%; }</style>",
+    ] {
+        let error = compile_rust(source, "Units.omni").unwrap_err().to_string();
+        assert!(error.contains("percentage"), "{}: {}", source, error);
+    }
+}
+
+#[test]
+fn test_compile_style_rules() {
+    init_tracing();
+
+    let source = r#"
+<canvas width={800} height={600}>
+    <circle class="dot" x={10} y={10} radius={5} />
+    <circle class="dot" fill="red" x={20} y={10} radius={5} />
+    <text class="label" x={0} y={0}>Total</text>
+</canvas>
+
+<style>
+    circle { fill: blue; opacity: 0.5; }
+    .dot { stroke: "black"; stroke-width: 2; }
+    text.label { font-size: 18px; font-weight: bold; text-anchor: middle; }
+</style>
+"#;
+
+    let rust_code = compile_rust(source, "Styled.omni").unwrap();
+    assert!(
+        rust_code.contains(
+            r#"Style { fill: Some(Color::parse("blue")), stroke: Some(Color::parse("black")), stroke_width: 2.0, opacity: 0.5, ..Default::default() }"#
+        ),
+        "{}",
+        rust_code
+    );
+    assert!(rust_code.contains(r#"fill: Some(Color::parse("red"))"#), "{}", rust_code);
+    assert!(
+        rust_code.contains(r#"TextContent { text: "Total".to_string(), font_size: 18.0, font_weight: FontWeight::Bold, ..Default::default() }"#),
+        "{}",
+        rust_code
+    );
+}
+
+#[test]
+fn test_compile_class_directives() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    const selected = signal(false);
+</script>
+
+<canvas width={800} height={600}>
+    <group class="legend" class:active={selected()}>
+        <circle id="dot" fill="green" x={10} y={10} radius={5} />
+    </group>
+    <rect class="plain" />
+</canvas>
+
+<style>
+    circle { fill: blue; }
+    .active circle, #dot.big { stroke: "black"; stroke-width: 2; }
+</style>
+"#;
+
+    let rust_code = compile_rust(source, "Legend.omni").unwrap();
+    let expected = [
+        r#"world.get_resource_or_insert_with(Stylesheet::new).insert("Legend", vec!["#,
+        r#"StyleRule::new("circle", StyleDeclarations { fill: Some(Color::parse("blue")), ..Default::default() }),"#,
+        r#"StyleRule::new(".active circle", StyleDeclarations { stroke: Some(Color::parse("black")), stroke_width: Some(2.0), ..Default::default() }),"#,
+        r##"StyleRule::new("#dot.big", "##,
+        r#"world.entity_mut(entity_0).insert(Class::new(&"legend"));"#,
+        "let selected = self.selected.clone();",
+        r#"world.bind_class(entity_0, "active", move || selected.get());"#,
+        r#"world.unsubscribe_on_unmount(entity_0, self.selected.subscribe(world.class_trigger(entity_0, "active")));"#,
+        r#"world.entity_mut(entity_0).insert((Style::default(), ScopedStyle::new("Legend", StyleDeclarations::default())));"#,
+        "let parent_0 = entity_0;",
+        r#"world.entity_mut(entity_0).insert(Id::new("dot"));"#,
+        "world.entity_mut(entity_0).insert(Parent(parent_0));",
+        r#"ScopedStyle::new("Legend", StyleDeclarations { fill: Some(Color::parse("green")), ..Default::default() })"#,
+        r#"world.entity_mut(entity_1).insert(Class::new(&"plain"));"#,
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+    assert!(!rust_code.contains("entity_1).insert(Parent"), "{}", rust_code);
+}
+
+#[test]
+fn test_compile_event_directives() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    const count = signal(0);
+
+    function increment() {
+        count.set(count() + 1);
+    }
+
+    function log(event) {
+        console.log(event);
+    }
+</script>
+
+<canvas width={800} height={600}>
+    <group on:pointerdown={log}>
+        <rect width={80} height={40} @click={increment} on:pointermove.self={log} />
+    </group>
+    <circle radius={10} @click.once.stop.prevent={() => count.set(0)} />
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Buttons.omni").unwrap();
+    let expected = [
+        "let this = self.clone();",
+        r#"world.on_event(entity_0, "pointerdown", EventModifiers::default(), move |event: &InputEvent| { this.log(event); });"#,
+        "let parent_0 = entity_0;",
+        "world.entity_mut(entity_0).insert(Parent(parent_0));",
+        r#"world.on_event(entity_0, "click", EventModifiers::default(), move |event: &InputEvent| { this.increment(); });"#,
+        r#"world.on_event(entity_0, "pointermove", EventModifiers { self_only: true, ..Default::default() }, move |event: &InputEvent| { this.log(event); });"#,
+        "let count = self.count.clone();",
+        r#"world.on_event(entity_1, "click", EventModifiers { once: true, stop: true, prevent: true, ..Default::default() }, move |event: &InputEvent| { count.set(0.0); });"#,
+        "fn log(&self, event: &InputEvent) {",
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+    assert!(!rust_code.contains("entity_1).insert(Parent"), "{}", rust_code);
+}
+
+#[test]
+fn test_compile_bind_directives() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    const posX = signal(100);
+    const label = signal("Drag me");
+    const visible = signal(true);
+</script>
+
+<canvas width={800} height={600}>
+    <text bind:x={posX} :text={label} y={50} />
+    {#if visible()}
+        <circle bind:x={posX} radius={5} />
+    {/if}
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Editor.omni").unwrap();
+    let expected = [
+        r#"let label = ctx.create_signal("Drag me".to_string());"#,
+        "world.bind_property(entity_0, BoundProperty::X, self.posX.clone());",
+        "world.bind_property(entity_0, BoundProperty::Text, self.label.clone());",
+        "world.bind_property(entity_100, BoundProperty::X, posX.clone());",
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+
+    let source = r#"
+<script>
+    const posX = signal(100);
+    const doubled = memo(() => posX() * 2);
+</script>
+
+<canvas width={800} height={600}>
+    <circle bind:x={doubled} radius={5} />
+</canvas>
+"#;
+    let error = compile_rust(source, "Editor.omni").unwrap_err();
+    assert!(error.to_string().contains("writable signal"), "{}", error);
+}
+
+#[test]
+fn test_compile_ref_and_use_directives() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    let ball;
+    const visible = signal(true);
+
+    function draggable(entity, options) {}
+
+    function reset() {
+        console.log(ball);
+    }
+</script>
+
+<canvas width={800} height={600}>
+    <circle ref={ball} use:draggable={{ axis: "x" }} radius={5} />
+    {#if visible()}
+        <rect use:tween @click={() => reset(ball)} />
+    {/if}
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Physics.omni").unwrap();
+    let expected = [
+        "pub ball: EntityRef,",
+        "ball: EntityRef::new(),",
+        "world.bind_ref(entity_0, &self.ball);",
+        "world.use_action(entity_0, { let this = this.clone(); move |_: &mut World, entity, options| this.draggable(entity, options) }, ",
+        "fn draggable(&self, entity: Entity, options: HashMap<String, String>) {",
+        "let ball = self.ball.clone();",
+        "world.use_action(entity_100, tween, ());",
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+
+    // Script functions used as actions are called through the component
+    let source = r#"
+<script>
+    const dragged = signal(false);
+
+    function draggable(entity, options) {
+        dragged.set(true);
+    }
+</script>
+
+<canvas width={800} height={600}>
+    {#if !dragged()}
+        <circle use:draggable={{ axis: "x" }} radius={5} />
+    {/if}
+</canvas>
+"#;
+    let rust_code = compile_rust(source, "Physics.omni").unwrap();
+    cargo_check("actions", &[(PathBuf::from("lib.rs"), rust_code)]);
+
+    let source = r#"
+<script>
+    const ball = null;
+</script>
+
+<canvas width={800} height={600}>
+    <circle ref={ball} radius={5} />
+</canvas>
+"#;
+    let error = compile_rust(source, "Physics.omni").unwrap_err();
+    assert!(error.to_string().contains("`let` variable"), "{}", error);
+}
+
+#[test]
+fn test_compile_slots() {
+    init_tracing();
+
+    let card = r#"
+<script>
+    const open = signal(true);
+</script>
+
+<canvas width={800} height={600}>
+    <group>
+        <slot name="header" title={"Stats"}>
+            <text>Untitled</text>
+        </slot>
+        {#if open()}
+            <slot />
+        {/if}
+    </group>
+</canvas>
+"#;
+
+    let rust_code = compile_rust(card, "Card.omni").unwrap();
+    let expected = [
+        "pub slots: Slots,",
+        "slots: Slots::new(),",
+        "let slots = self.props.slots.clone();",
+        "let parent_0 = entity_0;",
+        "if slots.contains(\"header\") {",
+        "world.render_slot(&slots, \"header\", &SlotProps::new().with(\"title\", \"Stats\".to_string()), Some(parent_0));",
+        "entities.extend(world.render_slot(&slots, \"default\", &SlotProps::new(), Some(parent_0)));",
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+
+    let app = r#"
+<script>
+    import Card from "./Card.omni";
+
+    const count = signal(0);
+</script>
+
+<canvas width={800} height={600}>
+    <Card let:index>
+        <text slot="header" let:title>{title}</text>
+        <circle x={index * 10} radius={count()} />
+    </Card>
+</canvas>
+"#;
+
+    let rust_code = compile_rust(app, "App.omni").unwrap();
+    let expected = [
+        "let mut slots_0 = Slots::new();",
+        "slots_0.insert(\"header\", move |world: &mut World, slot_props: &SlotProps| {",
+        "let title: String = slot_props.get(\"title\").unwrap_or_default();",
+        "let count = self.count.clone();",
+        "slots_0.insert(\"default\", move |world: &mut World, slot_props: &SlotProps| {",
+        "let index: f64 = slot_props.get(\"index\").unwrap_or_default();",
+        "slots: slots_0",
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+
+    let files = [("app/Card.omni", card), ("app/App.omni", app)];
+    let graph = ModuleGraph::build_with("app/App.omni", |path| {
+        let (_, source) = files.iter().find(|(name, _)| Path::new(name) == path).unwrap();
+        Ok(source.to_string())
+    })
+    .unwrap();
+    cargo_check("slots", &graph.compile().unwrap());
+}
+
+#[test]
+fn test_compile_keyed_elements() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    const items = signal([]);
+
+    function select(event) {}
+</script>
+
+<canvas width={800} height={600}>
+    {#each items() as item, i (item.id)}
+        <circle key={item.id} x={i * 20} radius={5} @click={select} />
+        <text>{item.label}</text>
+    {/each}
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "List.omni").unwrap();
+    let expected = [
+        "let (entity_0, reused_0) = world.spawn_keyed(Key::new(",
+        "entities.push(entity_0);",
+        "if !reused_0 {",
+        "let this = this.clone();",
+        "world.on_event(entity_0, \"click\", EventModifiers::default(), move |event: &InputEvent| { this.select(event); });",
+        "let (entity_1, reused_1) = world.spawn_keyed(Key::new(format!(\"{}#1\", item.id)));",
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+    // Attributes follow the data even when the entity is reused
+    let transform = rust_code.find("world.entity_mut(entity_0).insert(Transform::from_xy((i * 20.0) as f32, 0.0));").unwrap();
+    assert!(transform < rust_code.find("if !reused_0 {").unwrap());
+}
+
+#[test]
+fn test_compile_loops_and_assignment() {
+    init_tracing();
+
+    let source = r#"
+<canvas width={800} height={600}>
+    <text x={10} y={20}>{() => {
+        let total = 0;
+        for (let i = 0; i < 3; i++) {
+            if (i == 1) { continue; }
+            total += i;
+        }
+        while (total > 10) { total--; }
+        return total;
+    }}</text>
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Loops.omni").unwrap();
+    assert!(rust_code.contains("let mut total = 0.0;"), "{}", rust_code);
+    assert!(
+        rust_code.contains(
+            "{ let mut i = 0.0; let mut started = false; loop { if started { i += 1.0; } started = true; \
+             if !((i < 3.0)) { break; } { if (i == 1.0) { continue; } total += i; } } }"
+        ),
+        "{}",
+        rust_code
+    );
+    assert!(rust_code.contains("while (total > 10.0) { total -= 1.0; }"), "{}", rust_code);
+}
+
+#[test]
+fn test_compile_signal_assignment() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    let count = signal(0);
+
+    function step() {
+        count += 2;
+        count++;
+        const before = count--;
+        count = before * 2;
+    }
+</script>
+
+<canvas width={800} height={600}>
+    <circle x={count()} y={0} radius={5} @click={() => count = 0} />
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Signals.omni").unwrap();
+    for snippet in [
+        "count.set(count.get() + 2.0);",
+        "count.set(count.get() + 1.0);",
+        "let before = { let old = count.get(); count.set(old - 1.0); old };",
+        "count.set((before * 2.0));",
+        "move |event: &InputEvent| { count.set(0.0); }",
+    ] {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+    cargo_check("signals", &[(PathBuf::from("lib.rs"), rust_code)]);
+}
+
+#[test]
+fn test_compile_objects_and_optional_chaining() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    export let target: { x: number } | null = null;
+    const pos = signal({ x: 1, y: 2 });
+    const base = signal({ stroke: "black" });
+    const shape = signal({ type: "circle", radius: 10, ...base(), origin: { x: 5 } });
+    const shapes = signal([{ x: 1 }, { x: 2, label: "two" }, ...[{ x: 3 }]]);
+    const items = signal([1, 2, 3]);
+    const total = memo(() => {
+        const { x, y = 1, ...others } = pos();
+        const [first, ...rest] = items();
+        return x + y + first;
+    });
+</script>
+
+<canvas width={800} height={600}>
+    <circle x={pos().x} y={pos()?.y} radius={shape().radius} />
+    <circle x={total()} y={0} radius={5} />
+    <circle x={target?.x ?? 0} y={shape().origin.x} radius={shapes()[0].x} />
+    <text x={10} y={20}>{shape().type}</text>
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Objects.omni").unwrap();
+    let expected = [
+        // Objects mixing value types hold the runtime's `Value`
+        "pub shape: Signal<HashMap<String, Value>>,",
+        "pub shapes: Signal<Vec<HashMap<String, Value>>>,",
+        "map.insert(\"type\".to_string(), Value::from(\"circle\".to_string()));",
+        "map.extend(base.get().clone().into_iter().map(|(key, value)| (key, Value::from(value))));",
+        // Members of maps are looked up by key, and `?.` only maps an `Option`
+        "Transform::from_xy(pos.get()[\"x\"].clone() as f32, pos.get()[\"y\"].clone() as f32)",
+        "target.as_ref().map(|v| v[\"x\"].clone()).unwrap_or_else(|| 0.0) as f32",
+        "shape.get()[\"origin\"].clone().get(\"x\").as_number() as f32",
+        "radius: shapes.get()[0].clone()[\"x\"].clone().as_number() as f32",
+        // Destructuring
+        "let destructured = pos.get(); let x = destructured[\"x\"].clone(); \
+         let y = destructured.get(\"y\").cloned().unwrap_or_else(|| 1.0); \
+         let others = { let mut others = destructured.clone(); others.remove(\"x\"); others.remove(\"y\"); others };",
+        "let destructured = items.get(); let first = destructured[0].clone(); let rest = destructured[1..].to_vec();",
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+    cargo_check("objects", &[(PathBuf::from("lib.rs"), rust_code)]);
+}
+
+#[test]
+fn test_compile_control_flow_blocks() {
+    init_tracing();
+
+    let source = r#"
+<script>
+  const count = signal(0);
+  const items = signal([1, 2, 3]);
+</script>
+
+<canvas width={800} height={600}>
+    {#if count() > 5}
+        <text x={10} y={20}>Big</text>
+    {:else if count() > 2}
+        <text x={10} y={20}>Medium</text>
+    {:else}
+        {#each items() as item, i (item)}
+            <circle x={i * 20} y={50} radius={item} />
+        {/each}
+    {/if}
+    {#await load()}
+        <text>Loading</text>
+    {:then data}
+        <text>{data}</text>
+    {/await}
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Blocks.omni").unwrap();
+    let expected = [
+        "let count = self.count.clone();",
+        "world.spawn_region(move |world: &mut World| {",
+        "if (count.get() > 5.0) {",
+        "} else if (count.get() > 2.0) {",
+        "} else {",
+        "pub items: Signal<Vec<f64>>,",
+        "for (i, item) in items.get().into_iter().enumerate() {",
+        "let i = i as f64;",
+        "let (entity_0, reused_0) = world.spawn_keyed(Key::new(item));",
+        "entities.push(entity_0);",
+        "world.unsubscribe_on_unmount(region_0, items.subscribe(world.region_trigger(region_0)));",
+        "world.unsubscribe_on_unmount(region_0, self.count.subscribe(world.region_trigger(region_0)));",
+        "let promise_1 = load();",
+        "match promise_1.state() {",
+        "PromiseState::Resolved(data) => {",
+        "PromiseState::Rejected(_) => {}",
+        "world.unsubscribe_on_unmount(region_1, promise_1.subscribe(world.region_trigger(region_1)));",
+    ];
+    for snippet in expected {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+    assert!(!rust_code.contains("self.items.subscribe"), "{}", rust_code);
+
+    // Untyped values fall back to numbers rather than an unknown type
+    let source = r#"
+<script>
+  const count = signal(0);
+  const items = signal([]);
+  const labels = signal(["a", "b"]);
+</script>
+
+<canvas width={800} height={600}>
+    {#if count() > 5}
+        <text x={10} y={20}>Big {count()}</text>
+    {:else}
+        {#each items() as item, i (item)}
+            <circle x={i * 20} y={50} radius={item} />
+        {/each}
+    {/if}
+    {#each labels() as label}
+        <text>{label}: {count()}</text>
+        <text>{label}</text>
+    {/each}
+</canvas>
+"#;
+    let rust_code = compile_rust(source, "Blocks.omni").unwrap();
+    assert!(rust_code.contains("pub items: Signal<Vec<f64>>,"), "{}", rust_code);
+    // Texts reading signals follow them, cloning the block's locals
+    for snippet in [
+        "world.bind_value(entity_0, BoundProperty::Text, move || format!(\"Big {}\", count.get()).to_string());",
+        "let label = label.clone();",
+        "world.unsubscribe_on_unmount(entity_0, count.subscribe(world.binding_trigger(entity_0, BoundProperty::Text)));",
+    ] {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+    cargo_check("blocks", &[(PathBuf::from("lib.rs"), rust_code)]);
+
+    // Awaiting a host function that returns a runtime `Promise`
+    let source = r#"
+<script>
+    import { load } from "./loader";
+</script>
+
+<canvas width={800} height={600}>
+    {#await load()}
+        <text x={10} y={20}>Loading</text>
+    {:then data}
+        <text x={10} y={20}>{data}</text>
+    {:catch error}
+        <text x={10} y={20}>Failed: {error}</text>
+    {/await}
+</canvas>
+"#;
+    let rust_code = compile_rust(source, "Blocks.omni").unwrap();
+    assert!(rust_code.contains("PromiseState::Rejected(error) => {"), "{}", rust_code);
+    let loader = "use omnicraft_runtime::prelude::*;\n\npub fn load() -> Promise<String, String> {\n    Promise::pending()\n}\n";
+    cargo_check(
+        "await",
+        &[
+            (PathBuf::from("lib.rs"), "mod blocks;\nmod loader;\n".to_string()),
+            (PathBuf::from("blocks.rs"), rust_code),
+            (PathBuf::from("loader.rs"), loader.to_string()),
+        ],
+    );
+}
+
+#[test]
+fn test_compile_type_annotations() {
+    init_tracing();
+
+    let source = r#"
+<script>
+    const colors = signal<string[]>([]);
+    const doubled = memo<number>(() => colors().length * 2);
+    const selected: Signal<string | null> = signal(null);
+</script>
+
+<canvas width={800} height={600}>
+    <text x={10} y={20}>{(count: number, label: string = "n") => label}</text>
+    <text x={10} y={40}>{(scale: number) => {
+        let total: number = 0;
+        total += scale;
+        return total;
+    }}</text>
+</canvas>
+"#;
+
+    let rust_code = compile_rust(source, "Typed.omni").unwrap();
+    for snippet in [
+        "pub colors: Signal<Vec<String>>,",
+        "pub doubled: Signal<f64>,",
+        "pub selected: Signal<Option<String>>,",
+        "|count: f64, label: String| label",
+        "|scale: f64| { let mut total: f64 = 0.0;",
+    ] {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+
+    let ts_code = compile(source, "Typed.omni", CompilationTarget::TypeScript).unwrap();
+    for snippet in [
+        "readonly colors: Signal<string[]>;",
+        "readonly doubled: Signal<number>;",
+        "readonly selected: Signal<string | null>;",
+    ] {
+        assert!(ts_code.contains(snippet), "missing `{}` in\n{}", snippet, ts_code);
+    }
+}
+
+#[test]
+fn test_compile_component_props() {
+    init_tracing();
+
+    let badge = r##"
+<script>
+    export let radius: number = 10;
+    export let label: string;
+    export let color = "#ff0000";
+    const size = signal(radius * 2);
+</script>
+
+<canvas width={800} height={600}>
+    <circle x={0} y={0} radius={radius} fill={color} />
+</canvas>
+"##;
+
+    let rust_code = compile_rust(badge, "Badge.omni").unwrap();
+    for snippet in [
+        "pub struct BadgeProps {\n    pub radius: f64,\n    pub label: String,\n    pub color: String,\n}",
+        "radius: 10.0,\n            label: Default::default(),\n            color: \"#ff0000\".to_string(),",
+        "pub props: BadgeProps,",
+        "Self::with_props(ctx, BadgeProps::default())",
+        "pub fn with_props(ctx: &mut Context, props: BadgeProps) -> Self {\n        let radius = props.radius.clone();",
+        "let size = ctx.create_signal((radius * 2.0));",
+        "fn mount(&self, world: &mut World) {\n        let radius = self.props.radius.clone();",
+    ] {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+
+    let ts_code = compile(badge, "Badge.omni", CompilationTarget::TypeScript).unwrap();
+    for snippet in [
+        "export interface BadgeProps {\n    radius?: number;\n    label: string;\n    color?: string;\n}",
+        "constructor(props: BadgeProps);",
+    ] {
+        assert!(ts_code.contains(snippet), "missing `{}` in\n{}", snippet, ts_code);
+    }
+
+    let app = r#"
+<script>
+    const show = signal(true);
+</script>
+
+<canvas width={800} height={600}>
+    <Badge radius={5} label="New" />
+    {#if show()}
+        <Badge label={"Hot"} {...defaults} />
+    {/if}
+</canvas>
+"#;
+
+    let rust_code = compile_rust(app, "App.omni").unwrap();
+    for snippet in [
+        "let child_0 = BadgeComponent::with_props(ctx, \
+         BadgeProps { radius: 5.0, label: \"New\".to_string(), ..Default::default() });\n        child_0.mount(world);",
+        "BadgeProps { label: \"Hot\".to_string(), ..defaults.clone() });",
+        "entities.extend(world.mount_child(&child_100));",
+        "let ctx = ctx.clone();\n            let show = self.show.clone();\n            world.spawn_region(move |world: &mut World| {\n                let ctx = &mut ctx.clone();",
+        "ctx: ctx.clone(),",
+    ] {
+        assert!(rust_code.contains(snippet), "missing `{}` in\n{}", snippet, rust_code);
+    }
+    assert!(!rust_code.contains("Shape::Badge"));
+
+    // Number props are `f64` like every script number, and are cast where
+    // they reach the runtime's `f32` fields
+    assert!(compile_rust(badge, "Badge.omni").unwrap().contains("Shape::Circle { radius: radius as f32 }"));
+    let files = [
+        ("app/Badge.omni", badge),
+        (
+            "app/App.omni",
+            r##"
+<script>
+    import Badge from "./Badge.omni";
+    const size = signal(4);
+</script>
+
+<canvas width={800} height={600}>
+    <Badge radius={size() * 2} label="New" />
+    {#if size() > 2}
+        <Badge label={"Hot"} color="#00ff00" />
+    {/if}
+</canvas>
+"##,
+        ),
+    ];
+    let graph = ModuleGraph::build_with("app/App.omni", |path| {
+        let (_, source) = files.iter().find(|(name, _)| Path::new(name) == path).unwrap();
+        Ok(source.to_string())
+    })
+    .unwrap();
+    cargo_check("props", &graph.compile().unwrap());
+}
+
+#[test]
+fn test_compile_module_graph() {
+    init_tracing();
+
+    let files = [
+        (
+            "app/App.omni",
+            r#"
+<script>
+    import Pill from "./ui/Badge.omni";
+    import { scale, clamp as limit, double } from "./util.omni";
+</script>
+
+<canvas width={800} height={600}>
+    <Pill radius={limit(double(scale))} />
+</canvas>
+"#,
+        ),
+        (
+            "app/ui/Badge.omni",
+            r#"
+<script>
+    import * as util from "../util.omni";
+    export let radius = 10;
+</script>
+
+<canvas>
+    <circle x={0} y={0} radius={radius} />
+</canvas>
+"#,
+        ),
+        (
+            "app/util.omni",
+            r#"
+<script>
+    export const scale = 2;
+    export function clamp(value: number): number {
+        return value;
+    }
+    export function double(value) {
+        return value * 2;
+    }
+</script>
+"#,
+        ),
+    ];
+    let graph = ModuleGraph::build_with("app/App.omni", |path| {
+        let (_, source) = files.iter().find(|(name, _)| Path::new(name) == path).unwrap();
+        Ok(source.to_string())
+    })
+    .unwrap();
+
+    let output = graph.compile().unwrap();
+    let file = |name: &str| &output.iter().find(|(path, _)| path == Path::new(name)).unwrap().1;
+    let names: Vec<_> = output.iter().map(|(path, _)| path.display().to_string()).collect();
+    assert_eq!(names, ["lib.rs", "util.rs", "ui_badge.rs", "app.rs"]);
+
+    assert!(file("lib.rs").contains("pub mod util;\npub mod ui_badge;\npub mod app;\n"));
+
+    let app = file("app.rs");
+    for snippet in [
+        "use crate::ui_badge::{BadgeComponent as PillComponent, BadgeProps as PillProps};",
+        "use crate::util::{scale, clamp as limit, double};",
+        "PillComponent::with_props(ctx, PillProps { radius: limit(double(scale)), ..Default::default() });",
+        "#[wasm_bindgen(start)]",
+    ] {
+        assert!(app.contains(snippet), "missing `{}` in\n{}", snippet, app);
+    }
+
+    let badge = file("ui_badge.rs");
+    assert!(badge.contains("use crate::util;\n"), "{}", badge);
+    assert!(!badge.contains("#[wasm_bindgen(start)]"));
+
+    let util = file("util.rs");
+    for snippet in [
+        "#[allow(non_upper_case_globals)]\npub const scale: f64 = 2.0;",
+        "pub fn clamp(value: f64) -> f64 { return value; }",
+        // Without an annotation, the return type is inferred
+        "pub fn double(value: f64) -> f64 { return (value * 2.0); }",
+    ] {
+        assert!(util.contains(snippet), "missing `{}` in\n{}", snippet, util);
+    }
+    cargo_check("modules", &output);
+}
+
+#[test]
+fn test_compile_builder_dsl() {
+    let source = r##"
+component HelloWorld {
+    state count = 0
+
+    render {
+        Column {
+            Text("Hello, OmniCraft!").fontSize(24).color("#6366f1")
+            Button("Count: {count}").onClick(|| count += 1)
+            Circle(10).onClick(|| count += 1)
+        }
+        .gap(16)
+    }
+}
+
+component Button {
+    prop label = ""
+
+    render {
+        Rect(120, 40).fill("#4f46e5")
+        Text(label).color("#ffffff")
+    }
+}
+"##;
+    let code = compile(source, "HelloWorld.omni", CompilationTarget::Rust).unwrap();
+    for snippet in [
+        "pub struct HelloWorldComponent {",
+        "pub struct ButtonComponent {",
+        "ButtonComponent::with_props(ctx, ButtonProps { label: format!(\"Count: {}\", count.get()), ..Default::default() });",
+        "TextContent { text: \"Hello, OmniCraft!\".to_string(), font_size: 24.0, ..Default::default() }",
+        "Shape::Rectangle { width: 120.0, height: 40.0 }",
+        "count.set((count.get() + 1.0))",
+        "insert(Stack { direction: StackDirection::Column, gap: 16.0, ..Default::default() });",
+        // Handlers of a component go on an entity its entities bubble to
+        "let entity_1 = world.spawn(Transform::new()).id();",
+        "world.entity_mut(entity_1).insert(Parent(parent_0));",
+        "world.on_event(entity_1, \"click\"",
+        "let mounted_1 = world.mount_child(&child_1);",
+        "world.adopt(entity_1, &mounted_1);",
+    ] {
+        assert!(code.contains(snippet), "missing `{}` in\n{}", snippet, code);
+    }
+    assert_eq!(code.matches("//! Generated by OmniCraft Compiler").count(), 1);
+    assert_eq!(code.matches("#[wasm_bindgen(start)]").count(), 1);
+    assert!(code.find("HelloWorldComponent").unwrap() < code.find("ButtonComponent {").unwrap());
+
+    let types = compile(source, "HelloWorld.omni", CompilationTarget::TypeScript).unwrap();
+    assert!(types.contains("export declare class HelloWorld {"), "{}", types);
+    assert!(types.contains("export interface ButtonProps {\n    label?: string;\n}"), "{}", types);
+
+    cargo_check("builder", &[(PathBuf::from("lib.rs"), code)]);
+}
+
+#[test]
+fn test_compile_readme_example() {
+    let readme = fs::read_to_string(workspace_root().join("README.md")).unwrap();
+    let start = readme.find("```omni\ncomponent HelloWorld").unwrap() + "```omni\n".len();
+    let source = &readme[start..start + readme[start..].find("```").unwrap()];
+
+    let code = compile(source, "HelloWorld.omni", CompilationTarget::Rust).unwrap();
+    for snippet in [
+        "insert(Stack { direction: StackDirection::Column, gap: 16.0, padding: 32.0, ..Default::default() });",
+        // The built-in button is a row drawing its background behind a label
+        "insert(Stack { direction: StackDirection::Row, padding: 16.0, corner_radius: 8.0, background: Some(Color::parse(\"#4f46e5\")), ..Default::default() });",
+        "TextContent { text: format!(\"Count: {}\", count.get()).to_string(), font_size: 16.0, ..Default::default() }",
+        "world.on_event(entity_1, \"click\"",
+    ] {
+        assert!(code.contains(snippet), "missing `{}` in\n{}", snippet, code);
+    }
+    cargo_check("readme", &[(PathBuf::from("lib.rs"), code)]);
+}
+
+#[test]
+fn test_compile_builder_module_graph() {
+    let files = [
+        (
+            "app/App.omni",
+            r#"
+import { Badge } from "./widgets.omni"
+
+component App {
+    render {
+        // Props of components from other files are set by modifiers
+        Badge().radius(5)
+    }
+}
+"#,
+        ),
+        (
+            "app/widgets.omni",
+            r#"
+component Badge {
+    prop radius = 1
+
+    render {
+        Circle(radius)
+    }
+}
+
+component Pill {
+    render {
+        Rect(40, 10)
+    }
+}
+"#,
+        ),
+    ];
+    let graph = ModuleGraph::build_with("app/App.omni", |path| {
+        let (_, source) = files.iter().find(|(name, _)| Path::new(name) == path).unwrap();
+        Ok(source.to_string())
+    })
+    .unwrap();
+
+    let output = graph.compile().unwrap();
+    let file = |name: &str| &output.iter().find(|(path, _)| path == Path::new(name)).unwrap().1;
+    let app = file("app.rs");
+    assert!(app.contains("use crate::widgets::{BadgeComponent, BadgeProps};"), "{}", app);
+    assert!(app.contains("BadgeProps { radius: 5.0, ..Default::default() }"), "{}", app);
+
+    let widgets = file("widgets.rs");
+    assert!(widgets.contains("pub struct BadgeComponent {") && widgets.contains("pub struct PillComponent {"));
+    assert!(!widgets.contains("#[wasm_bindgen(start)]"));
+
+    let missing = ModuleGraph::build_with("app/App.omni", |path| {
+        Ok(match path.to_str().unwrap() {
+            "app/App.omni" => r#"import { Chip } from "./widgets.omni" component App {}"#,
+            _ => files[1].1,
+        }
+        .to_string())
+    });
+    assert!(missing.unwrap_err().to_string().contains("no export named `Chip`"));
+}
@@ -7,11 +7,11 @@ use tower_lsp::lsp_types::*;
 /// Get the server capabilities
 pub fn server_capabilities() -> ServerCapabilities {
     ServerCapabilities {
-        // Sync full document on change
+        // Sync edits only; documents are re-lexed and re-parsed incrementally
         text_document_sync: Some(TextDocumentSyncCapability::Options(
             TextDocumentSyncOptions {
                 open_close: Some(true),
-                change: Some(TextDocumentSyncKind::FULL),
+                change: Some(TextDocumentSyncKind::INCREMENTAL),
                 save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
                     include_text: Some(true),
                 })),
@@ -35,8 +35,9 @@ pub fn server_capabilities() -> ServerCapabilities {
         // Hover
         hover_provider: Some(HoverProviderCapability::Simple(true)),
         
-        // Document formatting (future)
-        // document_formatting_provider: Some(OneOf::Left(true)),
+        // Document and range formatting
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
         
         // Go to definition (future)
         // definition_provider: Some(OneOf::Left(true)),
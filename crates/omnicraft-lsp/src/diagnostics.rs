@@ -2,6 +2,8 @@
 //!
 //! Validates `.omni` source files and produces diagnostics.
 
+use omnicraft_compiler::IncrementalDocument;
+use omnicraft_compiler::lexer::Span;
 use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
 
 /// Provider for source code diagnostics
@@ -12,50 +14,34 @@ impl DiagnosticsProvider {
         Self
     }
 
-    /// Validate source code and return diagnostics
-    pub fn validate(&self, source: &str) -> Vec<Diagnostic> {
+    /// Validate a document and return diagnostics
+    pub fn validate(&self, document: &IncrementalDocument) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
+        let source = document.source();
 
-        // Try lexing
-        match omnicraft_compiler::Lexer::new(source).tokenize() {
-            Ok(tokens) => {
-                // Try parsing
-                match omnicraft_compiler::Parser::new(tokens, "document.omni").parse() {
-                    Ok(_component) => {
-                        // Parsing succeeded - no diagnostics
-                    }
-                    Err(parse_err) => {
-                        // Add parse error diagnostic
-                        let message = format!("{}", parse_err);
-                        diagnostics.push(Diagnostic {
-                            range: Range {
-                                start: Position { line: 0, character: 0 },
-                                end: Position { line: 0, character: 1 },
-                            },
-                            severity: Some(DiagnosticSeverity::ERROR),
-                            code: Some(tower_lsp::lsp_types::NumberOrString::String("parse-error".to_string())),
-                            source: Some("omnicraft".to_string()),
-                            message,
-                            ..Default::default()
-                        });
-                    }
-                }
-            }
-            Err(lex_err) => {
-                // Add lex error diagnostic
-                let message = format!("{}", lex_err);
-                diagnostics.push(Diagnostic {
-                    range: Range {
-                        start: Position { line: 0, character: 0 },
-                        end: Position { line: 0, character: 1 },
-                    },
-                    severity: Some(DiagnosticSeverity::ERROR),
-                    code: Some(tower_lsp::lsp_types::NumberOrString::String("lex-error".to_string())),
-                    source: Some("omnicraft".to_string()),
-                    message,
-                    ..Default::default()
-                });
-            }
+        // Report every lexical error
+        let lex_errors = document.lex_errors();
+        for lex_err in lex_errors {
+            diagnostics.push(Diagnostic {
+                range: span_to_range(source, lex_err.span()),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(tower_lsp::lsp_types::NumberOrString::String("lex-error".to_string())),
+                source: Some("omnicraft".to_string()),
+                message: format!("{}", lex_err),
+                ..Default::default()
+            });
+        }
+
+        // The parser ran over the recovered token stream, so report its errors too
+        for parse_err in document.parse_errors() {
+            diagnostics.push(Diagnostic {
+                range: span_to_range(source, parse_err.span()),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(tower_lsp::lsp_types::NumberOrString::String("parse-error".to_string())),
+                source: Some("omnicraft".to_string()),
+                message: format!("{}", parse_err),
+                ..Default::default()
+            });
         }
 
         diagnostics
@@ -67,3 +53,48 @@ impl Default for DiagnosticsProvider {
         Self::new()
     }
 }
+
+/// Convert a byte span into an LSP range
+pub fn span_to_range(source: &str, span: Span) -> Range {
+    Range {
+        start: offset_to_position(source, span.start),
+        end: offset_to_position(source, span.end),
+    }
+}
+
+/// Convert an LSP position (UTF-16 columns) into a byte offset
+pub fn position_to_offset(source: &str, position: Position) -> usize {
+    let line_start = if position.line == 0 {
+        0
+    } else {
+        match source.match_indices('\n').nth(position.line as usize - 1) {
+            Some((i, _)) => i + 1,
+            None => return source.len(),
+        }
+    };
+
+    let line = &source[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= position.character as usize {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    line_start + line.len()
+}
+
+/// Convert a byte offset into an LSP position (UTF-16 columns)
+pub fn offset_to_position(source: &str, offset: usize) -> Position {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let character = source[line_start..offset].encode_utf16().count();
+
+    Position {
+        line: line as u32,
+        character: character as u32,
+    }
+}
@@ -0,0 +1,73 @@
+//! Document Store
+//!
+//! Keeps an incrementally updated copy of every open `.omni` document.
+
+use crate::diagnostics::position_to_offset;
+use omnicraft_compiler::lexer::Span;
+use omnicraft_compiler::{IncrementalDocument, TextEdit};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tower_lsp::lsp_types::{TextDocumentContentChangeEvent, Url};
+
+/// Open documents keyed by URI
+pub struct DocumentStore {
+    documents: Mutex<HashMap<Url, IncrementalDocument>>,
+}
+
+impl DocumentStore {
+    pub fn new() -> Self {
+        Self {
+            documents: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start tracking a document with its full text
+    pub fn open(&self, uri: Url, text: String) {
+        let document = IncrementalDocument::new(text, &file_name(&uri));
+        self.documents.lock().unwrap().insert(uri, document);
+    }
+
+    /// Apply content changes in the order the client sent them
+    pub fn change(&self, uri: &Url, changes: Vec<TextDocumentContentChangeEvent>) {
+        let mut documents = self.documents.lock().unwrap();
+        for change in changes {
+            match (change.range, documents.get_mut(uri)) {
+                (Some(range), Some(document)) => {
+                    let source = document.source();
+                    let start = position_to_offset(source, range.start);
+                    let end = position_to_offset(source, range.end);
+                    document.apply_edit(&TextEdit::new(Span::new(start, end), change.text));
+                }
+                // Full-text change, or a document we never saw opened
+                _ => {
+                    let document = IncrementalDocument::new(change.text, &file_name(uri));
+                    documents.insert(uri.clone(), document);
+                }
+            }
+        }
+    }
+
+    /// Stop tracking a document
+    pub fn close(&self, uri: &Url) {
+        self.documents.lock().unwrap().remove(uri);
+    }
+
+    /// Run `f` on a tracked document
+    pub fn with_document<R>(&self, uri: &Url, f: impl FnOnce(&IncrementalDocument) -> R) -> Option<R> {
+        self.documents.lock().unwrap().get(uri).map(f)
+    }
+}
+
+impl Default for DocumentStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn file_name(uri: &Url) -> String {
+    uri.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("document.omni")
+        .to_string()
+}
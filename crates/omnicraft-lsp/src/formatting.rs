@@ -0,0 +1,66 @@
+//! Formatting Provider
+//!
+//! Formats whole documents and ranges with the compiler's formatter.
+
+use crate::diagnostics::{position_to_offset, span_to_range};
+use omnicraft_compiler::lexer::Span;
+use omnicraft_compiler::{format_range, format_source, FormatOptions};
+use tower_lsp::lsp_types::{FormattingOptions, Position, Range, TextEdit};
+
+/// Provider for document and range formatting
+pub struct FormattingProvider;
+
+impl FormattingProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Edits that format the whole document, or none if it cannot be
+    /// parsed or is already formatted
+    pub fn format(&self, source: &str, options: &FormattingOptions) -> Vec<TextEdit> {
+        match format_source(source, &format_options(options)) {
+            Ok(formatted) if formatted != source => vec![TextEdit {
+                range: Range {
+                    start: Position::new(0, 0),
+                    end: span_to_range(source, Span::new(0, source.len())).end,
+                },
+                new_text: formatted,
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Edits that format the statements or elements overlapping `range`
+    pub fn format_range(
+        &self,
+        source: &str,
+        range: Range,
+        options: &FormattingOptions,
+    ) -> Vec<TextEdit> {
+        let span = Span::new(
+            position_to_offset(source, range.start),
+            position_to_offset(source, range.end),
+        );
+        match format_range(source, span, &format_options(options)) {
+            Ok(Some(edit)) => vec![TextEdit {
+                range: span_to_range(source, edit.span),
+                new_text: edit.text,
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Default for FormattingProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn format_options(options: &FormattingOptions) -> FormatOptions {
+    FormatOptions {
+        indent_width: options.tab_size as usize,
+        use_tabs: !options.insert_spaces,
+        ..Default::default()
+    }
+}
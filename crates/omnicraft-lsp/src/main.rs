@@ -9,30 +9,47 @@ use tower_lsp::{Client, LanguageServer, LspService, Server};
 mod capabilities;
 mod diagnostics;
 mod completion;
+mod documents;
+mod formatting;
 mod hover;
 
 pub use capabilities::server_capabilities;
 pub use diagnostics::DiagnosticsProvider;
 pub use completion::CompletionProvider;
+pub use documents::DocumentStore;
+pub use formatting::FormattingProvider;
 pub use hover::HoverProvider;
 
 /// OmniCraft Language Server backend
 pub struct OmniCraftLsp {
     client: Client,
+    documents: DocumentStore,
     diagnostics: DiagnosticsProvider,
     completion: CompletionProvider,
     hover: HoverProvider,
+    formatting: FormattingProvider,
 }
 
 impl OmniCraftLsp {
     pub fn new(client: Client) -> Self {
         Self {
             client,
+            documents: DocumentStore::new(),
             diagnostics: DiagnosticsProvider::new(),
             completion: CompletionProvider::new(),
             hover: HoverProvider::new(),
+            formatting: FormattingProvider::new(),
         }
     }
+
+    /// Publish diagnostics for a tracked document
+    async fn publish_diagnostics(&self, uri: Url) {
+        let diagnostics = self
+            .documents
+            .with_document(&uri, |document| self.diagnostics.validate(document))
+            .unwrap_or_default();
+        self.client.publish_diagnostics(uri, diagnostics, None).await;
+    }
 }
 
 #[tower_lsp::async_trait]
@@ -59,30 +76,27 @@ impl LanguageServer for OmniCraftLsp {
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri;
-        let text = params.text_document.text;
-        
-        let diagnostics = self.diagnostics.validate(&text);
-        self.client.publish_diagnostics(uri, diagnostics, None).await;
+        self.documents.open(uri.clone(), params.text_document.text);
+        self.publish_diagnostics(uri).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri;
-        if let Some(change) = params.content_changes.first() {
-            let diagnostics = self.diagnostics.validate(&change.text);
-            self.client.publish_diagnostics(uri, diagnostics, None).await;
-        }
+        self.documents.change(&uri, params.content_changes);
+        self.publish_diagnostics(uri).await;
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
         let uri = params.text_document.uri;
         if let Some(text) = params.text {
-            let diagnostics = self.diagnostics.validate(&text);
-            self.client.publish_diagnostics(uri, diagnostics, None).await;
+            self.documents.open(uri.clone(), text);
         }
+        self.publish_diagnostics(uri).await;
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let uri = params.text_document.uri;
+        self.documents.close(&uri);
         self.client.publish_diagnostics(uri, vec![], None).await;
     }
 
@@ -95,6 +109,22 @@ impl LanguageServer for OmniCraftLsp {
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
         Ok(self.hover.get_hover(&params))
     }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        Ok(self.documents.with_document(&params.text_document.uri, |document| {
+            self.formatting.format(document.source(), &params.options)
+        }))
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        Ok(self.documents.with_document(&params.text_document.uri, |document| {
+            self.formatting
+                .format_range(document.source(), params.range, &params.options)
+        }))
+    }
 }
 
 #[tokio::main]
@@ -0,0 +1,312 @@
+//! Two-Way Bindings
+//!
+//! A `bind:` directive keeps a component field and a signal in sync: signal
+//! changes are written to the component, and changes made to the component
+//! by the world, such as dragging a shape or editing text, are pushed back
+//! into the signal. Both directions are synced by `sync_bindings` on every
+//! `App::tick`; when both sides changed since the last sync, the signal
+//! wins. A binding stops listening to its signal once its entity is
+//! unmounted.
+//!
+//! A value computed from signals, such as the text `Count: {count}`, is
+//! bound one way with `bind_value`: the generated code subscribes the
+//! signals it reads to its `binding_trigger`, and changes made to the
+//! component are not written back.
+
+use super::*;
+use crate::signals::Signal;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A component field a `bind:` directive can target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoundProperty {
+    /// `Transform::position.x`
+    X,
+    /// `Transform::position.y`
+    Y,
+    /// `Transform::rotation`
+    Rotation,
+    /// `TextContent::text`
+    Text,
+}
+
+/// Value of a bound property
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundValue {
+    Number(f64),
+    Text(String),
+}
+
+impl BoundProperty {
+    fn read(self, world: &World, entity: Entity) -> Option<BoundValue> {
+        match self {
+            BoundProperty::X => world.get::<Transform>(entity).map(|t| BoundValue::Number(t.position.x as f64)),
+            BoundProperty::Y => world.get::<Transform>(entity).map(|t| BoundValue::Number(t.position.y as f64)),
+            BoundProperty::Rotation => world.get::<Transform>(entity).map(|t| BoundValue::Number(t.rotation as f64)),
+            BoundProperty::Text => world.get::<TextContent>(entity).map(|t| BoundValue::Text(t.text.clone())),
+        }
+    }
+
+    fn write(self, world: &mut World, entity: Entity, value: &BoundValue) {
+        let mut entity = world.entity_mut(entity);
+        match (self, value) {
+            (BoundProperty::Text, BoundValue::Text(text)) => match entity.get_mut::<TextContent>() {
+                Some(mut content) => content.text = text.clone(),
+                None => {
+                    entity.insert(TextContent::new(text.clone()));
+                }
+            },
+            (property, BoundValue::Number(n)) => {
+                if !entity.contains::<Transform>() {
+                    entity.insert(Transform::new());
+                }
+                let mut transform = entity.get_mut::<Transform>().expect("transform was just inserted");
+                match property {
+                    BoundProperty::X => transform.position.x = *n as f32,
+                    BoundProperty::Y => transform.position.y = *n as f32,
+                    BoundProperty::Rotation => transform.rotation = *n as f32,
+                    BoundProperty::Text => {}
+                }
+            }
+            (_, BoundValue::Text(_)) => {}
+        }
+    }
+}
+
+/// Signal values a property can be bound to
+pub trait Bindable: Clone + 'static {
+    fn to_bound(&self) -> BoundValue;
+    fn from_bound(value: &BoundValue) -> Option<Self>;
+}
+
+impl Bindable for f64 {
+    fn to_bound(&self) -> BoundValue {
+        BoundValue::Number(*self)
+    }
+
+    fn from_bound(value: &BoundValue) -> Option<Self> {
+        match value {
+            BoundValue::Number(n) => Some(*n),
+            BoundValue::Text(_) => None,
+        }
+    }
+}
+
+impl Bindable for f32 {
+    fn to_bound(&self) -> BoundValue {
+        BoundValue::Number(*self as f64)
+    }
+
+    fn from_bound(value: &BoundValue) -> Option<Self> {
+        f64::from_bound(value).map(|n| n as f32)
+    }
+}
+
+impl Bindable for String {
+    fn to_bound(&self) -> BoundValue {
+        BoundValue::Text(self.clone())
+    }
+
+    fn from_bound(value: &BoundValue) -> Option<Self> {
+        match value {
+            BoundValue::Text(text) => Some(text.clone()),
+            BoundValue::Number(_) => None,
+        }
+    }
+}
+
+/// Read and write paths of a binding, and its invalidation flag
+struct PropertyBinding {
+    get: Box<dyn Fn() -> BoundValue>,
+    set: Box<dyn Fn(&BoundValue)>,
+    /// Value of both sides after the last sync
+    synced: Option<BoundValue>,
+    dirty: Rc<Cell<bool>>,
+}
+
+/// Bindings of every entity
+///
+/// Bindings hold signals, which are not `Send`, so they live in a non-send
+/// resource.
+#[derive(Default)]
+pub struct PropertyBindings {
+    bindings: HashMap<(Entity, BoundProperty), PropertyBinding>,
+}
+
+pub(crate) fn bind_property<T: Bindable>(world: &mut World, entity: Entity, property: BoundProperty, signal: Signal<T>) {
+    let dirty = Rc::new(Cell::new(true));
+    let trigger = dirty.clone();
+    let subscription = signal.subscribe(move || trigger.set(true));
+    lifecycle::on_unmount(world, entity, Box::new(move |_| subscription.unsubscribe()));
+
+    let source = signal.clone();
+    let binding = PropertyBinding {
+        get: Box::new(move || source.get().to_bound()),
+        set: Box::new(move |value| {
+            if let Some(value) = T::from_bound(value) {
+                signal.set(value);
+            }
+        }),
+        synced: None,
+        dirty,
+    };
+    if !world.contains_non_send::<PropertyBindings>() {
+        world.insert_non_send_resource(PropertyBindings::default());
+    }
+    world
+        .non_send_resource_mut::<PropertyBindings>()
+        .bindings
+        .insert((entity, property), binding);
+    sync_bindings(world);
+}
+
+pub(crate) fn bind_value<T: Bindable>(
+    world: &mut World,
+    entity: Entity,
+    property: BoundProperty,
+    value: impl Fn() -> T + 'static,
+) {
+    let binding = PropertyBinding {
+        get: Box::new(move || value().to_bound()),
+        set: Box::new(|_| {}),
+        synced: None,
+        dirty: Rc::new(Cell::new(true)),
+    };
+    if !world.contains_non_send::<PropertyBindings>() {
+        world.insert_non_send_resource(PropertyBindings::default());
+    }
+    world
+        .non_send_resource_mut::<PropertyBindings>()
+        .bindings
+        .insert((entity, property), binding);
+    sync_bindings(world);
+}
+
+pub(crate) fn binding_trigger(world: &World, entity: Entity, property: BoundProperty) -> Box<dyn Fn()> {
+    let dirty = world
+        .get_non_send_resource::<PropertyBindings>()
+        .and_then(|b| b.bindings.get(&(entity, property)))
+        .map(|binding| binding.dirty.clone());
+    Box::new(move || {
+        if let Some(dirty) = &dirty {
+            dirty.set(true);
+        }
+    })
+}
+
+/// Mark every binding of `entity` so its signal overwrites the component
+/// at the next sync
+pub(crate) fn invalidate_bindings(world: &World, entity: Entity) {
+    let Some(bindings) = world.get_non_send_resource::<PropertyBindings>() else {
+        return;
+    };
+    for ((bound, _), binding) in &bindings.bindings {
+        if *bound == entity {
+            binding.dirty.set(true);
+        }
+    }
+}
+
+pub(crate) fn sync_bindings(world: &mut World) {
+    let Some(mut bindings) = world.remove_non_send_resource::<PropertyBindings>() else {
+        return;
+    };
+    // Bindings of despawned entities, e.g. inside a re-rendered region
+    bindings.bindings.retain(|(entity, _), _| world.get_entity(*entity).is_ok());
+
+    for ((entity, property), binding) in &mut bindings.bindings {
+        let current = property.read(world, *entity);
+        if binding.dirty.replace(false) {
+            let value = (binding.get)();
+            // Only touch the component when the value differs, to keep
+            // change detection meaningful
+            if current.as_ref() != Some(&value) {
+                property.write(world, *entity, &value);
+            }
+            binding.synced = Some(value);
+        } else if let Some(current) = current
+            && binding.synced.as_ref() != Some(&current)
+        {
+            (binding.set)(&current);
+            // Setting the signal marks the binding, but both sides agree
+            binding.dirty.set(false);
+            binding.synced = Some(current);
+        }
+    }
+    world.insert_non_send_resource(bindings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::create_signal;
+
+    #[test]
+    fn test_signal_writes_component() {
+        let mut world = World::new();
+        let x = create_signal(10.0);
+        let entity = world.spawn_circle(0.0, 0.0, 5.0);
+        world.bind_property(entity, BoundProperty::X, x.clone());
+        assert_eq!(world.get::<Transform>(entity).unwrap().position.x, 10.0);
+
+        x.set(42.0);
+        assert_eq!(world.get::<Transform>(entity).unwrap().position.x, 10.0);
+        world.sync_bindings();
+        assert_eq!(world.get::<Transform>(entity).unwrap().position.x, 42.0);
+    }
+
+    #[test]
+    fn test_component_writes_signal() {
+        let mut world = World::new();
+        let y = create_signal(0.0);
+        let label = create_signal("Title".to_string());
+        let entity = world.spawn_circle(0.0, 0.0, 5.0);
+        world.bind_property(entity, BoundProperty::Y, y.clone());
+        world.bind_property(entity, BoundProperty::Text, label.clone());
+        assert_eq!(world.get::<TextContent>(entity).unwrap().text, "Title");
+
+        // A drag moves the shape and an edit changes the text
+        world.get_mut::<Transform>(entity).unwrap().position.y = 25.0;
+        world.get_mut::<TextContent>(entity).unwrap().text = "Edited".to_string();
+        world.sync_bindings();
+        assert_eq!(y.get(), 25.0);
+        assert_eq!(label.get(), "Edited");
+
+        // The signal wins when both sides changed
+        world.get_mut::<Transform>(entity).unwrap().position.y = 30.0;
+        y.set(5.0);
+        world.sync_bindings();
+        assert_eq!(world.get::<Transform>(entity).unwrap().position.y, 5.0);
+        assert_eq!(y.get(), 5.0);
+
+        world.despawn(entity);
+        world.sync_bindings();
+        assert!(world.non_send_resource::<PropertyBindings>().bindings.is_empty());
+    }
+
+    #[test]
+    fn test_value_follows_signals_it_reads() {
+        let mut world = World::new();
+        let count = create_signal(0.0);
+        let entity = world.spawn_text(0.0, 0.0, "");
+        let source = count.clone();
+        world.bind_value(entity, BoundProperty::Text, move || format!("Count: {}", source.get()));
+        let _subscription = count.subscribe(world.binding_trigger(entity, BoundProperty::Text));
+        assert_eq!(world.get::<TextContent>(entity).unwrap().text, "Count: 0");
+
+        count.set(2.0);
+        world.sync_bindings();
+        assert_eq!(world.get::<TextContent>(entity).unwrap().text, "Count: 2");
+
+        // Edits to the component stay until the value changes again
+        world.get_mut::<TextContent>(entity).unwrap().text = "Edited".to_string();
+        world.sync_bindings();
+        assert_eq!(count.get(), 2.0);
+        assert_eq!(world.get::<TextContent>(entity).unwrap().text, "Edited");
+        count.set(3.0);
+        world.sync_bindings();
+        assert_eq!(world.get::<TextContent>(entity).unwrap().text, "Count: 3");
+    }
+}
@@ -0,0 +1,168 @@
+//! Element Lifecycle
+//!
+//! `ref={el}` stores the entity of an element in an `EntityRef`, and
+//! `use:action={options}` calls an action with the entity when the element
+//! mounts. Both register cleanups that run once the entity is gone, e.g.
+//! after its region re-rendered: a ref is cleared, and an action's cleanup
+//! is called. Signal subscriptions made for an entity, such as the trigger
+//! of a nested region, are dropped the same way. Cleanups are run by
+//! `run_cleanups` on every `App::tick`.
+
+use super::*;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Runs when the entity it was registered for is despawned
+pub type Cleanup = Box<dyn FnOnce(&mut World)>;
+
+/// Shared handle to the entity of an element, set by `ref={...}`
+///
+/// Clones share the handle, so script code holding one sees the entity
+/// once the element mounts, and `None` after it unmounts.
+#[derive(Debug, Clone, Default)]
+pub struct EntityRef(Rc<Cell<Option<Entity>>>);
+
+impl EntityRef {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self) -> Option<Entity> {
+        self.0.get()
+    }
+
+    pub fn set(&self, entity: Option<Entity>) {
+        self.0.set(entity);
+    }
+}
+
+/// What an action returns: nothing, or a cleanup to run on unmount
+pub trait ActionResult {
+    fn into_cleanup(self) -> Option<Cleanup>;
+}
+
+impl ActionResult for () {
+    fn into_cleanup(self) -> Option<Cleanup> {
+        None
+    }
+}
+
+impl<F: FnOnce(&mut World) + 'static> ActionResult for F {
+    fn into_cleanup(self) -> Option<Cleanup> {
+        Some(Box::new(self))
+    }
+}
+
+/// Cleanups of every mounted entity
+///
+/// Cleanups capture script state, which is not `Send`, so they live in a
+/// non-send resource.
+#[derive(Default)]
+pub struct Cleanups {
+    cleanups: HashMap<Entity, Vec<Cleanup>>,
+}
+
+pub(crate) fn on_unmount(world: &mut World, entity: Entity, cleanup: Cleanup) {
+    if !world.contains_non_send::<Cleanups>() {
+        world.insert_non_send_resource(Cleanups::default());
+    }
+    world
+        .non_send_resource_mut::<Cleanups>()
+        .cleanups
+        .entry(entity)
+        .or_default()
+        .push(cleanup);
+}
+
+pub(crate) fn bind_ref(world: &mut World, entity: Entity, entity_ref: &EntityRef) {
+    entity_ref.set(Some(entity));
+    let entity_ref = entity_ref.clone();
+    on_unmount(
+        world,
+        entity,
+        Box::new(move |_| {
+            // The ref may point at a newer element by now
+            if entity_ref.get() == Some(entity) {
+                entity_ref.set(None);
+            }
+        }),
+    );
+}
+
+pub(crate) fn run_cleanups(world: &mut World) {
+    let Some(mut cleanups) = world.remove_non_send_resource::<Cleanups>() else {
+        return;
+    };
+    let unmounted: Vec<Entity> = cleanups
+        .cleanups
+        .keys()
+        .filter(|entity| world.get_entity(**entity).is_err())
+        .copied()
+        .collect();
+    let unmounted: Vec<Cleanup> = unmounted
+        .into_iter()
+        .flat_map(|entity| cleanups.cleanups.remove(&entity).unwrap_or_default())
+        .collect();
+    // Cleanups may register new cleanups, e.g. by mounting elements
+    world.insert_non_send_resource(cleanups);
+    for cleanup in unmounted {
+        cleanup(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::create_signal;
+
+    /// Counts its mounted entities
+    fn tracked(world: &mut World, entity: Entity, count: Rc<Cell<usize>>) -> Cleanup {
+        assert!(world.get_entity(entity).is_ok());
+        count.set(count.get() + 1);
+        Box::new(move |_| count.set(count.get() - 1))
+    }
+
+    #[test]
+    fn test_ref_and_action_follow_region() {
+        let mut world = World::new();
+        let show = create_signal(true);
+        let circle = EntityRef::new();
+        let mounted = Rc::new(Cell::new(0));
+
+        let (condition, target, count) = (show.clone(), circle.clone(), mounted.clone());
+        let region = world.spawn_region(move |world: &mut World| {
+            if !condition.get() {
+                return Vec::new();
+            }
+            let entity = world.spawn_circle(0.0, 0.0, 10.0);
+            world.bind_ref(entity, &target);
+            world.use_action(entity, tracked, count.clone());
+            vec![entity]
+        });
+        show.subscribe(world.region_trigger(region));
+
+        let entity = world.get::<Region>(region).unwrap().entities[0];
+        assert_eq!(circle.get(), Some(entity));
+        assert_eq!(mounted.get(), 1);
+
+        show.set(false);
+        world.refresh_dirty_regions();
+        world.run_cleanups();
+        assert_eq!(circle.get(), None);
+        assert_eq!(mounted.get(), 0);
+        assert!(world.non_send_resource::<Cleanups>().cleanups.is_empty());
+    }
+
+    #[test]
+    fn test_action_without_cleanup() {
+        let mut world = World::new();
+        let entity = world.spawn_circle(0.0, 0.0, 10.0);
+        let grow = |world: &mut World, entity: Entity, radius: f32| {
+            world.entity_mut(entity).insert(Shape::Circle { radius });
+        };
+        world.use_action(entity, grow, 20.0);
+        assert!(matches!(world.get::<Shape>(entity), Some(Shape::Circle { radius }) if *radius == 20.0));
+        assert!(!world.contains_non_send::<Cleanups>());
+    }
+}
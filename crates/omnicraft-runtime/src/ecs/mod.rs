@@ -4,8 +4,22 @@
 
 use bevy_ecs::prelude::*;
 
+pub mod binding;
+pub mod lifecycle;
+pub mod region;
+pub mod slot;
+pub mod stack;
+pub mod stylesheet;
 pub mod world_ext;
 
+pub use binding::{Bindable, BoundProperty, BoundValue, PropertyBindings};
+pub use lifecycle::{ActionResult, Cleanup, Cleanups, EntityRef};
+pub use region::{Key, Region, RegionRenderers, RenderFn};
+pub use slot::{SlotFn, SlotProps, Slots, DEFAULT_SLOT};
+pub use stack::{Stack, StackDirection};
+pub use stylesheet::{
+    resolve_styles, Class, ClassBindings, Id, ScopedStyle, StyleDeclarations, StyleRule, Stylesheet,
+};
 pub use world_ext::*;
 
 /// Transform component for position, rotation, and scale
@@ -320,4 +334,27 @@ mod tests {
         assert!((transform.rotation - std::f32::consts::PI).abs() < 0.001);
         assert_eq!(transform.scale, glam::Vec2::new(2.0, 2.0));
     }
+
+    #[test]
+    fn test_mount_child() {
+        struct Dots(usize);
+
+        impl crate::OmniComponent for Dots {
+            fn create(_ctx: &mut crate::Context) -> Self {
+                Dots(2)
+            }
+
+            fn mount(&self, world: &mut World) {
+                for i in 0..self.0 {
+                    world.spawn_circle(i as f32, 0.0, 1.0);
+                }
+            }
+        }
+
+        let mut world = World::new();
+        let existing = world.spawn_circle(0.0, 0.0, 5.0);
+        let entities = world.mount_child(&Dots(3));
+        assert_eq!(entities.len(), 3);
+        assert!(!entities.contains(&existing));
+    }
 }
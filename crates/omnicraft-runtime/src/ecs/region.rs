@@ -0,0 +1,232 @@
+//! Reactive Regions
+//!
+//! Template control flow (`{#if}`, `{#each}`, `{#await}`) renders into a
+//! region: an entity owning the entities spawned by a render function.
+//! When a region is invalidated, the next refresh despawns its entities and
+//! renders it again.
+//!
+//! Keyed elements (`key={item.id}`) are reconciled instead: a render that
+//! spawns an element with the key of one from the previous render gets the
+//! existing entity back, so reordering a list moves entities, keeping their
+//! state, handlers and bindings, rather than respawning them.
+
+use super::*;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Renders the content of a region, returning the entities it spawned
+pub type RenderFn = Rc<dyn Fn(&mut World) -> Vec<Entity>>;
+
+/// Entities currently rendered by a region
+#[derive(Component, Debug, Clone, Default)]
+pub struct Region {
+    pub entities: Vec<Entity>,
+}
+
+/// Identity of a keyed element within its region
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Key(pub String);
+
+impl Key {
+    pub fn new(key: impl ToString) -> Self {
+        Self(key.to_string())
+    }
+}
+
+/// Render function and invalidation flag of a region
+struct Renderer {
+    render: RenderFn,
+    dirty: Rc<Cell<bool>>,
+}
+
+/// Renderers of every region
+///
+/// Render functions capture signals, which are not `Send`, so they live in
+/// a non-send resource instead of the `Region` component.
+#[derive(Default)]
+pub struct RegionRenderers {
+    renderers: HashMap<Entity, Renderer>,
+    /// Keyed entities not yet claimed by the regions being rendered,
+    /// innermost last
+    keyed: Vec<HashMap<Key, Entity>>,
+}
+
+pub(crate) fn spawn_region(world: &mut World, render: RenderFn) -> Entity {
+    let region = world.spawn(Region::default()).id();
+    if !world.contains_non_send::<RegionRenderers>() {
+        world.insert_non_send_resource(RegionRenderers::default());
+    }
+    world.non_send_resource_mut::<RegionRenderers>().renderers.insert(
+        region,
+        Renderer {
+            render,
+            dirty: Rc::new(Cell::new(false)),
+        },
+    );
+    refresh_region(world, region);
+    region
+}
+
+pub(crate) fn region_trigger(world: &World, region: Entity) -> Box<dyn Fn()> {
+    let dirty = world
+        .get_non_send_resource::<RegionRenderers>()
+        .and_then(|r| r.renderers.get(&region))
+        .map(|renderer| renderer.dirty.clone());
+    Box::new(move || {
+        if let Some(dirty) = &dirty {
+            dirty.set(true);
+        }
+    })
+}
+
+pub(crate) fn refresh_region(world: &mut World, region: Entity) {
+    let Some(render) = world
+        .get_non_send_resource::<RegionRenderers>()
+        .and_then(|r| r.renderers.get(&region))
+        .map(|renderer| {
+            renderer.dirty.set(false);
+            renderer.render.clone()
+        })
+    else {
+        clear_region(world, region);
+        return;
+    };
+    let previous = world
+        .get_mut::<Region>(region)
+        .map(|mut content| std::mem::take(&mut content.entities))
+        .unwrap_or_default();
+    let keyed = previous
+        .iter()
+        .filter_map(|entity| world.get::<Key>(*entity).map(|key| (key.clone(), *entity)))
+        .collect();
+    world.non_send_resource_mut::<RegionRenderers>().keyed.push(keyed);
+    let entities = render(world);
+    world.non_send_resource_mut::<RegionRenderers>().keyed.pop();
+
+    // Despawn what the render didn't claim
+    let kept: HashSet<Entity> = entities.iter().copied().collect();
+    despawn_entities(world, previous.into_iter().filter(|entity| !kept.contains(entity)));
+    if let Some(mut content) = world.get_mut::<Region>(region) {
+        content.entities = entities;
+    }
+}
+
+pub(crate) fn spawn_keyed(world: &mut World, key: Key) -> (Entity, bool) {
+    let reused = world
+        .get_non_send_resource_mut::<RegionRenderers>()
+        .and_then(|mut r| r.keyed.last_mut().and_then(|keyed| keyed.remove(&key)));
+    match reused {
+        Some(entity) => {
+            // The render inserts the entity's components again, so its
+            // class and property bindings have to be applied again too
+            stylesheet::invalidate_class_bindings(world, entity);
+            binding::invalidate_bindings(world, entity);
+            (entity, true)
+        }
+        None => (world.spawn(key).id(), false),
+    }
+}
+
+pub(crate) fn refresh_dirty_regions(world: &mut World) {
+    let dirty: Vec<Entity> = world
+        .get_non_send_resource::<RegionRenderers>()
+        .map(|r| {
+            r.renderers
+                .iter()
+                .filter(|(_, renderer)| renderer.dirty.get())
+                .map(|(region, _)| *region)
+                .collect()
+        })
+        .unwrap_or_default();
+    for region in dirty {
+        // A region despawned by an enclosing refresh is gone by now
+        if world.get_entity(region).is_ok() {
+            refresh_region(world, region);
+        }
+    }
+}
+
+/// Despawn the entities of a region, along with nested regions
+fn clear_region(world: &mut World, region: Entity) {
+    let entities = world
+        .get_mut::<Region>(region)
+        .map(|mut content| std::mem::take(&mut content.entities))
+        .unwrap_or_default();
+    despawn_entities(world, entities);
+}
+
+fn despawn_entities(world: &mut World, entities: impl IntoIterator<Item = Entity>) {
+    for entity in entities {
+        if world.get::<Region>(entity).is_some() {
+            clear_region(world, entity);
+            if let Some(mut renderers) = world.get_non_send_resource_mut::<RegionRenderers>() {
+                renderers.renderers.remove(&entity);
+            }
+        }
+        world.despawn(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::create_signal;
+
+    #[test]
+    fn test_region_rerenders_when_triggered() {
+        let mut world = World::new();
+        let show = create_signal(true);
+        let condition = show.clone();
+        let region = world.spawn_region(move |world: &mut World| {
+            if condition.get() {
+                vec![world.spawn_circle(0.0, 0.0, 10.0)]
+            } else {
+                Vec::new()
+            }
+        });
+        show.subscribe(world.region_trigger(region));
+        assert_eq!(world.get::<Region>(region).unwrap().entities.len(), 1);
+        let circle = world.get::<Region>(region).unwrap().entities[0];
+
+        show.set(false);
+        world.refresh_dirty_regions();
+        assert!(world.get::<Region>(region).unwrap().entities.is_empty());
+        assert!(world.get_entity(circle).is_err());
+    }
+
+    #[test]
+    fn test_keyed_entities_survive_reorder() {
+        let mut world = World::new();
+        let items = create_signal(vec![1, 2, 3]);
+        let source = items.clone();
+        let region = world.spawn_region(move |world: &mut World| {
+            let mut entities = Vec::new();
+            for (i, item) in source.get().into_iter().enumerate() {
+                let (entity, reused) = world.spawn_keyed(Key::new(item));
+                if !reused {
+                    world.entity_mut(entity).insert(Name(format!("item {}", item)));
+                }
+                world.entity_mut(entity).insert(Transform::from_xy(i as f32 * 10.0, 0.0));
+                entities.push(entity);
+            }
+            entities
+        });
+        items.subscribe(world.region_trigger(region));
+        let before = world.get::<Region>(region).unwrap().entities.clone();
+
+        items.set(vec![3, 1, 4]);
+        world.refresh_dirty_regions();
+        let after = world.get::<Region>(region).unwrap().entities.clone();
+        assert_eq!(after[0], before[2]);
+        assert_eq!(after[1], before[0]);
+        assert!(!before.contains(&after[2]));
+        assert!(world.get_entity(before[1]).is_err());
+
+        // Reused entities keep their state and take their new place
+        assert_eq!(world.get::<Name>(after[0]).unwrap().0, "item 3");
+        assert_eq!(world.get::<Transform>(after[0]).unwrap().position.x, 0.0);
+        assert_eq!(world.get::<Key>(after[2]).unwrap(), &Key::new(4));
+        assert!(world.non_send_resource::<RegionRenderers>().keyed.is_empty());
+    }
+}
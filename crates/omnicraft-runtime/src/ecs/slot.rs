@@ -0,0 +1,113 @@
+//! Slots
+//!
+//! A component renders the content its parent nested in it wherever its
+//! template has a `<slot>`. The parent passes that content in the `slots`
+//! of the child's props, as one render function per slot name. The props a
+//! `<slot>` declares are handed to the render function, and the entities
+//! it spawns are parented to the element enclosing the `<slot>`, so they
+//! join the child component's hierarchy.
+
+use super::*;
+use std::any::Any;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Name of the slot filled by content without a `slot="..."` attribute
+pub const DEFAULT_SLOT: &str = "default";
+
+/// Values a `<slot>` passes back to the content rendered in it
+#[derive(Clone, Default)]
+pub struct SlotProps(HashMap<String, Rc<dyn Any>>);
+
+impl SlotProps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, value: impl Any) -> Self {
+        self.0.insert(name.to_string(), Rc::new(value));
+        self
+    }
+
+    /// The prop `name`, if the slot passes one of type `T`
+    pub fn get<T: Clone + 'static>(&self, name: &str) -> Option<T> {
+        self.0.get(name).and_then(|value| value.downcast_ref::<T>()).cloned()
+    }
+}
+
+/// Renders the content of a slot, returning the entities it spawned
+pub type SlotFn = Rc<dyn Fn(&mut World, &SlotProps) -> Vec<Entity>>;
+
+/// Content a parent provides for the slots of a child component
+#[derive(Clone, Default)]
+pub struct Slots(HashMap<String, SlotFn>);
+
+impl std::fmt::Debug for Slots {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+impl Slots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Provide the content of the slot `name`
+    pub fn insert(&mut self, name: &str, render: impl Fn(&mut World, &SlotProps) -> Vec<Entity> + 'static) {
+        self.0.insert(name.to_string(), Rc::new(render));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+}
+
+pub(crate) fn render_slot(
+    world: &mut World,
+    slots: &Slots,
+    name: &str,
+    props: &SlotProps,
+    parent: Option<Entity>,
+) -> Vec<Entity> {
+    let Some(render) = slots.0.get(name).cloned() else {
+        return Vec::new();
+    };
+    let entities = render(world, props);
+    if let Some(parent) = parent {
+        world.adopt(parent, &entities);
+    }
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_slot_with_props() {
+        let mut world = World::new();
+        let mut slots = Slots::new();
+        slots.insert("row", |world: &mut World, props: &SlotProps| {
+            let label = props.get::<String>("label").unwrap_or_default();
+            let text = world.spawn_text(0.0, 0.0, &label);
+            let dot = world.spawn_circle(0.0, 0.0, 2.0);
+            world.entity_mut(dot).insert(Parent(text));
+            vec![text, dot]
+        });
+        assert!(slots.contains("row"));
+        assert!(!slots.contains(DEFAULT_SLOT));
+
+        let card = world.spawn(Transform::new()).id();
+        let props = SlotProps::new().with("label", "Speed".to_string()).with("index", 3.0);
+        let entities = world.render_slot(&slots, "row", &props, Some(card));
+        assert_eq!(entities.len(), 2);
+        assert_eq!(world.get::<TextContent>(entities[0]).unwrap().text, "Speed");
+        assert_eq!(world.get::<Parent>(entities[0]).unwrap().0, card);
+        assert_eq!(world.get::<Parent>(entities[1]).unwrap().0, entities[0]);
+
+        assert_eq!(props.get::<f64>("index"), Some(3.0));
+        assert_eq!(props.get::<f32>("index"), None);
+        assert!(world.render_slot(&slots, DEFAULT_SLOT, &props, None).is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+//! Stack Layout
+//!
+//! Columns and rows place their children one after another. A stack's
+//! `Transform` is its top-left corner; each child is moved, along with the
+//! entities nested in it, so that its bounds start where the previous one
+//! ended, and the stack is sized to fit them.
+//!
+//! Bounds ignore rotation and scale, and the runtime doesn't measure text,
+//! so its width is estimated from the font size.
+
+use super::{Color, Parent, Shape, TextContent, Transform, Visibility};
+use bevy_ecs::prelude::*;
+use glam::Vec2;
+use std::collections::HashMap;
+
+/// Direction a stack places its children in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum StackDirection {
+    #[default]
+    Column,
+    Row,
+}
+
+/// A group laying out its children in a column or row
+#[derive(Component, Debug, Clone, Default)]
+pub struct Stack {
+    pub direction: StackDirection,
+    /// Space between two children
+    pub gap: f32,
+    /// Space around the children, inside the background
+    pub padding: f32,
+    pub background: Option<Color>,
+    pub corner_radius: f32,
+    /// Size of the stack, set by the layout
+    pub size: Vec2,
+}
+
+impl Stack {
+    pub fn column() -> Self {
+        Self::default()
+    }
+
+    pub fn row() -> Self {
+        Self {
+            direction: StackDirection::Row,
+            ..Default::default()
+        }
+    }
+
+    /// Whether `point`, relative to the stack's top-left corner, is inside it
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(Vec2::ZERO).all() && point.cmple(self.size).all()
+    }
+}
+
+/// Children of every entity with one, in spawn order
+type Hierarchy = HashMap<Entity, Vec<Entity>>;
+
+/// Lay out every stack, inner ones first
+pub(crate) fn layout_stacks(world: &mut World) {
+    if world.query::<&Stack>().iter(world).next().is_none() {
+        return;
+    }
+
+    let mut hierarchy = Hierarchy::new();
+    for (entity, parent) in world.query::<(Entity, &Parent)>().iter(world) {
+        hierarchy.entry(parent.0).or_default().push(entity);
+    }
+    for children in hierarchy.values_mut() {
+        children.sort_by_key(|entity| entity.index());
+    }
+    let roots: Vec<Entity> = world
+        .query_filtered::<Entity, Without<Parent>>()
+        .iter(world)
+        .collect();
+    for root in roots {
+        arrange(world, &hierarchy, root);
+    }
+}
+
+/// Lay out the stacks in the subtree of `entity`
+fn arrange(world: &mut World, hierarchy: &Hierarchy, entity: Entity) {
+    let children = hierarchy.get(&entity).map_or(&[][..], Vec::as_slice);
+    for child in children {
+        arrange(world, hierarchy, *child);
+    }
+    let Some(stack) = world.get::<Stack>(entity).cloned() else {
+        return;
+    };
+
+    let origin = world.get::<Transform>(entity).map_or(Vec2::ZERO, |t| t.position);
+    let mut cursor = origin + Vec2::splat(stack.padding);
+    let mut content = Vec2::ZERO;
+    let mut placed = 0;
+    for child in children {
+        let Some((min, max)) = bounds(world, hierarchy, *child) else {
+            continue;
+        };
+        translate(world, hierarchy, *child, cursor - min);
+
+        let size = max - min;
+        let gap = if placed > 0 { stack.gap } else { 0.0 };
+        match stack.direction {
+            StackDirection::Column => {
+                content = Vec2::new(content.x.max(size.x), content.y + gap + size.y);
+                cursor.y += size.y + stack.gap;
+            }
+            StackDirection::Row => {
+                content = Vec2::new(content.x + gap + size.x, content.y.max(size.y));
+                cursor.x += size.x + stack.gap;
+            }
+        }
+        placed += 1;
+    }
+
+    let size = content + Vec2::splat(stack.padding * 2.0);
+    if let Some(mut stack) = world.get_mut::<Stack>(entity)
+        && stack.size != size
+    {
+        stack.size = size;
+    }
+}
+
+/// Area covered by `entity` and the entities nested in it, as its top-left
+/// and bottom-right corners
+fn bounds(world: &World, hierarchy: &Hierarchy, entity: Entity) -> Option<(Vec2, Vec2)> {
+    if world.get::<Visibility>(entity).is_some_and(|v| !v.visible) {
+        return None;
+    }
+    let position = world.get::<Transform>(entity)?.position;
+    if let Some(stack) = world.get::<Stack>(entity) {
+        return Some((position, position + stack.size));
+    }
+
+    let own = if let Some(shape) = world.get::<Shape>(entity) {
+        shape_bounds(shape)
+    } else {
+        world.get::<TextContent>(entity).map(|text| {
+            let width = 0.6 * text.font_size * text.text.chars().count() as f32;
+            // Text is drawn on its baseline
+            (Vec2::new(0.0, -0.8 * text.font_size), Vec2::new(width, 0.2 * text.font_size))
+        })
+    };
+    let own = own.map(|(min, max)| (position + min, position + max));
+
+    let children = hierarchy.get(&entity).map_or(&[][..], Vec::as_slice);
+    children
+        .iter()
+        .filter_map(|child| bounds(world, hierarchy, *child))
+        .chain(own)
+        .reduce(|(min, max), (child_min, child_max)| (min.min(child_min), max.max(child_max)))
+}
+
+/// Area of a shape around its origin
+fn shape_bounds(shape: &Shape) -> Option<(Vec2, Vec2)> {
+    match shape {
+        Shape::Circle { radius } => Some((Vec2::splat(-radius), Vec2::splat(*radius))),
+        Shape::Rectangle { width, height } => {
+            let half = Vec2::new(*width, *height) / 2.0;
+            Some((-half, half))
+        }
+        Shape::Ellipse { rx, ry } => Some((-Vec2::new(*rx, *ry), Vec2::new(*rx, *ry))),
+        Shape::Line { x2, y2 } => {
+            let end = Vec2::new(*x2, *y2);
+            Some((end.min(Vec2::ZERO), end.max(Vec2::ZERO)))
+        }
+        Shape::Polygon { points } => points
+            .iter()
+            .map(|point| (*point, *point))
+            .reduce(|(min, max), (point, _)| (min.min(point), max.max(point))),
+        Shape::Path { .. } => None,
+    }
+}
+
+/// Move `entity` and the entities nested in it by `offset`
+fn translate(world: &mut World, hierarchy: &Hierarchy, entity: Entity, offset: Vec2) {
+    // Only touch transforms that move, to keep change detection meaningful
+    if offset == Vec2::ZERO {
+        return;
+    }
+    if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+        transform.position += offset;
+    }
+    for child in hierarchy.get(&entity).into_iter().flatten() {
+        translate(world, hierarchy, *child, offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(world: &World, entity: Entity) -> Vec2 {
+        world.get::<Transform>(entity).unwrap().position
+    }
+
+    #[test]
+    fn test_column_places_children_after_one_another() {
+        let mut world = World::new();
+        let column = world
+            .spawn((Transform::from_xy(10.0, 20.0), Stack { gap: 4.0, padding: 8.0, ..Stack::column() }))
+            .id();
+        let circle = world.spawn((Transform::new(), Shape::Circle { radius: 5.0 }, Parent(column))).id();
+        let rect = world
+            .spawn((Transform::from_xy(300.0, 300.0), Shape::Rectangle { width: 40.0, height: 20.0 }, Parent(column)))
+            .id();
+
+        layout_stacks(&mut world);
+        // Shapes are centered on their transform
+        assert_eq!(position(&world, circle), Vec2::new(23.0, 33.0));
+        assert_eq!(position(&world, rect), Vec2::new(38.0, 52.0));
+        assert_eq!(world.get::<Stack>(column).unwrap().size, Vec2::new(56.0, 50.0));
+
+        // Laying out again doesn't move anything
+        layout_stacks(&mut world);
+        assert_eq!(position(&world, rect), Vec2::new(38.0, 52.0));
+    }
+
+    #[test]
+    fn test_nested_stacks_and_groups() {
+        let mut world = World::new();
+        let column = world.spawn((Transform::new(), Stack { gap: 10.0, ..Stack::column() })).id();
+        let row = world
+            .spawn((Transform::new(), Stack { gap: 2.0, padding: 1.0, ..Stack::row() }, Parent(column)))
+            .id();
+        let a = world.spawn((Transform::new(), Shape::Rectangle { width: 10.0, height: 10.0 }, Parent(row))).id();
+        let b = world.spawn((Transform::new(), Shape::Rectangle { width: 10.0, height: 4.0 }, Parent(row))).id();
+
+        // A group without a shape covers its children, which move with it
+        let group = world.spawn((Transform::new(), Parent(column))).id();
+        let text = world
+            .spawn((Transform::new(), TextContent::new("Hi").with_font_size(10.0), Parent(group)))
+            .id();
+
+        layout_stacks(&mut world);
+        assert_eq!(world.get::<Stack>(row).unwrap().size, Vec2::new(24.0, 12.0));
+        assert_eq!(position(&world, a), Vec2::new(6.0, 6.0));
+        assert_eq!(position(&world, b), Vec2::new(18.0, 3.0));
+        assert_eq!(position(&world, group), Vec2::new(0.0, 30.0));
+        assert_eq!(position(&world, text), Vec2::new(0.0, 30.0));
+        assert_eq!(world.get::<Stack>(column).unwrap().size, Vec2::new(24.0, 32.0));
+    }
+}
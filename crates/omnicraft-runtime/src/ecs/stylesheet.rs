@@ -0,0 +1,434 @@
+//! Runtime Stylesheets
+//!
+//! Most style rules are resolved by the compiler. Elements whose classes
+//! can change at runtime, and the elements nested in them, are styled here
+//! instead: they carry a `ScopedStyle`, and `resolve_styles` recomputes
+//! their `Style` from the `Stylesheet` whenever a `Class` or `Id` changes.
+
+use super::*;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Classes of an element
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct Class(pub Vec<String>);
+
+impl Class {
+    /// Classes from a whitespace-separated list
+    pub fn new(classes: &str) -> Self {
+        Self(classes.split_whitespace().map(str::to_string).collect())
+    }
+
+    pub fn contains(&self, class: &str) -> bool {
+        self.0.iter().any(|c| c == class)
+    }
+
+    /// Add or remove `class`
+    pub fn toggle(&mut self, class: &str, enabled: bool) {
+        if enabled && !self.contains(class) {
+            self.0.push(class.to_string());
+        } else if !enabled {
+            self.0.retain(|c| c != class);
+        }
+    }
+}
+
+/// Id of an element
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Id(pub String);
+
+impl Id {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+}
+
+/// Style properties set by a rule or an element; `None` leaves a property
+/// as it is
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StyleDeclarations {
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+    pub stroke_width: Option<f32>,
+    pub opacity: Option<f32>,
+}
+
+impl StyleDeclarations {
+    pub fn apply(&self, style: &mut Style) {
+        if let Some(fill) = self.fill {
+            style.fill = Some(fill);
+        }
+        if let Some(stroke) = self.stroke {
+            style.stroke = Some(stroke);
+        }
+        if let Some(width) = self.stroke_width {
+            style.stroke_width = width;
+        }
+        if let Some(opacity) = self.opacity {
+            style.opacity = opacity;
+        }
+    }
+}
+
+/// Marks an element styled by the stylesheet of the component `scope`
+///
+/// `inline` holds the element's own style attributes, which win over the
+/// stylesheet.
+#[derive(Component, Debug, Clone)]
+pub struct ScopedStyle {
+    pub scope: String,
+    pub inline: StyleDeclarations,
+}
+
+impl ScopedStyle {
+    pub fn new(scope: impl Into<String>, inline: StyleDeclarations) -> Self {
+        Self { scope: scope.into(), inline }
+    }
+}
+
+/// `tag.class#id`, or `*`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+/// A rule with a single selector
+#[derive(Debug, Clone)]
+pub struct StyleRule {
+    /// Compound selectors, outermost first
+    compounds: Vec<Compound>,
+    pub declarations: StyleDeclarations,
+}
+
+impl StyleRule {
+    /// Rule for a selector like `.legend circle.active` or `#title`
+    pub fn new(selector: &str, declarations: StyleDeclarations) -> Self {
+        let compounds = selector.split_whitespace().map(parse_compound).collect();
+        Self { compounds, declarations }
+    }
+
+    /// `(ids, classes, tags)`, compared lexicographically
+    pub fn specificity(&self) -> (usize, usize, usize) {
+        self.compounds.iter().fold((0, 0, 0), |(ids, classes, tags), compound| {
+            (
+                ids + usize::from(compound.id.is_some()),
+                classes + compound.classes.len(),
+                tags + usize::from(compound.tag.is_some()),
+            )
+        })
+    }
+}
+
+fn parse_compound(text: &str) -> Compound {
+    let mut compound = Compound::default();
+    let mut rest = text.strip_prefix('*').unwrap_or(text);
+    while !rest.is_empty() {
+        let sigil = rest.chars().next().filter(|c| matches!(c, '.' | '#'));
+        let body = if sigil.is_some() { &rest[1..] } else { rest };
+        let end = body.find(['.', '#']).unwrap_or(body.len());
+        let name = body[..end].to_string();
+        match sigil {
+            Some('.') => compound.classes.push(name),
+            Some(_) => compound.id = Some(name),
+            None => compound.tag = Some(canonical_tag(&name).to_string()),
+        }
+        rest = &body[end..];
+    }
+    compound
+}
+
+/// The name an element is matched by, whichever alias the selector uses
+fn canonical_tag(tag: &str) -> &str {
+    match tag {
+        "rect" => "rectangle",
+        "img" => "image",
+        "g" => "group",
+        _ => tag,
+    }
+}
+
+/// Rules of every component with runtime-styled elements, in source order
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Stylesheet {
+    scopes: HashMap<String, Vec<StyleRule>>,
+}
+
+impl Stylesheet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the rules of the component `scope`
+    pub fn insert(&mut self, scope: impl Into<String>, rules: Vec<StyleRule>) {
+        self.scopes.insert(scope.into(), rules);
+    }
+
+    pub fn rules(&self, scope: &str) -> &[StyleRule] {
+        self.scopes.get(scope).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// What a selector sees of an element
+struct Subject<'a> {
+    tag: Option<&'static str>,
+    id: Option<&'a str>,
+    class: Option<&'a Class>,
+}
+
+impl Compound {
+    fn matches(&self, subject: &Subject) -> bool {
+        self.tag.as_deref().is_none_or(|tag| subject.tag == Some(tag))
+            && self.id.as_deref().is_none_or(|id| subject.id == Some(id))
+            && self
+                .classes
+                .iter()
+                .all(|class| subject.class.is_some_and(|c| c.contains(class)))
+    }
+}
+
+fn tag_of(shape: Option<&Shape>, text: Option<&TextContent>) -> Option<&'static str> {
+    match (shape, text) {
+        (Some(Shape::Circle { .. }), _) => Some("circle"),
+        (Some(Shape::Rectangle { .. }), _) => Some("rectangle"),
+        (Some(Shape::Ellipse { .. }), _) => Some("ellipse"),
+        (Some(Shape::Line { .. }), _) => Some("line"),
+        (Some(Shape::Path { .. }), _) => Some("path"),
+        (Some(Shape::Polygon { .. }), _) => Some("polygon"),
+        (None, Some(_)) => Some("text"),
+        // Elements without geometry are groups
+        (None, None) => Some("group"),
+    }
+}
+
+type SubjectQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Option<&'static Class>, Option<&'static Id>, Option<&'static Parent>, Option<&'static Shape>, Option<&'static TextContent>),
+>;
+
+fn subject<'a>(subjects: &'a SubjectQuery, entity: Entity) -> Option<(Subject<'a>, Option<Entity>)> {
+    let (class, id, parent, shape, text) = subjects.get(entity).ok()?;
+    let subject = Subject {
+        tag: tag_of(shape, text),
+        id: id.map(|id| id.0.as_str()),
+        class,
+    };
+    Some((subject, parent.map(|p| p.0)))
+}
+
+/// The last compound matches the element, earlier ones match ancestors
+/// from the inside out
+fn rule_matches(rule: &StyleRule, subjects: &SubjectQuery, entity: Entity) -> bool {
+    let Some((last, rest)) = rule.compounds.split_last() else {
+        return false;
+    };
+    let Some((element, mut parent)) = subject(subjects, entity) else {
+        return false;
+    };
+    if !last.matches(&element) {
+        return false;
+    }
+
+    rest.iter().rev().all(|compound| {
+        while let Some((ancestor, next)) = parent.and_then(|p| subject(subjects, p)) {
+            parent = next;
+            if compound.matches(&ancestor) {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Entities whose classes, id or scoped style changed, the last one when a
+/// render inserts it again
+type SelectorsChanged = Or<(Changed<Class>, Changed<Id>, Changed<ScopedStyle>)>;
+
+/// Recompute the `Style` of runtime-styled elements when a class or id
+/// changes anywhere, since it may be on an ancestor
+pub fn resolve_styles(
+    stylesheet: Option<Res<Stylesheet>>,
+    changed: Query<(), SelectorsChanged>,
+    mut styled: Query<(Entity, &ScopedStyle, &mut Style)>,
+    subjects: SubjectQuery,
+) {
+    let Some(stylesheet) = stylesheet else {
+        return;
+    };
+    if changed.is_empty() && !stylesheet.is_changed() {
+        return;
+    }
+
+    for (entity, scoped, mut style) in &mut styled {
+        let mut matched: Vec<(usize, &StyleRule)> = stylesheet
+            .rules(&scoped.scope)
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule_matches(rule, &subjects, entity))
+            .collect();
+        matched.sort_by_key(|(order, rule)| (rule.specificity(), *order));
+
+        let mut resolved = Style::default();
+        for (_, rule) in matched {
+            rule.declarations.apply(&mut resolved);
+        }
+        scoped.inline.apply(&mut resolved);
+        *style = resolved;
+    }
+}
+
+/// Condition of a `class:name={...}` binding and its invalidation flag
+struct ClassBinding {
+    condition: Rc<dyn Fn() -> bool>,
+    dirty: Rc<Cell<bool>>,
+}
+
+/// Class bindings of every element
+///
+/// Conditions capture signals, which are not `Send`, so they live in a
+/// non-send resource.
+#[derive(Default)]
+pub struct ClassBindings {
+    bindings: HashMap<(Entity, String), ClassBinding>,
+}
+
+pub(crate) fn bind_class(world: &mut World, entity: Entity, class: &str, condition: Rc<dyn Fn() -> bool>) {
+    if !world.contains_non_send::<ClassBindings>() {
+        world.insert_non_send_resource(ClassBindings::default());
+    }
+    world.non_send_resource_mut::<ClassBindings>().bindings.insert(
+        (entity, class.to_string()),
+        ClassBinding {
+            condition,
+            dirty: Rc::new(Cell::new(true)),
+        },
+    );
+    refresh_class_bindings(world);
+}
+
+pub(crate) fn class_trigger(world: &World, entity: Entity, class: &str) -> Box<dyn Fn()> {
+    let dirty = world
+        .get_non_send_resource::<ClassBindings>()
+        .and_then(|b| b.bindings.get(&(entity, class.to_string())))
+        .map(|binding| binding.dirty.clone());
+    Box::new(move || {
+        if let Some(dirty) = &dirty {
+            dirty.set(true);
+        }
+    })
+}
+
+/// Mark every class binding of `entity` for re-evaluation
+pub(crate) fn invalidate_class_bindings(world: &World, entity: Entity) {
+    let Some(bindings) = world.get_non_send_resource::<ClassBindings>() else {
+        return;
+    };
+    for ((bound, _), binding) in &bindings.bindings {
+        if *bound == entity {
+            binding.dirty.set(true);
+        }
+    }
+}
+
+pub(crate) fn refresh_class_bindings(world: &mut World) {
+    let Some(mut bindings) = world.remove_non_send_resource::<ClassBindings>() else {
+        return;
+    };
+    // Bindings of despawned entities, e.g. inside a re-rendered region
+    bindings.bindings.retain(|(entity, _), _| world.get_entity(*entity).is_ok());
+
+    for ((entity, class), binding) in &bindings.bindings {
+        if !binding.dirty.replace(false) {
+            continue;
+        }
+        let enabled = (binding.condition)();
+        let mut entity = world.entity_mut(*entity);
+        match entity.get_mut::<Class>() {
+            // Only touch the component when the class flips, to keep
+            // change detection meaningful
+            Some(mut classes) => {
+                if classes.contains(class) != enabled {
+                    classes.toggle(class, enabled);
+                }
+            }
+            None => {
+                let mut classes = Class::default();
+                classes.toggle(class, enabled);
+                entity.insert(classes);
+            }
+        }
+    }
+    world.insert_non_send_resource(bindings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::create_signal;
+
+    fn resolve(world: &mut World, schedule: &mut Schedule) {
+        world.refresh_class_bindings();
+        schedule.run(world);
+    }
+
+    #[test]
+    fn test_rule_parsing_and_specificity() {
+        let rule = StyleRule::new(".legend rect.big#main", StyleDeclarations::default());
+        assert_eq!(rule.specificity(), (1, 2, 1));
+        assert_eq!(rule.compounds[1].tag.as_deref(), Some("rectangle"));
+        assert_eq!(StyleRule::new("*", StyleDeclarations::default()).specificity(), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_class_toggle_restyles_entity() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(resolve_styles);
+
+        let red = StyleDeclarations { fill: Some(Color::RED), ..Default::default() };
+        let mut stylesheet = Stylesheet::new();
+        stylesheet.insert(
+            "App",
+            vec![
+                StyleRule::new("circle", StyleDeclarations { fill: Some(Color::BLUE), opacity: Some(0.5), ..Default::default() }),
+                StyleRule::new(".active circle", red),
+                StyleRule::new("#other", red),
+            ],
+        );
+        world.insert_resource(stylesheet);
+
+        let group = world.spawn(Class::new("legend")).id();
+        let circle = world
+            .spawn((
+                Shape::Circle { radius: 5.0 },
+                Style::default(),
+                Parent(group),
+                ScopedStyle::new("App", StyleDeclarations { opacity: Some(1.0), ..Default::default() }),
+            ))
+            .id();
+
+        let selected = create_signal(false);
+        let condition = selected.clone();
+        world.bind_class(group, "active", move || condition.get());
+        selected.subscribe(world.class_trigger(group, "active"));
+
+        resolve(&mut world, &mut schedule);
+        let style = world.get::<Style>(circle).unwrap();
+        assert_eq!(style.fill.map(|c| c.b), Some(1.0));
+        // The element's own attribute wins over the stylesheet
+        assert_eq!(style.opacity, 1.0);
+
+        selected.set(true);
+        resolve(&mut world, &mut schedule);
+        assert!(world.get::<Class>(group).unwrap().contains("active"));
+        assert_eq!(world.get::<Style>(circle).unwrap().fill.map(|c| c.r), Some(1.0));
+
+        selected.set(false);
+        resolve(&mut world, &mut schedule);
+        assert_eq!(world.get::<Class>(group).unwrap().0, vec!["legend".to_string()]);
+        assert_eq!(world.get::<Style>(circle).unwrap().fill.map(|c| c.b), Some(1.0));
+    }
+}
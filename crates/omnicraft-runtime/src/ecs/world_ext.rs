@@ -3,7 +3,9 @@
 //! Convenient methods for working with the ECS world.
 
 use super::*;
-use bevy_ecs::prelude::*;
+use crate::input::{self, EventModifiers, InputEvent};
+use crate::signals::{Signal, Subscription};
+use crate::OmniComponent;
 
 /// Extension trait for World with OmniCraft-specific methods
 pub trait WorldExt {
@@ -13,6 +15,70 @@ pub trait WorldExt {
     fn spawn_circle(&mut self, x: f32, y: f32, radius: f32) -> Entity;
     fn spawn_rectangle(&mut self, x: f32, y: f32, width: f32, height: f32) -> Entity;
     fn spawn_text(&mut self, x: f32, y: f32, text: &str) -> Entity;
+    /// Spawn a region rendered by `render`, and render it once
+    fn spawn_region(&mut self, render: impl Fn(&mut World) -> Vec<Entity> + 'static) -> Entity;
+    /// Callback marking `region` for re-rendering, to subscribe to signals
+    fn region_trigger(&self, region: Entity) -> Box<dyn Fn()>;
+    /// Despawn the content of `region` and render it again
+    fn refresh_region(&mut self, region: Entity);
+    /// Re-render every region marked by its trigger
+    fn refresh_dirty_regions(&mut self);
+    /// Spawn an entity identified by `key`, or take back the one with that
+    /// key from the previous render of the region being refreshed; returns
+    /// whether the entity was reused
+    fn spawn_keyed(&mut self, key: Key) -> (Entity, bool);
+    /// Mount a child component, returning the entities it spawned
+    fn mount_child(&mut self, component: &impl OmniComponent) -> Vec<Entity>;
+    /// Record `parent` as the `Parent` of those of `entities` without one
+    fn adopt(&mut self, parent: Entity, entities: &[Entity]);
+    /// Render the content a parent provided for the slot `name`, under
+    /// `parent`, returning the entities it spawned
+    fn render_slot(&mut self, slots: &Slots, name: &str, props: &SlotProps, parent: Option<Entity>) -> Vec<Entity>;
+    /// Keep `class` on `entity` while `condition` holds, and apply it once
+    fn bind_class(&mut self, entity: Entity, class: &str, condition: impl Fn() -> bool + 'static);
+    /// Callback marking a class binding for re-evaluation, to subscribe to signals
+    fn class_trigger(&self, entity: Entity, class: &str) -> Box<dyn Fn()>;
+    /// Re-evaluate every class binding marked by its trigger
+    fn refresh_class_bindings(&mut self);
+    /// Call `handler` for `kind` events that reach `entity`
+    fn on_event(
+        &mut self,
+        entity: Entity,
+        kind: &str,
+        modifiers: EventModifiers,
+        handler: impl Fn(&InputEvent) + 'static,
+    );
+    /// Dispatch an event to its handlers, returning whether one of them
+    /// asked to prevent the default action
+    fn dispatch_event(&mut self, event: &InputEvent) -> bool;
+    /// Keep `property` of `entity` and `signal` in sync in both directions,
+    /// starting from the signal's value
+    fn bind_property<T: Bindable>(&mut self, entity: Entity, property: BoundProperty, signal: Signal<T>);
+    /// Keep `property` of `entity` equal to `value`, and apply it once;
+    /// changes to the component are not written back
+    fn bind_value<T: Bindable>(&mut self, entity: Entity, property: BoundProperty, value: impl Fn() -> T + 'static);
+    /// Callback marking a binding for re-evaluation, to subscribe to signals
+    fn binding_trigger(&self, entity: Entity, property: BoundProperty) -> Box<dyn Fn()>;
+    /// Sync every binding whose signal or component changed
+    fn sync_bindings(&mut self);
+    /// Point `entity_ref` at `entity` until the entity is despawned
+    fn bind_ref(&mut self, entity: Entity, entity_ref: &EntityRef);
+    /// Call `action` with `entity` and `options`, keeping the cleanup it
+    /// returns for when the entity is despawned
+    fn use_action<P, R: ActionResult>(
+        &mut self,
+        entity: Entity,
+        action: impl FnOnce(&mut World, Entity, P) -> R,
+        options: P,
+    );
+    /// Run `cleanup` once `entity` is despawned
+    fn on_unmount(&mut self, entity: Entity, cleanup: impl FnOnce(&mut World) + 'static);
+    /// Unsubscribe `subscription` once `entity` is despawned
+    fn unsubscribe_on_unmount(&mut self, entity: Entity, subscription: Subscription);
+    /// Run the cleanups of every despawned entity
+    fn run_cleanups(&mut self);
+    /// Place the children of every column and row stack and size it
+    fn layout_stacks(&mut self);
 }
 
 impl WorldExt for World {
@@ -63,4 +129,118 @@ impl WorldExt for World {
         ))
         .id()
     }
+    fn spawn_region(&mut self, render: impl Fn(&mut World) -> Vec<Entity> + 'static) -> Entity {
+        region::spawn_region(self, std::rc::Rc::new(render))
+    }
+
+    fn region_trigger(&self, region: Entity) -> Box<dyn Fn()> {
+        region::region_trigger(self, region)
+    }
+
+    fn refresh_region(&mut self, region: Entity) {
+        region::refresh_region(self, region)
+    }
+
+    fn refresh_dirty_regions(&mut self) {
+        region::refresh_dirty_regions(self)
+    }
+
+    fn spawn_keyed(&mut self, key: Key) -> (Entity, bool) {
+        region::spawn_keyed(self, key)
+    }
+
+    fn mount_child(&mut self, component: &impl OmniComponent) -> Vec<Entity> {
+        let before: std::collections::HashSet<Entity> = self.query::<Entity>().iter(self).collect();
+        component.mount(self);
+        self.query::<Entity>()
+            .iter(self)
+            .filter(|entity| !before.contains(entity))
+            .collect()
+    }
+
+    fn adopt(&mut self, parent: Entity, entities: &[Entity]) {
+        // Nested entities already point at their own parent
+        for entity in entities {
+            if !self.entity(*entity).contains::<Parent>() {
+                self.entity_mut(*entity).insert(Parent(parent));
+            }
+        }
+    }
+
+    fn render_slot(&mut self, slots: &Slots, name: &str, props: &SlotProps, parent: Option<Entity>) -> Vec<Entity> {
+        slot::render_slot(self, slots, name, props, parent)
+    }
+
+    fn bind_class(&mut self, entity: Entity, class: &str, condition: impl Fn() -> bool + 'static) {
+        stylesheet::bind_class(self, entity, class, std::rc::Rc::new(condition))
+    }
+
+    fn class_trigger(&self, entity: Entity, class: &str) -> Box<dyn Fn()> {
+        stylesheet::class_trigger(self, entity, class)
+    }
+
+    fn refresh_class_bindings(&mut self) {
+        stylesheet::refresh_class_bindings(self)
+    }
+
+    fn on_event(
+        &mut self,
+        entity: Entity,
+        kind: &str,
+        modifiers: EventModifiers,
+        handler: impl Fn(&InputEvent) + 'static,
+    ) {
+        input::on_event(self, entity, kind, modifiers, std::rc::Rc::new(handler))
+    }
+
+    fn dispatch_event(&mut self, event: &InputEvent) -> bool {
+        input::dispatch_event(self, event)
+    }
+
+    fn bind_property<T: Bindable>(&mut self, entity: Entity, property: BoundProperty, signal: Signal<T>) {
+        binding::bind_property(self, entity, property, signal)
+    }
+
+    fn bind_value<T: Bindable>(&mut self, entity: Entity, property: BoundProperty, value: impl Fn() -> T + 'static) {
+        binding::bind_value(self, entity, property, value)
+    }
+
+    fn binding_trigger(&self, entity: Entity, property: BoundProperty) -> Box<dyn Fn()> {
+        binding::binding_trigger(self, entity, property)
+    }
+
+    fn sync_bindings(&mut self) {
+        binding::sync_bindings(self)
+    }
+
+    fn bind_ref(&mut self, entity: Entity, entity_ref: &EntityRef) {
+        lifecycle::bind_ref(self, entity, entity_ref)
+    }
+
+    fn use_action<P, R: ActionResult>(
+        &mut self,
+        entity: Entity,
+        action: impl FnOnce(&mut World, Entity, P) -> R,
+        options: P,
+    ) {
+        if let Some(cleanup) = action(self, entity, options).into_cleanup() {
+            lifecycle::on_unmount(self, entity, cleanup);
+        }
+    }
+
+    fn on_unmount(&mut self, entity: Entity, cleanup: impl FnOnce(&mut World) + 'static) {
+        lifecycle::on_unmount(self, entity, Box::new(cleanup))
+    }
+
+    fn unsubscribe_on_unmount(&mut self, entity: Entity, subscription: Subscription) {
+        lifecycle::on_unmount(self, entity, Box::new(move |_| subscription.unsubscribe()))
+    }
+
+    fn run_cleanups(&mut self) {
+        lifecycle::run_cleanups(self)
+    }
+
+    fn layout_stacks(&mut self) {
+        stack::layout_stacks(self)
+    }
 }
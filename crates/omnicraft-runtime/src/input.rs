@@ -0,0 +1,325 @@
+//! Input Handling
+//!
+//! The host forwards pointer and keyboard events to the `App`, which
+//! dispatches them to the handlers registered with `WorldExt::on_event`.
+//! Pointer events are hit-tested against the shapes in the world and go to
+//! the topmost shape under the pointer, then bubble up its `Parent`s.
+//! Keyboard events go to every entity listening for them.
+
+use crate::ecs::{Parent, Shape, Stack, Transform, Visibility, ZIndex};
+use bevy_ecs::prelude::*;
+use glam::Vec2;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Distance from a line within which it counts as hit
+const LINE_TOLERANCE: f32 = 4.0;
+
+/// A pointer or keyboard event
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputEvent {
+    /// DOM event type, e.g. `click`, `pointermove` or `keydown`
+    pub kind: String,
+    /// Pointer position in canvas coordinates
+    pub position: Option<Vec2>,
+    /// Key of a keyboard event
+    pub key: Option<String>,
+}
+
+impl InputEvent {
+    pub fn pointer(kind: impl Into<String>, x: f32, y: f32) -> Self {
+        Self {
+            kind: kind.into(),
+            position: Some(Vec2::new(x, y)),
+            key: None,
+        }
+    }
+
+    pub fn key(kind: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            position: None,
+            key: Some(key.into()),
+        }
+    }
+}
+
+/// Modifiers of an event directive, e.g. `@click.once.stop`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventModifiers {
+    /// Remove the handler after its first call
+    pub once: bool,
+    /// Don't pass the event on to parent entities
+    pub stop: bool,
+    /// Ask the host to prevent the event's default action
+    pub prevent: bool,
+    /// Ignore events that hit a child entity
+    pub self_only: bool,
+}
+
+/// Handles an event on an entity
+pub type EventHandler = Rc<dyn Fn(&InputEvent)>;
+
+/// Events an entity has handlers for
+#[derive(Component, Debug, Clone, Default)]
+pub struct Listeners(pub Vec<String>);
+
+impl Listeners {
+    pub fn contains(&self, kind: &str) -> bool {
+        self.0.iter().any(|event| event == kind)
+    }
+}
+
+struct Listener {
+    kind: String,
+    modifiers: EventModifiers,
+    handler: EventHandler,
+}
+
+/// Handlers of every entity
+///
+/// Handlers capture signals, which are not `Send`, so they live in a
+/// non-send resource next to the `Listeners` components.
+#[derive(Default)]
+pub struct EventHandlers {
+    listeners: HashMap<Entity, Vec<Listener>>,
+}
+
+pub(crate) fn on_event(
+    world: &mut World,
+    entity: Entity,
+    kind: &str,
+    modifiers: EventModifiers,
+    handler: EventHandler,
+) {
+    if !world.contains_non_send::<EventHandlers>() {
+        world.insert_non_send_resource(EventHandlers::default());
+    }
+    world
+        .non_send_resource_mut::<EventHandlers>()
+        .listeners
+        .entry(entity)
+        .or_default()
+        .push(Listener {
+            kind: kind.to_string(),
+            modifiers,
+            handler,
+        });
+
+    let mut entity = world.entity_mut(entity);
+    match entity.get_mut::<Listeners>() {
+        Some(mut listeners) if !listeners.contains(kind) => listeners.0.push(kind.to_string()),
+        Some(_) => {}
+        None => {
+            entity.insert(Listeners(vec![kind.to_string()]));
+        }
+    }
+}
+
+/// Call the handlers `event` reaches, returning whether one of them asked
+/// to prevent the default action
+pub(crate) fn dispatch_event(world: &mut World, event: &InputEvent) -> bool {
+    // Entities to notify, and whether the event hit each one directly
+    let targets: Vec<(Entity, bool)> = match event.position {
+        Some(point) => hit_test(world, point)
+            .map(|target| {
+                let mut chain = vec![(target, true)];
+                let mut current = target;
+                while let Some(parent) = world.get::<Parent>(current) {
+                    current = parent.0;
+                    chain.push((current, false));
+                }
+                chain
+            })
+            .unwrap_or_default(),
+        None => {
+            let mut listening: Vec<Entity> = world
+                .query::<(Entity, &Listeners)>()
+                .iter(world)
+                .filter(|(_, listeners)| listeners.contains(&event.kind))
+                .map(|(entity, _)| entity)
+                .collect();
+            listening.sort_by_key(|entity| entity.index());
+            listening.into_iter().map(|entity| (entity, true)).collect()
+        }
+    };
+
+    let Some(mut handlers) = world.remove_non_send_resource::<EventHandlers>() else {
+        return false;
+    };
+    // Handlers of despawned entities, e.g. inside a re-rendered region
+    handlers.listeners.retain(|entity, _| world.get_entity(*entity).is_ok());
+
+    let mut prevented = false;
+    for (entity, hit) in targets {
+        let Some(listeners) = handlers.listeners.get_mut(&entity) else {
+            continue;
+        };
+        let mut stop = false;
+        listeners.retain(|listener| {
+            if listener.kind != event.kind || (listener.modifiers.self_only && !hit) {
+                return true;
+            }
+            (listener.handler)(event);
+            prevented |= listener.modifiers.prevent;
+            stop |= listener.modifiers.stop;
+            !listener.modifiers.once
+        });
+        if stop {
+            break;
+        }
+    }
+    world.insert_non_send_resource(handlers);
+    prevented
+}
+
+/// The topmost visible shape or stack background containing `point`
+///
+/// Entities are ordered by `ZIndex`, then by spawn order.
+pub fn hit_test(world: &mut World, point: Vec2) -> Option<Entity> {
+    let shapes: Vec<(Entity, Option<ZIndex>)> = world
+        .query::<(Entity, &Transform, &Shape, Option<&Visibility>, Option<&ZIndex>)>()
+        .iter(world)
+        .filter(|(_, _, _, visibility, _)| visibility.is_none_or(|v| v.visible))
+        .filter(|(_, transform, shape, _, _)| contains(shape, to_local(transform, point)))
+        .map(|(entity, _, _, _, z_index)| (entity, z_index.copied()))
+        .collect();
+    // Stacks are hit where they draw a background
+    let stacks: Vec<(Entity, Option<ZIndex>)> = world
+        .query::<(Entity, &Transform, &Stack, Option<&Visibility>, Option<&ZIndex>)>()
+        .iter(world)
+        .filter(|(_, _, stack, visibility, _)| stack.background.is_some() && visibility.is_none_or(|v| v.visible))
+        .filter(|(_, transform, stack, _, _)| stack.contains(point - transform.position))
+        .map(|(entity, _, _, _, z_index)| (entity, z_index.copied()))
+        .collect();
+    shapes
+        .into_iter()
+        .chain(stacks)
+        .max_by_key(|(entity, z_index)| (z_index.map_or(0, |z| z.0), entity.index()))
+        .map(|(entity, _)| entity)
+}
+
+/// `point` in the coordinate space of an entity's shape
+fn to_local(transform: &Transform, point: Vec2) -> Vec2 {
+    Vec2::from_angle(-transform.rotation).rotate(point - transform.position) / transform.scale
+}
+
+/// Whether a shape centered on the origin contains `point`
+fn contains(shape: &Shape, point: Vec2) -> bool {
+    match shape {
+        Shape::Circle { radius } => point.length() <= *radius,
+        Shape::Rectangle { width, height } => point.x.abs() <= width / 2.0 && point.y.abs() <= height / 2.0,
+        Shape::Ellipse { rx, ry } => (point.x / rx).powi(2) + (point.y / ry).powi(2) <= 1.0,
+        Shape::Line { x2, y2 } => {
+            let end = Vec2::new(*x2, *y2);
+            let t = (point.dot(end) / end.length_squared()).clamp(0.0, 1.0);
+            let nearest = if t.is_nan() { Vec2::ZERO } else { end * t };
+            point.distance(nearest) <= LINE_TOLERANCE
+        }
+        Shape::Polygon { points } => {
+            // Even-odd rule
+            let mut inside = false;
+            for (i, a) in points.iter().enumerate() {
+                let b = points[(i + 1) % points.len()];
+                if (a.y > point.y) != (b.y > point.y)
+                    && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+                {
+                    inside = !inside;
+                }
+            }
+            inside
+        }
+        Shape::Path { .. } => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::{Color, WorldExt};
+    use std::cell::RefCell;
+
+    type Handler = Box<dyn Fn(&InputEvent)>;
+
+    fn recorder() -> (Rc<RefCell<Vec<String>>>, impl Fn(&'static str) -> Handler) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let sink = log.clone();
+        let handler = move |name: &'static str| -> Handler {
+            let sink = sink.clone();
+            Box::new(move |event: &InputEvent| sink.borrow_mut().push(format!("{}:{}", name, event.kind)))
+        };
+        (log, handler)
+    }
+
+    #[test]
+    fn test_hit_test() {
+        let mut world = World::new();
+        let back = world.spawn_rectangle(100.0, 100.0, 80.0, 40.0);
+        let front = world.spawn_circle(120.0, 100.0, 10.0);
+
+        assert_eq!(hit_test(&mut world, Vec2::new(125.0, 105.0)), Some(front));
+        assert_eq!(hit_test(&mut world, Vec2::new(70.0, 90.0)), Some(back));
+        assert_eq!(hit_test(&mut world, Vec2::new(141.0, 100.0)), None);
+
+        world.entity_mut(back).insert(ZIndex(1));
+        assert_eq!(hit_test(&mut world, Vec2::new(125.0, 105.0)), Some(back));
+
+        // Hit testing happens in the shape's rotated frame
+        world.get_mut::<Transform>(back).unwrap().rotation = std::f32::consts::FRAC_PI_2;
+        assert_eq!(hit_test(&mut world, Vec2::new(100.0, 135.0)), Some(back));
+
+        // A stack is hit inside its box, only when it draws a background
+        let stack = Stack { background: Some(Color::BLACK), size: Vec2::new(30.0, 20.0), ..Stack::row() };
+        let button = world.spawn((Transform::from_xy(300.0, 300.0), stack)).id();
+        assert_eq!(hit_test(&mut world, Vec2::new(329.0, 301.0)), Some(button));
+        world.get_mut::<Stack>(button).unwrap().background = None;
+        assert_eq!(hit_test(&mut world, Vec2::new(329.0, 301.0)), None);
+    }
+
+    #[test]
+    fn test_dispatch_bubbles_with_modifiers() {
+        let mut world = World::new();
+        let (log, handler) = recorder();
+        let group = world.spawn(Transform::from_xy(0.0, 0.0)).id();
+        let button = world.spawn_rectangle(50.0, 50.0, 20.0, 20.0);
+        world.entity_mut(button).insert(Parent(group));
+
+        let once = EventModifiers { once: true, ..Default::default() };
+        let self_only = EventModifiers { self_only: true, ..Default::default() };
+        world.on_event(button, "click", once, handler("button"));
+        world.on_event(group, "click", EventModifiers::default(), handler("group"));
+        world.on_event(group, "click", self_only, handler("group-self"));
+        assert_eq!(world.get::<Listeners>(group).unwrap().0, vec!["click".to_string()]);
+
+        assert!(!world.dispatch_event(&InputEvent::pointer("click", 55.0, 45.0)));
+        assert!(!world.dispatch_event(&InputEvent::pointer("click", 55.0, 45.0)));
+        assert!(!world.dispatch_event(&InputEvent::pointer("click", 500.0, 500.0)));
+        assert_eq!(*log.borrow(), ["button:click", "group:click", "group:click"]);
+
+        log.borrow_mut().clear();
+        let stop = EventModifiers { stop: true, prevent: true, ..Default::default() };
+        world.on_event(button, "pointerdown", stop, handler("button"));
+        world.on_event(group, "pointerdown", EventModifiers::default(), handler("group"));
+        assert!(world.dispatch_event(&InputEvent::pointer("pointerdown", 50.0, 50.0)));
+        assert_eq!(*log.borrow(), ["button:pointerdown"]);
+    }
+
+    #[test]
+    fn test_dispatch_keyboard_events() {
+        let mut world = World::new();
+        let (log, handler) = recorder();
+        let first = world.spawn_circle(0.0, 0.0, 5.0);
+        let second = world.spawn_circle(100.0, 0.0, 5.0);
+        world.on_event(first, "keydown", EventModifiers::default(), handler("first"));
+        world.on_event(second, "keydown", EventModifiers::default(), handler("second"));
+        world.on_event(second, "keyup", EventModifiers::default(), handler("second"));
+
+        world.dispatch_event(&InputEvent::key("keydown", "Enter"));
+        assert_eq!(*log.borrow(), ["first:keydown", "second:keydown"]);
+
+        world.despawn(first);
+        log.borrow_mut().clear();
+        world.dispatch_event(&InputEvent::key("keydown", "Enter"));
+        assert_eq!(*log.borrow(), ["second:keydown"]);
+    }
+}
@@ -147,8 +147,8 @@ impl SimpleLayoutStyle {
             _ => AlignItems::FlexStart,
         };
 
-        let width = self.width.map(|w| Dimension::length(w)).unwrap_or(Dimension::auto());
-        let height = self.height.map(|h| Dimension::length(h)).unwrap_or(Dimension::auto());
+        let width = self.width.map(Dimension::length).unwrap_or(Dimension::auto());
+        let height = self.height.map(Dimension::length).unwrap_or(Dimension::auto());
 
         taffy::Style {
             display,
@@ -230,10 +230,10 @@ impl LayoutManager {
     pub fn add_node(&mut self, style: &SimpleLayoutStyle, parent_id: Option<u32>) -> u32 {
         let node = self.taffy.new_leaf(style.to_taffy_style()).unwrap();
         
-        if let Some(pid) = parent_id {
-            if let Some(&parent_node) = self.id_to_node.get(&pid) {
-                let _ = self.taffy.add_child(parent_node, node);
-            }
+        if let Some(pid) = parent_id
+            && let Some(&parent_node) = self.id_to_node.get(&pid)
+        {
+            let _ = self.taffy.add_child(parent_node, node);
         }
         
         let id = self.next_id;
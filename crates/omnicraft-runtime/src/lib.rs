@@ -8,34 +8,39 @@
 //! - **Rendering**: 2D rendering pipeline (Lyon + Canvas)
 //! - **Tessellation**: GPU-ready path tessellation (Lyon)
 //! - **Layout**: Flexbox/Grid layout system (Taffy)
+//! - **Input**: Hit-tested pointer and keyboard event dispatch
 
 pub mod components;
 pub mod ecs;
+pub mod input;
 pub mod layout;
 pub mod render;
 pub mod signals;
 pub mod tessellation;
+pub mod value;
 
 pub mod prelude {
     //! Prelude module with commonly used exports
 
     pub use crate::components::*;
-    pub use crate::ecs::*;
+    pub use crate::input::*;
     pub use crate::layout::*;
     pub use crate::render::*;
     pub use crate::signals::*;
     pub use crate::tessellation::*;
+    pub use crate::value::Value;
     pub use crate::OmniComponent;
     pub use crate::Context;
 
     pub use bevy_ecs::prelude::{
-        Bundle, Component, Entity, Event, EventReader, EventWriter, Query, Res, ResMut, Resource,
+        Bundle, Component, Entity, Event, MessageReader, MessageWriter, Query, Res, ResMut, Resource,
         Schedule, System, World,
     };
     pub use glam::{Vec2, Vec3, Vec4};
 }
 
 use bevy_ecs::prelude::*;
+use ecs::WorldExt;
 use wasm_bindgen::prelude::*;
 
 /// OmniCraft Application
@@ -55,21 +60,41 @@ impl App {
         console_error_panic_hook::set_once();
 
         let mut world = World::new();
-        let schedule = Schedule::default();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(ecs::resolve_styles);
 
         // Initialize default resources
         world.insert_resource(crate::signals::SignalContext::new());
         world.insert_resource(crate::render::CanvasConfig::default());
 
-        web_sys::console::log_1(&"App::new called".into());
+        tracing::debug!("App::new called");
         Self { world, schedule }
     }
 
     /// Run the application frame
     #[wasm_bindgen]
     pub fn tick(&mut self) {
-        web_sys::console::log_1(&"App::tick called".into());
+        tracing::debug!("App::tick called");
+        self.world.refresh_dirty_regions();
+        self.world.run_cleanups();
+        self.world.refresh_class_bindings();
+        self.world.sync_bindings();
         self.schedule.run(&mut self.world);
+        self.world.layout_stacks();
+    }
+
+    /// Dispatch a pointer event at canvas coordinates, e.g. `click` or
+    /// `pointermove`; returns whether a handler asked to prevent the
+    /// default action
+    #[wasm_bindgen]
+    pub fn pointer_event(&mut self, kind: &str, x: f32, y: f32) -> bool {
+        self.world.dispatch_event(&input::InputEvent::pointer(kind, x, y))
+    }
+
+    /// Dispatch a keyboard event, e.g. `keydown`
+    #[wasm_bindgen]
+    pub fn key_event(&mut self, kind: &str, key: &str) -> bool {
+        self.world.dispatch_event(&input::InputEvent::key(kind, key))
     }
 
     /// Get the current canvas width
@@ -110,7 +135,9 @@ pub trait OmniComponent {
     fn mount(&self, world: &mut World);
 }
 
-/// Context for component creation
+/// Context for component creation; clones share it, so a component can
+/// keep one to create its child components with
+#[derive(Clone)]
 pub struct Context {
     signal_ctx: signals::SignalContext,
 }
@@ -2,9 +2,8 @@
 //!
 //! Renders ECS entities to HTML Canvas using Lyon for tessellation.
 
-use crate::ecs::{Color, Shape, Style, TextContent, Transform, Visibility};
+use crate::ecs::{Color, Shape, Stack, Style, TextContent, Transform, Visibility, ZIndex};
 use bevy_ecs::prelude::*;
-use wasm_bindgen::prelude::*;
 
 /// Canvas configuration resource
 #[derive(Resource, Debug, Clone)]
@@ -75,35 +74,41 @@ pub struct Renderer;
 impl Renderer {
     /// Render all visible entities to the render queue
     pub fn render(world: &mut World, queue: &mut RenderQueue) {
-        web_sys::console::log_1(&"Renderer::render called".into());
+        tracing::debug!("Renderer::render called");
         queue.clear();
 
         // Clear background
         if let Some(config) = world.get_resource::<CanvasConfig>() {
-            web_sys::console::log_1(&format!("CanvasConfig found: width={}, height={}, bg={:?}", config.width, config.height, config.background).into());
+            tracing::debug!("CanvasConfig found: width={}, height={}, bg={:?}", config.width, config.height, config.background);
             queue.push(RenderCommand::Clear { color: config.background });
         } else {
-            web_sys::console::log_1(&"CanvasConfig NOT found".into());
+            tracing::debug!("CanvasConfig NOT found");
         }
 
-        // Query all renderable entities
+        // Query all renderable entities, in the order hit testing sees them
+        // from the back
         let mut query = world.query::<(
+            Entity,
             &Transform,
             Option<&Shape>,
             Option<&Style>,
             Option<&TextContent>,
             Option<&Visibility>,
+            Option<&Stack>,
+            Option<&ZIndex>,
         )>();
+        let mut entities: Vec<_> = query.iter(world).collect();
+        entities.sort_by_key(|(entity, .., z_index)| (z_index.map_or(0, |z| z.0), entity.index()));
 
         let mut entity_count = 0;
-        for (transform, shape, style, text, visibility) in query.iter(world) {
+        for (_, transform, shape, style, text, visibility, stack, _) in entities {
             entity_count += 1;
 
             // Skip invisible entities
-            if let Some(vis) = visibility {
-                if !vis.visible {
-                    continue;
-                }
+            if let Some(vis) = visibility
+                && !vis.visible
+            {
+                continue;
             }
 
             let style = style.cloned().unwrap_or_default();
@@ -128,6 +133,11 @@ impl Renderer {
                 });
             }
 
+            // Render stack background, behind its children
+            if let Some(stack) = stack {
+                Self::render_stack(stack, queue);
+            }
+
             // Render shape
             if let Some(shape) = shape {
                 Self::render_shape(shape, &style, queue);
@@ -141,8 +151,8 @@ impl Renderer {
             // Restore transform state
             queue.push(RenderCommand::Restore);
         }
-        web_sys::console::log_1(&format!("Entities processed: {}", entity_count).into());
-        web_sys::console::log_1(&format!("Queue commands pushed: {}", queue.commands.len()).into());
+        tracing::debug!("Entities processed: {}", entity_count);
+        tracing::debug!("Queue commands pushed: {}", queue.commands.len());
     }
 
     fn render_shape(shape: &Shape, style: &Style, queue: &mut RenderQueue) {
@@ -263,6 +273,32 @@ impl Renderer {
         }
     }
 
+    /// Fill the background of a stack, from its top-left corner
+    fn render_stack(stack: &Stack, queue: &mut RenderQueue) {
+        let Some(background) = stack.background else {
+            return;
+        };
+        let (width, height) = (stack.size.x, stack.size.y);
+        let radius = stack.corner_radius.clamp(0.0, width.min(height) / 2.0);
+        let quarter = std::f32::consts::FRAC_PI_2;
+
+        queue.push(RenderCommand::BeginPath);
+        queue.push(RenderCommand::MoveTo { x: radius, y: 0.0 });
+        // Each corner is a quarter circle, clockwise from the top right
+        let corners = [
+            (width - radius, radius),
+            (width - radius, height - radius),
+            (radius, height - radius),
+            (radius, radius),
+        ];
+        for (i, (x, y)) in corners.into_iter().enumerate() {
+            let start = (i as f32 - 1.0) * quarter;
+            queue.push(RenderCommand::Arc { x, y, radius, start, end: start + quarter });
+        }
+        queue.push(RenderCommand::ClosePath);
+        queue.push(RenderCommand::Fill { color: background });
+    }
+
     fn render_text(text: &TextContent, style: &Style, queue: &mut RenderQueue) {
         let font = format!("{}px {}", text.font_size, text.font_family);
         queue.push(RenderCommand::SetFont { font });
@@ -282,13 +318,100 @@ impl Renderer {
 #[cfg(target_arch = "wasm32")]
 pub mod wasm {
     use super::*;
+    use crate::App;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use wasm_bindgen::prelude::*;
     use wasm_bindgen::JsCast;
-    use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+    use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, KeyboardEvent, MouseEvent};
+
+    /// Pointer events forwarded from the canvas to `App::pointer_event`
+    const POINTER_EVENTS: [&str; 4] = ["click", "pointerdown", "pointermove", "pointerup"];
+
+    /// Keyboard events forwarded from the window to `App::key_event`
+    const KEY_EVENTS: [&str; 2] = ["keydown", "keyup"];
 
     pub struct CanvasRenderer {
+        canvas: HtmlCanvasElement,
         ctx: CanvasRenderingContext2d,
     }
 
+    /// Keep `app` running on the canvas `canvas_id`: pointer and key events
+    /// are dispatched to it, and it is ticked and drawn after each event and
+    /// on every animation frame
+    pub fn run(app: App, canvas_id: &str) -> Result<(), JsValue> {
+        let window = web_sys::window().ok_or("no window")?;
+        let renderer = CanvasRenderer::new(canvas_id)?;
+        let canvas = renderer.canvas.clone();
+        let app = Rc::new(RefCell::new(app));
+        let renderer = Rc::new(renderer);
+
+        let draw = {
+            let app = app.clone();
+            let renderer = renderer.clone();
+            move || {
+                let mut app = app.borrow_mut();
+                app.tick();
+                let mut queue = RenderQueue::new();
+                Renderer::render(app.world_mut(), &mut queue);
+                renderer.execute(&queue.commands);
+            }
+        };
+        draw();
+
+        for kind in POINTER_EVENTS {
+            let app = app.clone();
+            let canvas_for_event = canvas.clone();
+            let draw = draw.clone();
+            let listener = Closure::<dyn FnMut(MouseEvent)>::new(move |event: MouseEvent| {
+                // Client coordinates, scaled to the canvas' own pixels
+                let rect = canvas_for_event.get_bounding_client_rect();
+                let scale_x = canvas_for_event.width() as f64 / rect.width().max(1.0);
+                let scale_y = canvas_for_event.height() as f64 / rect.height().max(1.0);
+                let x = (event.client_x() as f64 - rect.left()) * scale_x;
+                let y = (event.client_y() as f64 - rect.top()) * scale_y;
+                let prevent = app.borrow_mut().pointer_event(&event.type_(), x as f32, y as f32);
+                if prevent {
+                    event.prevent_default();
+                }
+                draw();
+            });
+            canvas.add_event_listener_with_callback(kind, listener.as_ref().unchecked_ref())?;
+            // The listener lives as long as the page
+            listener.forget();
+        }
+
+        for kind in KEY_EVENTS {
+            let app = app.clone();
+            let draw = draw.clone();
+            let listener = Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+                let prevent = app.borrow_mut().key_event(&event.type_(), &event.key());
+                if prevent {
+                    event.prevent_default();
+                }
+                draw();
+            });
+            window.add_event_listener_with_callback(kind, listener.as_ref().unchecked_ref())?;
+            listener.forget();
+        }
+
+        // Each frame schedules the next one through a closure that refers
+        // to itself
+        let frame = Rc::new(RefCell::new(None::<Closure<dyn FnMut()>>));
+        let next = frame.clone();
+        let frame_window = window.clone();
+        *frame.borrow_mut() = Some(Closure::new(move || {
+            draw();
+            if let Some(callback) = next.borrow().as_ref() {
+                let _ = frame_window.request_animation_frame(callback.as_ref().unchecked_ref());
+            }
+        }));
+        if let Some(callback) = frame.borrow().as_ref() {
+            window.request_animation_frame(callback.as_ref().unchecked_ref())?;
+        }
+        Ok(())
+    }
+
     impl CanvasRenderer {
         pub fn new(canvas_id: &str) -> Result<Self, JsValue> {
             let window = web_sys::window().unwrap();
@@ -303,7 +426,7 @@ pub mod wasm {
                 .unwrap()
                 .dyn_into::<CanvasRenderingContext2d>()?;
 
-            Ok(Self { ctx })
+            Ok(Self { canvas, ctx })
         }
 
         pub fn execute(&self, commands: &[RenderCommand]) {
@@ -315,7 +438,7 @@ pub mod wasm {
         fn execute_command(&self, command: &RenderCommand) {
             match command {
                 RenderCommand::Clear { color } => {
-                    self.ctx.set_fill_style(&JsValue::from_str(&color.to_css()));
+                    self.ctx.set_fill_style_str(&color.to_css());
                     self.ctx.fill_rect(0.0, 0.0, 10000.0, 10000.0);
                 }
                 RenderCommand::BeginPath => {
@@ -343,19 +466,19 @@ pub mod wasm {
                     self.ctx.rect(*x as f64, *y as f64, *width as f64, *height as f64);
                 }
                 RenderCommand::Fill { color } => {
-                    self.ctx.set_fill_style(&JsValue::from_str(&color.to_css()));
+                    self.ctx.set_fill_style_str(&color.to_css());
                     self.ctx.fill();
                 }
                 RenderCommand::Stroke { color, width } => {
-                    self.ctx.set_stroke_style(&JsValue::from_str(&color.to_css()));
+                    self.ctx.set_stroke_style_str(&color.to_css());
                     self.ctx.set_line_width(*width as f64);
                     self.ctx.stroke();
                 }
                 RenderCommand::SetFillStyle { color } => {
-                    self.ctx.set_fill_style(&JsValue::from_str(&color.to_css()));
+                    self.ctx.set_fill_style_str(&color.to_css());
                 }
                 RenderCommand::SetStrokeStyle { color, width } => {
-                    self.ctx.set_stroke_style(&JsValue::from_str(&color.to_css()));
+                    self.ctx.set_stroke_style_str(&color.to_css());
                     self.ctx.set_line_width(*width as f64);
                 }
                 RenderCommand::FillText { text, x, y } => {
@@ -4,7 +4,7 @@
 //! Provides `Signal`, `Memo`, and `Effect` primitives.
 
 use std::cell::RefCell;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::sync::{Arc, Mutex};
 
 use bevy_ecs::prelude::Resource;
@@ -54,13 +54,62 @@ impl Default for SignalContext {
     }
 }
 
+/// Callbacks notified when a signal changes
+type Subscribers = Rc<RefCell<SubscriberList>>;
+
+#[derive(Default)]
+struct SubscriberList {
+    next_id: u64,
+    callbacks: Vec<(u64, Box<dyn Fn()>)>,
+}
+
+fn add_subscriber(subscribers: &Subscribers, callback: Box<dyn Fn()>) -> Subscription {
+    let mut list = subscribers.borrow_mut();
+    let id = list.next_id;
+    list.next_id += 1;
+    list.callbacks.push((id, callback));
+    Subscription {
+        subscribers: Rc::downgrade(subscribers),
+        id,
+    }
+}
+
+fn notify_subscribers(subscribers: &Subscribers) {
+    for (_, subscriber) in subscribers.borrow().callbacks.iter() {
+        subscriber();
+    }
+}
+
+/// Handle to a callback registered with `subscribe`
+///
+/// Dropping the handle keeps the callback subscribed; `unsubscribe`
+/// removes it.
+pub struct Subscription {
+    subscribers: Weak<RefCell<SubscriberList>>,
+    id: u64,
+}
+
+impl Subscription {
+    pub fn unsubscribe(self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            subscribers.borrow_mut().callbacks.retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription").field("id", &self.id).finish()
+    }
+}
+
 /// Reactive signal holding a value
 ///
 /// When the value changes, any dependent computations are re-run.
 pub struct Signal<T> {
     id: SignalId,
     value: Rc<RefCell<T>>,
-    subscribers: Rc<RefCell<Vec<Box<dyn Fn()>>>>,
+    subscribers: Subscribers,
 }
 
 impl<T: std::fmt::Debug> std::fmt::Debug for Signal<T> {
@@ -68,7 +117,7 @@ impl<T: std::fmt::Debug> std::fmt::Debug for Signal<T> {
         f.debug_struct("Signal")
             .field("id", &self.id)
             .field("value", &self.value)
-            .field("subscribers", &format!("[{} subscribers]", self.subscribers.borrow().len()))
+            .field("subscribers", &format!("[{} subscribers]", self.subscribers.borrow().callbacks.len()))
             .finish()
     }
 }
@@ -78,7 +127,7 @@ impl<T: Clone> Signal<T> {
         Self {
             id,
             value: Rc::new(RefCell::new(value)),
-            subscribers: Rc::new(RefCell::new(Vec::new())),
+            subscribers: Subscribers::default(),
         }
     }
 
@@ -103,14 +152,17 @@ impl<T: Clone> Signal<T> {
     }
 
     /// Subscribe to value changes
-    pub fn subscribe(&self, callback: impl Fn() + 'static) {
-        self.subscribers.borrow_mut().push(Box::new(callback));
+    pub fn subscribe(&self, callback: impl Fn() + 'static) -> Subscription {
+        add_subscriber(&self.subscribers, Box::new(callback))
+    }
+
+    /// Number of callbacks currently subscribed
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.borrow().callbacks.len()
     }
 
     fn notify(&self) {
-        for subscriber in self.subscribers.borrow().iter() {
-            subscriber();
-        }
+        notify_subscribers(&self.subscribers);
     }
 
     pub fn id(&self) -> SignalId {
@@ -210,6 +262,81 @@ impl Effect {
     }
 }
 
+/// Settled state of a `Promise`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromiseState<T, E> {
+    Pending,
+    Resolved(T),
+    Rejected(E),
+}
+
+/// Reactive handle to the result of a future, backing `{#await}` blocks
+pub struct Promise<T, E> {
+    state: Rc<RefCell<PromiseState<T, E>>>,
+    subscribers: Subscribers,
+}
+
+impl<T: Clone + 'static, E: Clone + 'static> Promise<T, E> {
+    /// Promise that stays pending until `settle` is called
+    pub fn pending() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(PromiseState::Pending)),
+            subscribers: Subscribers::default(),
+        }
+    }
+
+    /// Drive `future` to completion
+    ///
+    /// On wasm the future runs on the browser event loop. Elsewhere it is
+    /// polled once and stays pending if it is not ready.
+    pub fn new(future: impl Future<Output = Result<T, E>> + 'static) -> Self {
+        let promise = Self::pending();
+        let handle = promise.clone();
+
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move { handle.settle(future.await) });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut future = std::pin::pin!(future);
+            let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+            if let std::task::Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+                handle.settle(result);
+            }
+        }
+
+        promise
+    }
+
+    /// Resolve or reject the promise and notify subscribers
+    pub fn settle(&self, result: Result<T, E>) {
+        *self.state.borrow_mut() = match result {
+            Ok(value) => PromiseState::Resolved(value),
+            Err(error) => PromiseState::Rejected(error),
+        };
+        notify_subscribers(&self.subscribers);
+    }
+
+    /// Get the current state
+    pub fn state(&self) -> PromiseState<T, E> {
+        self.state.borrow().clone()
+    }
+
+    /// Subscribe to the promise settling
+    pub fn subscribe(&self, callback: impl Fn() + 'static) -> Subscription {
+        add_subscriber(&self.subscribers, Box::new(callback))
+    }
+}
+
+impl<T, E> Clone for Promise<T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Rc::clone(&self.state),
+            subscribers: Rc::clone(&self.subscribers),
+        }
+    }
+}
+
 /// Batch multiple signal updates
 ///
 /// During a batch, effects are deferred until the batch completes.
@@ -240,6 +367,20 @@ pub fn create_effect(callback: impl Fn() + 'static) -> Effect {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_promise_settles() {
+        let ready = Promise::<i32, String>::new(async { Ok(7) });
+        assert_eq!(ready.state(), PromiseState::Resolved(7));
+
+        let promise = Promise::<i32, String>::pending();
+        let notified = Rc::new(RefCell::new(false));
+        let flag = notified.clone();
+        promise.subscribe(move || *flag.borrow_mut() = true);
+        promise.settle(Err("offline".to_string()));
+        assert_eq!(promise.state(), PromiseState::Rejected("offline".to_string()));
+        assert!(*notified.borrow());
+    }
+
     #[test]
     fn test_signal_get_set() {
         let signal = create_signal(0);
@@ -249,6 +390,21 @@ mod tests {
         assert_eq!(signal.get(), 42);
     }
 
+    #[test]
+    fn test_unsubscribe() {
+        let signal = create_signal(0);
+        let calls = Rc::new(RefCell::new(0));
+        let (first, second) = (calls.clone(), calls.clone());
+        let subscription = signal.subscribe(move || *first.borrow_mut() += 1);
+        signal.subscribe(move || *second.borrow_mut() += 10);
+        assert_eq!(signal.subscriber_count(), 2);
+
+        subscription.unsubscribe();
+        assert_eq!(signal.subscriber_count(), 1);
+        signal.set(1);
+        assert_eq!(*calls.borrow(), 10);
+    }
+
     #[test]
     fn test_signal_update() {
         let signal = create_signal(10);
@@ -5,7 +5,6 @@
 
 use glam::Vec2;
 use lyon::path::Path;
-use lyon::path::path::Builder;
 use lyon::tessellation::{
     BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions,
     StrokeTessellator, StrokeVertex, VertexBuffers,
@@ -174,7 +173,7 @@ impl PathTessellator {
         let mut builder = Path::builder();
         
         // Approximate circle with cubic bezier curves
-        let kappa = 0.5522847498; // Magic number for circle approximation
+        let kappa = 0.552_284_8; // Magic number for circle approximation
         let k = radius * kappa;
 
         builder.begin(lyon::geom::point(center.x + radius, center.y));
@@ -248,7 +247,7 @@ impl PathTessellator {
         let mut builder = Path::builder();
         
         // Approximate ellipse with cubic bezier curves
-        let kappa = 0.5522847498;
+        let kappa = 0.552_284_8;
         let kx = rx * kappa;
         let ky = ry * kappa;
 
@@ -0,0 +1,164 @@
+//! Script Values
+//!
+//! Object and array literals mixing value types, such as
+//! `{ type: "circle", x: 200 }`, compile to maps and vectors of `Value`.
+//! Reading a value as a number, string or boolean falls back to the
+//! script's notion of a missing value rather than failing.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// A value of any script type
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    List(Vec<Value>),
+    Object(HashMap<String, Value>),
+}
+
+impl Value {
+    /// The field `key` of an object, or `Null` if there is none
+    pub fn get(&self, key: &str) -> Value {
+        match self {
+            Value::Object(fields) => fields.get(key).cloned().unwrap_or_default(),
+            _ => Value::Null,
+        }
+    }
+
+    /// The value as a number, or `NaN` if it isn't one
+    pub fn as_number(&self) -> f64 {
+        match self {
+            Value::Number(n) => *n,
+            Value::Bool(b) => f64::from(u8::from(*b)),
+            Value::String(s) => s.trim().parse().unwrap_or(f64::NAN),
+            _ => f64::NAN,
+        }
+    }
+
+    /// The value as a string slice, or `""` if it isn't a string
+    pub fn as_str(&self) -> &str {
+        match self {
+            Value::String(s) => s,
+            _ => "",
+        }
+    }
+
+    /// Whether the value is truthy
+    pub fn as_bool(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::String(s) => !s.is_empty(),
+            Value::List(_) | Value::Object(_) => true,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => f.write_str(s),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                f.write_str(&items.join(","))
+            }
+            Value::Object(_) => f.write_str("[object Object]"),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Value::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<HashMap<String, T>> for Value {
+    fn from(map: HashMap<String, T>) -> Self {
+        Value::Object(map.into_iter().map(|(key, value)| (key, value.into())).collect())
+    }
+}
+
+impl PartialEq<f64> for Value {
+    fn eq(&self, other: &f64) -> bool {
+        matches!(self, Value::Number(n) if n == other)
+    }
+}
+
+impl PartialEq<&str> for Value {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other && matches!(self, Value::String(_))
+    }
+}
+
+impl PartialEq<bool> for Value {
+    fn eq(&self, other: &bool) -> bool {
+        matches!(self, Value::Bool(b) if b == other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_convert_and_compare() {
+        let shape = Value::from(HashMap::from([
+            ("type".to_string(), Value::from("circle")),
+            ("x".to_string(), Value::from(200.0)),
+            ("tags".to_string(), Value::from(vec!["a", "b"])),
+        ]));
+        let Value::Object(fields) = &shape else {
+            panic!("expected an object");
+        };
+        assert!(fields["type"] == "circle");
+        assert!(fields["x"] == 200.0);
+        assert_eq!(fields["x"].as_number(), 200.0);
+        assert_eq!(fields["tags"].to_string(), "a,b");
+        assert!(fields["type"].as_bool());
+        assert_eq!(shape.get("x"), Value::Number(200.0));
+        assert_eq!(shape.get("radius"), Value::Null);
+
+        assert!(Value::from("200").as_number() == 200.0);
+        assert!(Value::Null.as_number().is_nan());
+        assert_eq!(Value::from(None::<f64>), Value::Null);
+    }
+}
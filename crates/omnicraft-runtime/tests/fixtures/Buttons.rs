@@ -0,0 +1,99 @@
+//! Generated by OmniCraft Compiler
+//! Source: Buttons.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Props of Buttons
+#[derive(Clone)]
+pub struct ButtonsProps {
+}
+
+impl Default for ButtonsProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// Buttons
+#[derive(Clone)]
+pub struct ButtonsComponent {
+    pub props: ButtonsProps,
+    pub count: Signal<f64>,
+    pub bubbled: Signal<f64>,
+}
+
+impl OmniComponent for ButtonsComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, ButtonsProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let count = self.count.clone();
+        let bubbled = self.bubbled.clone();
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        let entity_0 = world.spawn_empty().id();
+        world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+        {
+            let bubbled = self.bubbled.clone();
+            world.on_event(entity_0, "click", EventModifiers::default(), move |event: &InputEvent| { bubbled.set((bubbled.get() + 1.0)); });
+        }
+
+        let parent_0 = entity_0;
+        let entity_0 = world.spawn_empty().id();
+        world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+        world.entity_mut(entity_0).insert(Shape::Rectangle { width: 80.0, height: 40.0 });
+        world.entity_mut(entity_0).insert(Parent(parent_0));
+        {
+            let count = self.count.clone();
+            world.on_event(entity_0, "click", EventModifiers::default(), move |event: &InputEvent| { count.set((count.get() + 1.0)); });
+        }
+
+        let entity_1 = world.spawn_empty().id();
+        world.entity_mut(entity_1).insert(Transform::from_xy(200.0, 200.0));
+        world.entity_mut(entity_1).insert(Shape::Circle { radius: 10.0 });
+        world.entity_mut(entity_1).insert(Parent(parent_0));
+        {
+            let count = self.count.clone();
+            world.on_event(entity_1, "click", EventModifiers { once: true, stop: true, ..Default::default() }, move |event: &InputEvent| { count.set((count.get() + 10.0)); });
+        }
+
+    }
+}
+
+impl ButtonsComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: ButtonsProps) -> Self {
+        let count = ctx.create_signal(0.0);
+        let bubbled = ctx.create_signal(0.0);
+        Self {
+            props,
+            count,
+            bubbled,
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = ButtonsComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
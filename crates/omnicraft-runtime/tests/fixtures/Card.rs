@@ -0,0 +1,88 @@
+//! Generated by OmniCraft Compiler
+//! Source: Card.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Props of Card
+#[derive(Clone)]
+pub struct CardProps {
+    pub title: String,
+    pub slots: Slots,
+}
+
+impl Default for CardProps {
+    fn default() -> Self {
+        Self {
+            title: "Card".to_string(),
+            slots: Slots::new(),
+        }
+    }
+}
+
+/// Card
+#[derive(Clone)]
+pub struct CardComponent {
+    pub props: CardProps,
+}
+
+impl OmniComponent for CardComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, CardProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let title = self.props.title.clone();
+        let slots = self.props.slots.clone();
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        let entity_0 = world.spawn_empty().id();
+        world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+
+        let parent_0 = entity_0;
+        if slots.contains("header") {
+            world.render_slot(&slots, "header", &SlotProps::new().with("title", title.clone()), Some(parent_0));
+        } else {
+            let entity_0 = world.spawn_empty().id();
+            world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+            world.entity_mut(entity_0).insert(TextContent { text: "Untitled".to_string(), ..Default::default() });
+
+        }
+
+        world.render_slot(&slots, "default", &SlotProps::new(), Some(parent_0));
+
+    }
+}
+
+impl CardComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: CardProps) -> Self {
+        let title = props.title.clone();
+        let slots = props.slots.clone();
+        Self {
+            props,
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = CardComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
@@ -0,0 +1,100 @@
+//! Generated by OmniCraft Compiler
+//! Source: Dashboard.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::card::{CardComponent, CardProps};
+
+/// Props of Dashboard
+#[derive(Clone)]
+pub struct DashboardProps {
+}
+
+impl Default for DashboardProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// Dashboard
+#[derive(Clone)]
+pub struct DashboardComponent {
+    pub props: DashboardProps,
+    ctx: Context,
+}
+
+impl OmniComponent for DashboardComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, DashboardProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let ctx = &mut self.ctx.clone();
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        let mut slots_0 = Slots::new();
+        {
+            slots_0.insert("header", move |world: &mut World, slot_props: &SlotProps| {
+                let title: String = slot_props.get("title").unwrap_or_default();
+                let mut entities = Vec::new();
+                let entity_0 = world.spawn_empty().id();
+                entities.push(entity_0);
+                world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+                world.entity_mut(entity_0).insert(TextContent { text: format!("{}", title).to_string(), ..Default::default() });
+
+                entities
+            });
+        }
+        {
+            slots_0.insert("default", move |world: &mut World, slot_props: &SlotProps| {
+                let mut entities = Vec::new();
+                let entity_0 = world.spawn_empty().id();
+                entities.push(entity_0);
+                world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+                world.entity_mut(entity_0).insert(Shape::Circle { radius: 5.0 });
+
+                entities
+            });
+        }
+        let child_0 = CardComponent::with_props(ctx, CardProps { title: "Stats".to_string(), slots: slots_0, ..Default::default() });
+        child_0.mount(world);
+
+        let child_1 = CardComponent::with_props(ctx, CardProps { ..Default::default() });
+        child_1.mount(world);
+
+    }
+}
+
+impl DashboardComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: DashboardProps) -> Self {
+        Self {
+            props,
+            ctx: ctx.clone(),
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = DashboardComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
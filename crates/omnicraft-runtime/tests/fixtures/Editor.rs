@@ -0,0 +1,101 @@
+//! Generated by OmniCraft Compiler
+//! Source: Editor.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Props of Editor
+#[derive(Clone)]
+pub struct EditorProps {
+}
+
+impl Default for EditorProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// Editor
+#[derive(Clone)]
+pub struct EditorComponent {
+    pub props: EditorProps,
+    pub pos_x: Signal<f64>,
+    pub label: Signal<String>,
+    pub visible: Signal<bool>,
+}
+
+impl OmniComponent for EditorComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, EditorProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let pos_x = self.pos_x.clone();
+        let label = self.label.clone();
+        let visible = self.visible.clone();
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        let entity_0 = world.spawn_empty().id();
+        world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 50.0));
+        world.entity_mut(entity_0).insert(TextContent { text: "".to_string(), ..Default::default() });
+        world.bind_property(entity_0, BoundProperty::X, self.pos_x.clone());
+        world.bind_property(entity_0, BoundProperty::Text, self.label.clone());
+
+        let region_1 = {
+            let pos_x = self.pos_x.clone();
+            let visible = self.visible.clone();
+            world.spawn_region(move |world: &mut World| {
+                let mut entities = Vec::new();
+                if visible.get() {
+                    let entity_100 = world.spawn_empty().id();
+                    entities.push(entity_100);
+                    world.entity_mut(entity_100).insert(Transform::from_xy(0.0, 100.0));
+                    world.entity_mut(entity_100).insert(Shape::Circle { radius: 5.0 });
+                    world.bind_property(entity_100, BoundProperty::X, pos_x.clone());
+
+                }
+                entities
+            })
+        };
+        world.unsubscribe_on_unmount(region_1, self.visible.subscribe(world.region_trigger(region_1)));
+
+    }
+}
+
+impl EditorComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: EditorProps) -> Self {
+        let pos_x = ctx.create_signal(100.0);
+        let label = ctx.create_signal("Drag me".to_string());
+        let visible = ctx.create_signal(true);
+        Self {
+            props,
+            pos_x,
+            label,
+            visible,
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = EditorComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
@@ -0,0 +1,99 @@
+//! Generated by OmniCraft Compiler
+//! Source: HelloWorld.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Props of HelloWorld
+#[derive(Clone)]
+pub struct HelloWorldProps {
+}
+
+impl Default for HelloWorldProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// HelloWorld
+#[derive(Clone)]
+pub struct HelloWorldComponent {
+    pub props: HelloWorldProps,
+    pub count: Signal<f64>,
+}
+
+impl OmniComponent for HelloWorldComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, HelloWorldProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let count = self.count.clone();
+
+        let entity_0 = world.spawn_empty().id();
+        world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+        world.entity_mut(entity_0).insert(Stack { direction: StackDirection::Column, gap: 16.0, padding: 32.0, ..Default::default() });
+
+        let parent_0 = entity_0;
+        let entity_0 = world.spawn_empty().id();
+        world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+        world.entity_mut(entity_0).insert(TextContent { text: "Hello, OmniCraft! 🎨".to_string(), font_size: 24.0, ..Default::default() });
+        world.entity_mut(entity_0).insert(Parent(parent_0));
+        world.entity_mut(entity_0).insert(Style { fill: Some(Color::parse("#6366f1")), stroke: None, ..Default::default() });
+
+        let entity_1 = world.spawn_empty().id();
+        world.entity_mut(entity_1).insert(Transform::from_xy(0.0, 0.0));
+        world.entity_mut(entity_1).insert(Stack { direction: StackDirection::Row, padding: 16.0, corner_radius: 8.0, background: Some(Color::parse("#4f46e5")), ..Default::default() });
+        world.entity_mut(entity_1).insert(Parent(parent_0));
+        {
+            let count = self.count.clone();
+            world.on_event(entity_1, "click", EventModifiers::default(), move |event: &InputEvent| { count.set((count.get() + 1.0)); });
+        }
+
+        let parent_1 = entity_1;
+        let entity_100 = world.spawn_empty().id();
+        world.entity_mut(entity_100).insert(Transform::from_xy(0.0, 0.0));
+        world.entity_mut(entity_100).insert(TextContent { text: format!("Count: {}", count.get()).to_string(), font_size: 16.0, ..Default::default() });
+        world.entity_mut(entity_100).insert(Parent(parent_1));
+        {
+            let count = self.count.clone();
+            world.bind_value(entity_100, BoundProperty::Text, move || format!("Count: {}", count.get()).to_string());
+        }
+        world.unsubscribe_on_unmount(entity_100, self.count.subscribe(world.binding_trigger(entity_100, BoundProperty::Text)));
+        world.entity_mut(entity_100).insert(Style { fill: Some(Color::parse("#ffffff")), stroke: None, ..Default::default() });
+
+    }
+}
+
+impl HelloWorldComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: HelloWorldProps) -> Self {
+        let count = ctx.create_signal(0.0);
+        Self {
+            props,
+            count,
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = HelloWorldComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
@@ -0,0 +1,108 @@
+//! Generated by OmniCraft Compiler
+//! Source: KeyedList.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Props of KeyedList
+#[derive(Clone)]
+pub struct KeyedListProps {
+}
+
+impl Default for KeyedListProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// KeyedList
+#[derive(Clone)]
+pub struct KeyedListComponent {
+    pub props: KeyedListProps,
+    pub items: Signal<Vec<f64>>,
+    pub selected: Signal<f64>,
+    pub x: Signal<f64>,
+}
+
+impl OmniComponent for KeyedListComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, KeyedListProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let items = self.items.clone();
+        let selected = self.selected.clone();
+        let x = self.x.clone();
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        world.get_resource_or_insert_with(Stylesheet::new).insert("KeyedList", vec![
+            StyleRule::new(".active", StyleDeclarations { fill: Some(Color::parse("#ff0000")), ..Default::default() }),
+        ]);
+
+        let region_0 = {
+            let items = self.items.clone();
+            let selected = self.selected.clone();
+            let x = self.x.clone();
+            world.spawn_region(move |world: &mut World| {
+                let mut entities = Vec::new();
+                for item in items.get() {
+                    let (entity_0, reused_0) = world.spawn_keyed(Key::new(item));
+                    entities.push(entity_0);
+                    world.entity_mut(entity_0).insert(Transform::from_xy(0.0, (item * 20.0) as f32));
+                    world.entity_mut(entity_0).insert(Shape::Circle { radius: 5.0 });
+                    if !reused_0 {
+                        {
+                            let selected = selected.clone();
+                            world.bind_class(entity_0, "active", move || (item == selected.get()));
+                        }
+                        world.unsubscribe_on_unmount(entity_0, selected.subscribe(world.class_trigger(entity_0, "active")));
+                        world.bind_property(entity_0, BoundProperty::X, x.clone());
+                    }
+                    world.entity_mut(entity_0).insert((Style::default(), ScopedStyle::new("KeyedList", StyleDeclarations::default())));
+
+                }
+                entities
+            })
+        };
+        world.unsubscribe_on_unmount(region_0, self.items.subscribe(world.region_trigger(region_0)));
+
+    }
+}
+
+impl KeyedListComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: KeyedListProps) -> Self {
+        let items = ctx.create_signal(vec![1.0, 2.0, 3.0]);
+        let selected = ctx.create_signal(2.0);
+        let x = ctx.create_signal(10.0);
+        Self {
+            props,
+            items,
+            selected,
+            x,
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = KeyedListComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
@@ -0,0 +1,92 @@
+//! Generated by OmniCraft Compiler
+//! Source: Legend.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Props of Legend
+#[derive(Clone)]
+pub struct LegendProps {
+}
+
+impl Default for LegendProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// Legend
+#[derive(Clone)]
+pub struct LegendComponent {
+    pub props: LegendProps,
+    pub selected: Signal<bool>,
+}
+
+impl OmniComponent for LegendComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, LegendProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let selected = self.selected.clone();
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        world.get_resource_or_insert_with(Stylesheet::new).insert("Legend", vec![
+            StyleRule::new("circle", StyleDeclarations { fill: Some(Color::parse("#0000ff")), ..Default::default() }),
+            StyleRule::new(".active circle", StyleDeclarations { stroke: Some(Color::parse("#000000")), stroke_width: Some(2.0), ..Default::default() }),
+        ]);
+
+        let entity_0 = world.spawn_empty().id();
+        world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+        world.entity_mut(entity_0).insert(Class::new(&"legend"));
+        {
+            let selected = self.selected.clone();
+            world.bind_class(entity_0, "active", move || selected.get());
+        }
+        world.unsubscribe_on_unmount(entity_0, self.selected.subscribe(world.class_trigger(entity_0, "active")));
+        world.entity_mut(entity_0).insert((Style::default(), ScopedStyle::new("Legend", StyleDeclarations::default())));
+
+        let parent_0 = entity_0;
+        let entity_0 = world.spawn_empty().id();
+        world.entity_mut(entity_0).insert(Transform::from_xy(10.0, 10.0));
+        world.entity_mut(entity_0).insert(Shape::Circle { radius: 5.0 });
+        world.entity_mut(entity_0).insert(Parent(parent_0));
+        world.entity_mut(entity_0).insert(Id::new("dot"));
+        world.entity_mut(entity_0).insert((Style::default(), ScopedStyle::new("Legend", StyleDeclarations { fill: Some(Color::parse("#00ff00")), ..Default::default() })));
+
+    }
+}
+
+impl LegendComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: LegendProps) -> Self {
+        let selected = ctx.create_signal(false);
+        Self {
+            props,
+            selected,
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = LegendComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
@@ -0,0 +1,100 @@
+//! Generated by OmniCraft Compiler
+//! Source: Loader.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::loader::load;
+
+/// Props of Loader
+#[derive(Clone)]
+pub struct LoaderProps {
+}
+
+impl Default for LoaderProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// Loader
+#[derive(Clone)]
+pub struct LoaderComponent {
+    pub props: LoaderProps,
+}
+
+impl OmniComponent for LoaderComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, LoaderProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        let promise_0 = load();
+        let region_0 = {
+            let promise_0 = promise_0.clone();
+            world.spawn_region(move |world: &mut World| {
+                let mut entities = Vec::new();
+                match promise_0.state() {
+                    PromiseState::Pending => {
+                        let entity_0 = world.spawn_empty().id();
+                        entities.push(entity_0);
+                        world.entity_mut(entity_0).insert(Transform::from_xy(10.0, 20.0));
+                        world.entity_mut(entity_0).insert(TextContent { text: "Loading".to_string(), ..Default::default() });
+
+                    }
+                    PromiseState::Resolved(data) => {
+                        let entity_0 = world.spawn_empty().id();
+                        entities.push(entity_0);
+                        world.entity_mut(entity_0).insert(Transform::from_xy(10.0, 20.0));
+                        world.entity_mut(entity_0).insert(TextContent { text: format!("{}", data).to_string(), ..Default::default() });
+
+                    }
+                    PromiseState::Rejected(error) => {
+                        let entity_0 = world.spawn_empty().id();
+                        entities.push(entity_0);
+                        world.entity_mut(entity_0).insert(Transform::from_xy(10.0, 20.0));
+                        world.entity_mut(entity_0).insert(TextContent { text: format!("Failed: {}", error).to_string(), ..Default::default() });
+
+                    }
+                }
+                entities
+            })
+        };
+        world.unsubscribe_on_unmount(region_0, promise_0.subscribe(world.region_trigger(region_0)));
+
+    }
+}
+
+impl LoaderComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: LoaderProps) -> Self {
+        Self {
+            props,
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = LoaderComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
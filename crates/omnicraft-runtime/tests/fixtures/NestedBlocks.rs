@@ -0,0 +1,102 @@
+//! Generated by OmniCraft Compiler
+//! Source: NestedBlocks.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Props of NestedBlocks
+#[derive(Clone)]
+pub struct NestedBlocksProps {
+}
+
+impl Default for NestedBlocksProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// NestedBlocks
+#[derive(Clone)]
+pub struct NestedBlocksComponent {
+    pub props: NestedBlocksProps,
+    pub show: Signal<bool>,
+    pub items: Signal<Vec<f64>>,
+}
+
+impl OmniComponent for NestedBlocksComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, NestedBlocksProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let show = self.show.clone();
+        let items = self.items.clone();
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        let region_0 = {
+            let show = self.show.clone();
+            let items = self.items.clone();
+            world.spawn_region(move |world: &mut World| {
+                let mut entities = Vec::new();
+                if show.get() {
+                    let region_0 = {
+                        let items = items.clone();
+                        world.spawn_region(move |world: &mut World| {
+                            let mut entities = Vec::new();
+                            for item in items.get() {
+                                let (entity_0, reused_0) = world.spawn_keyed(Key::new(item));
+                                entities.push(entity_0);
+                                world.entity_mut(entity_0).insert(Transform::from_xy((item * 20.0) as f32, 50.0));
+                                world.entity_mut(entity_0).insert(Shape::Circle { radius: 5.0 });
+
+                            }
+                            entities
+                        })
+                    };
+                    world.unsubscribe_on_unmount(region_0, items.subscribe(world.region_trigger(region_0)));
+                    entities.push(region_0);
+
+                }
+                entities
+            })
+        };
+        world.unsubscribe_on_unmount(region_0, self.show.subscribe(world.region_trigger(region_0)));
+
+    }
+}
+
+impl NestedBlocksComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: NestedBlocksProps) -> Self {
+        let show = ctx.create_signal(true);
+        let items = ctx.create_signal(vec![1.0, 2.0, 3.0]);
+        Self {
+            props,
+            show,
+            items,
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = NestedBlocksComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
@@ -0,0 +1,92 @@
+//! Generated by OmniCraft Compiler
+//! Source: Physics.omni
+//! Do not edit manually.
+
+#![allow(unused, clippy::all)]
+
+use omnicraft_runtime::prelude::*;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::actions::track;
+
+/// Props of Physics
+#[derive(Clone)]
+pub struct PhysicsProps {
+}
+
+impl Default for PhysicsProps {
+    fn default() -> Self {
+        Self {
+        }
+    }
+}
+
+/// Physics
+#[derive(Clone)]
+pub struct PhysicsComponent {
+    pub props: PhysicsProps,
+    pub visible: Signal<bool>,
+    pub ball: EntityRef,
+}
+
+impl OmniComponent for PhysicsComponent {
+    fn create(ctx: &mut Context) -> Self {
+        Self::with_props(ctx, PhysicsProps::default())
+    }
+
+    fn mount(&self, world: &mut World) {
+        let visible = self.visible.clone();
+        world.set_canvas_width(800.0);
+        world.set_canvas_height(600.0);
+
+        let region_0 = {
+            let visible = self.visible.clone();
+            let ball = self.ball.clone();
+            world.spawn_region(move |world: &mut World| {
+                let mut entities = Vec::new();
+                if visible.get() {
+                    let entity_0 = world.spawn_empty().id();
+                    entities.push(entity_0);
+                    world.entity_mut(entity_0).insert(Transform::from_xy(0.0, 0.0));
+                    world.entity_mut(entity_0).insert(Shape::Circle { radius: 5.0 });
+                    world.bind_ref(entity_0, &ball);
+                    world.use_action(entity_0, track, HashMap::from([("name".to_string(), "ball".to_string())]));
+
+                }
+                entities
+            })
+        };
+        world.unsubscribe_on_unmount(region_0, self.visible.subscribe(world.region_trigger(region_0)));
+
+    }
+}
+
+impl PhysicsComponent {
+    /// Create a new component instance
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::create(ctx)
+    }
+
+    /// Create a component instance with the given props
+    pub fn with_props(ctx: &mut Context, props: PhysicsProps) -> Self {
+        let visible = ctx.create_signal(true);
+        Self {
+            props,
+            visible,
+            ball: EntityRef::new(),
+        }
+    }
+}
+
+/// WASM Entry Point
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    let mut app = omnicraft_runtime::App::new();
+    let mut ctx = Context::new();
+    let component = PhysicsComponent::new(&mut ctx);
+    component.mount(app.world_mut());
+
+    omnicraft_runtime::render::wasm::run(app, "canvas")
+}
@@ -18,8 +18,6 @@ fn test_signal_effect_flow() {
     let mut ctx = Context::new();
     let count = ctx.create_signal(0);
     let output = Rc::new(RefCell::new(0));
-    let output_clone = output.clone();
-    let count_clone = count.clone(); // Signals are cheap and clonable
 
     // In the current simple implementation, we need to explicitly subscribe
     // because automatic dependency tracking isn't fully implemented yet.
@@ -0,0 +1,379 @@
+//! Template features at runtime
+//!
+//! The components are the Rust generator's output for the `.omni` files
+//! under `fixtures/`, kept current by the compiler's tests, so these tests
+//! pin down how generated code behaves once it runs in an `App`.
+
+use omnicraft_runtime::prelude::*;
+use omnicraft_runtime::App;
+
+fn region_entities(world: &World, region: Entity) -> Vec<Entity> {
+    world.get::<Region>(region).unwrap().entities.clone()
+}
+
+/// The region whose content is only shapes, i.e. the innermost one
+fn shape_region(world: &mut World) -> Entity {
+    let mut regions = world.query::<(Entity, &Region)>();
+    let regions: Vec<(Entity, Vec<Entity>)> =
+        regions.iter(world).map(|(region, content)| (region, content.entities.clone())).collect();
+    regions
+        .into_iter()
+        .find(|(_, entities)| !entities.is_empty() && entities.iter().all(|e| world.get::<Shape>(*e).is_some()))
+        .map(|(region, _)| region)
+        .unwrap()
+}
+
+#[path = "fixtures/NestedBlocks.rs"]
+mod nested_blocks;
+use nested_blocks::NestedBlocksComponent;
+
+#[test]
+fn test_outer_rerender_keeps_subscriber_count_flat() {
+    let mut app = App::new();
+    let component = NestedBlocksComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+    assert_eq!(component.items.subscriber_count(), 1);
+
+    for _ in 0..10 {
+        component.show.set(false);
+        app.tick();
+        component.show.set(true);
+        app.tick();
+    }
+    assert_eq!(component.items.subscriber_count(), 1);
+    assert_eq!(component.show.subscriber_count(), 1);
+}
+
+#[test]
+fn test_each_key_reuses_entities() {
+    let mut app = App::new();
+    let component = NestedBlocksComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+
+    let inner = shape_region(app.world_mut());
+    let before = region_entities(app.world_mut(), inner);
+    assert_eq!(before.len(), 3);
+
+    component.items.set(vec![3.0, 1.0, 2.0]);
+    app.tick();
+    let after = region_entities(app.world_mut(), inner);
+    assert_eq!(after, vec![before[2], before[0], before[1]]);
+    assert_eq!(app.world_mut().get::<Transform>(after[0]).unwrap().position.x, 60.0);
+}
+
+/// The entity with the given `id` attribute
+fn entity_with_id(world: &mut World, id: &str) -> Entity {
+    let mut query = world.query::<(Entity, &Id)>();
+    query.iter(world).find(|(_, entity_id)| entity_id.0 == id).map(|(entity, _)| entity).unwrap()
+}
+
+#[path = "fixtures/Legend.rs"]
+mod legend;
+use legend::LegendComponent;
+
+#[test]
+fn test_class_directive_restyles_nested_elements() {
+    let mut app = App::new();
+    let component = LegendComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+
+    let dot = entity_with_id(app.world_mut(), "dot");
+    let style = app.world_mut().get::<Style>(dot).unwrap().clone();
+    // The element's own attribute wins over the stylesheet
+    assert_eq!(style.fill.map(|c| c.g), Some(1.0));
+    assert!(style.stroke.is_none());
+
+    component.selected.set(true);
+    app.tick();
+    let style = app.world_mut().get::<Style>(dot).unwrap().clone();
+    assert_eq!(style.stroke.map(|c| c.r), Some(0.0));
+    assert_eq!(style.stroke_width, 2.0);
+
+    component.selected.set(false);
+    app.tick();
+    assert!(app.world_mut().get::<Style>(dot).unwrap().stroke.is_none());
+}
+
+#[path = "fixtures/Buttons.rs"]
+mod buttons;
+use buttons::ButtonsComponent;
+
+#[test]
+fn test_event_handlers_bubble_and_apply_modifiers() {
+    let mut app = App::new();
+    let component = ButtonsComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+
+    // A click on the rectangle bubbles up to the group
+    app.pointer_event("click", 10.0, 10.0);
+    assert_eq!((component.count.get(), component.bubbled.get()), (1.0, 1.0));
+
+    // `.stop` keeps it from the group, `.once` removes the handler
+    app.pointer_event("click", 200.0, 200.0);
+    assert_eq!((component.count.get(), component.bubbled.get()), (11.0, 1.0));
+    app.pointer_event("click", 200.0, 200.0);
+    assert_eq!((component.count.get(), component.bubbled.get()), (11.0, 2.0));
+
+    // Nothing is hit outside the shapes
+    app.pointer_event("click", 500.0, 500.0);
+    assert_eq!((component.count.get(), component.bubbled.get()), (11.0, 2.0));
+}
+
+#[path = "fixtures/Editor.rs"]
+mod editor;
+use editor::EditorComponent;
+
+#[test]
+fn test_bind_round_trip() {
+    let mut app = App::new();
+    let component = EditorComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+
+    let circle = shape_region(app.world_mut());
+    let circle = region_entities(app.world_mut(), circle)[0];
+    let mut texts = app.world_mut().query::<(Entity, &TextContent)>();
+    let text = texts.single(app.world_mut()).unwrap().0;
+    assert_eq!(app.world_mut().get::<TextContent>(text).unwrap().text, "Drag me");
+    assert_eq!(app.world_mut().get::<Transform>(circle).unwrap().position.x, 100.0);
+
+    // Dragging the circle writes the signal, which moves the text
+    app.world_mut().get_mut::<Transform>(circle).unwrap().position.x = 300.0;
+    app.tick();
+    assert_eq!(component.pos_x.get(), 300.0);
+    app.tick();
+    assert_eq!(app.world_mut().get::<Transform>(text).unwrap().position.x, 300.0);
+
+    // Editing the text writes the signal back
+    app.world_mut().get_mut::<TextContent>(text).unwrap().text = "Edited".to_string();
+    app.tick();
+    assert_eq!(component.label.get(), "Edited");
+
+    // Setting the signal updates the components
+    component.label.set("Set".to_string());
+    component.pos_x.set(42.0);
+    app.tick();
+    assert_eq!(app.world_mut().get::<TextContent>(text).unwrap().text, "Set");
+    assert_eq!(app.world_mut().get::<Transform>(circle).unwrap().position.x, 42.0);
+}
+
+#[test]
+fn test_bindings_unsubscribe_when_unmounted() {
+    let mut app = App::new();
+    let component = EditorComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+    assert_eq!(component.pos_x.subscriber_count(), 2);
+
+    for _ in 0..5 {
+        component.visible.set(false);
+        app.tick();
+        assert_eq!(component.pos_x.subscriber_count(), 1);
+        component.visible.set(true);
+        app.tick();
+    }
+    assert_eq!(component.pos_x.subscriber_count(), 2);
+}
+
+/// Host functions imported by the fixtures
+mod actions {
+    use omnicraft_runtime::prelude::*;
+    use std::collections::HashMap;
+
+    /// Mounts and unmounts seen by the `track` action
+    #[derive(Resource, Default)]
+    pub struct ActionLog(pub Vec<String>);
+
+    pub fn track(world: &mut World, _entity: Entity, options: HashMap<String, String>) -> impl FnOnce(&mut World) + use<> {
+        let name = options["name"].clone();
+        world.get_resource_or_insert_with(ActionLog::default).0.push(format!("mount {}", name));
+        move |world: &mut World| world.resource_mut::<ActionLog>().0.push(format!("unmount {}", name))
+    }
+}
+use actions::ActionLog;
+
+#[path = "fixtures/Physics.rs"]
+mod physics;
+use physics::PhysicsComponent;
+
+#[test]
+fn test_unmount_runs_cleanups_and_clears_refs() {
+    let mut app = App::new();
+    let component = PhysicsComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+
+    let region = shape_region(app.world_mut());
+    let ball = region_entities(app.world_mut(), region)[0];
+    assert_eq!(component.ball.get(), Some(ball));
+    assert_eq!(app.world_mut().resource::<ActionLog>().0, ["mount ball"]);
+
+    component.visible.set(false);
+    app.tick();
+    assert_eq!(component.ball.get(), None);
+    assert_eq!(app.world_mut().resource::<ActionLog>().0, ["mount ball", "unmount ball"]);
+
+    // A fresh element gets the ref and the action again
+    component.visible.set(true);
+    app.tick();
+    let ball = region_entities(app.world_mut(), region)[0];
+    assert_eq!(component.ball.get(), Some(ball));
+    assert_eq!(app.world_mut().resource::<ActionLog>().0, ["mount ball", "unmount ball", "mount ball"]);
+}
+
+#[path = "fixtures/Card.rs"]
+mod card;
+
+#[path = "fixtures/Dashboard.rs"]
+mod dashboard;
+use dashboard::DashboardComponent;
+
+#[test]
+fn test_slots_render_provided_content_or_fallback() {
+    let mut app = App::new();
+    let component = DashboardComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+
+    let world = app.world_mut();
+    let mut texts = world.query::<(&TextContent, Option<&Parent>)>();
+    let mut texts: Vec<(String, bool)> = texts
+        .iter(world)
+        .map(|(content, parent)| (content.text.clone(), parent.is_some()))
+        .collect();
+    texts.sort();
+    // The first card renders the provided header under its group, with the
+    // slot prop; the second falls back to its own content
+    assert_eq!(texts, [("Stats".to_string(), true), ("Untitled".to_string(), false)]);
+
+    // Only the first card was given default slot content
+    let mut circles = world.query::<(&Shape, &Parent)>();
+    assert_eq!(circles.iter(world).count(), 1);
+}
+
+#[path = "fixtures/KeyedList.rs"]
+mod keyed_list;
+use keyed_list::KeyedListComponent;
+
+#[test]
+fn test_keyed_reorder_reuses_entities_and_bindings() {
+    let mut app = App::new();
+    let component = KeyedListComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+
+    let region = shape_region(app.world_mut());
+    let mut entities = region_entities(app.world_mut(), region);
+    let by_item = |entities: &[Entity], items: &[f64], item: f64| entities[items.iter().position(|i| *i == item).unwrap()];
+    let first = [1.0, 2.0, 3.0].map(|item| by_item(&entities, &[1.0, 2.0, 3.0], item));
+    let subscribers = (component.selected.subscriber_count(), component.x.subscriber_count());
+    assert_eq!(subscribers, (3, 3));
+
+    for order in [[3.0, 1.0, 2.0], [2.0, 3.0, 1.0], [1.0, 3.0, 2.0], [3.0, 2.0, 1.0]] {
+        component.items.set(order.to_vec());
+        app.tick();
+        entities = region_entities(app.world_mut(), region);
+        for (i, item) in [1.0, 2.0, 3.0].into_iter().enumerate() {
+            assert_eq!(by_item(&entities, &order, item), first[i]);
+        }
+        assert_eq!((component.selected.subscriber_count(), component.x.subscriber_count()), subscribers);
+    }
+
+    // Bindings and classes survive the re-render of a reused entity
+    let world = app.world_mut();
+    assert_eq!(world.get::<Transform>(first[0]).unwrap().position.x, 10.0);
+    assert_eq!(component.x.get(), 10.0);
+    assert!(world.get::<Class>(first[1]).unwrap().contains("active"));
+    assert_eq!(world.get::<Style>(first[1]).unwrap().fill.map(|c| c.r), Some(1.0));
+    assert!(world.get::<Style>(first[0]).unwrap().fill.is_none());
+
+    component.x.set(30.0);
+    app.tick();
+    assert_eq!(app.world_mut().get::<Transform>(first[2]).unwrap().position.x, 30.0);
+}
+
+#[path = "fixtures/HelloWorld.rs"]
+mod hello_world;
+use hello_world::HelloWorldComponent;
+
+#[test]
+fn test_stacks_lay_out_and_button_takes_clicks() {
+    let mut app = App::new();
+    let component = HelloWorldComponent::new(&mut Context::new());
+    component.mount(app.world_mut());
+    app.tick();
+
+    let world = app.world_mut();
+    let stacks: Vec<(Transform, Stack)> =
+        world.query::<(&Transform, &Stack)>().iter(world).map(|(t, s)| (t.clone(), s.clone())).collect();
+    let stack = |direction| stacks.iter().find(|(_, stack)| stack.direction == direction).unwrap().clone();
+    let (column, button) = (stack(StackDirection::Column), stack(StackDirection::Row));
+    let mut labels = world.query::<(&Transform, &TextContent)>();
+    let title = labels.iter(world).find(|(_, text)| text.text.starts_with("Hello")).unwrap().0.clone();
+
+    // The column's padding offsets the title, and the gap separates it from
+    // the button below
+    assert_eq!(title.position.x, 32.0);
+    assert!(title.position.y > 32.0 && title.position.y < button.0.position.y);
+    assert_eq!(button.0.position.x, 32.0);
+    assert!(button.0.position.y - title.position.y >= 16.0);
+    assert!(column.1.size.y >= button.0.position.y + button.1.size.y);
+    assert_eq!(button.1.corner_radius, 8.0);
+
+    // The button's background takes clicks, and its label follows the count
+    assert!(texts(world).contains(&"Count: 0".to_string()));
+    let inside = button.0.position + button.1.size / 2.0;
+    app.pointer_event("click", inside.x, inside.y);
+    assert_eq!(component.count.get(), 1.0);
+    app.pointer_event("click", 8.0, 8.0);
+    assert_eq!(component.count.get(), 1.0);
+    app.tick();
+    assert!(texts(app.world_mut()).contains(&"Count: 1".to_string()));
+}
+
+mod loader {
+    use omnicraft_runtime::prelude::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        /// Promises returned by `load`, for the test to settle
+        pub static LOADS: RefCell<Vec<Promise<String, String>>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub fn load() -> Promise<String, String> {
+        let promise = Promise::pending();
+        LOADS.with(|loads| loads.borrow_mut().push(promise.clone()));
+        promise
+    }
+}
+
+#[path = "fixtures/Loader.rs"]
+mod loader_component;
+use loader_component::LoaderComponent;
+
+/// Texts on screen, in spawn order
+fn texts(world: &mut World) -> Vec<String> {
+    let mut query = world.query::<(Entity, &TextContent)>();
+    let mut texts: Vec<(Entity, String)> = query.iter(world).map(|(e, text)| (e, text.text.clone())).collect();
+    texts.sort_by_key(|(entity, _)| entity.index());
+    texts.into_iter().map(|(_, text)| text).collect()
+}
+
+#[test]
+fn test_await_block_follows_the_promise() {
+    for (result, expected) in [(Ok("ready".to_string()), "ready"), (Err("offline".to_string()), "Failed: offline")] {
+        let mut app = App::new();
+        LoaderComponent::new(&mut Context::new()).mount(app.world_mut());
+        app.tick();
+        assert_eq!(texts(app.world_mut()), ["Loading"]);
+
+        let promise = loader::LOADS.with(|loads| loads.borrow_mut().pop().unwrap());
+        promise.settle(result);
+        app.tick();
+        assert_eq!(texts(app.world_mut()), [expected]);
+    }
+}